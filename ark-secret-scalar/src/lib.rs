@@ -10,10 +10,12 @@ use core::{
 
 use ark_ff::{PrimeField}; // Field, Zero
 use ark_ec::{AffineRepr, Group}; // CurveGroup
+use ark_serialize::SerializationError;
+use ark_std::io::{Read, Write};
 
 use digest::{XofReader};
 pub use getrandom_or_panic::{RngCore,CryptoRng,rand_core,getrandom_or_panic};
-// use subtle::{Choice,ConstantTimeEq};
+use subtle::{Choice,ConstantTimeEq};
 use zeroize::Zeroize;
 
 // TODO:  Remove ark-transcript dependency once https://github.com/arkworks-rs/algebra/pull/643 lands
@@ -58,11 +60,27 @@ impl<F: PrimeField> Clone for SecretScalar<F> {
     }
 }
 
+impl<F: PrimeField> ConstantTimeEq for SecretScalar<F> {
+    /// We compare the canonical encoding of the difference of the sums
+    /// to zero without branching, but arkworks' own field arithmetic
+    /// remains only best effort constant-time.
+    fn ct_eq(&self, rhs: &SecretScalar<F>) -> Choice {
+        let mut d = self.operate(|lhs| rhs.operate(|rhs| (lhs[0] - rhs[0]) + (lhs[1] - rhs[1]) ));
+        let mut bytes = ark_std::vec![0u8; d.compressed_size()];
+        d.serialize_compressed(bytes.as_mut_slice())
+        .expect("Buffer has the compressed size");
+        let c = bytes.iter().fold(0u8, |acc, b| acc | b).ct_eq(&0u8);
+        bytes.zeroize();
+        d.zeroize();
+        self.resplit();
+        rhs.resplit();
+        c
+    }
+}
+
 impl<F: PrimeField> PartialEq for SecretScalar<F> {
     fn eq(&self, rhs: &SecretScalar<F>) -> bool {
-        let lhs = unsafe { &*self.0.get() };
-        let rhs = unsafe { &*rhs.0.get() };
-        ( (lhs[0] - rhs[0]) + (lhs[1] - rhs[1]) ).is_zero()
+        self.ct_eq(rhs).into()
     }
 }
 impl<F: PrimeField> Eq for SecretScalar<F> {}
@@ -75,6 +93,7 @@ impl<F: PrimeField> Zeroize for SecretScalar<F> {
 impl<F: PrimeField> Drop for SecretScalar<F> {
     fn drop(&mut self) { self.zeroize() }
 }
+impl<F: PrimeField> zeroize::ZeroizeOnDrop for SecretScalar<F> {}
 
 impl<F: PrimeField> SecretScalar<F> {
     /// Do computations with an immutable borrow of the two scalars.
@@ -102,7 +121,7 @@ impl<F: PrimeField> SecretScalar<F> {
     /// our other methods should never reveal references into the scalars,
     /// or even their individual valus.
     pub fn resplit(&self) {
-        let mut xof = Rng2Xof(getrandom_or_panic());
+        let mut xof = self.resplit_xof();
         let x = xof_read_reduced(&mut xof);
         let selfy = unsafe { &mut *self.0.get() };
         selfy[0] += &x;
@@ -110,21 +129,113 @@ impl<F: PrimeField> SecretScalar<F> {
     }
 
     pub fn resplit_mut(&mut self) {
-        let mut xof = Rng2Xof(getrandom_or_panic());
-        let x = xof_read_reduced(&mut xof);
+        let mut xof = self.resplit_xof();
+        self.resplit_with_xof(&mut xof);
+    }
+
+    /// Resplit using a user supplied RNG, which works without `getrandom`.
+    pub fn resplit_with_rng<R: RngCore+CryptoRng>(&mut self, rng: &mut R) {
+        self.resplit_with_xof(&mut Rng2Xof(rng));
+    }
+
+    fn resplit_with_xof<X: XofReader>(&mut self, xof: &mut X) {
+        let x = xof_read_reduced(xof);
         let selfy = self.0.get_mut();
         selfy[0] += &x;
         selfy[1] -= &x;
     }
 
+    /// Randomness for `resplit`, which comes from the system.
+    #[cfg(feature = "getrandom")]
+    fn resplit_xof(&self) -> impl XofReader {
+        Rng2Xof(getrandom_or_panic())
+    }
+
+    /// Randomness for `resplit` without `getrandom`, like in enclaves
+    /// or on microcontrollers.
+    ///
+    /// We hash the current split, so the split still changes every time,
+    /// but deterministically, which weakens our side channel defenses.
+    /// `resplit_with_rng` restores them whenever an RNG exists.
+    #[cfg(not(feature = "getrandom"))]
+    fn resplit_xof(&self) -> impl XofReader {
+        use digest::{Update, ExtendableOutput};
+        let mut h = ark_transcript::Shake128::default();
+        h.update(b"SecretScalarResplit");
+        let mut bytes = ark_std::vec![0u8; F::zero().compressed_size()];
+        self.operate(|ss| for s in ss {
+            s.serialize_compressed(bytes.as_mut_slice())
+            .expect("Buffer has the compressed size");
+            h.update(&bytes);
+        });
+        bytes.zeroize();
+        h.finalize_xof()
+    }
+
     /// Initialize and unbiased `SecretScalar` from a `XofReaader`.
     pub fn from_xof<R: XofReader>(xof: &mut R) -> Self {
+        let mut ss = Self::from_xof_unsplit(xof);
+        ss.resplit_mut();
+        ss
+    }
+
+    /// Initialize and unbiased `SecretScalar` from a `XofReaader`,
+    /// and split it using a user supplied RNG.
+    pub fn from_xof_with_rng<X: XofReader, R: RngCore+CryptoRng>(xof: &mut X, rng: &mut R) -> Self {
+        let mut ss = Self::from_xof_unsplit(xof);
+        ss.resplit_with_rng(rng);
+        ss
+    }
+
+    /// Initialize and unbiased `SecretScalar` from a user supplied RNG,
+    /// which also splits it, so this works without `getrandom`.
+    pub fn from_rng<R: RngCore+CryptoRng>(rng: &mut R) -> Self {
+        let mut ss = Self::from_xof_unsplit(&mut Rng2Xof(&mut *rng));
+        ss.resplit_with_rng(rng);
+        ss
+    }
+
+    fn from_xof_unsplit<R: XofReader>(xof: &mut R) -> Self {
         let mut xof = || xof_read_reduced(&mut *xof);
-        let mut ss = SecretScalar(UnsafeCell::new([xof(), xof()]) );
+        SecretScalar(UnsafeCell::new([xof(), xof()]) )
+    }
+
+    /// Initialize a `SecretScalar` from a scalar, which callers
+    /// should zeroize themselves.
+    pub fn from_scalar(x: F) -> Self {
+        let mut ss = SecretScalar(UnsafeCell::new([x, F::zero()]) );
         ss.resplit_mut();
         ss
     }
 
+    /// Serialize the sum of our two scalars, for encrypted storage only.
+    ///
+    /// We deliberately avoid implementing `CanonicalSerialize` so that
+    /// secrets never wind up in transcripts or logs by accident.
+    pub fn serialize_secret<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        let o = self.operate(|ss| {
+            let mut x = ss[0] + ss[1];
+            let o = x.serialize_compressed(writer);
+            x.zeroize();
+            o
+        });
+        self.resplit();
+        o
+    }
+
+    /// Serialized length of `serialize_secret`.
+    pub fn secret_serialized_size(&self) -> usize {
+        F::zero().compressed_size()
+    }
+
+    /// Deserialize from `serialize_secret` output.
+    pub fn deserialize_secret<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        let mut x = F::deserialize_compressed(reader) ?;
+        let ss = SecretScalar::from_scalar(x);
+        x.zeroize();
+        Ok(ss)
+    }
+
     /// Multiply by a scalar.
     pub fn mul_by_challenge(&self, rhs: &F) -> F {
         let o = self.operate(|ss| (ss[0] * rhs) + (ss[1] * rhs) );
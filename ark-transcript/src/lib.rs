@@ -24,6 +24,8 @@ use rand_core::{RngCore,CryptoRng};
 pub use sha3::{Shake128};
 pub use digest;
 use digest::{Update,XofReader,ExtendableOutput};
+#[cfg(any(feature = "sha512", feature = "blake2"))]
+use digest::{Digest,Output};
 
 #[cfg(test)]
 mod tests;
@@ -78,6 +80,57 @@ impl<T: Borrow<[u8]>> IntoTranscript for IsLabel<T> {
         Transcript::new_labeled(self)
     }
 }
+
+/// Associated data for VRF signatures, like a block hash or epoch,
+/// which users may control.
+/// 
+/// We create a transcript labeled `b"AssociatedData"` and then append
+/// the associated data, so unlike raw `&[u8]` this needs no further
+/// domain seperation within one application.
+#[derive(Clone,Debug)]
+pub struct AssociatedData<T>(pub T);
+impl<T: AsRef<[u8]>> IntoTranscript for AssociatedData<T> {
+    type Taken = Transcript;
+    fn into_transcript(self) -> Transcript {
+        let ad: &[u8] = self.0.as_ref();
+        let mut t = Transcript::new_labeled(b"AssociatedData");
+        t.append(ad);
+        t
+    }
+}
+
+/// Signing context for one protocol, like schnorrkel's `signing_context`.
+///
+/// `SigningContext::new(b"my-proto").bytes(msg)` yields a transcript
+/// labeled `b"SigningContext"`, into which we append the context and
+/// then the message labeled `b"Message"`.  We absorb the context once,
+/// so contexts could be reused across many messages.
+#[derive(Clone)]
+pub struct SigningContext(Transcript);
+
+impl SigningContext {
+    /// Initialize a signing context for protocol `context`.
+    pub fn new(context: &[u8]) -> SigningContext {
+        let mut t = Transcript::new_labeled(b"SigningContext");
+        t.append(context);
+        SigningContext(t)
+    }
+
+    /// Transcript for signing the message `msg` in this context.
+    pub fn bytes(&self, msg: &[u8]) -> Transcript {
+        let mut t = self.0.clone();
+        t.label(b"Message");
+        t.append(msg);
+        t
+    }
+
+    /// Transcript of only this context, into which callers append
+    /// their own structured message.
+    pub fn transcript(&self) -> Transcript {
+        self.0.clone()
+    }
+}
+
 impl<'a> IntoTranscript for &'a [u8] {
     type Taken = Transcript;
     fn into_transcript(self) -> Transcript {
@@ -99,6 +152,12 @@ impl<'a, const N: usize> IntoTranscript for &'a [u8; N] {
 enum Mode {
     /// Actual Shake128 hasher being written to.
     Hash(Shake128),
+    /// Actual Sha512 hasher being written to.
+    #[cfg(feature = "sha512")]
+    Sha512(sha2::Sha512),
+    /// Actual Blake2b hasher being written to.
+    #[cfg(feature = "blake2")]
+    Blake2b(blake2::Blake2b512),
     /// Accumulate bytes instead of hashing them.
     Accumulate(Vec<u8>),
 }
@@ -108,6 +167,10 @@ impl Mode {
     fn raw_write(&mut self, bytes: &[u8]) {
         match self {
             Mode::Hash(hasher) => hasher.update(bytes),
+            #[cfg(feature = "sha512")]
+            Mode::Sha512(hasher) => Update::update(hasher, bytes),
+            #[cfg(feature = "blake2")]
+            Mode::Blake2b(hasher) => Update::update(hasher, bytes),
             Mode::Accumulate(acc) => acc.extend_from_slice(bytes),
         }
     }
@@ -119,20 +182,25 @@ impl Mode {
         #[cfg(feature = "debug-transcript")]
         println!("Shake128 {}transcript XoF reader",self.debug_name);
         match self {
-            Mode::Hash(hasher) => Reader(hasher.clone().finalize_xof()),
+            Mode::Hash(hasher) => Reader(ReaderMode::Shake128(hasher.finalize_xof())),
+            #[cfg(feature = "sha512")]
+            Mode::Sha512(hasher) => Reader(ReaderMode::Sha512(CounterReader::new(hasher))),
+            #[cfg(feature = "blake2")]
+            Mode::Blake2b(hasher) => Reader(ReaderMode::Blake2b(CounterReader::new(hasher))),
             Mode::Accumulate(_) => panic!("Attempt to read from accumulating Transcript"),
         }
     }
 }
 
-/// Shake128 transcript style hasher.
+/// Transcript style hasher, using Shake128 by default, or else Sha512
+/// or Blake2b from `from_sha512` or `from_blake2b`.
 #[derive(Clone)]
 pub struct Transcript {
     /// Length writen between `seperate()` calls.  Always less than 2^31.
     /// `None` means `write` was not yet invoked, so seperate() does nothing.
     /// We need this to distinguish zero length write calls.
     length: Option<u32>,
-    /// Actual Shake128 hasher being written to, or maybe an accumulator
+    /// Actual hasher being written to, or maybe an accumulator
     mode: Mode,
     /// Is this a witness transcript?
     #[cfg(feature = "debug-transcript")]
@@ -177,6 +245,46 @@ impl Transcript {
         } 
     }
 
+    /// Create a `Transcript` from `Sha512`, whose `Reader`s run
+    /// Sha512 in counter mode.
+    #[cfg(feature = "sha512")]
+    pub fn from_sha512(hasher: sha2::Sha512) -> Transcript {
+        Transcript {
+            length: None,
+            mode: Mode::Sha512(hasher),
+            #[cfg(feature = "debug-transcript")]
+            debug_name: "",
+        } 
+    }
+
+    /// Create a fresh Sha512 `Transcript` with an initial domain label.
+    #[cfg(feature = "sha512")]
+    pub fn new_labeled_sha512(label: impl AsLabel) -> Transcript {
+        let mut t = Transcript::from_sha512(Default::default());
+        t.label(label);
+        t
+    }
+
+    /// Create a `Transcript` from `Blake2b512`, whose `Reader`s run
+    /// Blake2b in counter mode.
+    #[cfg(feature = "blake2")]
+    pub fn from_blake2b(hasher: blake2::Blake2b512) -> Transcript {
+        Transcript {
+            length: None,
+            mode: Mode::Blake2b(hasher),
+            #[cfg(feature = "debug-transcript")]
+            debug_name: "",
+        } 
+    }
+
+    /// Create a fresh Blake2b `Transcript` with an initial domain label.
+    #[cfg(feature = "blake2")]
+    pub fn new_labeled_blake2b(label: impl AsLabel) -> Transcript {
+        let mut t = Transcript::from_blake2b(Default::default());
+        t.label(label);
+        t
+    }
+
     /// Create a `Transcript` from previously accumulated bytes.
     /// 
     /// We do not domain seperate these initial bytes, but we domain
@@ -234,8 +342,8 @@ impl Transcript {
     pub fn accumulator_finalize(mut self) -> Vec<u8> {
         self.seperate();
         match self.mode {
-            Mode::Hash(_) => panic!("Attempte to accumulator_finalize a hashing Transcript"),
             Mode::Accumulate(acc) => acc,
+            _ => panic!("Attempte to accumulator_finalize a hashing Transcript"),
         }
     }
 
@@ -366,6 +474,11 @@ impl Transcript {
     /// touch the original.  After forking, you should write any
     /// secret seeds into the transcript, and then invoke `witness`
     /// with system randomness.
+    ///
+    /// Unlike merlin's `SigningTranscript`, we have no transcript trait,
+    /// so nothing demands `Clone` of callers' transcripts.  Branches off
+    /// a borrowed transcript, like witnesses, delinearization weights,
+    /// and batch weights, should `fork` instead of `clone` and `label`.
     pub fn fork(&self, label: impl AsLabel) -> Transcript {
         let mut fork = self.clone();
         #[cfg(feature = "debug-transcript")]
@@ -407,18 +520,85 @@ impl Transcript {
         self.write_bytes(&rand);
         self.mode.raw_reader()
     }
+
+    /// Create a witness reader from a forked transcript, but without
+    /// any randomness, ala RFC 6979.
+    /// 
+    /// Only the secret seeds written into the fork protect the witness,
+    /// so prefer `witness` whenever system randomness exists, because
+    /// hedging with randomness helps against fault attacks.
+    pub fn deterministic_witness(mut self) -> Reader {
+        self.seperate();
+        self.mode.raw_reader()
+    }
+}
+
+
+/// Fixed output hash run as an XoF in counter mode.
+/// 
+/// We finalize the transcript into a seed, and then output blocks
+/// `H(seed || I2OSP(i,8))` for `i = 0, 1, ..`.
+#[cfg(any(feature = "sha512", feature = "blake2"))]
+struct CounterReader<D: Digest> {
+    seed: Output<D>,
+    counter: u64,
+    block: Output<D>,
+    position: usize,
 }
 
+#[cfg(any(feature = "sha512", feature = "blake2"))]
+impl<D: Digest> CounterReader<D> {
+    fn new(hasher: D) -> Self {
+        let block = Output::<D>::default();
+        let position = block.len();
+        CounterReader { seed: hasher.finalize(), counter: 0, block, position }
+    }
+
+    fn read(&mut self, mut buf: &mut [u8]) {
+        while ! buf.is_empty() {
+            if self.position == self.block.len() {
+                self.block = D::new()
+                    .chain_update(&self.seed)
+                    .chain_update(self.counter.to_be_bytes())
+                    .finalize();
+                self.counter += 1;
+                self.position = 0;
+            }
+            let l = ark_std::cmp::min(self.block.len() - self.position, buf.len());
+            buf[..l].copy_from_slice(&self.block[self.position..self.position + l]);
+            self.position += l;
+            buf = &mut buf[l..];
+        }
+    }
+}
 
-/// Shake128 transcript style XoF reader, used for both 
-/// Fiat-Shamir challenges and witnesses.
+/// Inner XoF reader.
+enum ReaderMode {
+    Shake128(sha3::Shake128Reader),
+    #[cfg(feature = "sha512")]
+    Sha512(CounterReader<sha2::Sha512>),
+    #[cfg(feature = "blake2")]
+    Blake2b(CounterReader<blake2::Blake2b512>),
+}
+
+/// Transcript style XoF reader, used for both Fiat-Shamir challenges
+/// and witnesses.
+///
+/// We read Shake128's own XoF, or run Sha512 or Blake2b in counter
+/// mode, whichever hasher our `Transcript` used.
 #[repr(transparent)]
-pub struct Reader(sha3::Shake128Reader);
+pub struct Reader(ReaderMode);
 
 impl Reader {
     /// Read bytes from the transcript into the buffer.
     pub fn read_bytes(&mut self, buf: &mut [u8]) {
-        XofReader::read(&mut self.0, buf);
+        match &mut self.0 {
+            ReaderMode::Shake128(xof) => XofReader::read(xof, buf),
+            #[cfg(feature = "sha512")]
+            ReaderMode::Sha512(xof) => xof.read(buf),
+            #[cfg(feature = "blake2")]
+            ReaderMode::Blake2b(xof) => xof.read(buf),
+        }
     }
 
     /// Read bytes from the transcript. Always succeed fully.
@@ -104,3 +104,78 @@ fn accumulation() {
     let c2: [u8; 32] = t3.challenge(b"challenge").read_byte_array();
     assert_eq!(c1,c2);
 }
+
+#[test]
+fn shake128_test_vectors() {
+    let mut t = Transcript::new_labeled(b"test");
+    t.append_u64(7);
+
+    // Witnesses mix secret seeds and system randomness, which
+    // `TestVectorFakeRng` replaces by zeros.
+    let mut witness = t.fork(b"witness").chain(b"secret").witness(&mut TestVectorFakeRng);
+    assert_eq!(
+        hex::encode(witness.read_byte_array::<32>()),
+        "2c9f526a012d1c94b929ae766f7e489055488423811efc0d853ca64292d186ea"
+    );
+
+    let mut challenge = t.challenge(b"c");
+    assert_eq!(
+        hex::encode(challenge.read_byte_array::<32>()),
+        "6adf6438c008b66fa1fc0e5f50d021674e2b17dc1fa2740c277c4a82b07d10e0"
+    );
+}
+
+#[cfg(any(feature = "sha512", feature = "blake2"))]
+fn counter_mode_vector(mut t: Transcript, expected: &str) {
+    t.append_u64(7);
+    let mut reader = t.challenge(b"c");
+    // Read across the 64 byte block boundary in two calls.
+    let mut buf = [0u8; 80];
+    reader.read_bytes(&mut buf[..30]);
+    reader.read_bytes(&mut buf[30..]);
+    assert_eq!(hex::encode(buf), expected);
+}
+
+#[cfg(feature = "sha512")]
+#[test]
+fn sha512_test_vector() {
+    counter_mode_vector(
+        Transcript::new_labeled_sha512(b"test"),
+        "cb883e221742334395499f0fdd1c47561cdc4ae3d792e62ccc306348a8aae4aa\
+         1619bdafa9c61e91e20b259ffb5318a4a56ec7ac36af5c5dc3d6a5e1df1ad25b\
+         f35a9a4e77b53ae60d967f988a599ea3"
+    );
+}
+
+#[cfg(feature = "blake2")]
+#[test]
+fn blake2b_test_vector() {
+    counter_mode_vector(
+        Transcript::new_labeled_blake2b(b"test"),
+        "ed74eef49e793cd5443023166846ce36ed720ff681640ddc66b2a99f3e762c97\
+         e2c57682971f8c914a3c6942f877d471433aa44ab80f3318db47d3f4ee4474d0\
+         a10b2d20caf1439f3a76f81b855116c1"
+    );
+}
+
+#[test]
+fn signing_context_domain_seperation() {
+    use ark_ed25519::Fr;
+
+    let challenge = |mut t: Transcript| -> Fr { t.challenge(b"c").read_uniform() };
+    let ctx = SigningContext::new(b"my-proto");
+
+    assert_eq!(challenge(ctx.bytes(b"msg")), challenge(ctx.clone().bytes(b"msg")));
+    assert_ne!(challenge(ctx.bytes(b"msg")), challenge(ctx.bytes(b"msh")));
+    assert_ne!(challenge(ctx.bytes(b"msg")), challenge(SigningContext::new(b"my-protp").bytes(b"msg")));
+    // Bytes cannot migrate between the context and the message.
+    assert_ne!(
+        challenge(SigningContext::new(b"ab").bytes(b"c")),
+        challenge(SigningContext::new(b"a").bytes(b"bc"))
+    );
+
+    let mut t = ctx.transcript();
+    t.label(b"Message");
+    t.append(&b"msg"[..]);
+    assert_eq!(challenge(t), challenge(ctx.bytes(b"msg")));
+}
@@ -0,0 +1,100 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use ark_std::UniformRand;
+use rand_chacha::{ChaCha20Rng, rand_core::SeedableRng};
+
+use bandersnatch_vrfs::{
+    ring::RingContext, bandersnatch::SWAffine as Jubjub, Message, IntoVrfInput, PublicKey, SecretKey, VrfInOut,
+    ThinVrfSignature, RingProver, RingVerifier, RingVrfSignature,
+    batch_verify_thin, pedersen_vrf,
+};
+
+/// Ring sizes from 2^6 through 2^14.
+const LOG_RING_SIZES: [u32; 5] = [6, 8, 10, 12, 14];
+
+/// Batch sizes for batch verification.
+const BATCH_SIZES: [usize; 3] = [4, 16, 64];
+
+fn input(i: usize) -> bandersnatch_vrfs::VrfInput {
+    Message { domain: b"bench", message: &i.to_le_bytes() }.into_vrf_input()
+}
+
+fn thin(c: &mut Criterion) {
+    let secret = SecretKey::from_seed(&[0; 32]);
+    let public = secret.to_public();
+    let io = secret.vrf_inout(input(0));
+    let signature: ThinVrfSignature<1> = secret.sign_thin_vrf(b"bench", &[io]);
+
+    c.bench_function("thin sign", |b| b.iter(|| -> ThinVrfSignature<1> {
+        secret.sign_thin_vrf(b"bench", &[io])
+    }));
+    c.bench_function("thin verify", |b| b.iter(|| {
+        public.verify_thin_vrf(b"bench", [input(0)], &signature).unwrap()
+    }));
+
+    let mut group = c.benchmark_group("thin batch verify");
+    for n in BATCH_SIZES {
+        let signatures: Vec<ThinVrfSignature<1>> = (0..n)
+            .map(|i| secret.sign_thin_vrf(b"bench", &[secret.vrf_inout(input(i))]))
+            .collect();
+        group.bench_with_input(BenchmarkId::from_parameter(n), &signatures, |b, signatures| b.iter(|| {
+            batch_verify_thin(signatures.iter().enumerate()
+                .map(|(i,s)| (b"bench", [input(i)], &public, s))
+            ).unwrap()
+        }));
+    }
+    group.finish();
+}
+
+fn pedersen(c: &mut Criterion) {
+    let secret = SecretKey::from_seed(&[0; 32]);
+    let io = secret.vrf_inout(input(0));
+    let (signature, _) = pedersen_vrf().sign_pedersen_vrf(b"bench", &[io], None, &secret);
+
+    c.bench_function("pedersen sign", |b| b.iter(|| {
+        pedersen_vrf().sign_pedersen_vrf(b"bench", &[io], None, &secret)
+    }));
+    c.bench_function("pedersen verify", |b| b.iter(|| {
+        pedersen_vrf().verify_pedersen_vrf(b"bench", &[io], &signature).unwrap();
+    }));
+}
+
+fn ring(c: &mut Criterion) {
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    let secret = SecretKey::from_seed(&[0; 32]);
+    let ios: Vec<[VrfInOut; 1]> = (0..BATCH_SIZES[0]).map(|i| [secret.vrf_inout(input(i))]).collect();
+
+    let mut prove = c.benchmark_group("ring prove");
+    prove.sample_size(10);
+    let mut setups = Vec::new();
+    for log_n in LOG_RING_SIZES {
+        let ring_size = 1usize << log_n;
+        let ctx = RingContext::insecure_setup(ring_size, &mut rng).unwrap();
+        let mut pks: Vec<PublicKey> = (0..ring_size).map(|_| dleq_vrf::PublicKey(Jubjub::rand(&mut rng))).collect();
+        pks[0] = secret.to_public();
+        let ring_prover = ctx.ring_prover(&pks, 0).unwrap();
+        let ring_verifier = ctx.ring_verifier(&pks).unwrap();
+        let prover = RingProver { ring_prover: &ring_prover, secret: &secret };
+        prove.bench_function(BenchmarkId::from_parameter(ring_size), |b| b.iter(|| {
+            prover.sign_ring_vrf(b"bench", &ios[0])
+        }));
+        let signatures: Vec<RingVrfSignature<1>> = ios.iter()
+            .map(|io| prover.sign_ring_vrf(b"bench", io))
+            .collect();
+        setups.push((ring_size, ring_verifier, signatures));
+    }
+    prove.finish();
+
+    let mut verify = c.benchmark_group("ring verify");
+    verify.sample_size(10);
+    for (ring_size, ring_verifier, signatures) in setups.iter() {
+        verify.bench_function(BenchmarkId::from_parameter(ring_size), |b| b.iter(|| {
+            RingVerifier(ring_verifier).verify_ring_vrf(b"bench", [input(0)], &signatures[0]).unwrap()
+        }));
+    }
+    verify.finish();
+
+}
+
+criterion_group!(benches, thin, pedersen, ring);
+criterion_main!(benches);
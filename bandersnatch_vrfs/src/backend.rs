@@ -0,0 +1,205 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Ring proof backends
+//!
+//! Ring VRF signatures consist of a Pedersen VRF signature, whose key
+//! commitment `pk + b BLINDING_BASE` hides the signer's public key, and
+//! a ring proof that this key commitment opens to some public key in
+//! the ring.  A `RingProofBackend` commits to rings, and supplies and
+//! serializes these ring proofs:
+//!
+//! - `Kzg` uses w3f's ring-proof, whose proofs and verification have
+//!   constant size, but which needs a KZG trusted setup.
+//! - `one_of_many::OneOfMany` needs no trusted setup, but its proofs
+//!   grow logarithmically and its verification linearly in the ring.
+//!
+//! `BackendRingProver` and `BackendRingVerifier` sign and verify
+//! `RingVrfProof<B::Proof>`s for any backend `B`, while `RingProver`
+//! and `RingVerifier` remain the richer KZG specific interfaces.
+
+use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, SerializationError};
+use ark_std::vec::Vec;
+
+use crate::{
+    Jubjub, PublicKey, SecretKey, SecretBlinding, RingVrfProof, IntoTranscript, IntoVrfInput,
+    VrfInOut, VrfSignature, VrfSignatureVec, EcVrfSigner, EcVrfVerifier,
+    SignatureError, pedersen_vrf, vrf,
+};
+
+
+/// Ring proof system, which proves that a Pedersen VRF key commitment
+/// opens to some public key in a ring.
+pub trait RingProofBackend {
+    /// Ring proof, as carried inside `RingVrfProof<Self::Proof>`.
+    type Proof: Clone+CanonicalSerialize+CanonicalDeserialize+'static;
+    /// Prover for one ring member.
+    type Prover;
+    /// Verifier for one ring.
+    type Verifier;
+    /// Setup shared by all rings, like a KZG setup.
+    type Context;
+    /// Commitment to one ring, from which we build its `Verifier`,
+    /// so verifiers need not store the ring itself.
+    type Commitment: Clone;
+
+    /// Commit to the ring `pks`, in this order.
+    fn commit(ctx: &Self::Context, pks: &[PublicKey]) -> Self::Commitment;
+
+    /// Verifier for the ring committed to by `commitment`.
+    fn verifier(ctx: &Self::Context, commitment: &Self::Commitment) -> Self::Verifier;
+
+    /// Prove `key_commitment` opens to the prover's ring member,
+    /// using blinding `secret_blinding`.
+    fn prove(
+        prover: &Self::Prover,
+        secret: &SecretKey,
+        secret_blinding: &SecretBlinding,
+        key_commitment: &Jubjub,
+    ) -> Self::Proof;
+
+    /// Verify `key_commitment` opens to some member of the ring.
+    fn verify(verifier: &Self::Verifier, proof: &Self::Proof, key_commitment: &Jubjub) -> bool;
+
+    /// Serialize a ring proof, compressed.
+    fn serialize_proof(proof: &Self::Proof) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(proof.compressed_size());
+        proof.serialize_compressed(&mut bytes).expect("Vec serialization is infallible");
+        bytes
+    }
+
+    /// Deserialize a compressed ring proof, with validation.
+    fn deserialize_proof(bytes: &[u8]) -> Result<Self::Proof, SerializationError> {
+        <Self::Proof as CanonicalDeserialize>::deserialize_compressed(bytes)
+    }
+}
+
+/// KZG based ring proofs from w3f's ring-proof.
+pub struct Kzg;
+
+impl RingProofBackend for Kzg {
+    type Proof = crate::ring::RingProof;
+    type Prover = crate::ring::RingProver;
+    type Verifier = crate::ring::RingVerifier;
+    type Context = crate::ring::RingContext;
+    type Commitment = crate::ring::RingCommitment;
+
+    fn commit(ctx: &Self::Context, pks: &[PublicKey]) -> Self::Commitment {
+        ctx.ring_commitment(pks)
+    }
+
+    fn verifier(ctx: &Self::Context, commitment: &Self::Commitment) -> Self::Verifier {
+        crate::ring::ring_verifier_from_commitment(commitment, ctx.kzg_vk(), ctx.domain_size as usize)
+    }
+
+    fn prove(prover: &Self::Prover, _secret: &SecretKey, secret_blinding: &SecretBlinding, _key_commitment: &Jubjub) -> Self::Proof {
+        prover.prove(secret_blinding.0[0])
+    }
+
+    fn verify(verifier: &Self::Verifier, proof: &Self::Proof, key_commitment: &Jubjub) -> bool {
+        verifier.verify_ring_proof(proof.clone(), *key_commitment)
+    }
+}
+
+
+/// Ring VRF signer for any `RingProofBackend`.
+pub struct BackendRingProver<'a, B: RingProofBackend> {
+    pub ring_prover: &'a B::Prover,
+    pub secret: &'a SecretKey,
+}
+
+impl<'a, B: RingProofBackend> core::borrow::Borrow<SecretKey> for BackendRingProver<'a, B> {
+    fn borrow(&self) -> &SecretKey { self.secret }
+}
+
+impl<'a, B: RingProofBackend> EcVrfSigner for BackendRingProver<'a, B> {
+    type Proof = RingVrfProof<B::Proof>;
+    type Error = ();
+    type Secret = SecretKey;
+    fn vrf_sign_detached(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut]
+    ) -> Result<RingVrfProof<B::Proof>,()>
+    {
+        Ok(self.sign_detached(t, ios))
+    }
+}
+
+impl<'a, B: RingProofBackend> BackendRingProver<'a, B> {
+    fn sign_detached(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut]
+    ) -> RingVrfProof<B::Proof>
+    {
+        let (dleq_proof, secret_blinding) = pedersen_vrf().sign_pedersen_vrf(t, ios, None, self.secret);
+        let key_commitment = dleq_proof.as_key_commitment().0;
+        let ring_proof = B::prove(self.ring_prover, self.secret, &secret_blinding, &key_commitment);
+        RingVrfProof { dleq_proof, ring_proof }
+    }
+
+    pub fn sign_ring_vrf<const N: usize>(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut; N],
+    ) -> VrfSignature<RingVrfProof<B::Proof>,N>
+    {
+        let proof = self.sign_detached(t, ios);
+        let preouts = core::array::from_fn(|i| ios[i].preoutput);
+        VrfSignature { preouts, proof }
+    }
+
+    pub fn sign_ring_vrf_vec(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut],
+    ) -> VrfSignatureVec<RingVrfProof<B::Proof>>
+    {
+        let proof = self.sign_detached(t, ios);
+        VrfSignatureVec { preouts: vrf::collect_preoutputs_vec(ios), proof }
+    }
+}
+
+/// Ring VRF verifier for any `RingProofBackend`.
+pub struct BackendRingVerifier<'a, B: RingProofBackend>(pub &'a B::Verifier);
+
+impl<B: RingProofBackend> EcVrfVerifier for BackendRingVerifier<'_, B> {
+    type Proof = RingVrfProof<B::Proof>;
+    type Error = SignatureError;
+
+    fn vrf_verify_detached<'a>(
+        &self,
+        t: impl IntoTranscript,
+        ios: &'a [VrfInOut],
+        signature: &RingVrfProof<B::Proof>,
+    ) -> Result<&'a [VrfInOut],Self::Error> {
+        pedersen_vrf().verify_pedersen_vrf(t,ios,&signature.dleq_proof) ?;
+        let key_commitment = signature.dleq_proof.as_key_commitment();
+        match B::verify(self.0, &signature.ring_proof, &key_commitment.0) {
+            true => Ok(ios),
+            false => Err(SignatureError::RingProofInvalid),
+        }
+    }
+}
+
+impl<B: RingProofBackend> BackendRingVerifier<'_, B> {
+    pub fn verify_ring_vrf<const N: usize>(
+        &self,
+        t: impl IntoTranscript,
+        inputs: impl IntoIterator<Item = impl IntoVrfInput<Jubjub>>,
+        signature: &VrfSignature<RingVrfProof<B::Proof>,N>,
+    ) -> Result<[VrfInOut; N],SignatureError>
+    {
+        self.vrf_verify(t, inputs, signature)
+    }
+
+    pub fn verify_ring_vrf_vec(
+        &self,
+        t: impl IntoTranscript,
+        inputs: impl IntoIterator<Item = impl IntoVrfInput<Jubjub>>,
+        signature: &VrfSignatureVec<RingVrfProof<B::Proof>>,
+    ) -> Result<Vec<VrfInOut>,SignatureError>
+    {
+        self.vrf_verify_vec(t, inputs, signature)
+    }
+}
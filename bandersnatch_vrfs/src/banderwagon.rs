@@ -0,0 +1,128 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Banderwagon encoding for Bandersnatch points
+//!
+//! Banderwagon identifies each Bandersnatch point `P` in the order `2 r`
+//! subgroup with `P + T2`, where `T2` denotes the twisted Edwards point
+//! `(0,-1)` of order two, which yields a prime order quotient group.
+//! We follow the encoding used by Ethereum's Verkle trees, which writes
+//! the twisted Edwards `x` coordinate, negated unless `y` is
+//! lexicographically largest, as 32 big endian bytes.
+//!
+//! We always decode into the representative in the prime order subgroup,
+//! so decoded public keys and pre-outputs have no cofactor malleability.
+
+use ark_ec::AffineRepr;
+use ark_ff::{Field, One, PrimeField, Zero};
+use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, SerializationError};
+
+use crate::{Jubjub, PublicKey, VrfPreOut, bandersnatch::Fq, elligator2::{J, K}, edwards::{sw_to_te, te_to_sw}};
+
+
+/// Length of the Banderwagon encoding of a point.
+pub const BANDERWAGON_POINT_LENGTH: usize = 32;
+
+/// Twisted Edwards coefficients `a = (J + 2) / K` and `d = (J - 2) / K`.
+fn te_coefficients() -> (Fq, Fq) {
+    let k_inv = K.inverse().unwrap();
+    ((J + Fq::from(2u8)) * k_inv, (J - Fq::from(2u8)) * k_inv)
+}
+
+fn lexicographically_largest(y: &Fq) -> bool {
+    y.into_bigint() > Fq::MODULUS_MINUS_ONE_DIV_TWO
+}
+
+/// Banderwagon encoding of a point, which should lie in the prime
+/// order subgroup.
+pub fn serialize_point(p: &Jubjub) -> [u8; BANDERWAGON_POINT_LENGTH] {
+    let x = match sw_to_te(p) {
+        Some((x,y)) if lexicographically_largest(&y) => x,
+        Some((x,_)) => -x,
+        None => Fq::zero(),
+    };
+    let mut bytes = [0u8; BANDERWAGON_POINT_LENGTH];
+    x.serialize_compressed(&mut bytes[..]).expect("Fq serializes into 32 bytes");
+    bytes.reverse();
+    bytes
+}
+
+/// Decode a Banderwagon encoded point into its representative in
+/// the prime order subgroup.
+///
+/// We reject non-canonical field elements and any `x` not
+/// representing a point in the order `2 r` subgroup.
+pub fn deserialize_point(bytes: &[u8; BANDERWAGON_POINT_LENGTH]) -> Result<Jubjub, SerializationError> {
+    let mut le = *bytes;
+    le.reverse();
+    let x = Fq::deserialize_compressed(&le[..]) ?;
+    if x.is_zero() {
+        return Ok(Jubjub::zero());
+    }
+    let (a, d) = te_coefficients();
+    let x2 = x.square();
+    // Subgroup check from the Banderwagon specification
+    let num = Fq::one() - a * x2;
+    if num.legendre().is_qnr() {
+        return Err(SerializationError::InvalidData);
+    }
+    let mut y = (num * (Fq::one() - d * x2).inverse().ok_or(SerializationError::InvalidData)?)
+        .sqrt().ok_or(SerializationError::InvalidData) ?;
+    if ! lexicographically_largest(&y) { y = -y; }
+    for (x,y) in [(x,y), (-x,-y)] {
+        let p = te_to_sw(x,y).ok_or(SerializationError::InvalidData) ?;
+        if p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve() {
+            return Ok(p);
+        }
+    }
+    Err(SerializationError::InvalidData)
+}
+
+pub fn serialize_publickey(pk: &PublicKey) -> [u8; BANDERWAGON_POINT_LENGTH] {
+    serialize_point(&pk.0)
+}
+
+pub fn deserialize_publickey(bytes: &[u8; BANDERWAGON_POINT_LENGTH]) -> Result<PublicKey, SerializationError> {
+    deserialize_point(bytes).map(dleq_vrf::PublicKey)
+}
+
+pub fn serialize_preout(preout: &VrfPreOut) -> [u8; BANDERWAGON_POINT_LENGTH] {
+    serialize_point(&preout.0)
+}
+
+pub fn deserialize_preout(bytes: &[u8; BANDERWAGON_POINT_LENGTH]) -> Result<VrfPreOut, SerializationError> {
+    deserialize_point(bytes).map(dleq_vrf::vrf::VrfPreOut)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::{CurveGroup, twisted_edwards::TECurveConfig};
+    use crate::bandersnatch::BandersnatchConfig;
+
+    #[test]
+    fn check_te_coefficients() {
+        let (a, d) = te_coefficients();
+        assert_eq!(a, <BandersnatchConfig as TECurveConfig>::COEFF_A);
+        assert_eq!(d, <BandersnatchConfig as TECurveConfig>::COEFF_D);
+    }
+
+    #[test]
+    fn banderwagon_roundtrip() {
+        let t2 = Jubjub::new_unchecked(J * (Fq::from(3u8) * K).inverse().unwrap(), Fq::zero());
+        assert!(t2.is_on_curve());
+        assert!(Jubjub::from(t2 + t2).is_zero());
+
+        let g = Jubjub::generator();
+        assert_eq!(deserialize_point(&serialize_point(&Jubjub::zero())).unwrap(), Jubjub::zero());
+        for i in 1u64..8 {
+            let p = (g * crate::bandersnatch::Fr::from(i)).into_affine();
+            let bytes = serialize_point(&p);
+            assert_eq!(deserialize_point(&bytes).unwrap(), p);
+            // p and p + T2 share one encoding
+            assert_eq!(serialize_point(&(p + t2).into_affine()), bytes);
+            assert_ne!(serialize_point(&(-p)), bytes);
+        }
+        assert!(deserialize_point(&[0xff; 32]).is_err());
+    }
+}
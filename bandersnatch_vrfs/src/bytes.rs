@@ -0,0 +1,135 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Byte slice entrypoints
+//!
+//! Substrate host functions and FFI pass only byte slices, so these
+//! entrypoints do all deserialization internally.  We follow the
+//! conventions of `bandersnatch_vrfs_ffi`:
+//!
+//! - VRF inputs hash `domain` and each input message by `Message`.
+//! - Associated data `ad` becomes the label of a fresh `Transcript`.
+//! - Signatures encode the compressed proof followed by one compressed
+//!   pre-output per input, without any length prefix, exactly like
+//!   `VrfSignature<_,N>::to_bytes`.
+//! - VRF outputs are 32 bytes read from a transcript labeled
+//!   `b"VrfOutput"`, one per input.
+
+use ark_std::vec::Vec;
+
+use crate::{
+    CanonicalSerialize, CanonicalDeserialize, IntoVrfInput, Message, SecretKey, PublicKey, deserialize_canonical,
+    Transcript, VrfInput, VrfInOut, VrfPreOut, SignatureError, RingVerifier, RingVrfProof,
+    ThinVrfProof, transcript::IsLabel, ring::RingVerifierKey, EcVrfVerifier,
+};
+
+
+/// Byte slice entrypoint failures
+#[derive(Debug)]
+pub enum BytesError {
+    /// Some input failed to deserialize, or had trailing bytes
+    Deserialize,
+    /// Signature failed to verify
+    Signature(SignatureError),
+}
+
+impl core::fmt::Display for BytesError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BytesError::Deserialize => f.write_str("input fails to deserialize canonically"),
+            BytesError::Signature(e) => write!(f, "signature failed: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BytesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BytesError::Deserialize => None,
+            BytesError::Signature(e) => Some(e),
+        }
+    }
+}
+
+impl From<SignatureError> for BytesError {
+    fn from(e: SignatureError) -> BytesError {
+        BytesError::Signature(e)
+    }
+}
+
+/// VRF output bytes for one input-output pair.
+pub fn output_bytes(io: &VrfInOut) -> [u8; 32] {
+    io.vrf_output_bytes(Transcript::new_labeled(b"VrfOutput"))
+}
+
+fn inputs_from_bytes(domain: &[u8], inputs: &[&[u8]]) -> Vec<VrfInput> {
+    inputs.iter().map(|message| Message { domain, message }.into_vrf_input()).collect()
+}
+
+fn ad_transcript(ad: &[u8]) -> Transcript {
+    Transcript::new_labeled(IsLabel(ad))
+}
+
+/// Deserialize a proof followed by exactly `n` pre-outputs, rejecting
+/// non-canonical encodings, so signature bytes are unique.
+fn signature_from_bytes<P>(encoded: &[u8], n: usize) -> Result<(P, Vec<VrfPreOut>), BytesError>
+where P: CanonicalSerialize + CanonicalDeserialize,
+{
+    let mut bytes = encoded;
+    let proof = P::deserialize_compressed(&mut bytes).map_err(|_| BytesError::Deserialize) ?;
+    let preouts = (0..n).map(|_| VrfPreOut::deserialize_compressed(&mut bytes))
+        .collect::<Result<Vec<_>,_>>().map_err(|_| BytesError::Deserialize) ?;
+    let mut canonical = Vec::with_capacity(encoded.len());
+    proof.serialize_compressed(&mut canonical).expect("Vec serialization is infallible");
+    for preout in preouts.iter() {
+        preout.serialize_compressed(&mut canonical).expect("Vec serialization is infallible");
+    }
+    if canonical != encoded { return Err(BytesError::Deserialize); }
+    Ok((proof, preouts))
+}
+
+fn attach(inputs: Vec<VrfInput>, preouts: Vec<VrfPreOut>) -> Vec<VrfInOut> {
+    inputs.into_iter().zip(preouts).map(|(input, preout)| preout.attach_input(input)).collect()
+}
+
+/// Thin VRF signature by the secret key given by `seed`, on `inputs`
+/// within `domain`, with associated data `ad`.
+pub fn sign_thin_bytes(seed: &[u8; 32], domain: &[u8], inputs: &[&[u8]], ad: &[u8]) -> Vec<u8> {
+    let secret = SecretKey::from_seed(seed);
+    let ios: Vec<VrfInOut> = inputs_from_bytes(domain, inputs).into_iter()
+        .map(|input| secret.vrf_inout(input)).collect();
+    let proof = secret.sign_thin_vrf_detached(ad_transcript(ad), &ios);
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes).expect("Vec serialization is infallible");
+    for io in ios.iter() {
+        io.preoutput.serialize_compressed(&mut bytes).expect("Vec serialization is infallible");
+    }
+    bytes
+}
+
+/// Verify a thin VRF signature by compressed public key `public`,
+/// returning the VRF output for each input.
+pub fn verify_thin_bytes(public: &[u8], domain: &[u8], inputs: &[&[u8]], signature: &[u8], ad: &[u8])
+ -> Result<Vec<[u8; 32]>, BytesError>
+{
+    let public: PublicKey = deserialize_canonical(public).map_err(|_| BytesError::Deserialize) ?;
+    let (proof, preouts) = signature_from_bytes::<ThinVrfProof>(signature, inputs.len()) ?;
+    let ios = attach(inputs_from_bytes(domain, inputs), preouts);
+    public.vrf_verify_detached(ad_transcript(ad), &ios, &proof) ?;
+    Ok(ios.iter().map(output_bytes).collect())
+}
+
+/// Verify a ring VRF signature by any member of the ring given by
+/// the serialized `ring::RingVerifierKey`, returning the VRF output
+/// for each input.
+pub fn verify_ring_bytes(ring_verifier_key: &[u8], domain: &[u8], inputs: &[&[u8]], signature: &[u8], ad: &[u8])
+ -> Result<Vec<[u8; 32]>, BytesError>
+{
+    let ring_verifier = RingVerifierKey::deserialize_compressed(ring_verifier_key)
+        .map_err(|_| BytesError::Deserialize) ?
+        .into_ring_verifier();
+    let (proof, preouts) = signature_from_bytes::<RingVrfProof>(signature, inputs.len()) ?;
+    let ios = attach(inputs_from_bytes(domain, inputs), preouts);
+    RingVerifier(&ring_verifier).vrf_verify_detached(ad_transcript(ad), &ios, &proof) ?;
+    Ok(ios.iter().map(output_bytes).collect())
+}
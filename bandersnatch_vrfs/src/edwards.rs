@@ -0,0 +1,104 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Twisted Edwards form of Bandersnatch
+//!
+//! We compute in Bandersnatch's short Weierstrass form, but encodings
+//! like Banderwagon and the ECVRF suite of the Bandersnatch VRF
+//! specification use its twisted Edwards form, so we map between the
+//! two via the Montgomery form.
+
+use ark_ec::AffineRepr;
+use ark_ff::{Field, One, Zero};
+
+use crate::{Jubjub, bandersnatch::{EdwardsAffine, Fq}, elligator2::{J, K}};
+
+
+/// Twisted Edwards coordinates of a short Weierstrass point,
+/// via the Montgomery point `(s,t) = (K u - J/3, K v)`.
+///
+/// Returns `None` for the identity, and the point of order two
+/// which the birational map leaves exceptional.
+pub(crate) fn sw_to_te(p: &Jubjub) -> Option<(Fq, Fq)> {
+    let (u, v) = p.xy()?;
+    let s = K * u - J * Fq::from(3u8).inverse().unwrap();
+    let t = K * v;
+    Some(( s * t.inverse()?, (s - Fq::one()) * (s + Fq::one()).inverse()? ))
+}
+
+/// Short Weierstrass point from twisted Edwards coordinates with `x != 0`.
+pub(crate) fn te_to_sw(x: Fq, y: Fq) -> Option<Jubjub> {
+    let s = (Fq::one() + y) * (Fq::one() - y).inverse()?;
+    let t = s * x.inverse()?;
+    let k_inv = K.inverse().unwrap();
+    let u = (s + J * Fq::from(3u8).inverse().unwrap()) * k_inv;
+    Some(Jubjub::new_unchecked(u, t * k_inv))
+}
+
+/// Short Weierstrass form of the twisted Edwards point `(0,-1)` of order two,
+/// being the Montgomery point `(0,0)`.
+fn t2() -> Jubjub {
+    Jubjub::new_unchecked(J * (Fq::from(3u8) * K).inverse().unwrap(), Fq::zero())
+}
+
+/// Twisted Edwards form of a short Weierstrass point.
+///
+/// Returns `None` for the two other points of order two, whose twisted
+/// Edwards images lie at infinity.  All points in the prime order
+/// subgroup have affine twisted Edwards images.
+pub fn to_edwards(p: &Jubjub) -> Option<EdwardsAffine> {
+    if let Some((x,y)) = sw_to_te(p) {
+        return Some(EdwardsAffine::new_unchecked(x, y));
+    }
+    if p.is_zero() { return Some(EdwardsAffine::zero()); }
+    if *p == t2() { return Some(EdwardsAffine::new_unchecked(Fq::zero(), -Fq::one())); }
+    None
+}
+
+/// Short Weierstrass form of a twisted Edwards point.
+pub fn from_edwards(p: &EdwardsAffine) -> Jubjub {
+    if p.x.is_zero() {
+        return if p.y.is_one() { Jubjub::zero() } else { t2() };
+    }
+    te_to_sw(p.x, p.y).expect("Affine twisted Edwards points with x != 0 have y != 1")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::{CurveGroup, twisted_edwards::TECurveConfig};
+    use crate::bandersnatch::{BandersnatchConfig, Fr};
+
+    #[test]
+    fn edwards_roundtrip() {
+        assert_eq!(to_edwards(&Jubjub::generator()), Some(EdwardsAffine::generator()));
+        assert_eq!(to_edwards(&Jubjub::zero()), Some(EdwardsAffine::zero()));
+        let g = EdwardsAffine::generator();
+        for i in 1u64..8 {
+            let p = (g * Fr::from(i)).into_affine();
+            let q = from_edwards(&p);
+            assert!(q.is_on_curve());
+            assert_eq!(to_edwards(&q), Some(p));
+            assert_eq!(from_edwards(&(-p)), -q);
+        }
+        let t2 = EdwardsAffine::new_unchecked(Fq::zero(), -Fq::one());
+        assert!(from_edwards(&t2).is_on_curve());
+        assert_eq!(to_edwards(&from_edwards(&t2)), Some(t2));
+    }
+
+    #[test]
+    fn points_at_infinity() {
+        // Montgomery points `(s,0)` with `s^2 + J s + 1 = 0`, which exist
+        // because `a d` is square, even though neither `a` nor `d` are.
+        let (a, d) = (<BandersnatchConfig as TECurveConfig>::COEFF_A, <BandersnatchConfig as TECurveConfig>::COEFF_D);
+        assert!((a * d).legendre().is_qr());
+        let root = (J.square() - Fq::from(4u8)).sqrt().unwrap();
+        let two_inv = Fq::from(2u8).inverse().unwrap();
+        for s in [(-J + root) * two_inv, (-J - root) * two_inv] {
+            let u = (s + J * Fq::from(3u8).inverse().unwrap()) * K.inverse().unwrap();
+            let p = Jubjub::new_unchecked(u, Fq::zero());
+            assert!(p.is_on_curve());
+            assert_eq!(to_edwards(&p), None);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Elligator2 hash-to-curve for Bandersnatch
+//!
+//! We follow the random oracle `hash_to_curve` construction from
+//! [RFC 9380](https://www.rfc-editor.org/rfc/rfc9380.html), using
+//! `expand_message_xmd` with SHA-512 for `hash_to_field`, and the
+//! Elligator2 map of section 6.7.1 onto Bandersnatch's Montgomery form,
+//! from which we move into the short Weierstrass form used by `Jubjub`.
+//!
+//! We share the map itself with other curves via `dleq_vrf::elligator2`.
+
+use ark_ec::AffineRepr;
+use ark_ff::{Field, MontFp};
+use dleq_vrf::{Elligator2Map, Elligator2Hasher};
+use sha2::Digest;
+
+use crate::{Jubjub, VrfInput, bandersnatch::{BandersnatchConfig, Fq}};
+
+
+/// Suite identifier appended to the application's domain in our DST.
+pub const SUITE_ID: &[u8] = b"Bandersnatch_XMD:SHA-512_ELL2_RO_";
+
+/// Suite identifier appended to the application's domain in our DST
+/// for messages pre-hashed by `MessageHasher`.
+///
+/// We end differently from `SUITE_ID`, so no pre-hashed input shares
+/// its DST with any directly hashed input.
+pub const PREHASH_SUITE_ID: &[u8] = b"Bandersnatch_XMD:SHA-512_ELL2_RO_PREHASH_";
+
+/// Montgomery form coefficient `J = 2 (a + d) / (a - d)`, where `a` and `d`
+/// are the twisted Edwards coefficients.
+pub(crate) const J: Fq = MontFp!("29978822694968839326280996386011761570173833766074948509196803838190355340952");
+
+/// Montgomery form coefficient `K = 4 / (a - d)`, where `a` and `d`
+/// are the twisted Edwards coefficients.
+pub(crate) const K: Fq = MontFp!("25465760566081946422412445027709227188579564747101592991722834452325077642517");
+
+/// Non-square selected by `find_z_elligator2` from RFC 9380 appendix H.3.
+const Z: Fq = MontFp!("5");
+
+/// Elligator2 from RFC 9380 section 6.7.1, but without cofactor clearing.
+///
+/// We map onto the curve `y^2 = x^3 + (J/K) x^2 + x/K^2` using
+/// `dleq_vrf::Elligator2Map`, and then translate by `J/(3K)` into
+/// the short Weierstrass form.
+fn map_to_curve(map: &Elligator2Map<BandersnatchConfig>, shift: Fq, u: Fq) -> Jubjub {
+    let (x, y) = map.map_to_scaled_montgomery(u);
+    let p = Jubjub::new_unchecked(x + shift, y);
+    debug_assert!(p.is_on_curve());
+    p
+}
+
+/// Application domain prepared for hashing many messages to Bandersnatch.
+///
+/// We build the DST and its `expand_message_xmd` state, and invert the
+/// Elligator2 map's constants, only once per domain, so hot domains
+/// like a per-epoch ticket domain avoid repeating this work for every
+/// message.  `PreparedDomain::new(domain).hash(message)` always equals
+/// `hash_to_bandersnatch_curve(domain, message)`.
+pub struct PreparedDomain {
+    hasher: Elligator2Hasher<BandersnatchConfig, sha2::Sha512>,
+    /// Translation `J/(3K)` from our Montgomery form into short Weierstrass form.
+    shift: Fq,
+}
+
+impl PreparedDomain {
+    /// Prepare the application domain `domain`, which becomes our
+    /// DST `domain || SUITE_ID`.
+    pub fn new(domain: &[u8]) -> PreparedDomain {
+        PreparedDomain::with_suite(domain, SUITE_ID)
+    }
+
+    /// Prepare the application domain `domain` for messages pre-hashed
+    /// by `MessageHasher`, so our DST becomes `domain || PREHASH_SUITE_ID`.
+    pub fn new_prehashed(domain: &[u8]) -> PreparedDomain {
+        PreparedDomain::with_suite(domain, PREHASH_SUITE_ID)
+    }
+
+    pub(crate) fn with_suite(domain: &[u8], suite_id: &[u8]) -> PreparedDomain {
+        let map = Elligator2Map::new(Z);
+        let shift = map.j_over_k() * Fq::from(3u8).inverse().unwrap();
+        PreparedDomain { hasher: Elligator2Hasher::new(domain, suite_id, map), shift }
+    }
+
+    /// Hash `message` within this domain.
+    pub fn hash(&self, message: &[u8]) -> VrfInput {
+        let map = self.hasher.map();
+        let [u0, u1] = self.hasher.hash_to_field(message);
+        let p = map_to_curve(map, self.shift, u0).into_group()
+            + map_to_curve(map, self.shift, u1);
+        dleq_vrf::vrf::VrfInput( Jubjub::from(p).clear_cofactor() )
+    }
+}
+
+/// Streaming hash to Bandersnatch, for messages like files or large
+/// extrinsics which callers cannot or should not hold in memory.
+///
+/// We absorb chunks into SHA-512, and then hash the 64 byte digest to
+/// the curve with DST `domain || PREHASH_SUITE_ID`, so pre-hashed
+/// inputs never equal `hash_to_bandersnatch_curve` inputs, even for
+/// identical messages.  Chunk boundaries do not matter.
+#[derive(Clone)]
+pub struct MessageHasher<'a> {
+    domain: &'a [u8],
+    hasher: sha2::Sha512,
+}
+
+impl<'a> MessageHasher<'a> {
+    /// Begin hashing a message within the application domain `domain`.
+    pub fn new(domain: &'a [u8]) -> MessageHasher<'a> {
+        MessageHasher { domain, hasher: sha2::Sha512::new() }
+    }
+
+    /// Absorb the next chunk of our message.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+    }
+
+    /// Hash our whole message to the curve.
+    pub fn finalize(self) -> VrfInput {
+        let prepared = PreparedDomain::new_prehashed(self.domain);
+        self.finalize_prepared(&prepared)
+    }
+
+    /// Hash our whole message to the curve, using a domain prepared
+    /// once by `PreparedDomain::new_prehashed(domain)`.
+    pub fn finalize_prepared(self, prepared: &PreparedDomain) -> VrfInput {
+        prepared.hash(&self.hasher.finalize())
+    }
+}
+
+impl<'a> crate::IntoVrfInput<Jubjub> for MessageHasher<'a> {
+    fn into_vrf_input(self) -> VrfInput {
+        self.finalize()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> std::io::Write for MessageHasher<'a> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+}
+
+/// Our hash-to-curve as a `HashToVrfInput`, for usage like
+/// `#[vrf_input(hasher = bandersnatch_vrfs::Elligator2)]`.
+pub struct Elligator2;
+
+impl crate::vrf::HashToVrfInput for Elligator2 {
+    type Affine = Jubjub;
+
+    fn hash_to_vrf_input(domain: &[u8], message: &[u8]) -> VrfInput {
+        hash_to_bandersnatch_curve(domain, message)
+    }
+}
+
+/// Hash to Bandersnatch using Elligator2, aka the suite
+/// `Bandersnatch_XMD:SHA-512_ELL2_RO_` in the terminology of RFC 9380.
+///
+/// We use `domain || SUITE_ID` as the domain seperation tag (DST).
+/// Prefer `PreparedDomain` when hashing many messages in one domain.
+pub fn hash_to_bandersnatch_curve(domain: &[u8], message: &[u8]) -> VrfInput {
+    PreparedDomain::new(domain).hash(message)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::twisted_edwards::TECurveConfig;
+
+    #[test]
+    fn check_montgomery_coefficients() {
+        let a = <BandersnatchConfig as TECurveConfig>::COEFF_A;
+        let d = <BandersnatchConfig as TECurveConfig>::COEFF_D;
+        let a_minus_d_inv = (a - d).inverse().unwrap();
+        assert_eq!(J, (a + d).double() * a_minus_d_inv);
+        assert_eq!(K, Fq::from(4u8) * a_minus_d_inv);
+        assert!(Z.sqrt().is_none());
+    }
+
+    #[test]
+    fn edwards_map_agrees() {
+        // RFC 9380 appendix D.1 sends the point of order two, where u = 0
+        // lands, to the identity, which cofactor clearing makes harmless.
+        let map = Elligator2Map::<BandersnatchConfig>::new(Z);
+        let shift = map.j_over_k() * Fq::from(3u8).inverse().unwrap();
+        for u in [Fq::from(0u8), Fq::from(1u8), Fq::from(7u8), -Fq::from(12345u32)] {
+            let sw = crate::edwards::to_edwards(&map_to_curve(&map, shift, u)).unwrap();
+            assert_eq!(sw.clear_cofactor(), map.map_to_edwards(u).clear_cofactor());
+        }
+    }
+
+    #[test]
+    fn hash_to_curve_lands_in_subgroup() {
+        let p = hash_to_bandersnatch_curve(b"domain", b"message").0;
+        assert!(p.is_on_curve());
+        assert!(p.is_in_correct_subgroup_assuming_on_curve());
+        assert!(!p.is_zero());
+        assert_eq!(p, hash_to_bandersnatch_curve(b"domain", b"message").0);
+        assert_ne!(p, hash_to_bandersnatch_curve(b"other", b"message").0);
+        assert_ne!(p, hash_to_bandersnatch_curve(b"domain", b"other").0);
+    }
+
+    #[test]
+    fn prepared_domain_matches() {
+        let prepared = PreparedDomain::new(b"domain");
+        for message in [&b""[..], b"message", b"other"] {
+            assert_eq!(prepared.hash(message), hash_to_bandersnatch_curve(b"domain", message));
+        }
+        let long_domain = [7u8; 300];
+        assert_eq!(
+            PreparedDomain::new(&long_domain).hash(b"message"),
+            hash_to_bandersnatch_curve(&long_domain, b"message")
+        );
+    }
+
+    #[test]
+    fn streaming_message_hasher() {
+        let message = [3u8; 70_000];
+        let mut whole = MessageHasher::new(b"domain");
+        whole.update(&message);
+        let whole = whole.finalize();
+        let mut chunked = MessageHasher::new(b"domain");
+        for chunk in message.chunks(4096) {
+            chunked.update(chunk);
+        }
+        assert_eq!(chunked.finalize(), whole);
+
+        let mut prepared = MessageHasher::new(b"domain");
+        prepared.update(&message);
+        assert_eq!(prepared.finalize_prepared(&PreparedDomain::new_prehashed(b"domain")), whole);
+
+        // Pre-hashing separates from direct hashing of the same message
+        // or of its digest.
+        let digest = sha2::Sha512::digest(&message);
+        assert_ne!(whole, hash_to_bandersnatch_curve(b"domain", &message));
+        assert_ne!(whole, hash_to_bandersnatch_curve(b"domain", &digest));
+        assert_eq!(whole, PreparedDomain::new_prehashed(b"domain").hash(&digest));
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Epoch ring management
+//!
+//! Chains like Sassafras fix their ring of validator keys for a whole
+//! epoch, while validators register and deregister for the next epoch.
+//! `RingState` holds both rings:
+//!
+//! - the active ring of the current epoch, which verifiers use, and
+//! - the next epoch's ring, whose commitment we update by one
+//!   Lagrangian base per registration, so rotating epochs costs nothing.
+//!
+//! Keys keep their slots across epochs, so provers' indices only change
+//! when they re-register.  Nodes persist `RingSnapshot`s, which hold
+//! only keys, and rebuild both commitments from their cached
+//! `StaticVerifierKey` when restoring.
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::vec::Vec;
+
+use crate::{PublicKey, SignatureError};
+use crate::ring::{
+    DynamicRingCommitment, RingCommitment, RingContext, RingError, RingProver,
+    RingVerifier, StaticVerifierKey, PADDING_POINT, ring_verifier_from_commitment,
+};
+
+
+/// Active ring of the current epoch, along with the next epoch's ring.
+#[derive(Clone)]
+pub struct RingState {
+    epoch: u64,
+    domain_size: u32,
+    current: DynamicRingCommitment,
+    next: DynamicRingCommitment,
+}
+
+/// Serialized form of a `RingState`, holding every slot of both rings.
+#[derive(Clone,Debug,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct RingSnapshot {
+    pub epoch: u64,
+    pub domain_size: u32,
+    pub current: Vec<Option<PublicKey>>,
+    pub next: Vec<Option<PublicKey>>,
+}
+
+/// Reject the padding point as a ring key, since its slot would then
+/// look empty to provers using `RingState::padded_keys`.
+fn check_key(pk: &PublicKey) -> Result<(), RingError> {
+    if pk.0 == PADDING_POINT {
+        return Err(RingError::PaddingKey);
+    }
+    Ok(())
+}
+
+fn rebuild(svk: &StaticVerifierKey, domain_size: u32, keys: &[Option<PublicKey>]) -> Result<DynamicRingCommitment, RingError> {
+    let mut ring = svk.dynamic_ring_commitment(domain_size as usize) ?;
+    if keys.len() > ring.keys().len() {
+        return Err(RingError::TooManyKeys);
+    }
+    for (index, pk) in keys.iter().enumerate() {
+        if let Some(pk) = pk {
+            check_key(pk) ?;
+            ring.insert(&svk.lag_g1, index, pk.clone()) ?;
+        }
+    }
+    Ok(ring)
+}
+
+impl RingState {
+    /// Begin at `epoch` with the ring `pks`, which also begins as
+    /// the next epoch's ring.
+    pub fn new(svk: &StaticVerifierKey, domain_size: u32, epoch: u64, pks: &[PublicKey]) -> Result<RingState, RingError> {
+        let keys: Vec<_> = pks.iter().cloned().map(Some).collect();
+        let current = rebuild(svk, domain_size, &keys) ?;
+        Ok(RingState { epoch, domain_size, next: current.clone(), current })
+    }
+
+    /// Current epoch index.
+    pub fn epoch(&self) -> u64 { self.epoch }
+
+    pub fn domain_size(&self) -> u32 { self.domain_size }
+
+    /// Ring of the current epoch.
+    pub fn current(&self) -> &DynamicRingCommitment { &self.current }
+
+    /// Ring of the next epoch, as registered so far.
+    pub fn next(&self) -> &DynamicRingCommitment { &self.next }
+
+    /// Compact commitment to the current epoch's ring.
+    pub fn current_commitment(&self) -> &RingCommitment { self.current.commitment() }
+
+    /// Compact commitment to the next epoch's ring, as registered so far.
+    pub fn next_commitment(&self) -> &RingCommitment { self.next.commitment() }
+
+    /// Register `pk` for the next epoch, in the first empty slot,
+    /// returning this slot.
+    pub fn register(&mut self, svk: &StaticVerifierKey, pk: PublicKey) -> Result<usize, RingError> {
+        check_key(&pk) ?;
+        if self.next.keys().iter().flatten().any(|k| *k == pk) {
+            return Err(RingError::SlotOccupied);
+        }
+        let index = self.next.keys().iter().position(Option::is_none)
+            .ok_or(RingError::TooManyKeys) ?;
+        self.next.insert(&svk.lag_g1, index, pk) ?;
+        Ok(index)
+    }
+
+    /// Deregister `pk` from the next epoch, returning its former slot.
+    pub fn deregister(&mut self, svk: &StaticVerifierKey, pk: &PublicKey) -> Result<usize, RingError> {
+        let index = self.next.keys().iter().position(|k| k.as_ref() == Some(pk))
+            .ok_or(RingError::SlotEmpty) ?;
+        self.next.remove(&svk.lag_g1, index) ?;
+        Ok(index)
+    }
+
+    /// Begin the next epoch, whose ring we keep updating from the
+    /// new current ring.
+    pub fn rotate(&mut self) -> u64 {
+        self.current = self.next.clone();
+        self.epoch += 1;
+        self.epoch
+    }
+
+    /// Slot of `pk` in the current epoch's ring.
+    pub fn index_of(&self, pk: &PublicKey) -> Option<usize> {
+        self.current.keys().iter().position(|k| k.as_ref() == Some(pk))
+    }
+
+    /// Current epoch's ring keys, with padding in empty slots.
+    pub fn padded_keys(&self) -> Vec<crate::bandersnatch::SWAffine> {
+        self.current.padded_keys()
+    }
+
+    /// Ring prover for `pk` in the current epoch.
+    pub fn prover(&self, ctx: &RingContext, pk: &PublicKey) -> Result<RingProver, RingError> {
+        let index = self.index_of(pk).ok_or(RingError::SlotEmpty) ?;
+        Ok(ctx.init_ring_prover(ctx.prover_key(self.padded_keys()) ?, index))
+    }
+
+    /// Ring verifier for the current epoch, from only our commitment.
+    ///
+    /// We fail with `SrsMismatch` if `svk` serves some other domain,
+    /// since its verifier would reject every signature.
+    pub fn verifier(&self, svk: &StaticVerifierKey) -> Result<RingVerifier, SignatureError> {
+        if svk.lag_g1.len() != self.domain_size as usize {
+            return Err(SignatureError::SrsMismatch);
+        }
+        Ok(ring_verifier_from_commitment(self.current.commitment(), svk.kzg_vk.clone(), self.domain_size as usize))
+    }
+
+    /// Snapshot both rings for persistence.
+    pub fn snapshot(&self) -> RingSnapshot {
+        RingSnapshot {
+            epoch: self.epoch,
+            domain_size: self.domain_size,
+            current: self.current.keys().to_vec(),
+            next: self.next.keys().to_vec(),
+        }
+    }
+
+    /// Restore from a snapshot, rebuilding both commitments.
+    ///
+    /// We reject snapshots whose domain size disagrees with `svk`, or
+    /// which hold the padding point as some key.
+    pub fn restore(svk: &StaticVerifierKey, snapshot: &RingSnapshot) -> Result<RingState, RingError> {
+        Ok(RingState {
+            epoch: snapshot.epoch,
+            domain_size: snapshot.domain_size,
+            current: rebuild(svk, snapshot.domain_size, &snapshot.current) ?,
+            next: rebuild(svk, snapshot.domain_size, &snapshot.next) ?,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epoch_rotation_and_snapshots() {
+        let ctx = RingContext::testing_kzg_setup([0; 32], 1 << 9);
+        let svk = ctx.static_verifier_key();
+        let pks: Vec<_> = (0..4u8).map(|i| crate::SecretKey::from_seed(&[i; 32]).to_public()).collect();
+        let commitment = |c: &RingCommitment| (c.cx, c.cy);
+
+        let mut state = RingState::new(&svk, ctx.domain_size, 7, &pks[..3]).unwrap();
+        assert_eq!(state.register(&svk, pks[3].clone()), Ok(3));
+        assert_eq!(state.register(&svk, pks[3].clone()), Err(RingError::SlotOccupied));
+        assert_eq!(commitment(state.current_commitment()), commitment(&ctx.ring_commitment(&pks[..3])));
+        assert_eq!(commitment(state.next_commitment()), commitment(&ctx.ring_commitment(&pks)));
+        assert_eq!(state.index_of(&pks[3]), None);
+
+        assert_eq!(state.rotate(), 8);
+        assert_eq!(state.index_of(&pks[3]), Some(3));
+        assert_eq!(state.deregister(&svk, &pks[1]), Ok(1));
+        assert_eq!(state.deregister(&svk, &pks[1]), Err(RingError::SlotEmpty));
+        assert_eq!(commitment(state.current_commitment()), commitment(&ctx.ring_commitment(&pks)));
+
+        let mut bytes = Vec::new();
+        state.snapshot().serialize_compressed(&mut bytes).unwrap();
+        let snapshot = RingSnapshot::deserialize_compressed(bytes.as_slice()).unwrap();
+        let restored = RingState::restore(&svk, &snapshot).unwrap();
+        assert_eq!(restored.epoch(), 8);
+        assert_eq!(commitment(restored.current_commitment()), commitment(state.current_commitment()));
+        assert_eq!(commitment(restored.next_commitment()), commitment(state.next_commitment()));
+
+        // We reject foreign domain sizes and padding keys.
+        let mut bad = snapshot.clone();
+        bad.domain_size = 1 << 10;
+        assert_eq!(RingState::restore(&svk, &bad).err(), Some(RingError::DomainSize));
+        bad.domain_size = (1 << 9) + 1;
+        assert_eq!(RingState::restore(&svk, &bad).err(), Some(RingError::DomainSize));
+        let mut bad = snapshot.clone();
+        bad.next[1] = Some(dleq_vrf::PublicKey(PADDING_POINT));
+        assert_eq!(RingState::restore(&svk, &bad).err(), Some(RingError::PaddingKey));
+        assert_eq!(state.register(&svk, dleq_vrf::PublicKey(PADDING_POINT)), Err(RingError::PaddingKey));
+        let other = RingContext::testing_kzg_setup([0; 32], 1 << 10).static_verifier_key();
+        assert_eq!(state.verifier(&other).err(), Some(SignatureError::SrsMismatch));
+
+        // Slot 1 now holds padding, like a freshly padded ring.
+        state.rotate();
+        let mut padded = pks.clone();
+        padded[1] = dleq_vrf::PublicKey(PADDING_POINT);
+        assert_eq!(commitment(state.current_commitment()), commitment(&ctx.ring_commitment(&padded)));
+    }
+}
@@ -0,0 +1,359 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### EVM encoding of ring VRF signatures
+//!
+//! Bridges verifying bandersnatch ring VRFs on Ethereum cannot afford
+//! point decompression, nor little endian field elements, so we offer
+//! an alternate encoding of `RingVrfProof`s:
+//!
+//! - Bandersnatch points become uncompressed `x || y`, each a 32 byte
+//!   big endian element of BLS12-381's scalar field.
+//! - Bandersnatch scalars and BLS12-381 scalars become 32 bytes big endian.
+//! - BLS12-381 G1 and G2 points use EIP-2537's 128 and 256 byte encodings,
+//!   with each base field element padded to 64 bytes big endian.
+//!
+//! We map elements one by one between this encoding and our compressed
+//! encoding, following `RING_VRF_PROOF_LAYOUT`, which records the field
+//! order of the Pedersen VRF proof and then of the ring proof.
+//!
+//! We offer no EVM verifier for ring proofs themselves.  Ring-proof's
+//! PLONK verifier evaluates its constraints and aggregates its KZG
+//! openings internally, exposing neither step, so contracts must port
+//! that verifier, and off-chain code should decode and then verify via
+//! `RingVerifier`.  We only model the final pairing check such a port
+//! needs:  `pairing_check` evaluates exactly like EIP-2537's `PAIRING`
+//! precompile, given inputs from `pairing_input`, and
+//! `kzg_opening_pairing_input` builds this input for one KZG opening.
+
+use ark_ec::{AffineRepr, CurveGroup, pairing::Pairing, short_weierstrass::SWCurveConfig};
+use ark_ff::{Field, PrimeField, BigInteger, Zero, One};
+use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, SerializationError};
+use ark_std::{vec::Vec, iter};
+
+use crate::{
+    Jubjub, RingVrfProof, RingVrfSignature, VrfPreOut, VrfSignature,
+    RING_VRF_PROOF_LENGTH,
+    bandersnatch,
+    bls12_381::{self, Bls12_381, G1Affine, G2Affine},
+    ring::KzgVk,
+};
+
+
+/// Elements from which we build our proofs.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Element {
+    /// Bandersnatch point, 33 bytes compressed, 64 bytes in EVM encoding.
+    Point,
+    /// Bandersnatch scalar, 32 bytes in either encoding.
+    Scalar,
+    /// BLS12-381 G1 point, 48 bytes compressed, 128 bytes in EVM encoding.
+    G1,
+    /// BLS12-381 scalar, 32 bytes in either encoding.
+    Fr,
+}
+
+impl Element {
+    pub const fn compressed_length(self) -> usize {
+        match self {
+            Element::Point => 33,
+            Element::Scalar | Element::Fr => 32,
+            Element::G1 => 48,
+        }
+    }
+
+    pub const fn evm_length(self) -> usize {
+        match self {
+            Element::Point => 64,
+            Element::Scalar | Element::Fr => 32,
+            Element::G1 => 128,
+        }
+    }
+}
+
+use Element::*;
+
+/// Field order of a compressed `RingVrfProof`.
+///
+/// Its Pedersen VRF proof holds the key commitment, the keying and
+/// blinding responses, and then both nonce commitments.  Its ring proof
+/// holds four column commitments, seven column evaluations at zeta,
+/// the quotient commitment, the linearization evaluation at zeta*omega,
+/// and then both KZG opening proofs.
+pub const RING_VRF_PROOF_LAYOUT: [Element; 20] = [
+    Point, Scalar, Scalar, Point, Point,
+    G1, G1, G1, G1,
+    Fr, Fr, Fr, Fr, Fr, Fr, Fr,
+    G1, Fr, G1, G1,
+];
+
+const fn evm_length(layout: &[Element]) -> usize {
+    let mut l = 0;
+    let mut i = 0;
+    while i < layout.len() {
+        l += layout[i].evm_length();
+        i += 1;
+    }
+    l
+}
+
+/// EVM encoded length of a `RingVrfProof`.
+pub const EVM_RING_VRF_PROOF_LENGTH: usize = evm_length(&RING_VRF_PROOF_LAYOUT);
+
+/// EVM encoded length of a `RingVrfSignature<N>`, being the proof
+/// followed by `N` pre-outputs.
+pub const fn evm_ring_vrf_signature_length(n: usize) -> usize {
+    EVM_RING_VRF_PROOF_LENGTH + n * Point.evm_length()
+}
+
+
+/// Append `f` as `len` bytes big endian.
+fn write_be<F: PrimeField>(f: &F, len: usize, out: &mut Vec<u8>) {
+    let be = f.into_bigint().to_bytes_be();
+    out.extend(iter::repeat(0u8).take(len - be.len()));
+    out.extend(be);
+}
+
+/// Read a canonical field element from `len` bytes big endian.
+fn read_be<F: PrimeField>(bytes: &mut &[u8], len: usize) -> Result<F, SerializationError> {
+    if bytes.len() < len { return Err(SerializationError::InvalidData); }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    let size = F::zero().compressed_size();
+    let (padding, be) = head.split_at(len - size);
+    if padding.iter().any(|b| *b != 0) { return Err(SerializationError::InvalidData); }
+    let mut le = be.to_vec();
+    le.reverse();
+    F::deserialize_compressed(le.as_slice())
+}
+
+/// Check a decoded point like checked deserialization would.
+fn checked<P: SWCurveConfig>(p: ark_ec::short_weierstrass::Affine<P>) -> Result<ark_ec::short_weierstrass::Affine<P>, SerializationError> {
+    if p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve() {
+        Ok(p)
+    } else {
+        Err(SerializationError::InvalidData)
+    }
+}
+
+fn write_point(p: &Jubjub, out: &mut Vec<u8>) {
+    let (x, y) = p.xy().unwrap_or((&bandersnatch::Fq::ZERO, &bandersnatch::Fq::ZERO));
+    write_be(x, 32, out);
+    write_be(y, 32, out);
+}
+
+fn read_point(bytes: &mut &[u8]) -> Result<Jubjub, SerializationError> {
+    let x: bandersnatch::Fq = read_be(bytes, 32) ?;
+    let y: bandersnatch::Fq = read_be(bytes, 32) ?;
+    if x.is_zero() && y.is_zero() { return Ok(Jubjub::zero()); }
+    checked(Jubjub::new_unchecked(x, y))
+}
+
+/// EIP-2537 encoding of a G1 point, with the identity being all zeros.
+pub fn encode_g1(p: &G1Affine, out: &mut Vec<u8>) {
+    let (x, y) = p.xy().unwrap_or((&bls12_381::Fq::ZERO, &bls12_381::Fq::ZERO));
+    write_be(x, 64, out);
+    write_be(y, 64, out);
+}
+
+/// EIP-2537 decoding of a G1 point, including its subgroup check.
+pub fn decode_g1(bytes: &mut &[u8]) -> Result<G1Affine, SerializationError> {
+    let x: bls12_381::Fq = read_be(bytes, 64) ?;
+    let y: bls12_381::Fq = read_be(bytes, 64) ?;
+    if x.is_zero() && y.is_zero() { return Ok(G1Affine::zero()); }
+    checked(G1Affine::new_unchecked(x, y))
+}
+
+/// EIP-2537 encoding of a G2 point, with `c0` preceding `c1`.
+pub fn encode_g2(p: &G2Affine, out: &mut Vec<u8>) {
+    let zero = bls12_381::Fq2::ZERO;
+    let (x, y) = p.xy().unwrap_or((&zero, &zero));
+    for c in [x.c0, x.c1, y.c0, y.c1] {
+        write_be(&c, 64, out);
+    }
+}
+
+/// EIP-2537 decoding of a G2 point, including its subgroup check.
+pub fn decode_g2(bytes: &mut &[u8]) -> Result<G2Affine, SerializationError> {
+    let mut c = [bls12_381::Fq::ZERO; 4];
+    for ci in c.iter_mut() {
+        *ci = read_be(bytes, 64) ?;
+    }
+    let x = bls12_381::Fq2::new(c[0], c[1]);
+    let y = bls12_381::Fq2::new(c[2], c[3]);
+    if x.is_zero() && y.is_zero() { return Ok(G2Affine::zero()); }
+    checked(G2Affine::new_unchecked(x, y))
+}
+
+/// Convert one compressed element into its EVM encoding.
+fn compressed_to_evm(e: Element, compressed: &mut &[u8], out: &mut Vec<u8>) -> Result<(), SerializationError> {
+    match e {
+        Point => write_point(&Jubjub::deserialize_compressed(&mut *compressed) ?, out),
+        Scalar => write_be(&bandersnatch::Fr::deserialize_compressed(&mut *compressed) ?, 32, out),
+        G1 => encode_g1(&G1Affine::deserialize_compressed(&mut *compressed) ?, out),
+        Fr => write_be(&bls12_381::Fr::deserialize_compressed(&mut *compressed) ?, 32, out),
+    }
+    Ok(())
+}
+
+/// Convert one EVM encoded element into its compressed encoding.
+fn evm_to_compressed(e: Element, evm: &mut &[u8], out: &mut Vec<u8>) -> Result<(), SerializationError> {
+    match e {
+        Point => read_point(evm)?.serialize_compressed(out),
+        Scalar => read_be::<bandersnatch::Fr>(evm, 32)?.serialize_compressed(out),
+        G1 => decode_g1(evm)?.serialize_compressed(out),
+        Fr => read_be::<bls12_381::Fr>(evm, 32)?.serialize_compressed(out),
+    }
+}
+
+/// EVM encoding of a `RingVrfProof`, failing only if the proof holds
+/// points our compressed encoding cannot represent.
+pub fn encode_ring_vrf_proof(proof: &RingVrfProof, out: &mut Vec<u8>) -> Result<(), SerializationError> {
+    let mut compressed = Vec::with_capacity(RING_VRF_PROOF_LENGTH);
+    proof.serialize_compressed(&mut compressed) ?;
+    let mut compressed = compressed.as_slice();
+    for e in RING_VRF_PROOF_LAYOUT {
+        compressed_to_evm(e, &mut compressed, out) ?;
+    }
+    if !compressed.is_empty() { return Err(SerializationError::InvalidData); }
+    Ok(())
+}
+
+/// Decode an EVM encoded `RingVrfProof`, with all the checks of
+/// checked deserialization.
+pub fn decode_ring_vrf_proof(evm: &mut &[u8]) -> Result<RingVrfProof, SerializationError> {
+    let mut compressed = Vec::with_capacity(RING_VRF_PROOF_LENGTH);
+    for e in RING_VRF_PROOF_LAYOUT {
+        evm_to_compressed(e, evm, &mut compressed) ?;
+    }
+    RingVrfProof::deserialize_compressed(compressed.as_slice())
+}
+
+/// EVM encoding of a `RingVrfSignature<N>`.
+pub fn encode_ring_vrf_signature<const N: usize>(signature: &RingVrfSignature<N>) -> Result<Vec<u8>, SerializationError> {
+    let mut out = Vec::with_capacity(evm_ring_vrf_signature_length(N));
+    encode_ring_vrf_proof(&signature.proof, &mut out) ?;
+    for preout in signature.preouts.iter() {
+        write_point(&preout.0, &mut out);
+    }
+    Ok(out)
+}
+
+/// Decode an EVM encoded `RingVrfSignature<N>`, rejecting trailing bytes.
+pub fn decode_ring_vrf_signature<const N: usize>(mut evm: &[u8]) -> Result<RingVrfSignature<N>, SerializationError> {
+    let proof = decode_ring_vrf_proof(&mut evm) ?;
+    let preouts = (0..N).map(|_| read_point(&mut evm).map(dleq_vrf::vrf::VrfPreOut))
+        .collect::<Result<Vec<VrfPreOut>,_>>() ?;
+    if !evm.is_empty() { return Err(SerializationError::InvalidData); }
+    let preouts = preouts.try_into().map_err(|_| SerializationError::InvalidData) ?;
+    Ok(VrfSignature { proof, preouts })
+}
+
+
+/// Input for EIP-2537's `PAIRING` precompile, checking the product
+/// of the pairings of `pairs` equals one.
+pub fn pairing_input(pairs: &[(G1Affine, G2Affine)]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(pairs.len() * 384);
+    for (g1, g2) in pairs {
+        encode_g1(g1, &mut out);
+        encode_g2(g2, &mut out);
+    }
+    out
+}
+
+/// Reference EIP-2537 `PAIRING` precompile, which fails upon malformed
+/// inputs, and otherwise returns whether the pairing product equals one.
+pub fn pairing_check(mut input: &[u8]) -> Result<bool, SerializationError> {
+    if input.is_empty() || input.len() % 384 != 0 {
+        return Err(SerializationError::InvalidData);
+    }
+    let mut g1s = Vec::with_capacity(input.len() / 384);
+    let mut g2s = Vec::with_capacity(input.len() / 384);
+    while !input.is_empty() {
+        g1s.push(decode_g1(&mut input) ?);
+        g2s.push(decode_g2(&mut input) ?);
+    }
+    Ok(Bls12_381::multi_pairing(g1s, g2s).0.is_one())
+}
+
+/// `pairing_check` input for the KZG opening `proof` that the polynomial
+/// committed by `commitment` evaluates to `value` at `point`.
+///
+/// We check `e(C - v G + z P, H) e(-P, tau H) = 1`, which needs only
+/// one G1 multi-scalar multiplication, and one two pair `PAIRING` call.
+pub fn kzg_opening_pairing_input(
+    vk: &KzgVk,
+    commitment: G1Affine,
+    point: bls12_381::Fr,
+    value: bls12_381::Fr,
+    proof: G1Affine,
+) -> Vec<u8> {
+    let lhs = commitment.into_group() - vk.g1 * value + proof * point;
+    pairing_input(&[
+        (lhs.into_affine(), vk.g2),
+        ((-proof.into_group()).into_affine(), vk.tau_in_g2),
+    ])
+}
+
+
+#[cfg(all(test, feature = "getrandom"))]
+mod tests {
+    use super::*;
+    use crate::{SecretKey, Message, RingProver, RingVerifier, ring, IntoVrfInput};
+
+    #[test]
+    fn layout_matches_compressed_length() {
+        let compressed: usize = RING_VRF_PROOF_LAYOUT.iter().map(|e| e.compressed_length()).sum();
+        assert_eq!(compressed, RING_VRF_PROOF_LENGTH);
+    }
+
+    #[test]
+    fn ring_signature_roundtrip() {
+        let secret = & SecretKey::from_seed(&[0; 32]);
+        let ctx = ring::RingContext::testing_kzg_setup([0; 32], 2u32.pow(9));
+        let pks: Vec<_> = (1..4u8).map(|i| SecretKey::from_seed(&[i; 32]).to_public())
+            .chain(iter::once(secret.to_public()))
+            .collect();
+        let ring_prover = ctx.ring_prover_key(&pks).unwrap().into_ring_prover(pks.len() - 1);
+        let ring_verifier = ctx.ring_verifier_key(&pks).unwrap().into_ring_verifier();
+
+        let input = Message { domain: b"domain", message: b"evm" }.into_vrf_input();
+        let io = secret.vrf_inout(input);
+        let signature: RingVrfSignature<1> = RingProver {
+            ring_prover: &ring_prover, secret,
+        }.sign_ring_vrf(b"bridge", &[io]);
+
+        let evm = encode_ring_vrf_signature(&signature).unwrap();
+        assert_eq!(evm.len(), evm_ring_vrf_signature_length(1));
+        let decoded = decode_ring_vrf_signature::<1>(&evm).unwrap();
+        RingVerifier(&ring_verifier)
+        .verify_ring_vrf(b"bridge", iter::once(input), &decoded).unwrap();
+        assert_eq!(encode_ring_vrf_signature(&decoded).unwrap(), evm);
+
+        let mut bad = evm.clone();
+        bad[0] = 0xff;
+        assert!(decode_ring_vrf_signature::<1>(&bad).is_err());
+        assert!(decode_ring_vrf_signature::<1>(&evm[..evm.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn kzg_opening_pairing() {
+        use ark_ff::UniformRand;
+        let mut rng = ark_std::test_rng();
+        let tau = bls12_381::Fr::rand(&mut rng);
+        let vk = KzgVk {
+            g1: G1Affine::generator(),
+            g2: G2Affine::generator(),
+            tau_in_g2: (G2Affine::generator() * tau).into_affine(),
+        };
+        // Commit to p(x) = a + b x, whose opening proof is b G at any point.
+        let (a, b, z) = (bls12_381::Fr::from(3u64), bls12_381::Fr::from(5u64), bls12_381::Fr::from(7u64));
+        let commitment = (vk.g1 * (a + b * tau)).into_affine();
+        let proof = (vk.g1 * b).into_affine();
+        let input = kzg_opening_pairing_input(&vk, commitment, z, a + b * z, proof);
+        assert_eq!(input.len(), 2 * 384);
+        assert!(pairing_check(&input).unwrap());
+        let input = kzg_opening_pairing_input(&vk, commitment, z, a + b * z + bls12_381::Fr::one(), proof);
+        assert!(!pairing_check(&input).unwrap());
+        assert!(pairing_check(&input[1..]).is_err());
+    }
+}
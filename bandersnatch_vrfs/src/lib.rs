@@ -5,13 +5,33 @@
 #![doc = include_str!("../README.md")]
 
 pub mod ring;
+pub mod epoch;
 pub mod zcash_consts;
+pub mod sassafras;
+pub mod backend;
+pub mod one_of_many;
+pub mod bytes;
+pub mod edwards;
+
+mod elligator2;
+pub use elligator2::{hash_to_bandersnatch_curve, PreparedDomain, MessageHasher, Elligator2};
+
+#[cfg(feature = "banderwagon")]
+pub mod banderwagon;
+
+#[cfg(feature = "testvectors")]
+pub mod testvectors;
+
+#[cfg(feature = "spec-conformance")]
+pub mod spec_conformance;
+
+#[cfg(feature = "srs")]
+pub mod srs;
+
+#[cfg(feature = "evm")]
+pub mod evm;
 
 use ark_ff::MontFp;
-use ark_ec::{
-    AffineRepr, CurveGroup,
-    // hashing::{HashToCurveError, curve_maps, map_to_curve_hasher::MapToCurveBasedHasher, HashToCurve},
-};
 use ark_std::vec::Vec;   // io::{Read, Write}
 
 pub use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, SerializationError, Compress};
@@ -33,53 +53,98 @@ pub use curves::*;
 // Conversion discussed in https://github.com/arkworks-rs/curves/pull/76#issuecomment-929121470
 
 pub use dleq_vrf::{
-    Transcript, IntoTranscript, transcript,
+    Transcript, IntoTranscript, AssociatedData, SigningContext, transcript,
     error::{SignatureResult, SignatureError},
     vrf::{self, IntoVrfInput},
     EcVrfSecret,EcVrfSigner,EcVrfVerifier,
     VrfSignature,VrfSignatureVec,
+    MsmBackend,ArkworksMsm,
+    deserialize_canonical,
     scale,
 };
 
+#[cfg(feature = "arbitrary")]
+pub use dleq_vrf::fuzzing;
+
+/// `#[derive(VrfInput)]`, which hashes protocol structs into VRF inputs
+/// when used with `#[vrf_input(hasher = bandersnatch_vrfs::Elligator2, crate = "bandersnatch_vrfs")]`.
+#[cfg(feature = "derive")]
+pub mod derive {
+    pub use dleq_vrf::VrfInput;
+}
+
 use bandersnatch::SWAffine as Jubjub;
 
 pub type VrfInput = dleq_vrf::vrf::VrfInput<Jubjub>;
 pub type VrfPreOut = dleq_vrf::vrf::VrfPreOut<Jubjub>;
 pub type VrfInOut = dleq_vrf::vrf::VrfInOut<Jubjub>;
 
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
 pub struct Message<'a> {
     pub domain: &'a [u8],
     pub message: &'a [u8],
 }
 
-/*
-type H2C = MapToCurveBasedHasher::<
-G1Projective,
-    DefaultFieldHasher<sha2::Sha256>,
-    curve_maps::wb::WBMap<curve::g1::Config>,
->;
-
-pub fn hash_to_bandersnatch_curve(domain: &[u8],message: &[u8]) -> Result<VrfInput,HashToCurveError> {
-    dleq_vrf::vrf::ark_hash_to_curve::<Jubjub,H2C>(domain,message)
+/// Hash a `Message` to the curve using Elligator2, with `domain` as
+/// the application's portion of the IRTF domain seperation tag.
+impl<'a> IntoVrfInput<Jubjub> for Message<'a> {
+    fn into_vrf_input(self) -> VrfInput {
+        hash_to_bandersnatch_curve(self.domain,self.message)
+    }
 }
-*/
 
-impl<'a> IntoVrfInput<Jubjub> for Message<'a> {
+impl<'a> IntoVrfInput<Jubjub> for &Message<'a> {
     fn into_vrf_input(self) -> VrfInput {
-        // TODO: Add Elligator to Arkworks
-        // hash_to_bandersnatch_curve(self.domain,self.message)
-        // .expect("Hash-to-curve error, IRTF spec forbids messages longer than 2^16!")
-        let label = b"TemporaryDoNotDeploy".as_ref();
-        let mut t = Transcript::new_labeled(label);
-        t.label(b"domain");
-        t.append(self.domain);
-        t.label(b"message");
-        t.append(self.message);
-        let p: <Jubjub as AffineRepr>::Group = t.challenge(b"vrf-input").read_uniform();
-        vrf::VrfInput( p.into_affine() )
+        (*self).into_vrf_input()
     }
 }
 
+/// `Message` from a `(domain, message)` pair.
+///
+/// Orphan rules forbid implementing `IntoVrfInput<Jubjub>` for tuples
+/// here, so write `Message::from((domain, message))` instead.
+impl<'a> From<(&'a [u8], &'a [u8])> for Message<'a> {
+    fn from((domain, message): (&'a [u8], &'a [u8])) -> Message<'a> {
+        Message { domain, message }
+    }
+}
+
+/// `Message` from a `(domain, message)` pair of strings, whose UTF-8
+/// bytes we hash exactly like byte slices.
+impl<'a> From<(&'a str, &'a str)> for Message<'a> {
+    fn from((domain, message): (&'a str, &'a str)) -> Message<'a> {
+        Message { domain: domain.as_bytes(), message: message.as_bytes() }
+    }
+}
+
+/// Application domain for hashing `Message`s to the curve, so call
+/// sites name their domain once, like in a `const`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct Domain<'a>(pub &'a [u8]);
+
+impl<'a> Domain<'a> {
+    /// `Message` within this domain.
+    pub fn message(self, message: &'a [u8]) -> Message<'a> {
+        Message { domain: self.0, message }
+    }
+
+    /// VRF input for `message` within this domain.
+    pub fn input(self, message: &[u8]) -> VrfInput {
+        hash_to_bandersnatch_curve(self.0, message)
+    }
+
+    /// Prepare this domain for hashing many messages.
+    pub fn prepare(self) -> PreparedDomain {
+        PreparedDomain::new(self.0)
+    }
+}
+
+/// Blinding base for Pedersen VRF key commitments.
+/// 
+/// We derive this nothing-up-my-sleeve point by sampling uniformly
+/// from a `Transcript` labeled `b"Bandersnatch VRF blinding base"`,
+/// so nobody knows its discrete logarithm with respect to the keying
+/// base.  Our `check_blinding_base` test reproduces this derivation.
 pub const BLINDING_BASE: Jubjub = {
     const X: bandersnatch::Fq = MontFp!("4956610287995045830459834427365747411162584416641336688940534788579455781570");
     const Y: bandersnatch::Fq = MontFp!("52360910621642801549936840538960627498114783432181489929217988668068368626761");
@@ -96,17 +161,113 @@ pub fn thin_vrf() -> ThinVrf {
 
 type PedersenVrf = dleq_vrf::PedersenVrf<Jubjub>;
 
-/// Pedersen VRF configured by the G1 generator for public key certs.
+/// Pedersen VRF configured by the G1 generator for public key certs,
+/// and blinded by `BLINDING_BASE`.
 pub fn pedersen_vrf() -> PedersenVrf {
     thin_vrf().pedersen_vrf([ BLINDING_BASE ])
 }
 
+/// Pedersen VRF blinded by `B` bases, whose key commitments hide `B`
+/// scalars alongside the public key.
+///
+/// We envision multi-attribute key commitments here:  A signer puts a
+/// random blinding first, and attributes like a credential expiry or a
+/// membership tier after, so the key commitment hides the public key
+/// and all attributes.  `SecretBlinding::verify` or `KeyCommitmentOpening`
+/// later open the commitment to all of them at once.
+///
+/// Attributes themselves are only hidden by the first random blinding,
+/// so never sign with low entropy values in every slot.
+pub type MultiPedersenVrf<const B: usize> = dleq_vrf::PedersenVrf<Jubjub,Jubjub,B>;
+
+/// Pedersen VRF proof with `B` blinding bases, whose compressed
+/// encoding consists of the extended key commitment, two curve points,
+/// the keying response, and `B` blinding responses, so has length
+/// `pedersen_vrf_proof_length(B)`.
+pub type MultiPedersenVrfProof<const B: usize> = dleq_vrf::Batchable<MultiPedersenVrf<B>>;
+
+/// Blindings, and maybe attributes, for the key commitment inside
+/// a `MultiPedersenVrfProof<B>`.
+pub type MultiSecretBlinding<const B: usize> = dleq_vrf::SecretBlinding<Jubjub,B>;
+
+/// Blinding bases for `MultiPedersenVrf<B>`, starting with `BLINDING_BASE`.
+///
+/// We derive the others like `BLINDING_BASE`, by sampling uniformly
+/// from a `Transcript` labeled `b"Bandersnatch VRF blinding bases"`
+/// into which we append the index, so nobody knows any discrete
+/// logarithms between them.
+pub fn blinding_bases<const B: usize>() -> [Jubjub; B] {
+    use ark_ec::{AffineRepr, CurveGroup};
+    core::array::from_fn(|i| if i == 0 { BLINDING_BASE } else {
+        let mut t = Transcript::new_labeled(b"Bandersnatch VRF blinding bases");
+        t.append_u64(i as u64);
+        let p: <Jubjub as AffineRepr>::Group = t.challenge(b"vrf-input").read_uniform();
+        p.into_affine()
+    })
+}
+
+/// Pedersen VRF configured by the G1 generator for public key certs,
+/// and blinded by `blinding_bases::<B>()`, so `multi_pedersen_vrf::<1>()`
+/// equals `pedersen_vrf()`.
+pub fn multi_pedersen_vrf<const B: usize>() -> MultiPedersenVrf<B> {
+    thin_vrf().pedersen_vrf(blinding_bases())
+}
+
 
+/// Bandersnatch VRF secret key.
+///
+/// Without `getrandom`, like in enclaves or on microcontrollers, create
+/// keys using `SecretKey::from_rng` or `SecretKey::from_seed_with_rng`,
+/// which also split the secret scalar using the supplied RNG.  Signing
+/// then defaults to `NonceStrategy::Deterministic`.
 pub type SecretKey = dleq_vrf::SecretKey<Jubjub>;
 
+/// Compressed length of a `PublicKey`, which includes flag bits.
 pub const PUBLIC_KEY_LENGTH: usize = 33;
 pub type PublicKeyBytes = [u8; PUBLIC_KEY_LENGTH];
 
+/// Compressed length of a `VrfPreOut`, exactly like `PublicKey`.
+pub const PREOUT_LENGTH: usize = 33;
+
+/// Compressed length of a thin VRF proof, being one curve point and one scalar.
+pub const THIN_VRF_PROOF_LENGTH: usize = 33 + 32;
+
+/// Compressed length of a `ThinVrfSignature<N>`, for usage like
+/// `signature.to_bytes::<{ thin_vrf_signature_length(N) }>()`.
+pub const fn thin_vrf_signature_length(n: usize) -> usize {
+    THIN_VRF_PROOF_LENGTH + n * PREOUT_LENGTH
+}
+
+/// Compressed length of a Pedersen VRF proof, being the key commitment,
+/// two curve points, and two scalars.
+pub const PEDERSEN_VRF_PROOF_LENGTH: usize = pedersen_vrf_proof_length(1);
+
+/// Compressed length of a `MultiPedersenVrfProof<B>`, which holds one
+/// scalar per blinding base beyond `PEDERSEN_VRF_PROOF_LENGTH`'s one.
+pub const fn pedersen_vrf_proof_length(b: usize) -> usize {
+    33 + 2 * 33 + (1 + b) * 32
+}
+
+/// Compressed length of a `ring::RingProof`, being seven BLS12-381 G1
+/// points and eight scalars, regardless of the ring size.
+pub const RING_PROOF_LENGTH: usize = 7 * 48 + 8 * 32;
+
+/// Compressed length of a `RingVrfProof`.
+pub const RING_VRF_PROOF_LENGTH: usize = PEDERSEN_VRF_PROOF_LENGTH + RING_PROOF_LENGTH;
+
+/// Compressed length of a non-batchable Pedersen VRF proof, being the
+/// key commitment, the keying and blinding responses, and the challenge.
+pub const COMPACT_PEDERSEN_VRF_PROOF_LENGTH: usize = 33 + 3 * 32;
+
+/// Compressed length of a `CompactRingVrfProof`.
+pub const COMPACT_RING_VRF_PROOF_LENGTH: usize = COMPACT_PEDERSEN_VRF_PROOF_LENGTH + RING_PROOF_LENGTH;
+
+/// Compressed length of a `RingVrfSignature<N>`, so runtimes could
+/// allocate fixed storage and weight verification.
+pub const fn ring_vrf_signature_length(n: usize) -> usize {
+    RING_VRF_PROOF_LENGTH + n * PREOUT_LENGTH
+}
+
 pub type PublicKey = dleq_vrf::PublicKey<Jubjub>;
 
 pub fn serialize_publickey(pk: &PublicKey) -> PublicKeyBytes {
@@ -125,40 +286,262 @@ type ThinVrfProof = dleq_vrf::Batchable<ThinVrf>;
 
 pub type ThinVrfSignature<const N: usize> = dleq_vrf::VrfSignature<ThinVrfProof,N>;
 
+/// Thin VRF signature with a runtime number of pre-outputs.
+pub type ThinVrfSignatureVec = dleq_vrf::VrfSignatureVec<ThinVrfProof>;
+
+/// Proof-of-possession of a secret key, which rings should demand
+/// before admitting public keys.
+pub type ProofOfPossession = dleq_vrf::ProofOfPossession<Jubjub>;
+
+/// Plain Schnorr signature, by `SecretKey::sign_message`.
+pub type Signature = dleq_vrf::SchnorrSignature<Jubjub>;
+
+/// Compressed length of a `Signature`, exactly like a thin VRF proof.
+pub const SIGNATURE_LENGTH: usize = THIN_VRF_PROOF_LENGTH;
+
+/// Batch verify thin VRF signatures by one multi-scalar multiplication.
+/// 
+/// See `dleq_vrf::ThinVrf::batch_verify_thin_vrf`, which this wraps.
+pub fn batch_verify_thin<'a,T,I,const N: usize>(
+    batch: impl IntoIterator<Item = (T, I, &'a PublicKey, &'a ThinVrfSignature<N>)>
+) -> SignatureResult<()>
+where T: IntoTranscript, I: IntoIterator, <I as IntoIterator>::Item: IntoVrfInput<Jubjub>,
+{
+    let batch = batch.into_iter().map(|(t,inputs,public,signature)| {
+        Ok((t, signature.attach_inputs(inputs) ?, public, &signature.proof))
+    }).collect::<SignatureResult<Vec<_>>>() ?;
+    thin_vrf().batch_verify_thin_vrf(batch)
+}
+
+/// Batch verify thin VRF signatures with runtime numbers of pre-outputs,
+/// failing with `WrongInputCount` if any inputs iterator yields more or
+/// fewer inputs than its signature has pre-outputs.
+pub fn batch_verify_thin_vec<'a,T,I>(
+    batch: impl IntoIterator<Item = (T, I, &'a PublicKey, &'a ThinVrfSignatureVec)>
+) -> SignatureResult<()>
+where T: IntoTranscript, I: IntoIterator, <I as IntoIterator>::Item: IntoVrfInput<Jubjub>,
+{
+    let batch = batch.into_iter().map(|(t,inputs,public,signature)| {
+        Ok((t, signature.attach_inputs(inputs) ?, public, &signature.proof))
+    }).collect::<SignatureResult<Vec<_>>>() ?;
+    thin_vrf().batch_verify_thin_vrf(batch)
+}
+
+
+/// ECVRF cipher suite `Bandersnatch_SHA-512_ELL2` from the Bandersnatch
+/// VRF specification, whose inputs should come from `encode_to_curve`.
+///
+/// We encode points in twisted Edwards form, and take the full 32 byte
+/// challenge, which the specification reads as a big endian integer.
+/// Like the specification, we hash `Gamma` into the output without
+/// multiplying by the cofactor.
+///
+/// Points of order two lacking affine twisted Edwards forms encode like
+/// the identity, but RFC 9381 excludes them from inputs and public keys,
+/// and our decoding never yields them.
+pub struct BandersnatchSha512Ell2;
+
+impl BandersnatchSha512Ell2 {
+    /// ECVRF `encode_to_curve` from RFC 9381 section 5.4.1.2, using
+    /// our Elligator2 with DST `"ECVRF_" || elligator2::SUITE_ID || suite_string`.
+    pub fn encode_to_curve(alpha: &[u8]) -> VrfInput {
+        use dleq_vrf::ietf::Suite;
+        let suite_id = [elligator2::SUITE_ID, Self::SUITE_ID].concat();
+        PreparedDomain::with_suite(b"ECVRF_", &suite_id).hash(alpha)
+    }
+}
+
+impl dleq_vrf::ietf::Suite for BandersnatchSha512Ell2 {
+    type Affine = Jubjub;
+    type Hasher = sha2::Sha512;
+    const SUITE_ID: &'static [u8] = b"Bandersnatch_SHA-512_ELL2";
+    const CHALLENGE_LENGTH: usize = 32;
+    const OUTPUT_CLEARS_COFACTOR: bool = false;
+
+    fn point_to_string(p: &Jubjub, buf: &mut Vec<u8>) {
+        edwards::to_edwards(p).unwrap_or_default()
+            .serialize_compressed(buf).expect("Vec serialization is infallible");
+    }
+
+    fn string_to_point(bytes: &mut &[u8]) -> Result<Jubjub, SerializationError> {
+        let p = bandersnatch::EdwardsAffine::deserialize_compressed(bytes) ?;
+        Ok(edwards::from_edwards(&p))
+    }
+
+    fn challenge_to_scalar(c: &[u8]) -> bandersnatch::Fr {
+        use ark_ff::PrimeField;
+        bandersnatch::Fr::from_be_bytes_mod_order(c)
+    }
+}
+
+/// RFC 9381 style ECVRF proof, see `dleq_vrf::ietf`.
+pub type IetfProof = dleq_vrf::ietf::Proof<BandersnatchSha512Ell2>;
+
+/// Serialized length of an `IetfProof`, being `Gamma || c || s`.
+pub const IETF_PROOF_LENGTH: usize = 32 + 32 + 32;
+
 
 type PedersenVrfProof = dleq_vrf::Batchable<PedersenVrf>;
 
-#[derive(Clone,CanonicalSerialize,CanonicalDeserialize)]
-pub struct RingVrfProof {
+/// Ring VRF proof, being a Pedersen VRF proof along with a ring proof
+/// that its key commitment opens to some public key in the ring.
+///
+/// We default to KZG based ring proofs, but `backend::RingProofBackend`s
+/// could supply other ring proofs `P`.
+#[derive(Clone)]
+pub struct RingVrfProof<P = ring::RingProof> {
     pub dleq_proof: PedersenVrfProof,
-    pub ring_proof: ring::RingProof,
+    pub ring_proof: P,
+}
+
+// Arkworks' derives add no bounds on `P`, so we implement these by hand.
+impl<P: CanonicalSerialize> CanonicalSerialize for RingVrfProof<P> {
+    fn serialize_with_mode<W: ark_serialize::Write>(
+        &self,
+        mut writer: W,
+        compress: Compress
+    ) -> Result<(), SerializationError>
+    {
+        self.dleq_proof.serialize_with_mode(&mut writer, compress) ?;
+        self.ring_proof.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.dleq_proof.serialized_size(compress)
+        + self.ring_proof.serialized_size(compress)
+    }
+}
+
+impl<P: ark_serialize::Valid> ark_serialize::Valid for RingVrfProof<P> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.dleq_proof.check() ?;
+        self.ring_proof.check()
+    }
 }
 
-impl dleq_vrf::EcVrfProof for RingVrfProof {
+impl<P: CanonicalDeserialize> CanonicalDeserialize for RingVrfProof<P> {
+    fn deserialize_with_mode<R: ark_serialize::Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: ark_serialize::Validate
+    ) -> Result<Self, SerializationError>
+    {
+        let dleq_proof = PedersenVrfProof::deserialize_with_mode(&mut reader, compress, validate) ?;
+        let ring_proof = P::deserialize_with_mode(&mut reader, compress, validate) ?;
+        Ok(RingVrfProof { dleq_proof, ring_proof })
+    }
+}
+
+impl<P> dleq_vrf::EcVrfProof for RingVrfProof<P>
+where P: Clone+CanonicalSerialize+CanonicalDeserialize+'static
+{
     type H = Jubjub;
 }
 
+/// Blinding factor for the key commitment inside a `RingVrfProof`.
+pub type SecretBlinding = dleq_vrf::SecretBlinding<Jubjub,1>;
+
+/// Proof that a `RingVrfProof` was created by a specific public key.
+pub type RingVrfReveal = dleq_vrf::KeyCommitmentOpening<Jubjub,1>;
+
+impl RingVrfProof {
+    /// Prove that `public` created this ring VRF signature, given the
+    /// `SecretBlinding` from `RingProver::sign_ring_vrf_revealable`.
+    /// 
+    /// We bind this signature's Pedersen VRF proof into the transcript,
+    /// so the reveal never applies to other signatures.  Anyone could
+    /// verify this by `verify_reveal`, but the ring VRF signature remains
+    /// anonymous without it.
+    pub fn reveal(&self, t: impl IntoTranscript, secret_blinding: &SecretBlinding, public: &PublicKey) -> RingVrfReveal {
+        let t = self.reveal_transcript(t);
+        pedersen_vrf().prove_key_commitment_opening(t, self.dleq_proof.as_key_commitment(), public, secret_blinding)
+    }
+
+    /// Verify that `public` created this ring VRF signature.
+    pub fn verify_reveal(&self, t: impl IntoTranscript, public: &PublicKey, reveal: &RingVrfReveal) -> SignatureResult<()> {
+        let t = self.reveal_transcript(t);
+        pedersen_vrf().verify_key_commitment_opening(t, self.dleq_proof.as_key_commitment(), public, reveal)
+    }
+
+    fn reveal_transcript<T: IntoTranscript>(&self, t: T) -> T::Taken {
+        let mut t = t.into_transcript();
+        let tm: &mut Transcript = core::borrow::BorrowMut::borrow_mut(&mut t);
+        tm.label(b"RingVrfReveal");
+        tm.append(&self.dleq_proof);
+        t
+    }
+}
+
+/// Shorter encoding of a `RingVrfProof`, whose Pedersen VRF proof
+/// replaces both nonce commitments by the challenge, which verifiers
+/// recompute the nonce commitments from.
+///
+/// We encode `CompactRingVrfProof`s as the compressed key commitment
+/// (33 bytes), keying response (32 bytes), blinding response (32 bytes),
+/// and challenge (32 bytes), followed by the compressed ring proof
+/// (`RING_PROOF_LENGTH` bytes), so `COMPACT_RING_VRF_PROOF_LENGTH` bytes
+/// in total, or 34 bytes less than `RING_VRF_PROOF_LENGTH`.
+///
+/// We save only about 4.5%, since the ring proof dominates the size,
+/// and no Pedersen VRF encoding could save much more.  We also lose
+/// batch verification of the Pedersen VRF proofs, but ring proofs
+/// dominate verification time anyways.
+#[derive(Clone,CanonicalSerialize,CanonicalDeserialize)]
+pub struct CompactRingVrfProof {
+    pub dleq_proof: dleq_vrf::NonBatchable<PedersenVrf>,
+    pub ring_proof: ring::RingProof,
+}
+
+impl RingVrfProof {
+    /// Verify our Pedersen VRF proof, and then shorten us into a
+    /// `CompactRingVrfProof`, given the transcript and `VrfInOut`s we signed.
+    pub fn compact(&self, t: impl IntoTranscript, ios: &[VrfInOut]) -> SignatureResult<CompactRingVrfProof> {
+        let dleq_proof = pedersen_vrf().to_non_batchable(t, ios, &self.dleq_proof) ?;
+        Ok(CompactRingVrfProof { dleq_proof, ring_proof: self.ring_proof.clone() })
+    }
+}
+
+impl CompactRingVrfProof {
+    /// Verify our Pedersen VRF proof, and then expand us back into
+    /// a `RingVrfProof`, which `RingVerifier` verifies.
+    pub fn expand(&self, t: impl IntoTranscript, ios: &[VrfInOut]) -> SignatureResult<RingVrfProof> {
+        let dleq_proof = pedersen_vrf().to_batchable(t, ios, &self.dleq_proof) ?;
+        Ok(RingVrfProof { dleq_proof, ring_proof: self.ring_proof.clone() })
+    }
+}
+
 // TODO: Can you impl Debug+Eq+PartialEq for ring::RingProof please Sergey?  We'll then derive Debug.
 mod tmp {
-    use ark_std::fmt::{Debug,Formatter,Error};
-    impl Debug for crate::RingVrfProof {
+    use ark_std::{vec::Vec, fmt::{Debug,Formatter,Error}};
+    use ark_serialize::CanonicalSerialize;
+    impl<P> Debug for crate::RingVrfProof<P> {
         fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
             self.dleq_proof.fmt(f)
         }
     }
-    impl Eq for crate::RingVrfProof {}
-    impl PartialEq for crate::RingVrfProof {
+    impl<P: CanonicalSerialize> Eq for crate::RingVrfProof<P> {}
+    impl<P: CanonicalSerialize> PartialEq for crate::RingVrfProof<P> {
         fn eq(&self, other: &Self) -> bool {
-            // Ignore ring_proof for now
+            // RingProof lacks PartialEq, so compare its canonical encoding.
+            let ring_proof_bytes = |p: &crate::RingVrfProof<P>| {
+                let mut buf = Vec::with_capacity(p.ring_proof.compressed_size());
+                p.ring_proof.serialize_compressed(&mut buf).expect("Vec serialization is infallible");
+                buf
+            };
             self.dleq_proof == other.dleq_proof
+            && ring_proof_bytes(self) == ring_proof_bytes(other)
         }
     }
 }
 
 impl scale::ArkScaleMaxEncodedLen for RingVrfProof {
     fn max_encoded_len(compress: Compress) -> usize {
-        <PedersenVrfProof as scale::ArkScaleMaxEncodedLen>::max_encoded_len(compress)
-        + 4096  // TODO: How large is RingProof, Sergey?
+        // Uncompressed G1 points take 96 bytes.
+        let ring_proof = match compress {
+            Compress::Yes => RING_PROOF_LENGTH,
+            Compress::No => 7 * 96 + 8 * 32,
+        };
+        <PedersenVrfProof as scale::ArkScaleMaxEncodedLen>::max_encoded_len(compress) + ring_proof
     }
 }
 
@@ -167,6 +550,9 @@ pub struct RingVerifier<'a>(pub &'a ring::RingVerifier);
 
 pub type RingVrfSignature<const N: usize> = dleq_vrf::VrfSignature<RingVrfProof,N>;
 
+/// Ring VRF signature with a runtime number of pre-outputs.
+pub type RingVrfSignatureVec = dleq_vrf::VrfSignatureVec<RingVrfProof>;
+
 impl EcVrfVerifier for RingVerifier<'_> {
     type Proof = RingVrfProof;
     type Error = SignatureError;
@@ -183,7 +569,7 @@ impl EcVrfVerifier for RingVerifier<'_> {
         let key_commitment = signature.dleq_proof.as_key_commitment();
         match ring_verifier.verify_ring_proof(signature.ring_proof.clone(), key_commitment.0.clone()) {
             true => Ok(ios),
-            false => Err(SignatureError::Invalid),
+            false => Err(SignatureError::RingProofInvalid),
         }
     }
 }
@@ -198,9 +584,21 @@ impl RingVerifier<'_> {
     {
         self.vrf_verify(t, inputs, signature)
     }
+
+    pub fn verify_ring_vrf_vec(
+        &self,
+        t: impl IntoTranscript,
+        inputs: impl IntoIterator<Item = impl IntoVrfInput<Jubjub>>,
+        signature: &RingVrfSignatureVec,
+    ) -> Result<Vec<VrfInOut>,SignatureError>
+    {
+        self.vrf_verify_vec(t, inputs, signature)
+    }
 }
 
 
+/// Ring VRF signer, which also signs `VrfSignRequest`s like
+/// `secret.vrf_sign().input(msg).ad(b"..").sign_with(&ring_prover)`.
 // #[derive(Clone)]
 pub struct RingProver<'a> {
     pub ring_prover: &'a ring::RingProver,
@@ -220,49 +618,215 @@ impl<'a> EcVrfSigner for RingProver<'a> {
         t: impl IntoTranscript,
         ios: &[VrfInOut]
     ) -> Result<RingVrfProof,()>
+    {
+        Ok(self.sign_detached_with_blinding(t, ios).0)
+    }
+}
+
+impl<'a> RingProver<'a> {
+    fn sign_detached_with_blinding(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut]
+    ) -> (RingVrfProof, SecretBlinding)
     {
         let RingProver { ring_prover, secret } = *self;
         let secret_blinding = None; // TODO: Set this first so we can hash the ring proof
         let (dleq_proof,secret_blinding) = pedersen_vrf().sign_pedersen_vrf(t, ios, secret_blinding, secret);
+        // The ring proof shows the Pedersen VRF's key commitment opens,
+        // using the same secret blinding, to some public key in the ring.
         let ring_proof = ring_prover.prove(secret_blinding.0[0]);
-        Ok(RingVrfProof { dleq_proof, ring_proof, })
+        (RingVrfProof { dleq_proof, ring_proof, }, secret_blinding)
+    }
+
+    /// Ring VRF signature along with its `SecretBlinding`, which signers
+    /// could store so they could later `RingVrfProof::reveal` themselves.
+    pub fn sign_ring_vrf_revealable<const N: usize>(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut; N],
+    ) -> (RingVrfSignature<N>, SecretBlinding)
+    {
+        let (proof, secret_blinding) = self.sign_detached_with_blinding(t, ios);
+        let preouts = core::array::from_fn(|i| ios[i].preoutput);
+        (RingVrfSignature { proof, preouts }, secret_blinding)
     }
-}
 
-impl<'a> RingProver<'a> {
     pub fn sign_ring_vrf<const N: usize>(
         &self,
         t: impl IntoTranscript,
         ios: &[VrfInOut; N],
     ) -> RingVrfSignature<N>
     {
-        self.vrf_sign(t, ios).expect("no failure modes")
+        self.sign_ring_vrf_revealable(t, ios).0
+    }
+
+    pub fn sign_ring_vrf_vec(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut],
+    ) -> RingVrfSignatureVec
+    {
+        let proof = self.sign_detached_with_blinding(t, ios).0;
+        RingVrfSignatureVec { preouts: vrf::collect_preoutputs_vec(ios), proof }
     }
 }
 
+impl ring::RingContext {
+    /// Ring VRF signature by `secret` for the ring `pks`, which
+    /// must contain `secret`'s public key.
+    ///
+    /// We index the whole ring for every signature, so signers who sign
+    /// repeatedly for one ring should keep a `RingProver` instead.
+    pub fn sign_ring_vrf<const N: usize>(
+        &self,
+        pks: &[PublicKey],
+        secret: &SecretKey,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut; N],
+    ) -> Result<RingVrfSignature<N>, ring::RingError>
+    {
+        self.check_ring_size(pks.len()) ?;
+        let pk = secret.to_public();
+        let k = pks.iter().position(|p| *p == pk).ok_or(ring::RingError::SlotEmpty) ?;
+        let ring_prover = self.ring_prover(pks, k) ?;
+        Ok(RingProver { ring_prover: &ring_prover, secret }.sign_ring_vrf(t, ios))
+    }
 
-// TODO:  Run test vector tests once we have some even without getrandom
-// #[cfg(test)]
-// mod testvectors {
-// }
+    /// Verify a ring VRF signature for the ring `pks`.
+    ///
+    /// We fail with `SrsMismatch` if our domain cannot hold `pks`.
+    /// Verifiers who verify repeatedly for one ring should keep a
+    /// `RingVerifier` instead.
+    pub fn verify_ring_vrf<const N: usize>(
+        &self,
+        pks: &[PublicKey],
+        t: impl IntoTranscript,
+        inputs: impl IntoIterator<Item = impl IntoVrfInput<Jubjub>>,
+        signature: &RingVrfSignature<N>,
+    ) -> Result<[VrfInOut; N],SignatureError>
+    {
+        let ring_verifier = self.ring_verifier(pks).map_err(|_| SignatureError::SrsMismatch) ?;
+        RingVerifier(&ring_verifier).verify_ring_vrf(t, inputs, signature)
+    }
+}
+
+/// Run `f`, like ring VRF signing, upon a dedicated pool of `threads`
+/// rayon threads.
+///
+/// With the `parallel` feature, ring-proof parallelizes the FFTs,
+/// multi-scalar multiplications, and quotient computation of ring
+/// proving, by default across rayon's global pool.  Block authors
+/// with a fixed thread budget for ticket generation should instead
+/// sign within this.
+#[cfg(feature = "parallel")]
+pub fn with_thread_budget<R, F>(threads: usize, f: F) -> Result<R, rayon::ThreadPoolBuildError>
+where R: Send, F: FnOnce() -> R + Send,
+{
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build() ?;
+    Ok(pool.install(f))
+}
+
+
+// We sign test vectors deterministically, so these run even without getrandom.
+#[cfg(all(test, feature = "testvectors"))]
+mod testvectors_tests {
+    use crate::testvectors::*;
+
+    #[test]
+    fn testvectors_known_answers() {
+        let tvs = TestVector::parse_all(KNOWN_ANSWERS).unwrap();
+        assert_eq!(tvs.len(), INPUTS.len());
+        for tv in tvs.iter() {
+            tv.check().unwrap();
+        }
+        assert_eq!(emit_all().trim_end(), KNOWN_ANSWERS.trim_end());
+    }
+
+    #[test]
+    fn testvectors_detect_changes() {
+        let mut tv = TestVector::parse_all(KNOWN_ANSWERS).unwrap().pop().unwrap();
+        tv.thin[40] ^= 1;
+        assert_eq!(tv.check(), Err("thin VRF signature"));
+        let mut tv = TestVector::parse_all(KNOWN_ANSWERS).unwrap().pop().unwrap();
+        tv.ad.push(0);
+        assert_eq!(tv.check(), Err("thin VRF signature"));
+    }
+}
+
+#[cfg(all(test, feature = "spec-conformance"))]
+mod spec_conformance_tests {
+    use crate::spec_conformance::*;
+
+    #[test]
+    fn spec_conformance_vectors() {
+        assert_eq!(check_json(SPEC_VECTORS), Ok(7));
+        let vectors = parse_json(SPEC_VECTORS).unwrap();
+        assert_eq!(parse_json(&emit_json(&vectors)).unwrap(), vectors);
+    }
+
+    #[test]
+    fn spec_conformance_detect_changes() {
+        let tamper = |index: usize, f: fn(&mut SpecVector)| {
+            let mut vectors = parse_json(SPEC_VECTORS).unwrap();
+            f(&mut vectors[index]);
+            check_json(&emit_json(&vectors))
+        };
+        assert_eq!(tamper(1, |v| v.beta[0] ^= 1), Err(ConformanceError::Mismatch { index: 1, field: "beta" }));
+        assert_eq!(tamper(2, |v| v.h[0] ^= 1), Err(ConformanceError::Mismatch { index: 2, field: "h" }));
+        assert_eq!(tamper(3, |v| v.proof_s[0] ^= 1), Err(ConformanceError::Mismatch { index: 3, field: "proof_s" }));
+        assert_eq!(tamper(5, |v| v.ad.push(0)), Err(ConformanceError::Mismatch { index: 5, field: "proof_c" }));
+        assert_eq!(check_json("{}"), Err(ConformanceError::Json));
+    }
+}
 
 #[cfg(all(test, feature = "getrandom"))]
 mod tests {
     use super::*;
     use core::iter;
+    use ark_ec::{AffineRepr, CurveGroup};
     use ark_std::rand::RngCore;
 
     #[test]
     fn check_blinding_base() {
         let mut t = b"Bandersnatch VRF blinding base".into_transcript();
         let blinding_base: <Jubjub as AffineRepr>::Group = t.challenge(b"vrf-input").read_uniform();
-        debug_assert_eq!(blinding_base.into_affine(), BLINDING_BASE);
+        assert_eq!(blinding_base.into_affine(), BLINDING_BASE);
+        assert!(BLINDING_BASE.is_on_curve());
+        assert!(BLINDING_BASE.is_in_correct_subgroup_assuming_on_curve());
     }
 
     #[test]
     fn good_max_encoded_len() {
         use dleq_vrf::scale::MaxEncodedLen;
         assert_eq!(crate::PUBLIC_KEY_LENGTH, <PublicKey as MaxEncodedLen>::max_encoded_len());
+        assert_eq!(crate::PREOUT_LENGTH, <VrfPreOut as MaxEncodedLen>::max_encoded_len());
+        assert_eq!(
+            thin_vrf_signature_length(2),
+            <ThinVrfSignature<2> as MaxEncodedLen>::max_encoded_len()
+        );
+        assert_eq!(
+            ring_vrf_signature_length(1),
+            <RingVrfSignature<1> as MaxEncodedLen>::max_encoded_len()
+        );
+    }
+
+    #[test]
+    fn scale_encoding_thin_signature() {
+        use dleq_vrf::scale::{Encode, Decode, MaxEncodedLen, TypeInfo, scale_info::TypeDef};
+
+        let secret = SecretKey::from_seed(&[0; 32]);
+        let input = Message { domain: b"domain", message: b"message" };
+        let io = secret.vrf_inout(input);
+        let signature: ThinVrfSignature<1> = secret.sign_thin_vrf(b"label", &[io]);
+
+        let bytes = signature.encode();
+        assert_eq!(bytes.len(), <ThinVrfSignature<1> as MaxEncodedLen>::max_encoded_len());
+        assert_eq!(signature, ThinVrfSignature::<1>::decode(&mut bytes.as_slice()).unwrap());
+        match <ThinVrfSignature<1> as TypeInfo>::type_info().type_def {
+            TypeDef::Array(a) => assert_eq!(a.len as usize, bytes.len()),
+            _ => panic!("Expected byte array TypeInfo"),
+        }
     }
 
     #[test]
@@ -287,13 +851,178 @@ mod tests {
         assert!(result.is_ok());
         let io2 = result.unwrap();
         assert_eq!(io2[0].preoutput, io.preoutput);
+
+        let bytes = signature.to_bytes::<{ thin_vrf_signature_length(1) }>();
+        assert_eq!(signature, ThinVrfSignature::<1>::from_bytes(&bytes).unwrap());
+
+        let signature = secret.sign_message(b"context", b"message");
+        assert_eq!(signature.compressed_size(), SIGNATURE_LENGTH);
+        public.verify_message(b"context", b"message", &signature).unwrap();
+    }
+
+    #[test]
+    fn thin_batch_verify_vec() {
+        let secrets: Vec<_> = (0..3u8).map(|i| SecretKey::from_seed(&[i; 32])).collect();
+        let inputs = |n: usize| (0..n).map(|i| Message { domain: b"domain", message: &[i as u8] }.into_vrf_input());
+        let signatures: Vec<ThinVrfSignatureVec> = secrets.iter().enumerate().map(|(n,secret)| {
+            let ios: Vec<_> = inputs(n).map(|input| secret.vrf_inout(input)).collect();
+            secret.sign_thin_vrf_vec(b"vec", &ios)
+        }).collect();
+        let publics: Vec<_> = secrets.iter().map(|secret| secret.to_public()).collect();
+
+        batch_verify_thin_vec(publics.iter().zip(&signatures).enumerate()
+            .map(|(n,(public,signature))| (b"vec", inputs(n), public, signature))
+        ).unwrap();
+        assert!(matches!(
+            batch_verify_thin_vec(publics.iter().zip(&signatures).enumerate()
+                .map(|(n,(public,signature))| (b"vec", inputs(n + 1), public, signature))),
+            Err(SignatureError::WrongInputCount)
+        ));
+    }
+
+    /// Small torsion point, since any point outside the prime order
+    /// subgroup times the group order yields one.
+    fn small_torsion() -> Jubjub {
+        use ark_ff::{One, PrimeField, Zero};
+        let mut x = bandersnatch::Fq::one();
+        loop {
+            if let Some(p) = Jubjub::get_point_from_x_unchecked(x, false) {
+                let t = p.mul_bigint(bandersnatch::Fr::MODULUS);
+                if !t.is_zero() { break t.into_affine(); }
+            }
+            x += bandersnatch::Fq::one();
+        }
+    }
+
+    /// Secret key, and an input-output pair whose pre-output carries
+    /// a small torsion component.
+    fn torsion_inout() -> (SecretKey, VrfInOut) {
+        let secret = SecretKey::from_seed(&[0; 32]);
+        let input = Message { domain: b"domain", message: b"torsion" }.into_vrf_input();
+        let mut io = secret.vrf_inout(input);
+        io.preoutput.0 = (io.preoutput.0 + small_torsion()).into_affine();
+        (secret, io)
+    }
+
+    fn strict_pedersen_vrf() -> PedersenVrf {
+        thin_vrf().strict_cofactor().pedersen_vrf([ BLINDING_BASE ])
+    }
+
+    /// Non-batchable proofs recompute their nonce commitments exactly,
+    /// so signers grind their transcript until the challenge kills the
+    /// torsion, which takes a few attempts.
+    fn grind_non_batchable(
+        sign: impl Fn(&[u8]) -> dleq_vrf::NonBatchable<PedersenVrf>,
+        verify: impl Fn(&[u8], &dleq_vrf::NonBatchable<PedersenVrf>) -> bool,
+    ) -> ([u8; 1], dleq_vrf::NonBatchable<PedersenVrf>) {
+        (0u8..=255).map(|i| ([i], sign(&[i])))
+        .find(|(ad, proof)| verify(ad, proof))
+        .expect("Challenges kill small torsion with probability at least 1/4")
+    }
+
+    #[test]
+    fn strict_cofactor_rejects_torsion() {
+        let torsion = small_torsion();
+
+        let secret = SecretKey::from_seed(&[0; 32]);
+        let input = Message { domain: b"domain", message: b"torsion" }.into_vrf_input();
+        let mut io = secret.vrf_inout(input);
+        io.preoutput.0 = (io.preoutput.0 + torsion).into_affine();
+        let proof = secret.sign_thin_vrf_detached(b"torsion", &[io]);
+
+        thin_vrf().verify_thin_vrf(b"torsion", &[io], secret.as_publickey(), &proof).unwrap();
+        assert!(thin_vrf().strict_cofactor()
+            .verify_thin_vrf(b"torsion", &[io], secret.as_publickey(), &proof).is_err());
+
+        // Public keys differing by small torsion compare, hash, and sort equal.
+        let public = secret.to_public();
+        let twin = dleq_vrf::PublicKey((public.0 + torsion).into_affine());
+        assert_eq!(twin, public);
+        assert_eq!(twin.cmp(&public), core::cmp::Ordering::Equal);
+        let keys: std::collections::HashSet<_> = [public, twin].into_iter().collect();
+        assert_eq!(keys.len(), 1);
+    }
+
+    #[test]
+    fn strict_cofactor_rejects_torsion_in_thin_batches() {
+        let (secret, io) = torsion_inout();
+        let proof = secret.sign_thin_vrf_detached(b"torsion", &[io]);
+        let batch = || [(b"torsion", [io], secret.as_publickey(), &proof)];
+        thin_vrf().batch_verify_thin_vrf(batch()).unwrap();
+        assert!(thin_vrf().strict_cofactor().batch_verify_thin_vrf(batch()).is_err());
+    }
+
+    #[test]
+    fn strict_cofactor_rejects_torsion_in_pedersen_batches() {
+        let (secret, io) = torsion_inout();
+        let (proof, _) = pedersen_vrf().sign_pedersen_vrf(b"torsion", &[io], None, &secret);
+        let batch = || [(b"torsion", [io], &proof)];
+        pedersen_vrf().batch_verify_pedersen_vrf(batch()).unwrap();
+        assert!(strict_pedersen_vrf().batch_verify_pedersen_vrf(batch()).is_err());
+    }
+
+    #[test]
+    fn strict_cofactor_rejects_torsion_in_non_batchable() {
+        let (secret, io) = torsion_inout();
+        let (ad, proof) = grind_non_batchable(
+            |ad| pedersen_vrf().sign_non_batchable_pedersen_vrf(ad, &[io], None, &secret).0,
+            |ad, proof| pedersen_vrf().verify_non_batchable_pedersen_vrf(ad, &[io], proof).is_ok(),
+        );
+        assert!(strict_pedersen_vrf().verify_non_batchable_pedersen_vrf(&ad, &[io], &proof).is_err());
+    }
+
+    #[test]
+    fn strict_cofactor_rejects_torsion_in_to_batchable() {
+        let (secret, io) = torsion_inout();
+        let (ad, proof) = grind_non_batchable(
+            |ad| pedersen_vrf().sign_non_batchable_pedersen_vrf(ad, &[io], None, &secret).0,
+            |ad, proof| pedersen_vrf().to_batchable(ad, &[io], proof).is_ok(),
+        );
+        assert!(strict_pedersen_vrf().to_batchable(&ad, &[io], &proof).is_err());
+    }
+
+    #[test]
+    fn strict_cofactor_rejects_torsion_in_key_commitment_openings() {
+        let (secret, io) = torsion_inout();
+        let (proof, secret_blinding) = pedersen_vrf().sign_pedersen_vrf(b"torsion", &[io], None, &secret);
+        let compk = proof.as_key_commitment();
+        let twin = dleq_vrf::PublicKey((secret.as_publickey().0 + small_torsion()).into_affine());
+        let opening = pedersen_vrf().prove_key_commitment_opening(b"open", compk, &twin, &secret_blinding);
+        pedersen_vrf().verify_key_commitment_opening(b"open", compk, &twin, &opening).unwrap();
+        assert!(strict_pedersen_vrf().verify_key_commitment_opening(b"open", compk, &twin, &opening).is_err());
+    }
+
+    #[test]
+    fn strict_cofactor_rejects_torsion_in_committed_vrfs() {
+        let (secret, io) = torsion_inout();
+        let (_, secret_blinding) = pedersen_vrf().sign_pedersen_vrf(b"commit", &[io], None, &secret);
+        let (ad, proof) = grind_non_batchable(
+            |ad| pedersen_vrf().prove_committed_vrf(ad, &[io], &secret_blinding, &secret),
+            |ad, proof| pedersen_vrf().verify_committed_vrf(ad, &[io], proof.as_key_commitment(), proof).is_ok(),
+        );
+        let compk = proof.as_key_commitment();
+        assert!(strict_pedersen_vrf().verify_committed_vrf(&ad, &[io], compk, &proof).is_err());
+    }
+
+    #[test]
+    fn ietf_prove_verify() {
+        use dleq_vrf::ietf;
+
+        let secret = SecretKey::from_seed(&[0; 32]);
+        let input = BandersnatchSha512Ell2::encode_to_curve(b"message");
+        let proof = ietf::prove::<BandersnatchSha512Ell2>(&secret, input, b"");
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), IETF_PROOF_LENGTH);
+        let proof = IetfProof::from_bytes(&bytes).unwrap();
+        let output = ietf::verify(&thin_vrf(), secret.as_publickey(), input, b"", &proof).unwrap();
+        assert_eq!(output, ietf::proof_to_hash(&proof));
     }
 
     fn ring_test_init(pk: PublicKey) -> (ring::RingProver, ring::RingVerifier) {
         use ark_std::UniformRand;
 
-        let kzg = ring::KZG::testing_kzg_setup([0; 32], 2u32.pow(10));
-        let keyset_size = kzg.max_keyset_size();
+        let ctx = ring::RingContext::testing_kzg_setup([0; 32], 2u32.pow(10));
+        let keyset_size = ctx.max_keyset_size();
 
         let mut rng = rand_core::OsRng;
 		let mut l = [0u8; 8];
@@ -301,16 +1030,13 @@ mod tests {
 		let keyset_size = usize::from_le_bytes(l) % keyset_size;
 
         // Gen a bunch of random public keys
-        let mut pks: Vec<_> = (0..keyset_size).map(|_| Jubjub::rand(&mut rng)).collect();
+        let mut pks: Vec<_> = (0..keyset_size).map(|_| dleq_vrf::PublicKey(Jubjub::rand(&mut rng))).collect();
         // Just select one index for the actual key we are for signing
         let secret_key_idx = keyset_size / 2;
-        pks[secret_key_idx] = pk.0.into();
-
-        let prover_key = kzg.prover_key(pks.clone());
-        let ring_prover = kzg.init_ring_prover(prover_key, secret_key_idx);
+        pks[secret_key_idx] = pk;
 
-        let verifier_key = kzg.verifier_key(pks);
-        let ring_verifier = kzg.init_ring_verifier(verifier_key);
+        let ring_prover = ctx.ring_prover(&pks, secret_key_idx).unwrap();
+        let ring_verifier = ctx.ring_verifier(&pks).unwrap();
 
         (ring_prover, ring_verifier)
     }
@@ -332,10 +1058,361 @@ mod tests {
             ring_prover: &ring_prover, secret,
         }.sign_ring_vrf(transcript, &[io]);
         
-        // TODO: serialize signature
+        let mut buf = Vec::new();
+        signature.serialize_compressed(&mut buf).unwrap();
+        assert_eq!(signature.proof.dleq_proof.compressed_size(), PEDERSEN_VRF_PROOF_LENGTH);
+        assert_eq!(signature.proof.ring_proof.compressed_size(), RING_PROOF_LENGTH);
+        assert_eq!(buf.len(), ring_vrf_signature_length(1));
+        let signature2 = RingVrfSignature::<1>::deserialize_compressed(buf.as_slice()).unwrap();
+        assert_eq!(signature, signature2);
 
         let result = RingVerifier(&ring_verifier)
-        .verify_ring_vrf(transcript, iter::once(input), &signature);
+        .verify_ring_vrf(transcript, iter::once(input), &signature2);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn ring_sign_verify_by_context() {
+        let secret = & SecretKey::from_seed(&[0; 32]);
+        let ctx = ring::RingContext::testing_kzg_setup([0; 32], 2u32.pow(9));
+        let mut pks: Vec<_> = (1..5u8).map(|i| SecretKey::from_seed(&[i; 32]).to_public()).collect();
+        pks.push(secret.to_public());
+
+        let input = Message { domain: b"domain", message: b"message" }.into_vrf_input();
+        let io = secret.vrf_inout(input);
+        let signature = ctx.sign_ring_vrf(&pks, secret, b"Meow", &[io]).unwrap();
+        let ios = ctx.verify_ring_vrf(&pks, b"Meow", iter::once(input), &signature).unwrap();
+        assert_eq!(ios[0].preoutput, io.preoutput);
+
+        // Signers must belong to the ring, and verifiers must use the signed ring.
+        let outsider = SecretKey::from_seed(&[9; 32]);
+        assert_eq!(ctx.sign_ring_vrf(&pks[..4], secret, b"Meow", &[io]).err(), Some(ring::RingError::SlotEmpty));
+        assert!(ctx.verify_ring_vrf(&pks[..4], b"Meow", iter::once(input), &signature).is_err());
+        let too_many = vec![outsider.to_public(); ctx.max_keyset_size() + 1];
+        assert_eq!(ctx.sign_ring_vrf(&too_many, &outsider, b"Meow", &[io]).err(), Some(ring::RingError::TooManyKeys));
+    }
+
+    #[test]
+    fn ring_verify_by_commitment() {
+        let secret = & SecretKey::from_seed(&[0; 32]);
+        let ctx = ring::RingContext::testing_kzg_setup([0; 32], 2u32.pow(10));
+        let pks: Vec<_> = (1..8u8).map(|i| SecretKey::from_seed(&[i; 32]).to_public())
+            .chain(iter::once(secret.to_public()))
+            .collect();
+        let ring_prover = ctx.ring_prover(&pks, pks.len() - 1).unwrap();
+
+        // Verifiers keep only the commitment and the KZG verifier key.
+        let commitment = ctx.ring_commitment(&pks);
+        let ring_verifier = ring::ring_verifier_from_commitment(&commitment, ctx.kzg_vk(), ctx.domain_size as usize);
+
+        let input = Message { domain: b"domain", message: b"commitment" }.into_vrf_input();
+        let io = secret.vrf_inout(input.clone());
+        let signature: RingVrfSignature<1> = RingProver {
+            ring_prover: &ring_prover, secret,
+        }.sign_ring_vrf(b"Meow", &[io]);
+        RingVerifier(&ring_verifier)
+        .verify_ring_vrf(b"Meow", iter::once(input.clone()), &signature).unwrap();
+
+        let other = ctx.ring_commitment(&pks[..pks.len() - 1]);
+        let other_verifier = ring::ring_verifier_from_commitment(&other, ctx.kzg_vk(), ctx.domain_size as usize);
+        assert!(RingVerifier(&other_verifier)
+            .verify_ring_vrf(b"Meow", iter::once(input), &signature).is_err());
+    }
+
+    #[test]
+    fn persisted_ring_keys() {
+        let secret = & SecretKey::from_seed(&[0; 32]);
+        let ctx = ring::RingContext::testing_kzg_setup([0; 32], 2u32.pow(9));
+        let pks: Vec<_> = (1..4u8).map(|i| SecretKey::from_seed(&[i; 32]).to_public())
+            .chain(iter::once(secret.to_public()))
+            .collect();
+
+        let mut prover_bytes = Vec::new();
+        ctx.ring_prover_key(&pks).unwrap().serialize_compressed(&mut prover_bytes).unwrap();
+        let mut verifier_bytes = Vec::new();
+        ctx.ring_verifier_key(&pks).unwrap().serialize_compressed(&mut verifier_bytes).unwrap();
+
+        let ring_prover = ring::RingProverKey::deserialize_compressed(prover_bytes.as_slice()).unwrap()
+            .into_ring_prover(pks.len() - 1);
+        let ring_verifier = ring::RingVerifierKey::deserialize_compressed(verifier_bytes.as_slice()).unwrap()
+            .into_ring_verifier();
+
+        let input = Message { domain: b"domain", message: b"persisted" }.into_vrf_input();
+        let io = secret.vrf_inout(input.clone());
+        let signature: RingVrfSignature<1> = RingProver {
+            ring_prover: &ring_prover, secret,
+        }.sign_ring_vrf(b"Meow", &[io]);
+        RingVerifier(&ring_verifier)
+        .verify_ring_vrf(b"Meow", iter::once(input), &signature).unwrap();
+
+        verifier_bytes[0] += 1;
+        assert!(ring::RingVerifierKey::deserialize_compressed(verifier_bytes.as_slice()).is_err());
+        verifier_bytes[0] -= 1;
+
+        // We reject domain sizes which are no power of two.
+        verifier_bytes[1..5].copy_from_slice(&3u32.to_le_bytes());
+        assert!(ring::RingVerifierKey::deserialize_compressed(verifier_bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn byte_slice_entrypoints() {
+        use bytes::*;
+        let seed = [0; 32];
+        let secret = & SecretKey::from_seed(&seed);
+        let public = serialize_publickey(&secret.to_public());
+        let inputs: [&[u8]; 2] = [b"first", b"second"];
+
+        let signature = sign_thin_bytes(&seed, b"domain", &inputs, b"ad");
+        assert_eq!(signature.len(), thin_vrf_signature_length(2));
+        let outputs = verify_thin_bytes(&public, b"domain", &inputs, &signature, b"ad").unwrap();
+        let io = secret.vrf_inout(Message { domain: b"domain", message: b"second" });
+        assert_eq!(outputs[1], output_bytes(&io));
+
+        assert!(matches!(
+            verify_thin_bytes(&public, b"domain", &inputs, &signature, b"da"),
+            Err(BytesError::Signature(_))
+        ));
+        assert!(matches!(
+            verify_thin_bytes(&public, b"domain", &inputs[..1], &signature, b"ad"),
+            Err(BytesError::Deserialize)
+        ));
+
+        let ctx = ring::RingContext::testing_kzg_setup([0; 32], 2u32.pow(9));
+        let pks: Vec<_> = (1..4u8).map(|i| SecretKey::from_seed(&[i; 32]).to_public())
+            .chain(iter::once(secret.to_public()))
+            .collect();
+        let mut verifier_key = Vec::new();
+        ctx.ring_verifier_key(&pks).unwrap().serialize_compressed(&mut verifier_key).unwrap();
+        let ring_prover = ctx.ring_prover_key(&pks).unwrap().into_ring_prover(pks.len() - 1);
+        let signature: RingVrfSignature<1> = RingProver {
+            ring_prover: &ring_prover, secret,
+        }.sign_ring_vrf(Transcript::new_labeled(transcript::IsLabel(&b"ad"[..])), &[io]);
+        let mut signature_bytes = Vec::new();
+        signature.serialize_compressed(&mut signature_bytes).unwrap();
+        let outputs = verify_ring_bytes(&verifier_key, b"domain", &inputs[1..], &signature_bytes, b"ad").unwrap();
+        assert_eq!(outputs, [output_bytes(&io)]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn ring_prover_thread_budget() {
+        let secret = & SecretKey::from_seed(&[0; 32]);
+        let (ring_prover, ring_verifier) = ring_test_init(secret.to_public());
+        let input = Message { domain: b"domain", message: b"budget" }.into_vrf_input();
+        let io = secret.vrf_inout(input);
+        // Our secret key caches lazily, so we move a clone into the pool.
+        let owned = secret.clone();
+        let signature: RingVrfSignature<1> = with_thread_budget(2, move || RingProver {
+            ring_prover: &ring_prover, secret: &owned,
+        }.sign_ring_vrf(b"Meow", &[io])).unwrap();
+        RingVerifier(&ring_verifier)
+        .verify_ring_vrf(b"Meow", iter::once(input), &signature).unwrap();
+    }
+
+    #[test]
+    fn compact_ring_vrf_proof() {
+        let secret = & SecretKey::from_seed(&[0; 32]);
+        let (ring_prover, ring_verifier) = ring_test_init(secret.to_public());
+        let input = Message { domain: b"domain", message: b"compact" }.into_vrf_input();
+        let io = secret.vrf_inout(input);
+        let signature: RingVrfSignature<1> = RingProver {
+            ring_prover: &ring_prover, secret,
+        }.sign_ring_vrf(b"Meow", &[io]);
+
+        let compact = signature.proof.compact(b"Meow", &[io]).unwrap();
+        assert_eq!(compact.compressed_size(), COMPACT_RING_VRF_PROOF_LENGTH);
+        assert_eq!(RING_VRF_PROOF_LENGTH - COMPACT_RING_VRF_PROOF_LENGTH, 34);
+        assert!(signature.proof.compact(b"Woof", &[io]).is_err());
+
+        let mut bytes = Vec::new();
+        compact.serialize_compressed(&mut bytes).unwrap();
+        let compact = CompactRingVrfProof::deserialize_compressed(bytes.as_slice()).unwrap();
+        let proof = compact.expand(b"Meow", &[io]).unwrap();
+        assert_eq!(proof, signature.proof);
+        let signature = RingVrfSignature { proof, preouts: [io.preoutput] };
+        RingVerifier(&ring_verifier)
+        .verify_ring_vrf(b"Meow", iter::once(input), &signature).unwrap();
+        assert!(compact.expand(b"Woof", &[io]).is_err());
+    }
+
+    #[test]
+    fn multi_attribute_key_commitment() {
+        use ark_ff::Field;
+        assert_eq!(multi_pedersen_vrf::<1>(), pedersen_vrf());
+        let bases = blinding_bases::<3>();
+        assert!(bases[1] != bases[2] && bases[1] != BLINDING_BASE);
+        assert!(bases[2].is_in_correct_subgroup_assuming_on_curve());
+
+        let secret = SecretKey::from_seed(&[0; 32]);
+        let io = secret.vrf_inout(Message { domain: b"domain", message: b"attributes" });
+        let flavor = multi_pedersen_vrf::<3>();
+        let attributes: MultiSecretBlinding<3> = dleq_vrf::SecretBlinding([
+            bandersnatch::Fr::from(0x1234u64),
+            bandersnatch::Fr::from(7u64),
+            bandersnatch::Fr::ONE,
+        ]);
+        let (proof, blinding) = flavor.sign_pedersen_vrf(b"Meow", &[io], Some(attributes.clone()), &secret);
+        flavor.verify_pedersen_vrf(b"Meow", &[io], &proof).unwrap();
+        assert!(blinding.verify(&flavor, proof.as_key_commitment(), secret.as_publickey()));
+
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), pedersen_vrf_proof_length(3));
+        let proof = MultiPedersenVrfProof::<3>::deserialize_compressed(bytes.as_slice()).unwrap();
+        flavor.verify_pedersen_vrf(b"Meow", &[io], &proof).unwrap();
+
+        let mut other = attributes;
+        other.0[2] = bandersnatch::Fr::from(2u64);
+        assert!(!other.verify(&flavor, proof.as_key_commitment(), secret.as_publickey()));
+    }
+
+    #[test]
+    fn vrf_input_conversions() {
+        const DOMAIN: Domain = Domain(b"domain");
+        let input = Message { domain: b"domain", message: b"message" }.into_vrf_input();
+        assert_eq!(DOMAIN.input(b"message"), input);
+        assert_eq!(DOMAIN.message(b"message").into_vrf_input(), input);
+        assert_eq!(DOMAIN.prepare().hash(b"message"), input);
+        assert_eq!(Message::from((&b"domain"[..], &b"message"[..])).into_vrf_input(), input);
+        assert_eq!(Message::from(("domain", "message")).into_vrf_input(), input);
+        assert_eq!((&DOMAIN.message(b"message")).into_vrf_input(), input);
+
+        // Verifiers may pass already computed inputs by reference.
+        let secret = SecretKey::from_seed(&[0; 32]);
+        let inputs = [input, DOMAIN.input(b"other")];
+        let ios = inputs.map(|input| secret.vrf_inout(input));
+        let signature: ThinVrfSignature<2> = secret.sign_thin_vrf(b"label", &ios);
+        secret.as_publickey().verify_thin_vrf(b"label", inputs.iter(), &signature).unwrap();
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_vrf_input() {
+        #[derive(Clone, derive::VrfInput)]
+        #[vrf_input(domain = b"envelope", hasher = crate::Elligator2, crate = "crate")]
+        struct TicketEnvelope {
+            attempt: u32,
+            #[vrf_input(scale)]
+            extra: Vec<u8>,
+            #[vrf_input(skip)]
+            #[allow(dead_code)]
+            note: &'static str,
+        }
+
+        let envelope = TicketEnvelope { attempt: 3, extra: vec![1, 2, 3], note: "ignored" };
+        let input = envelope.clone().into_vrf_input();
+        assert_eq!(input, TicketEnvelope { note: "other", ..envelope.clone() }.into_vrf_input());
+        assert_ne!(input, TicketEnvelope { attempt: 4, ..envelope.clone() }.into_vrf_input());
+        assert_ne!(input, TicketEnvelope { extra: vec![1, 2], ..envelope.clone() }.into_vrf_input());
+
+        let secret = SecretKey::from_seed(&[0; 32]);
+        let io = secret.vrf_inout(envelope.clone());
+        let signature: ThinVrfSignature<1> = secret.sign_thin_vrf(b"label", &[io]);
+        secret.as_publickey().verify_thin_vrf(b"label", [envelope], &signature).unwrap();
+    }
+
+    #[test]
+    fn error_source_chains() {
+        use std::error::Error;
+        let e: Box<dyn Error> = Box::new(bytes::BytesError::Signature(SignatureError::RingProofInvalid));
+        let source = e.source().unwrap();
+        assert_eq!(source.to_string(), SignatureError::RingProofInvalid.to_string());
+        assert!(e.to_string().ends_with(&source.to_string()));
+        let e: Box<dyn Error> = Box::new(ring::RingError::TooManyKeys);
+        assert!(e.source().is_none());
+    }
+
+    #[test]
+    fn sassafras_ticket() {
+        use sassafras::*;
+
+        let secret = & SecretKey::from_seed(&[0; 32]);
+        let (ring_prover, ring_verifier) = ring_test_init(secret.to_public());
+        let ring_prover = RingProver { ring_prover: &ring_prover, secret };
+        let ring_verifier = RingVerifier(&ring_verifier);
+
+        let randomness = [7; 32];
+        let body = TicketBody { attempt_idx: 3, erased_public: [1; 32], revealed_public: [2; 32] };
+        let (id, envelope) = sign_ticket(&ring_prover, &randomness, 42, body);
+        assert_eq!(verify_ticket(&ring_verifier, &randomness, 42, &envelope).unwrap(), id);
+        assert!(verify_ticket(&ring_verifier, &randomness, 43, &envelope).is_err());
+
+        let mut forged = envelope.clone();
+        forged.body.revealed_public = [3; 32];
+        assert!(verify_ticket(&ring_verifier, &randomness, 42, &forged).is_err());
+    }
+
+    #[test]
+    fn sassafras_substrate_transcripts() {
+        use sassafras::*;
+
+        let secret = SecretKey::from_seed(&[0; 32]);
+        let randomness = [7; 32];
+
+        // Substrate's `VrfInput::new(b"sassafras", [b"ticket-id", ..].concat())`
+        let message = [&b"ticket-id"[..], &randomness, &3u32.to_le_bytes(), &42u64.to_le_bytes()].concat();
+        let input = Message { domain: b"sassafras", message: &message }.into_vrf_input();
+        assert_eq!(input, ticket_id_input(&randomness, 3, 42));
+        // Substrate's `make_bytes::<16>(b"ticket-id", input)`
+        let io = secret.vrf_inout(input);
+        let id = u128::from_le_bytes(io.vrf_output_bytes(Transcript::new_labeled(b"ticket-id")));
+        assert_eq!(id, make_ticket_id(&io));
+        assert_eq!(id, 0xfa0ac4069ee167947d9bb3149dc81c3a);
+
+        let body = TicketBody { attempt_idx: 3, erased_public: [1; 32], revealed_public: [2; 32] };
+        let mut t = Transcript::new_labeled(b"sassafras-ticket-body-transcript");
+        t.append(&body.encode()[..]);
+        assert_eq!(t.challenge(b"check").read_byte_array::<32>(), ticket_body_transcript(&body).challenge(b"check").read_byte_array::<32>());
+
+        let claim = sign_slot_claim(&secret, &randomness, 1000, 42);
+        let io = verify_slot_claim(&secret.to_public(), &randomness, 1000, 42, &claim).unwrap();
+        assert_eq!(io.input, slot_claim_input(&randomness, 1000, 42));
+        assert!(verify_slot_claim(&secret.to_public(), &randomness, 1001, 42, &claim).is_err());
+        assert_ne!(revealed_key_input(&randomness, 3, 42), ticket_id_input(&randomness, 3, 42));
+    }
+
+    #[test]
+    fn ring_reveal() {
+        let secret = & SecretKey::from_seed(&[0; 32]);
+        let other = SecretKey::from_seed(&[1; 32]).to_public();
+        let (ring_prover, ring_verifier) = ring_test_init(secret.to_public());
+
+        let input = Message { domain: b"domain", message: b"reveal" }.into_vrf_input();
+        let io = secret.vrf_inout(input.clone());
+        let (signature, secret_blinding) = RingProver {
+            ring_prover: &ring_prover, secret,
+        }.sign_ring_vrf_revealable(b"Meow", &[io]);
+        RingVerifier(&ring_verifier)
+        .verify_ring_vrf(b"Meow", iter::once(input), &signature).unwrap();
+
+        let reveal = signature.proof.reveal(b"Claim", &secret_blinding, &secret.to_public());
+        signature.proof.verify_reveal(b"Claim", &secret.to_public(), &reveal).unwrap();
+        assert!(signature.proof.verify_reveal(b"Claim", &other, &reveal).is_err());
+        assert!(signature.proof.verify_reveal(b"Other", &secret.to_public(), &reveal).is_err());
+    }
+
+    #[test]
+    fn ring_sign_verify_non_member() {
+        let secret = & SecretKey::from_seed(&[0; 32]);
+        let other = SecretKey::from_seed(&[1; 32]).to_public();
+
+        // Our ring contains other in our slot, so our key commitment
+        // cannot open to any member.
+        let (ring_prover, ring_verifier) = ring_test_init(other);
+
+        let input = Message {
+            domain: b"domain",
+            message: b"message",
+        }.into_vrf_input();
+        let io = secret.vrf_inout(input.clone());
+        let transcript: &[u8] = b"Meow";
+
+        let signature: RingVrfSignature<1> = RingProver {
+            ring_prover: &ring_prover, secret,
+        }.sign_ring_vrf(transcript, &[io]);
+
+        let result = RingVerifier(&ring_verifier)
+        .verify_ring_vrf(transcript, iter::once(input), &signature);
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,290 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### One-out-of-many ring proofs without trusted setup
+//!
+//! Chains unwilling to rely upon a KZG trusted setup could use the
+//! `OneOfMany` ring backend, which proves membership by Groth and
+//! Kohlweiss' one-out-of-many proofs, so only discrete logarithm
+//! hardness on Bandersnatch itself.
+//!
+//! A key commitment `C = pk + b BLINDING_BASE` opens to the ring member
+//! `P_l` exactly when `C - P_l = b BLINDING_BASE`, so we prove one of
+//! the differences `D_i = C - P_i` is a commitment to zero, using
+//! Pedersen commitments to the bits of `l` under the keying base and
+//! `BLINDING_BASE`.  We pad rings to a power of two `2^n` by the
+//! ring's padding point, whose discrete logarithm nobody knows.
+//!
+//! Proofs hold `4 n` points and `3 n + 1` scalars, so around 2.3 kb
+//! for rings of 1024 keys, and verification costs one multi-scalar
+//! multiplication over the ring.  We provide no Merkle or curve tree
+//! membership argument, whose verification would cost only logarithmic
+//! time, because those need a Bulletproofs style circuit over a cycle
+//! of curves, which nothing here provides.
+
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, One, Zero};
+use ark_serialize::{CanonicalSerialize, CanonicalDeserialize};
+use ark_std::{vec, vec::Vec};
+
+use crate::{
+    Jubjub, PublicKey, SecretKey, SecretBlinding, Transcript, BLINDING_BASE,
+    backend::RingProofBackend,
+    ring::{RingError, PADDING_POINT},
+};
+
+
+type Scalar = <Jubjub as AffineRepr>::ScalarField;
+type Group = <Jubjub as AffineRepr>::Group;
+
+/// Ring of public keys, padded to a power of two, for `OneOfMany`
+/// ring proofs.
+#[derive(Clone)]
+pub struct OneOfManyRing {
+    keys: Vec<Jubjub>,
+    bits: usize,
+    digest: [u8; 32],
+}
+
+impl OneOfManyRing {
+    /// Ring of `pks` in this order, which provers and verifiers must agree upon.
+    pub fn new(pks: &[PublicKey]) -> OneOfManyRing {
+        let size = pks.len().max(2).next_power_of_two();
+        let mut keys: Vec<Jubjub> = pks.iter().map(|pk| pk.0).collect();
+        keys.resize(size, PADDING_POINT);
+        let mut t = Transcript::new_labeled(b"OneOfManyRing");
+        t.append_slice::<Jubjub,_>(&keys);
+        let digest = t.challenge(b"digest").read_byte_array();
+        OneOfManyRing { keys, bits: size.trailing_zeros() as usize, digest }
+    }
+
+    /// Ring size, including padding.
+    pub fn len(&self) -> usize { self.keys.len() }
+
+    pub fn is_empty(&self) -> bool { false }
+
+    /// Prover for the ring member at `index`.
+    pub fn prover(&self, index: usize) -> Result<OneOfManyRingProver, RingError> {
+        if index >= self.keys.len() {
+            return Err(RingError::IndexOutOfRange);
+        }
+        Ok(OneOfManyRingProver { ring: self.clone(), index })
+    }
+
+    fn transcript(&self, key_commitment: &Jubjub) -> Transcript {
+        let mut t = Transcript::new_labeled(b"OneOfManyRingProof");
+        t.append(&self.digest[..]);
+        t.append(key_commitment);
+        t
+    }
+}
+
+/// Prover for one member of a `OneOfManyRing`.
+pub struct OneOfManyRingProver {
+    ring: OneOfManyRing,
+    index: usize,
+}
+
+/// One-out-of-many ring proof.
+#[derive(Clone,Debug,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct OneOfManyRingProof {
+    /// Commitments to the bits of the signer's index
+    cl: Vec<Jubjub>,
+    ca: Vec<Jubjub>,
+    cb: Vec<Jubjub>,
+    /// Commitments to the coefficients of the ring polynomials
+    cd: Vec<Jubjub>,
+    f: Vec<Scalar>,
+    za: Vec<Scalar>,
+    zb: Vec<Scalar>,
+    zd: Scalar,
+}
+
+/// Multiply every polynomial in `polys` by `a0 + a1 x` for index bit
+/// zero, and then by `b0 + b1 x` for index bit one.
+fn extend_polys(polys: Vec<Vec<Scalar>>, zero: [Scalar; 2], one: [Scalar; 2]) -> Vec<Vec<Scalar>> {
+    let mul = |p: &Vec<Scalar>, [c0, c1]: [Scalar; 2]| {
+        let mut q = vec![Scalar::zero(); p.len() + 1];
+        for (k, pk) in p.iter().enumerate() {
+            q[k] += c0 * pk;
+            q[k + 1] += c1 * pk;
+        }
+        q
+    };
+    polys.iter().map(|p| mul(p, zero))
+        .chain(polys.iter().map(|p| mul(p, one)))
+        .collect()
+}
+
+fn msm(bases: &[Jubjub], scalars: &[Scalar]) -> Group {
+    Group::msm(bases, scalars).expect("Equal numbers of bases and scalars")
+}
+
+/// One-out-of-many ring proofs, which need no trusted setup.
+pub struct OneOfMany;
+
+impl RingProofBackend for OneOfMany {
+    type Proof = OneOfManyRingProof;
+    type Prover = OneOfManyRingProver;
+    type Verifier = OneOfManyRing;
+    type Context = ();
+    /// We need the whole ring to verify, so our commitment is the ring.
+    type Commitment = Vec<PublicKey>;
+
+    fn commit(_ctx: &(), pks: &[PublicKey]) -> Vec<PublicKey> {
+        pks.to_vec()
+    }
+
+    fn verifier(_ctx: &(), pks: &Vec<PublicKey>) -> OneOfManyRing {
+        OneOfManyRing::new(pks)
+    }
+
+    fn prove(prover: &OneOfManyRingProver, secret: &SecretKey, secret_blinding: &SecretBlinding, key_commitment: &Jubjub) -> OneOfManyRingProof {
+        let OneOfManyRingProver { ring, index } = prover;
+        let n = ring.bits;
+        let g = Jubjub::generator();
+        let h = BLINDING_BASE;
+        let mut t = ring.transcript(key_commitment);
+
+        let mut w = secret.witness(&t, b"OneOfManyRingProof");
+        let mut read = |_| w.read_reduce::<Scalar>();
+        let r: Vec<Scalar> = (0..n).map(&mut read).collect();
+        let a: Vec<Scalar> = (0..n).map(&mut read).collect();
+        let s: Vec<Scalar> = (0..n).map(&mut read).collect();
+        let tau: Vec<Scalar> = (0..n).map(&mut read).collect();
+        let rho: Vec<Scalar> = (0..n).map(&mut read).collect();
+        let l: Vec<Scalar> = (0..n).map(|j| Scalar::from(((index >> j) & 1) as u64)).collect();
+
+        let commit = |m: Scalar, r: Scalar| g * m + h * r;
+        let cl = Group::normalize_batch(&(0..n).map(|j| commit(l[j], r[j])).collect::<Vec<_>>());
+        let ca = Group::normalize_batch(&(0..n).map(|j| commit(a[j], s[j])).collect::<Vec<_>>());
+        let cb = Group::normalize_batch(&(0..n).map(|j| commit(l[j] * a[j], tau[j])).collect::<Vec<_>>());
+
+        // Ring polynomials p_i(x) = prod_j f_{j,i_j}(x), where
+        // f_{j,1}(x) = l_j x + a_j and f_{j,0}(x) = x - f_{j,1}(x).
+        let polys = (0..n).fold(vec![vec![Scalar::one()]], |polys, j| {
+            extend_polys(polys, [-a[j], Scalar::one() - l[j]], [a[j], l[j]])
+        });
+        let c = key_commitment.into_group();
+        let d = Group::normalize_batch(&ring.keys.iter().map(|pk| c - pk).collect::<Vec<_>>());
+        let cd = Group::normalize_batch(&(0..n).map(|k| {
+            let coeffs: Vec<Scalar> = polys.iter().map(|p| p[k]).collect();
+            msm(&d, &coeffs) + h * rho[k]
+        }).collect::<Vec<_>>());
+
+        t.append_slice::<Jubjub,_>(&cl);
+        t.append_slice::<Jubjub,_>(&ca);
+        t.append_slice::<Jubjub,_>(&cb);
+        t.append_slice::<Jubjub,_>(&cd);
+        let x: Scalar = t.challenge(b"OneOfManyChallenge").read_reduce();
+
+        let f: Vec<Scalar> = (0..n).map(|j| l[j] * x + a[j]).collect();
+        let za = (0..n).map(|j| r[j] * x + s[j]).collect();
+        let zb = (0..n).map(|j| r[j] * (x - f[j]) + tau[j]).collect();
+        let mut zd = secret_blinding.0[0] * x.pow([n as u64]);
+        let mut xk = Scalar::one();
+        for rho_k in rho.iter() {
+            zd -= *rho_k * xk;
+            xk *= x;
+        }
+        OneOfManyRingProof { cl, ca, cb, cd, f, za, zb, zd }
+    }
+
+    fn verify(ring: &OneOfManyRing, proof: &OneOfManyRingProof, key_commitment: &Jubjub) -> bool {
+        let n = ring.bits;
+        let OneOfManyRingProof { cl, ca, cb, cd, f, za, zb, zd } = proof;
+        if [cl.len(), ca.len(), cb.len(), cd.len(), f.len(), za.len(), zb.len()].iter().any(|len| *len != n) {
+            return false;
+        }
+        let g = Jubjub::generator();
+        let h = BLINDING_BASE;
+        let mut t = ring.transcript(key_commitment);
+        t.append_slice::<Jubjub,_>(cl);
+        t.append_slice::<Jubjub,_>(ca);
+        t.append_slice::<Jubjub,_>(cb);
+        t.append_slice::<Jubjub,_>(cd);
+        let x: Scalar = t.challenge(b"OneOfManyChallenge").read_reduce();
+
+        // Bit commitments open to f_j = l_j x + a_j for bits l_j.
+        for j in 0..n {
+            let bit = msm(&[cl[j], ca[j], g, h], &[x, Scalar::one(), -f[j], -za[j]]);
+            let square = msm(&[cl[j], cb[j], h], &[x - f[j], Scalar::one(), -zb[j]]);
+            if !bit.is_zero() || !square.is_zero() {
+                return false;
+            }
+        }
+
+        // sum_i p_i(x) D_i - sum_k x^k cd_k = zd h, where sum_i p_i(x) = x^n.
+        let e = (0..n).fold(vec![Scalar::one()], |e, j| {
+            e.iter().map(|e| *e * (x - f[j])).chain(e.iter().map(|e| *e * f[j])).collect()
+        });
+        let mut bases = ring.keys.clone();
+        let mut scalars: Vec<Scalar> = e.iter().map(|e| -*e).collect();
+        let mut xk = Scalar::one();
+        for cd_k in cd.iter() {
+            bases.push(*cd_k);
+            scalars.push(-xk);
+            xk *= x;
+        }
+        bases.extend([*key_commitment, h]);
+        scalars.extend([xk, -*zd]);
+        msm(&bases, &scalars).is_zero()
+    }
+}
+
+
+#[cfg(all(test, feature = "getrandom"))]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendRingProver, BackendRingVerifier};
+    use crate::{Message, IntoVrfInput};
+
+    #[test]
+    fn one_of_many_ring_vrf() {
+        let secrets: Vec<_> = (0..5u8).map(|i| SecretKey::from_seed(&[i; 32])).collect();
+        let pks: Vec<_> = secrets.iter().map(|s| s.to_public()).collect();
+        let ring = OneOfManyRing::new(&pks);
+        assert_eq!(ring.len(), 8);
+
+        let input = Message { domain: b"domain", message: b"message" }.into_vrf_input();
+        let io = secrets[3].vrf_inout(input.clone());
+        let ring_prover = ring.prover(3).unwrap();
+        let prover = BackendRingProver::<OneOfMany> { ring_prover: &ring_prover, secret: &secrets[3] };
+        let signature = prover.sign_ring_vrf(b"ad", &[io]);
+
+        let mut bytes = Vec::new();
+        signature.serialize_compressed(&mut bytes).unwrap();
+        let signature = crate::VrfSignature::deserialize_compressed(bytes.as_slice()).unwrap();
+        let verifier = BackendRingVerifier::<OneOfMany>(&ring);
+        let [io2] = verifier.verify_ring_vrf(b"ad", [input.clone()], &signature).unwrap();
+        assert_eq!(io2.preoutput, io.preoutput);
+        verifier.verify_ring_vrf(b"other", [input.clone()], &signature).expect_err("WTF?!?");
+
+        // Signers outside the ring fail, even claiming a ring slot.
+        let outsider = SecretKey::from_seed(&[9; 32]);
+        let io = outsider.vrf_inout(input.clone());
+        let prover = BackendRingProver::<OneOfMany> { ring_prover: &ring_prover, secret: &outsider };
+        let signature = prover.sign_ring_vrf(b"ad", &[io]);
+        verifier.verify_ring_vrf(b"ad", [input], &signature).expect_err("WTF?!?");
+    }
+
+    #[test]
+    fn ring_proof_backend_commitments() {
+        let secrets: Vec<_> = (0..3u8).map(|i| SecretKey::from_seed(&[i; 32])).collect();
+        let pks: Vec<_> = secrets.iter().map(|s| s.to_public()).collect();
+        let commitment = OneOfMany::commit(&(), &pks);
+        let ring = OneOfMany::verifier(&(), &commitment);
+
+        let input = Message { domain: b"domain", message: b"message" }.into_vrf_input();
+        let io = secrets[1].vrf_inout(input.clone());
+        let ring_prover = ring.prover(1).unwrap();
+        let prover = BackendRingProver::<OneOfMany> { ring_prover: &ring_prover, secret: &secrets[1] };
+        let signature = prover.sign_ring_vrf(b"ad", &[io]);
+
+        let bytes = OneOfMany::serialize_proof(&signature.proof.ring_proof);
+        let ring_proof = OneOfMany::deserialize_proof(&bytes).unwrap();
+        assert_eq!(ring_proof, signature.proof.ring_proof);
+        let key_commitment = signature.proof.dleq_proof.as_key_commitment().0;
+        assert!(OneOfMany::verify(&ring, &ring_proof, &key_commitment));
+        OneOfMany::deserialize_proof(&bytes[1..]).expect_err("WTF?!?");
+    }
+}
@@ -2,12 +2,16 @@ extern crate alloc;
 
 use alloc::vec::Vec;
 
+use ark_ec::{AffineRepr, CurveGroup};
 use ark_ff::{Field, MontFp};
 use ark_serialize::{
     CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid,
     Validate, Write,
 };
-use ark_std::{rand::{Rng, SeedableRng}, vec};
+use ark_std::vec;
+#[cfg(any(test, feature = "testing"))]
+use ark_std::rand::{Rng, SeedableRng};
+#[cfg(any(test, feature = "testing"))]
 use fflonk::pcs::PCS;
 use merlin::Transcript;
 use ring::Domain;
@@ -58,8 +62,11 @@ pub fn make_ring_verifier(verifier_key: VerifierKey, domain_size: usize) -> Ring
     RingVerifier::init(verifier_key, piop_params, Transcript::new(b"ring-vrf-test"))
 }
 
+/// Ring VRF context, which manages the KZG SRS along with the PIOP
+/// parameters for one ring size, and from which we construct ring
+/// provers and verifiers for specific rings of public keys.
 #[derive(Clone)]
-pub struct KZG {
+pub struct RingContext {
     pub domain_size: u32,
     piop_params: PiopParams,
     pub pcs_params: PcsParams,
@@ -74,6 +81,47 @@ pub struct StaticVerifierKey {
     pub kzg_vk: KzgVk,
 }
 
+impl StaticVerifierKey {
+    fn lagrangian_bases(&self, range: core::ops::Range<usize>) -> Result<Vec<bls12_381::G1Affine>, ()> {
+        self.lag_g1.get(range).map(|bases| bases.to_vec()).ok_or(())
+    }
+
+    /// Check `domain_size` is the domain our Lagrangian bases serve,
+    /// since other domain sizes would make `make_piop_params` panic.
+    fn check_domain_size(&self, domain_size: usize) -> Result<(), RingError> {
+        check_domain_size(domain_size) ?;
+        if domain_size != self.lag_g1.len() {
+            return Err(RingError::DomainSize);
+        }
+        Ok(())
+    }
+
+    /// Compact KZG commitment to the ring `pks`, computed by one MSM over
+    /// our cached Lagrangian bases, without any FFT.
+    ///
+    /// Agrees with `RingContext::ring_commitment` for the same domain.
+    pub fn ring_commitment(&self, domain_size: usize, pks: &[crate::PublicKey]) -> Result<RingCommitment, RingError> {
+        self.check_domain_size(domain_size) ?;
+        let piop_params = make_piop_params(domain_size);
+        if pks.len() > piop_params.keyset_part_size {
+            return Err(RingError::TooManyKeys);
+        }
+        let pks: Vec<SWAffine> = pks.iter().map(|pk| pk.0).collect();
+        let mut ring = RingCommitment::empty(&piop_params, |r| self.lagrangian_bases(r), self.kzg_vk.g1.into());
+        ring.append(&pks, |r| self.lagrangian_bases(r));
+        Ok(ring)
+    }
+
+    /// Empty ring commitment, whose keys we then insert individually
+    /// using `lag_g1`.
+    pub fn dynamic_ring_commitment(&self, domain_size: usize) -> Result<DynamicRingCommitment, RingError> {
+        Ok(DynamicRingCommitment {
+            commitment: self.ring_commitment(domain_size, &[]) ?,
+            keys: vec![None; make_piop_params(domain_size).keyset_part_size],
+        })
+    }
+}
+
 #[derive(CanonicalDeserialize, CanonicalSerialize)]
 pub struct StaticProverKey {
     // `3N+1` monomial bases `G1, tau.G1, ..., tau^(3N).G1`, where `N=2^m` is domain size.
@@ -82,37 +130,106 @@ pub struct StaticProverKey {
     pub kzg_vk: KzgVk,
 }
 
-impl KZG {
-    // TODO: Import powers of tau
+/// Smallest evaluation domain we consider in `domain_size_for_ring`.
+const MIN_DOMAIN_SIZE: usize = 1 << 9;
+
+/// Largest evaluation domain our `u32` domain sizes could express.
+const MAX_DOMAIN_SIZE: usize = 1 << 31;
+
+/// Check `domain_size` is a power of two domain we support.
+fn check_domain_size(domain_size: usize) -> Result<(), RingError> {
+    if !domain_size.is_power_of_two() || !(MIN_DOMAIN_SIZE..=MAX_DOMAIN_SIZE).contains(&domain_size) {
+        return Err(RingError::DomainSize);
+    }
+    Ok(())
+}
+
+/// Smallest power of two domain whose keyset part holds `ring_size` keys,
+/// but no larger than `max_domain_size`, like our SRS supports.
+pub fn domain_size_for_ring(ring_size: usize, max_domain_size: usize) -> Result<usize, RingError> {
+    let mut domain_size = MIN_DOMAIN_SIZE;
+    loop {
+        if domain_size > max_domain_size.min(MAX_DOMAIN_SIZE) {
+            return Err(RingError::SrsTooSmall);
+        }
+        if make_piop_params(domain_size).keyset_part_size >= ring_size {
+            return Ok(domain_size);
+        }
+        domain_size <<= 1;
+    }
+}
+
+impl RingContext {
+    /// Insecure KZG setup with an SRS sampled from `rng`, so anyone who
+    /// knows `rng` knows the trapdoor and can forge ring proofs.
+    #[cfg(any(test, feature = "testing"))]
     pub fn insecure_kzg_setup<R: Rng>(domain_size: u32, rng: &mut R) -> Self {
         let piop_params = make_piop_params(domain_size as usize);
         let pcs_params = RealKZG::setup(3 * (domain_size as usize), rng);
-        KZG {
+        RingContext {
             domain_size,
             piop_params,
             pcs_params,
         }
     }
 
-    pub fn kzg_setup(domain_size: usize, srs: StaticProverKey) -> Self {
+    /// Ring context for the power of two `domain_size`, failing if
+    /// `srs` lacks the `3N+1` monomial bases this domain requires.
+    pub fn kzg_setup(domain_size: usize, srs: StaticProverKey) -> Result<Self, RingError> {
+        check_domain_size(domain_size) ?;
+        if srs.mon_g1.len() < 3 * domain_size + 1 {
+            return Err(RingError::SrsTooSmall);
+        }
         let piop_params = make_piop_params(domain_size);
         let pcs_params = fflonk::pcs::kzg::urs::URS  {
             powers_in_g1: srs.mon_g1,
             powers_in_g2: vec![srs.kzg_vk.g2, srs.kzg_vk.tau_in_g2],
         };
-        KZG {
+        Ok(RingContext {
             domain_size: domain_size as u32,
             piop_params,
             pcs_params,
-        }
+        })
     }
 
     // Testing only kzg setup.
+    #[cfg(any(test, feature = "testing"))]
     pub fn testing_kzg_setup(preseed: [u8;32], domain_size: u32) -> Self {
         let mut rng = rand_chacha::ChaCha20Rng::from_seed(preseed);
         Self::insecure_kzg_setup(domain_size, &mut rng)
     }
 
+    /// Insecure setup supporting rings of at least `ring_size` keys,
+    /// only for unit tests of ring VRF flows, never for deployment.
+    /// 
+    /// We choose the smallest power of two domain whose keyset part
+    /// holds `ring_size` keys.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn insecure_setup<R: Rng>(ring_size: usize, rng: &mut R) -> Result<Self, RingError> {
+        let domain_size = domain_size_for_ring(ring_size, MAX_DOMAIN_SIZE) ?;
+        Ok(Self::insecure_kzg_setup(domain_size as u32, rng))
+    }
+
+    /// Ring context supporting rings of at least `ring_size` keys, using
+    /// the smallest domain which fits them, so small chains could use
+    /// tiny SRS files.
+    ///
+    /// We require `3N+1` monomial bases in `srs` for domain size `N`,
+    /// so we never consider domains which `srs` cannot support.
+    pub fn new(srs: StaticProverKey, ring_size: usize) -> Result<Self, RingError> {
+        let max_domain_size = srs.mon_g1.len().saturating_sub(1) / 3;
+        let domain_size = domain_size_for_ring(ring_size, max_domain_size) ?;
+        Self::kzg_setup(domain_size, srs)
+    }
+
+    /// Check our domain supports rings of `ring_size` keys.
+    pub fn check_ring_size(&self, ring_size: usize) -> Result<(), RingError> {
+        if ring_size > self.max_keyset_size() {
+            return Err(RingError::TooManyKeys);
+        }
+        Ok(())
+    }
+
     pub fn max_keyset_size(&self) -> usize {
         self.piop_params.keyset_part_size
     }
@@ -128,12 +245,30 @@ impl KZG {
     }
     */
 
-    pub fn prover_key(&self, pks: Vec<SWAffine>) -> ProverKey {
-        ring::index(self.pcs_params.clone(), &self.piop_params, pks).0
+    /// Prover key for the ring `pks`, failing with `TooManyKeys`
+    /// unless our domain holds them.
+    pub fn prover_key(&self, pks: Vec<SWAffine>) -> Result<ProverKey, RingError> {
+        self.check_ring_size(pks.len()) ?;
+        Ok(ring::index(self.pcs_params.clone(), &self.piop_params, pks).0)
+    }
+
+    /// Verifier key for the ring `pks`, failing with `TooManyKeys`
+    /// unless our domain holds them.
+    pub fn verifier_key(&self, pks: Vec<SWAffine>) -> Result<VerifierKey, RingError> {
+        self.check_ring_size(pks.len()) ?;
+        Ok(ring::index(self.pcs_params.clone(), &self.piop_params, pks).1)
     }
 
-    pub fn verifier_key(&self, pks: Vec<SWAffine>) -> VerifierKey {
-        ring::index(self.pcs_params.clone(), &self.piop_params, pks).1
+    /// Prover key for the ring `pks`, ready for persisting.
+    pub fn ring_prover_key(&self, pks: &[crate::PublicKey]) -> Result<RingProverKey, RingError> {
+        let pks = pks.iter().map(|pk| pk.0).collect();
+        Ok(RingKey { domain_size: self.domain_size, key: self.prover_key(pks) ? })
+    }
+
+    /// Verifier key for the ring `pks`, ready for persisting.
+    pub fn ring_verifier_key(&self, pks: &[crate::PublicKey]) -> Result<RingVerifierKey, RingError> {
+        let pks = pks.iter().map(|pk| pk.0).collect();
+        Ok(RingKey { domain_size: self.domain_size, key: self.verifier_key(pks) ? })
     }
 
     /// `k` is the prover secret index in [0..keyset_size).
@@ -144,9 +279,356 @@ impl KZG {
     pub fn init_ring_verifier(&self, verifier_key: VerifierKey) -> RingVerifier {
         RingVerifier::init(verifier_key, self.piop_params.clone(), Transcript::new(b"ring-vrf-test"))
     }
+
+    /// Ring prover for the signer at index `k` in the ring `pks`.
+    pub fn ring_prover(&self, pks: &[crate::PublicKey], k: usize) -> Result<RingProver, RingError> {
+        let pks = pks.iter().map(|pk| pk.0).collect();
+        Ok(self.init_ring_prover(self.prover_key(pks) ?, k))
+    }
+
+    /// Ring verifier for the ring `pks`.
+    pub fn ring_verifier(&self, pks: &[crate::PublicKey]) -> Result<RingVerifier, RingError> {
+        let pks = pks.iter().map(|pk| pk.0).collect();
+        Ok(self.init_ring_verifier(self.verifier_key(pks) ?))
+    }
+
+    /// KZG verifier key, which `ring_verifier_from_commitment` needs
+    /// alongside the ring commitment.
+    pub fn kzg_vk(&self) -> KzgVk {
+        KzgVk {
+            g1: self.pcs_params.powers_in_g1[0],
+            g2: self.pcs_params.powers_in_g2[0],
+            tau_in_g2: self.pcs_params.powers_in_g2[1],
+        }
+    }
+
+    /// Compact KZG commitment to the ring `pks`, padded like `prover_key`.
+    pub fn ring_commitment(&self, pks: &[crate::PublicKey]) -> RingCommitment {
+        let pks: Vec<SWAffine> = pks.iter().map(|pk| pk.0).collect();
+        let builder_key = ring::ring::RingBuilderKey::from_srs(&self.pcs_params, self.domain_size as usize);
+        RingCommitment::with_keys(&self.piop_params, &pks, &builder_key)
+    }
+
+    /// Builder for rings padded to our keyset size.
+    pub fn ring_builder(&self) -> RingBuilder<'_> {
+        RingBuilder { ctx: self, keys: Vec::new() }
+    }
+
+    /// Lagrangian bases `L1(tau).G1, ..., LN(tau).G1` for updating
+    /// ring commitments, as in `StaticVerifierKey::lag_g1`.
+    pub fn lagrangian_bases(&self) -> Vec<bls12_381::G1Affine> {
+        ring::ring::RingBuilderKey::from_srs(&self.pcs_params, self.domain_size as usize).lis_in_g1
+    }
+
+    /// Precompute our Lagrangian bases, which nodes should cache in
+    /// serialized form, and then build ring commitments by
+    /// `StaticVerifierKey::ring_commitment`.
+    pub fn static_verifier_key(&self) -> StaticVerifierKey {
+        StaticVerifierKey { lag_g1: self.lagrangian_bases(), kzg_vk: self.kzg_vk() }
+    }
+
+    /// Empty ring commitment, whose keys we then insert individually.
+    pub fn dynamic_ring_commitment(&self) -> DynamicRingCommitment {
+        DynamicRingCommitment {
+            commitment: self.ring_commitment(&[]),
+            keys: vec![None; self.max_keyset_size()],
+        }
+    }
 }
 
-impl CanonicalSerialize for KZG {
+/// Ring construction and update failures, with stable numeric codes
+/// given by `code`, like `SignatureError`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum RingError {
+    /// Index lies beyond the ring's keyset part
+    IndexOutOfRange = 1,
+    /// Index already holds some public key
+    SlotOccupied = 2,
+    /// Index holds no public key
+    SlotEmpty = 3,
+    /// Ring holds more keys than the domain's keyset part
+    TooManyKeys = 4,
+    /// SRS holds too few powers for the domain needed by the ring size
+    SrsTooSmall = 5,
+    /// Domain size is no power of two matching our Lagrangian bases
+    DomainSize = 6,
+    /// Public key equals the padding point, so its slot would look empty
+    PaddingKey = 7,
+    /// Public key is the identity, which has no affine coordinates
+    IdentityKey = 8,
+}
+
+impl RingError {
+    /// Stable numeric code for this failure.
+    pub const fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Failure with the given stable numeric code, if any.
+    pub const fn from_code(code: u8) -> Option<RingError> {
+        use RingError::*;
+        Some(match code {
+            1 => IndexOutOfRange,
+            2 => SlotOccupied,
+            3 => SlotEmpty,
+            4 => TooManyKeys,
+            5 => SrsTooSmall,
+            6 => DomainSize,
+            7 => PaddingKey,
+            8 => IdentityKey,
+            _ => return None,
+        })
+    }
+}
+
+impl core::fmt::Display for RingError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            RingError::IndexOutOfRange => "index lies beyond the ring's keyset part",
+            RingError::SlotOccupied => "index already holds some public key",
+            RingError::SlotEmpty => "index holds no public key",
+            RingError::TooManyKeys => "ring holds more keys than the domain's keyset part",
+            RingError::SrsTooSmall => "SRS holds too few powers for the ring size",
+            RingError::DomainSize => "domain size is no power of two matching the Lagrangian bases",
+            RingError::PaddingKey => "public key equals the padding point",
+            RingError::IdentityKey => "public key is the identity",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RingError {}
+
+/// Builds rings of public keys for one `RingContext`, padding them with
+/// the well-known `PADDING_POINT` up to the domain's keyset size, so
+/// every implementation derives the same ring from the same keys.
+///
+/// We record the real ring size, and reject oversized rings.
+pub struct RingBuilder<'a> {
+    ctx: &'a RingContext,
+    keys: Vec<SWAffine>,
+}
+
+impl<'a> RingBuilder<'a> {
+    /// Append one public key to the ring.
+    pub fn push(&mut self, pk: &crate::PublicKey) -> Result<(), RingError> {
+        self.ctx.check_ring_size(self.keys.len() + 1) ?;
+        self.keys.push(pk.0);
+        Ok(())
+    }
+
+    /// Append many public keys to the ring, adding none if they
+    /// do not all fit.
+    pub fn extend(&mut self, pks: &[crate::PublicKey]) -> Result<(), RingError> {
+        self.ctx.check_ring_size(self.keys.len() + pks.len()) ?;
+        self.keys.extend(pks.iter().map(|pk| pk.0));
+        Ok(())
+    }
+
+    /// Real ring size, excluding padding.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Ring keys padded to the domain's keyset size.
+    pub fn padded_keys(&self) -> Vec<SWAffine> {
+        let mut keys = self.keys.clone();
+        keys.resize(self.ctx.max_keyset_size(), PADDING_POINT);
+        keys
+    }
+
+    /// Ring prover for the signer at index `k` among the real keys.
+    pub fn prover(&self, k: usize) -> Result<RingProver, RingError> {
+        if k >= self.keys.len() {
+            return Err(RingError::IndexOutOfRange);
+        }
+        Ok(self.ctx.init_ring_prover(self.ctx.prover_key(self.padded_keys()) ?, k))
+    }
+
+    /// Ring verifier for this ring.
+    pub fn verifier(&self) -> RingVerifier {
+        // We pad to exactly our keyset size, which `push` never exceeds.
+        let verifier_key = self.ctx.verifier_key(self.padded_keys())
+            .expect("Padded rings fill exactly our keyset part");
+        self.ctx.init_ring_verifier(verifier_key)
+    }
+}
+
+/// Ring commitment along with the keys it commits to, which we update
+/// by one Lagrangian base per key inserted or removed, instead of
+/// rebuilding from the whole key list.
+///
+/// Empty slots hold the ring's padding point, so an updated commitment
+/// equals the commitment built from scratch with the same keys.
+///
+/// Removing a key leaves a hole of padding in its slot, so other keys
+/// keep their indices, but the ring never shrinks.  Provers must index
+/// the ring by `padded_keys`, not by the real keys alone, and callers
+/// who want a compact ring should rebuild it.
+#[derive(Clone)]
+pub struct DynamicRingCommitment {
+    commitment: RingCommitment,
+    keys: Vec<Option<crate::PublicKey>>,
+}
+
+impl DynamicRingCommitment {
+    /// Compact commitment for `ring_verifier_from_commitment`.
+    pub fn commitment(&self) -> &RingCommitment {
+        &self.commitment
+    }
+
+    /// Public key at every slot, or `None` for padding.
+    pub fn keys(&self) -> &[Option<crate::PublicKey>] {
+        &self.keys
+    }
+
+    /// Every slot's key, with the padding point in empty slots, exactly
+    /// as our commitment commits to them.
+    pub fn padded_keys(&self) -> Vec<SWAffine> {
+        self.keys.iter()
+            .map(|k| k.as_ref().map_or(self.commitment.padding_point, |pk| pk.0))
+            .collect()
+    }
+
+    /// Add `(new - old).L_index(tau)` to the key columns' commitments.
+    fn update(&mut self, lag_g1: &[bls12_381::G1Affine], index: usize, old: SWAffine, new: SWAffine) -> Result<(), RingError> {
+        let (old_x, old_y) = old.xy().ok_or(RingError::IdentityKey) ?;
+        let (new_x, new_y) = new.xy().ok_or(RingError::IdentityKey) ?;
+        let l = *lag_g1.get(index).ok_or(RingError::IndexOutOfRange) ?;
+        self.commitment.cx = (l * (*new_x - old_x) + self.commitment.cx).into_affine();
+        self.commitment.cy = (l * (*new_y - old_y) + self.commitment.cy).into_affine();
+        Ok(())
+    }
+
+    /// Insert `pk` into the empty slot `index`, using Lagrangian bases
+    /// like `StaticVerifierKey::lag_g1` or `RingContext::lagrangian_bases`.
+    pub fn insert(&mut self, lag_g1: &[bls12_381::G1Affine], index: usize, pk: crate::PublicKey) -> Result<(), RingError> {
+        match self.keys.get(index) {
+            None => return Err(RingError::IndexOutOfRange),
+            Some(Some(_)) => return Err(RingError::SlotOccupied),
+            Some(None) => {},
+        }
+        if pk.0 == self.commitment.padding_point {
+            return Err(RingError::PaddingKey);
+        }
+        self.update(lag_g1, index, self.commitment.padding_point, pk.0) ?;
+        self.keys[index] = Some(pk);
+        Ok(())
+    }
+
+    /// Remove the public key at slot `index`, leaving a hole of padding
+    /// there, so no other key changes slots.
+    pub fn remove(&mut self, lag_g1: &[bls12_381::G1Affine], index: usize) -> Result<crate::PublicKey, RingError> {
+        let pk = self.keys.get(index).ok_or(RingError::IndexOutOfRange)?
+            .clone().ok_or(RingError::SlotEmpty) ?;
+        self.update(lag_g1, index, pk.0, self.commitment.padding_point) ?;
+        self.keys[index] = None;
+        Ok(pk)
+    }
+}
+
+/// Ring verifier from only a ring commitment, the KZG verifier key,
+/// and the domain size, so on-chain verifiers need not store the ring
+/// nor the SRS.
+pub fn ring_verifier_from_commitment(commitment: &RingCommitment, kzg_vk: KzgVk, domain_size: usize) -> RingVerifier {
+    make_ring_verifier(VerifierKey::from_ring_and_kzg_vk(commitment, kzg_vk), domain_size)
+}
+
+/// Version of our `RingKey` serialization format.
+pub const RING_KEY_VERSION: u8 = 1;
+
+/// Ring prover or verifier key along with its domain size, which nodes
+/// persist across restarts instead of rederiving from the SRS.
+///
+/// We serialize a version byte, the domain size, and then the key,
+/// and reject other versions when deserializing.
+#[derive(Clone)]
+pub struct RingKey<T> {
+    pub domain_size: u32,
+    pub key: T,
+}
+
+pub type RingProverKey = RingKey<ProverKey>;
+pub type RingVerifierKey = RingKey<VerifierKey>;
+
+impl RingKey<ProverKey> {
+    /// `k` is the prover secret index in [0..keyset_size).
+    pub fn into_ring_prover(self, k: usize) -> RingProver {
+        let piop_params = make_piop_params(self.domain_size as usize);
+        RingProver::init(self.key, piop_params, k, Transcript::new(b"ring-vrf-test"))
+    }
+}
+
+impl RingKey<VerifierKey> {
+    pub fn into_ring_verifier(self) -> RingVerifier {
+        make_ring_verifier(self.key, self.domain_size as usize)
+    }
+}
+
+impl<T: CanonicalSerialize> CanonicalSerialize for RingKey<T> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress
+    ) -> Result<(), SerializationError>
+    {
+        RING_KEY_VERSION.serialize_compressed(&mut writer) ?;
+        self.domain_size.serialize_compressed(&mut writer) ?;
+        self.key.serialize_with_mode(&mut writer, compress) ?;
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        RING_KEY_VERSION.compressed_size()
+        + self.domain_size.compressed_size()
+        + self.key.serialized_size(compress)
+    }
+}
+
+/// Deserialize a domain size, which must be a power of two domain we
+/// consider, since `make_piop_params` panics otherwise.
+fn deserialize_domain_size<R: Read>(reader: R) -> Result<u32, SerializationError> {
+    let domain_size = <u32 as CanonicalDeserialize>::deserialize_compressed(reader) ?;
+    let size = domain_size as usize;
+    if !domain_size.is_power_of_two() || !(MIN_DOMAIN_SIZE..=MAX_DOMAIN_SIZE).contains(&size) {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(domain_size)
+}
+
+impl<T: CanonicalDeserialize> CanonicalDeserialize for RingKey<T> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate
+    ) -> Result<Self, SerializationError>
+    {
+        let version = <u8 as CanonicalDeserialize>::deserialize_compressed(&mut reader) ?;
+        if version != RING_KEY_VERSION {
+            return Err(SerializationError::InvalidData);
+        }
+        let domain_size = deserialize_domain_size(&mut reader) ?;
+        let key = T::deserialize_with_mode(&mut reader, compress, validate) ?;
+        Ok(RingKey { domain_size, key })
+    }
+}
+
+impl<T: Valid> Valid for RingKey<T> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.key.check()
+    }
+}
+
+/// Formerly our name for `RingContext`.
+pub type KZG = RingContext;
+
+impl CanonicalSerialize for RingContext {
     // Required methods
     fn serialize_with_mode<W: Write>(
         &self,
@@ -165,17 +647,17 @@ impl CanonicalSerialize for KZG {
     }
 }
 
-impl CanonicalDeserialize for KZG {
+impl CanonicalDeserialize for RingContext {
     fn deserialize_with_mode<R: Read>(
         mut reader: R,
         compress: Compress,
         validate: Validate
     ) -> Result<Self, SerializationError>
     {
-        let domain_size = <u32 as CanonicalDeserialize>::deserialize_compressed(&mut reader) ?;
+        let domain_size = deserialize_domain_size(&mut reader) ?;
         let piop_params = make_piop_params(domain_size as usize);
         let pcs_params = <PcsParams as CanonicalDeserialize>::deserialize_with_mode(&mut reader, compress, validate) ?;
-        Ok(KZG {
+        Ok(RingContext {
             domain_size,
             piop_params,
             pcs_params,
@@ -183,7 +665,7 @@ impl CanonicalDeserialize for KZG {
     }
 }
 
-impl Valid for KZG {
+impl Valid for RingContext {
     fn check(&self) -> Result<(), SerializationError> {
         self.pcs_params.check()
     }
@@ -198,6 +680,134 @@ mod tests {
         assert_eq!(COMPLEMENT_POINT, ring::find_complement_point::<crate::bandersnatch::BandersnatchConfig>());
     }
 
+    #[test]
+    fn insecure_setup_fits_ring() {
+        let mut rng = rand_chacha::ChaCha20Rng::from_seed([0; 32]);
+        let ctx = RingContext::insecure_setup(300, &mut rng).unwrap();
+        assert!(ctx.max_keyset_size() >= 300);
+        assert_eq!(ctx.domain_size, 1 << 10);
+    }
+
+    #[test]
+    fn dynamic_ring_commitment() {
+        let ctx = RingContext::testing_kzg_setup([0; 32], 1 << 9);
+        let lag_g1 = ctx.lagrangian_bases();
+        let pks: Vec<_> = (0..4u8).map(|i| crate::SecretKey::from_seed(&[i; 32]).to_public()).collect();
+
+        let mut dynamic = ctx.dynamic_ring_commitment();
+        for (i, pk) in pks.iter().enumerate() {
+            dynamic.insert(&lag_g1, i, pk.clone()).unwrap();
+        }
+        let full = ctx.ring_commitment(&pks);
+        assert_eq!((dynamic.commitment().cx, dynamic.commitment().cy), (full.cx, full.cy));
+        assert_eq!(dynamic.insert(&lag_g1, 0, pks[0].clone()), Err(RingError::SlotOccupied));
+        assert_eq!(dynamic.insert(&lag_g1, ctx.max_keyset_size(), pks[0].clone()), Err(RingError::IndexOutOfRange));
+
+        assert_eq!(dynamic.insert(&lag_g1, 4, dleq_vrf::PublicKey(PADDING_POINT)), Err(RingError::PaddingKey));
+        assert_eq!(dynamic.insert(&lag_g1, 4, dleq_vrf::PublicKey(SWAffine::zero())), Err(RingError::IdentityKey));
+        assert_eq!(dynamic.insert(&lag_g1[..4], 4, pks[0].clone()), Err(RingError::IndexOutOfRange));
+
+        assert_eq!(dynamic.remove(&lag_g1, 3).unwrap(), pks[3]);
+        assert_eq!(dynamic.remove(&lag_g1, 3), Err(RingError::SlotEmpty));
+        let full = ctx.ring_commitment(&pks[..3]);
+        assert_eq!((dynamic.commitment().cx, dynamic.commitment().cy), (full.cx, full.cy));
+
+        // Removing a middle key leaves a hole of padding.
+        assert_eq!(dynamic.remove(&lag_g1, 1).unwrap(), pks[1]);
+        let padded = dynamic.padded_keys();
+        assert_eq!((padded[0], padded[1], padded[2]), (pks[0].0, PADDING_POINT, pks[2].0));
+        let mut holed = pks[..3].to_vec();
+        holed[1] = dleq_vrf::PublicKey(PADDING_POINT);
+        let full = ctx.ring_commitment(&holed);
+        assert_eq!((dynamic.commitment().cx, dynamic.commitment().cy), (full.cx, full.cy));
+    }
+
+    #[test]
+    fn lagrangian_ring_commitment() {
+        let ctx = RingContext::testing_kzg_setup([0; 32], 1 << 9);
+        let pks: Vec<_> = (0..4u8).map(|i| crate::SecretKey::from_seed(&[i; 32]).to_public()).collect();
+
+        let mut bytes = Vec::new();
+        ctx.static_verifier_key().serialize_compressed(&mut bytes).unwrap();
+        let svk = StaticVerifierKey::deserialize_compressed(bytes.as_slice()).unwrap();
+
+        let fast = svk.ring_commitment(ctx.domain_size as usize, &pks).unwrap();
+        let full = ctx.ring_commitment(&pks);
+        assert_eq!((fast.cx, fast.cy, fast.selector), (full.cx, full.cy, full.selector));
+
+        // We reject foreign or malformed domains, and oversized rings.
+        assert_eq!(svk.ring_commitment(1 << 10, &pks).err(), Some(RingError::DomainSize));
+        assert_eq!(svk.ring_commitment((1 << 9) + 1, &pks).err(), Some(RingError::DomainSize));
+        assert_eq!(svk.dynamic_ring_commitment(1 << 8).err(), Some(RingError::DomainSize));
+        let too_many = vec![pks[0].clone(); ctx.max_keyset_size() + 1];
+        assert_eq!(svk.ring_commitment(ctx.domain_size as usize, &too_many).err(), Some(RingError::TooManyKeys));
+    }
+
+    #[test]
+    fn ring_builder_pads_and_rejects() {
+        let ctx = RingContext::testing_kzg_setup([0; 32], 1 << 9);
+        let pks: Vec<_> = (0..4u8).map(|i| crate::SecretKey::from_seed(&[i; 32]).to_public()).collect();
+        let mut builder = ctx.ring_builder();
+        builder.extend(&pks).unwrap();
+        assert_eq!(builder.len(), 4);
+        let padded = builder.padded_keys();
+        assert_eq!(padded.len(), ctx.max_keyset_size());
+        assert!(padded[4..].iter().all(|p| *p == PADDING_POINT));
+        assert_eq!(builder.prover(4).err(), Some(RingError::IndexOutOfRange));
+
+        let too_many = vec![pks[0].clone(); ctx.max_keyset_size()];
+        assert_eq!(builder.extend(&too_many), Err(RingError::TooManyKeys));
+        assert_eq!(builder.len(), 4);
+    }
+
+    #[test]
+    fn ring_context_for_ring_size() {
+        let mut rng = rand_chacha::ChaCha20Rng::from_seed([0; 32]);
+        let pcs_params = RealKZG::setup(3 * (1 << 9), &mut rng);
+        let ctx = RingContext::insecure_kzg_setup(1 << 9, &mut rng);
+        let srs = || StaticProverKey {
+            mon_g1: pcs_params.powers_in_g1.clone(),
+            kzg_vk: ctx.kzg_vk(),
+        };
+        let small = RingContext::new(srs(), 100).unwrap();
+        assert_eq!(small.domain_size, 1 << 9);
+        assert_eq!(small.check_ring_size(small.max_keyset_size() + 1), Err(RingError::TooManyKeys));
+        assert_eq!(RingContext::new(srs(), 300).err(), Some(RingError::SrsTooSmall));
+        assert_eq!(RingContext::new(srs(), usize::MAX).err(), Some(RingError::SrsTooSmall));
+        assert_eq!(domain_size_for_ring(100, 1 << 8), Err(RingError::SrsTooSmall));
+
+        assert_eq!(RingContext::kzg_setup(1 << 9, srs()).unwrap().domain_size, 1 << 9);
+        assert_eq!(RingContext::kzg_setup((1 << 9) + 1, srs()).err(), Some(RingError::DomainSize));
+        assert_eq!(RingContext::kzg_setup(1 << 8, srs()).err(), Some(RingError::DomainSize));
+        assert_eq!(RingContext::kzg_setup(1 << 10, srs()).err(), Some(RingError::SrsTooSmall));
+    }
+
+    #[test]
+    fn ring_constructors_reject_too_many_keys() {
+        let ctx = RingContext::testing_kzg_setup([0; 32], 1 << 9);
+        let pk = crate::SecretKey::from_seed(&[0; 32]).to_public();
+        let pks = vec![pk; ctx.max_keyset_size() + 1];
+        let points: Vec<SWAffine> = pks.iter().map(|pk| pk.0).collect();
+        assert_eq!(ctx.prover_key(points.clone()).err(), Some(RingError::TooManyKeys));
+        assert_eq!(ctx.verifier_key(points).err(), Some(RingError::TooManyKeys));
+        assert_eq!(ctx.ring_prover_key(&pks).err(), Some(RingError::TooManyKeys));
+        assert_eq!(ctx.ring_verifier_key(&pks).err(), Some(RingError::TooManyKeys));
+        assert!(matches!(ctx.ring_prover(&pks, 0), Err(RingError::TooManyKeys)));
+        assert!(matches!(ctx.ring_verifier(&pks), Err(RingError::TooManyKeys)));
+    }
+
+    #[test]
+    fn ring_error_codes() {
+        for code in 0..=u8::MAX {
+            if let Some(e) = RingError::from_code(code) {
+                assert_eq!(e.code(), code);
+            }
+        }
+        assert_eq!(RingError::IndexOutOfRange.code(), 1);
+        assert_eq!(RingError::IdentityKey.code(), 8);
+        assert_eq!(RingError::from_code(0), None);
+    }
+
     #[test]
     fn check_padding_point() {
         let padding_point = ring::hash_to_curve::<crate::Jubjub>(b"w3f/ring-proof/common/padding");
@@ -0,0 +1,179 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Sassafras tickets
+//!
+//! Sassafras validators anonymously submit tickets for block production
+//! slots, each being a ring VRF signature upon a ticket identifier input
+//! derived from the epoch randomness, the epoch index, and an attempt
+//! index.  The ticket identifier comes from the VRF output, and the
+//! signature also signs the `TicketBody` as associated data.
+//!
+//! Block authors claim their slots with thin VRF signatures upon a
+//! slot claim input.
+//!
+//! We encode here the exact VRF inputs and transcripts used by
+//! Substrate's `sp-consensus-sassafras`, so runtimes need not hand
+//! roll them:
+//!
+//! - All inputs use domain `b"sassafras"`, and messages which begin
+//!   with `b"ticket-id"`, `b"revealed"`, or `b"slot-claim"` respectively,
+//!   followed by `randomness || attempt || epoch`, or by
+//!   `randomness || slot || epoch` for slot claims, with integers
+//!   little endian.
+//! - Ticket bodies get signed via a transcript labeled
+//!   `b"sassafras-ticket-body-transcript"` which appends the body's
+//!   SCALE encoding, like Substrate's `VrfSignData::new_unchecked`.
+//! - Slot claims get signed via a transcript labeled
+//!   `b"sassafras-slot-claim-transcript"` with no further data.
+//! - Ticket identifiers are the first 16 bytes of the VRF output with
+//!   the transcript labeled `b"ticket-id"`, read as a little endian
+//!   `u128`, like Substrate's `make_bytes::<16>(b"ticket-id", input)`.
+//!
+//! Substrate hashed inputs to the curve before we adopted Elligator2,
+//! so our ticket identifiers differ from those of such deployments,
+//! even though our transcripts agree.
+
+use crate::{
+    Message, IntoVrfInput, VrfInput, VrfInOut, Transcript, SecretKey, PublicKey,
+    RingProver, RingVerifier, RingVrfSignature, ThinVrfSignature, SignatureResult,
+};
+
+
+/// Epoch randomness, as in Sassafras.
+pub type Randomness = [u8; 32];
+
+/// Ticket identifier, compared against the epoch's ticket threshold.
+pub type TicketId = u128;
+
+/// Slot index, as in Sassafras.
+pub type Slot = u64;
+
+/// Ticket contents signed as associated data by the ticket's ring VRF
+/// signature.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct TicketBody {
+    /// Attempt index
+    pub attempt_idx: u32,
+    /// Ephemeral public key, which gets erased once the ticket gets claimed
+    pub erased_public: [u8; 32],
+    /// Ephemeral public key revealed when the ticket gets claimed
+    pub revealed_public: [u8; 32],
+}
+
+/// Length of a `TicketBody` SCALE encoding.
+pub const TICKET_BODY_LENGTH: usize = 4 + 32 + 32;
+
+impl TicketBody {
+    /// SCALE encoding, being the attempt index little endian, followed
+    /// by both public keys.
+    pub fn encode(&self) -> [u8; TICKET_BODY_LENGTH] {
+        let mut bytes = [0u8; TICKET_BODY_LENGTH];
+        bytes[..4].copy_from_slice(&self.attempt_idx.to_le_bytes());
+        bytes[4..36].copy_from_slice(&self.erased_public);
+        bytes[36..].copy_from_slice(&self.revealed_public);
+        bytes
+    }
+}
+
+/// Ticket as submitted on-chain, being its body and ring VRF signature.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct TicketEnvelope {
+    pub body: TicketBody,
+    pub signature: RingVrfSignature<1>,
+}
+
+fn input(label: &[u8], randomness: &Randomness, index: &[u8], epoch: u64) -> VrfInput {
+    let message = [label, &randomness[..], index, &epoch.to_le_bytes()].concat();
+    Message { domain: b"sassafras", message: &message }.into_vrf_input()
+}
+
+/// VRF input for the ticket identifier of attempt `attempt` in epoch `epoch`.
+pub fn ticket_id_input(randomness: &Randomness, attempt: u32, epoch: u64) -> VrfInput {
+    input(b"ticket-id", randomness, &attempt.to_le_bytes(), epoch)
+}
+
+/// VRF input for the revealed key of attempt `attempt` in epoch `epoch`.
+pub fn revealed_key_input(randomness: &Randomness, attempt: u32, epoch: u64) -> VrfInput {
+    input(b"revealed", randomness, &attempt.to_le_bytes(), epoch)
+}
+
+/// VRF input for claiming slot `slot` in epoch `epoch`.
+pub fn slot_claim_input(randomness: &Randomness, slot: Slot, epoch: u64) -> VrfInput {
+    input(b"slot-claim", randomness, &slot.to_le_bytes(), epoch)
+}
+
+/// Transcript by which ticket signatures sign their `TicketBody`.
+pub fn ticket_body_transcript(body: &TicketBody) -> Transcript {
+    let mut t = Transcript::new_labeled(b"sassafras-ticket-body-transcript");
+    t.append(&body.encode()[..]);
+    t
+}
+
+/// Transcript signed by slot claims.
+pub fn slot_claim_transcript() -> Transcript {
+    Transcript::new_labeled(b"sassafras-slot-claim-transcript")
+}
+
+/// Ticket identifier from the ticket identifier's `VrfInOut`.
+pub fn make_ticket_id(io: &VrfInOut) -> TicketId {
+    TicketId::from_le_bytes(io.vrf_output_bytes(Transcript::new_labeled(b"ticket-id")))
+}
+
+/// Sign a ticket for `body.attempt_idx`, returning its identifier and
+/// its envelope.
+/// 
+/// Callers should check the identifier lies below the epoch's ticket
+/// threshold before submitting the envelope.
+pub fn sign_ticket(
+    ring_prover: &RingProver,
+    randomness: &Randomness,
+    epoch: u64,
+    body: TicketBody,
+) -> (TicketId, TicketEnvelope)
+{
+    let secret: &SecretKey = ring_prover.secret;
+    let io = secret.vrf_inout(ticket_id_input(randomness, body.attempt_idx, epoch));
+    let signature = ring_prover.sign_ring_vrf(ticket_body_transcript(&body), &[io]);
+    (make_ticket_id(&io), TicketEnvelope { body, signature })
+}
+
+/// Verify a ticket's ring VRF signature, returning its identifier.
+pub fn verify_ticket(
+    ring_verifier: &RingVerifier,
+    randomness: &Randomness,
+    epoch: u64,
+    envelope: &TicketEnvelope,
+) -> SignatureResult<TicketId>
+{
+    let input = ticket_id_input(randomness, envelope.body.attempt_idx, epoch);
+    let [io] = ring_verifier.verify_ring_vrf(
+        ticket_body_transcript(&envelope.body), [input], &envelope.signature,
+    ) ?;
+    Ok(make_ticket_id(&io))
+}
+
+/// Claim slot `slot` in epoch `epoch`.
+pub fn sign_slot_claim(
+    secret: &SecretKey,
+    randomness: &Randomness,
+    slot: Slot,
+    epoch: u64,
+) -> ThinVrfSignature<1>
+{
+    let io = secret.vrf_inout(slot_claim_input(randomness, slot, epoch));
+    secret.sign_thin_vrf(slot_claim_transcript(), &[io])
+}
+
+/// Verify a claim upon slot `slot` in epoch `epoch`, returning its `VrfInOut`.
+pub fn verify_slot_claim(
+    public: &PublicKey,
+    randomness: &Randomness,
+    slot: Slot,
+    epoch: u64,
+    signature: &ThinVrfSignature<1>,
+) -> SignatureResult<VrfInOut>
+{
+    let input = slot_claim_input(randomness, slot, epoch);
+    let [io] = public.verify_thin_vrf(slot_claim_transcript(), [input], signature) ?;
+    Ok(io)
+}
@@ -0,0 +1,209 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Specification conformance harness
+//!
+//! We check ECVRF vectors given as JSON arrays of objects, whose
+//! fields follow the W3F bandersnatch VRFs specification's vectors,
+//! being `comment`, `sk`, `pk`, `alpha`, `salt`, `ad`, `h`, `gamma`,
+//! `beta`, `proof_c`, and `proof_s`, all but `comment` in hex.
+//! Downstream CI could run `check_json` against vectors from any
+//! implementation of the `Bandersnatch_SHA-512_ELL2` suite.
+//!
+//! We check byte-for-byte that `sk` yields `pk`, that `salt || alpha`
+//! hashes to `h`, that `sk` maps `h` to `gamma`, that `gamma` hashes
+//! to `beta`, and that our own proof with associated data `ad` has
+//! exactly the challenge `proof_c` and response `proof_s`, and that
+//! the given proof verifies.  Points use the twisted Edwards encoding
+//! of `BandersnatchSha512Ell2`.
+//!
+//! `SPEC_VECTORS` embeds the specification's vectors, as published
+//! in `data/vectors/bandersnatch_sha-512_ell2_ietf.json` of ark-vrf 0.1.0.
+
+use ark_ff::PrimeField;
+use ark_std::{fmt, string::{String, ToString}, vec::Vec};
+
+use dleq_vrf::ietf::{self, Suite};
+
+use crate::{
+    CanonicalSerialize, CanonicalDeserialize, SecretKey, BandersnatchSha512Ell2, IetfProof,
+    bandersnatch::Fr, thin_vrf,
+};
+
+
+/// The specification's vectors for the `Bandersnatch_SHA-512_ELL2` suite.
+pub const SPEC_VECTORS: &str = include_str!("../vectors/bandersnatch_sha-512_ell2_ietf.json");
+
+/// Conformance failures, naming the first field which failed.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum ConformanceError {
+    /// Input was not a JSON array of objects.
+    Json,
+    /// Vector `index` lacks field `field`, or it is not valid hex or encoding.
+    Field { index: usize, field: &'static str },
+    /// Vector `index` disagrees with our implementation at `field`.
+    Mismatch { index: usize, field: &'static str },
+}
+
+impl fmt::Display for ConformanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConformanceError::Json => f.write_str("expected a JSON array of objects"),
+            ConformanceError::Field { index, field } => write!(f, "vector {}: invalid field {}", index, field),
+            ConformanceError::Mismatch { index, field } => write!(f, "vector {}: mismatch in {}", index, field),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConformanceError {}
+
+/// One ECVRF vector in the specification's JSON schema.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct SpecVector {
+    pub comment: String,
+    /// Secret scalar, little endian.
+    pub sk: Vec<u8>,
+    pub pk: Vec<u8>,
+    pub alpha: Vec<u8>,
+    /// Prefix hashed to the curve along with `alpha`.
+    pub salt: Vec<u8>,
+    /// Associated data hashed into the challenge.
+    pub ad: Vec<u8>,
+    /// VRF input point.
+    pub h: Vec<u8>,
+    /// VRF pre-output point.
+    pub gamma: Vec<u8>,
+    /// VRF output.
+    pub beta: Vec<u8>,
+    pub proof_c: Vec<u8>,
+    pub proof_s: Vec<u8>,
+}
+
+fn point_to_string(p: &crate::Jubjub) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    BandersnatchSha512Ell2::point_to_string(p, &mut bytes);
+    bytes
+}
+
+impl SpecVector {
+    /// Generate a vector from our implementation.
+    pub fn generate(comment: &str, sk: [u8; 32], alpha: &[u8], salt: &[u8], ad: &[u8]) -> SpecVector {
+        let key = Fr::from_le_bytes_mod_order(&sk);
+        let secret = SecretKey::from_parts(key, &[0; 32]);
+        let h = BandersnatchSha512Ell2::encode_to_curve(&[salt, alpha].concat());
+        let proof = ietf::prove::<BandersnatchSha512Ell2>(&secret, h, ad);
+        let bytes = proof.to_bytes();
+        let (proof_c, proof_s) = bytes[32..].split_at(BandersnatchSha512Ell2::CHALLENGE_LENGTH);
+        let mut sk = Vec::new();
+        key.serialize_compressed(&mut sk).expect("Vec serialization is infallible");
+        SpecVector {
+            comment: comment.to_string(),
+            sk,
+            pk: point_to_string(&secret.as_publickey().0),
+            alpha: alpha.to_vec(),
+            salt: salt.to_vec(),
+            ad: ad.to_vec(),
+            h: point_to_string(&h.0),
+            gamma: point_to_string(&proof.gamma.0),
+            beta: ietf::proof_to_hash(&proof).to_vec(),
+            proof_c: proof_c.to_vec(),
+            proof_s: proof_s.to_vec(),
+        }
+    }
+
+    /// Check this vector against our implementation, see the module docs.
+    pub fn check(&self, index: usize) -> Result<(), ConformanceError> {
+        let field = |field| ConformanceError::Field { index, field };
+        let mismatch = |field| ConformanceError::Mismatch { index, field };
+
+        let sk: [u8; 32] = self.sk.as_slice().try_into().map_err(|_| field("sk")) ?;
+        Fr::deserialize_compressed(&sk[..]).map_err(|_| field("sk")) ?;
+        let ours = SpecVector::generate(&self.comment, sk, &self.alpha, &self.salt, &self.ad);
+        for ((name, theirs), (_, ours)) in self.fields().into_iter().zip(ours.fields()) {
+            if theirs != ours { return Err(mismatch(name)); }
+        }
+
+        // Verify their proof independently of our own proving.
+        let public = BandersnatchSha512Ell2::string_to_point(&mut self.pk.as_slice())
+            .map(dleq_vrf::PublicKey).map_err(|_| field("pk")) ?;
+        let h = BandersnatchSha512Ell2::string_to_point(&mut self.h.as_slice())
+            .map(dleq_vrf::VrfInput).map_err(|_| field("h")) ?;
+        let bytes = [&self.gamma[..], &self.proof_c, &self.proof_s].concat();
+        let proof = IetfProof::from_bytes(&bytes).map_err(|_| field("proof")) ?;
+        let beta = ietf::verify(&thin_vrf(), &public, h, &self.ad, &proof).map_err(|_| mismatch("proof")) ?;
+        if beta[..] != self.beta[..] { return Err(mismatch("beta")); }
+        Ok(())
+    }
+
+    fn fields(&self) -> [(&'static str, &[u8]); 10] {
+        [
+            ("sk", &self.sk),
+            ("pk", &self.pk),
+            ("alpha", &self.alpha),
+            ("salt", &self.salt),
+            ("ad", &self.ad),
+            ("h", &self.h),
+            ("gamma", &self.gamma),
+            ("beta", &self.beta),
+            ("proof_c", &self.proof_c),
+            ("proof_s", &self.proof_s),
+        ]
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut obj = serde_json::Map::new();
+        obj.insert("comment".to_string(), self.comment.clone().into());
+        for (name, bytes) in self.fields() {
+            obj.insert(name.to_string(), hex::encode(bytes).into());
+        }
+        obj.into()
+    }
+
+    fn from_json(index: usize, value: &serde_json::Value) -> Result<SpecVector, ConformanceError> {
+        let obj = value.as_object().ok_or(ConformanceError::Json) ?;
+        let take = |field: &'static str| -> Result<Vec<u8>, ConformanceError> {
+            obj.get(field).and_then(|v| v.as_str())
+                .and_then(|s| hex::decode(s).ok())
+                .ok_or(ConformanceError::Field { index, field })
+        };
+        // Absent salt or associated data means empty ones.
+        let optional = |field: &'static str| -> Result<Vec<u8>, ConformanceError> {
+            if obj.contains_key(field) { take(field) } else { Ok(Vec::new()) }
+        };
+        Ok(SpecVector {
+            comment: obj.get("comment").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+            sk: take("sk")?,
+            pk: take("pk")?,
+            alpha: take("alpha")?,
+            salt: optional("salt")?,
+            ad: optional("ad")?,
+            h: take("h")?,
+            gamma: take("gamma")?,
+            beta: take("beta")?,
+            proof_c: take("proof_c")?,
+            proof_s: take("proof_s")?,
+        })
+    }
+}
+
+/// Parse a JSON array of vectors.
+pub fn parse_json(json: &str) -> Result<Vec<SpecVector>, ConformanceError> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|_| ConformanceError::Json) ?;
+    let array = value.as_array().ok_or(ConformanceError::Json) ?;
+    array.iter().enumerate().map(|(i, v)| SpecVector::from_json(i, v)).collect()
+}
+
+/// Emit vectors as a JSON array.
+pub fn emit_json(vectors: &[SpecVector]) -> String {
+    let array: Vec<serde_json::Value> = vectors.iter().map(SpecVector::to_json).collect();
+    serde_json::to_string_pretty(&array).expect("JSON values serialize")
+}
+
+/// Check every vector in a JSON array, returning how many passed.
+pub fn check_json(json: &str) -> Result<usize, ConformanceError> {
+    let vectors = parse_json(json) ?;
+    for (i, v) in vectors.iter().enumerate() {
+        v.check(i) ?;
+    }
+    Ok(vectors.len())
+}
@@ -0,0 +1,270 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Importing published KZG setups
+//!
+//! We load our `StaticProverKey` from existing BLS12-381 powers-of-tau
+//! ceremonies, so deployments need no new trusted setup:
+//!
+//! - Zcash's and Filecoin's powers-of-tau `challenge` and `response`
+//!   files, which begin with a 64 byte hash, followed by `2L-1` powers
+//!   of tau in G1 and then `L` powers of tau in G2, all uncompressed
+//!   in challenges and compressed in responses.
+//! - Ethereum's KZG ceremony transcript, whose JSON holds `G1Powers`
+//!   and `G2Powers` as lists of compressed hex points.
+//!
+//! All use the Zcash point encoding, which arkworks' BLS12-381 uses
+//! too.  We check every point lies in the prime order subgroup, that
+//! the first powers are the generators, and that consecutive powers
+//! have the same ratio, via one randomized pairing check.
+//!
+//! A domain of size `N` needs `3N+1` powers in G1, so Ethereum's 4096
+//! G1 powers support domains up to `2^10`.
+
+use std::io::Read;
+
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM, pairing::Pairing};
+use ark_serialize::{CanonicalDeserialize, SerializationError};
+use ark_std::{vec::Vec, Zero};
+use serde_json::Value;
+
+use crate::{Transcript, bls12_381::{Bls12_381, G1Affine, G2Affine}};
+use crate::ring::{KzgVk, StaticProverKey};
+
+
+/// Powers-of-tau import failures
+#[derive(Debug)]
+pub enum SrsError {
+    /// Malformed point encoding, or point outside the prime order subgroup
+    Serialization(SerializationError),
+    /// Malformed Ethereum ceremony JSON
+    Json,
+    /// Ceremony holds too few powers for the requested domain size
+    NotEnoughPowers,
+    /// First powers are not the standard generators
+    NotGenerators,
+    /// Consecutive powers do not share one ratio
+    RatioCheck,
+}
+
+impl core::fmt::Display for SrsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SrsError::Serialization(e) => write!(f, "malformed SRS point: {}", e),
+            SrsError::Json => f.write_str("malformed Ethereum ceremony JSON"),
+            SrsError::NotEnoughPowers => f.write_str("ceremony holds too few powers for the domain"),
+            SrsError::NotGenerators => f.write_str("first powers are not the standard generators"),
+            SrsError::RatioCheck => f.write_str("consecutive powers do not share one ratio"),
+        }
+    }
+}
+
+impl std::error::Error for SrsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SrsError::Serialization(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<SerializationError> for SrsError {
+    fn from(e: SerializationError) -> SrsError {
+        SrsError::Serialization(e)
+    }
+}
+
+impl From<std::io::Error> for SrsError {
+    fn from(e: std::io::Error) -> SrsError {
+        SrsError::Serialization(SerializationError::IoError(e))
+    }
+}
+
+/// Check the powers of tau start from the generators, and that every
+/// consecutive pair in G1 shares the ratio of the two G2 powers.
+///
+/// We combine all ratios using challenges from a transcript of every
+/// power, so one pairing check suffices.
+pub fn check_powers(g1: &[G1Affine], g2: G2Affine, tau_g2: G2Affine) -> Result<(), SrsError> {
+    if g1.len() < 2 { return Err(SrsError::NotEnoughPowers); }
+    if g1[0] != G1Affine::generator() || g2 != G2Affine::generator() {
+        return Err(SrsError::NotGenerators);
+    }
+    let mut t = Transcript::new_labeled(b"PowersOfTau");
+    t.append_slice::<G1Affine,_>(g1);
+    t.append(&tau_g2);
+    let mut challenges = t.challenge(b"ratios");
+    let r: Vec<<Bls12_381 as Pairing>::ScalarField> = (1..g1.len())
+        .map(|_| challenges.read_reduce()).collect();
+
+    let lo = <Bls12_381 as Pairing>::G1::msm(&g1[..g1.len() - 1], &r)
+        .expect("Equal numbers of bases and scalars");
+    let hi = <Bls12_381 as Pairing>::G1::msm(&g1[1..], &r)
+        .expect("Equal numbers of bases and scalars");
+    let gt = Bls12_381::multi_pairing(
+        [hi.into_affine(), (-lo).into_affine()],
+        [g2, tau_g2],
+    );
+    if gt.is_zero() { Ok(()) } else { Err(SrsError::RatioCheck) }
+}
+
+/// Number `3N+1` of G1 powers needed for domains of size `N`.
+fn g1_powers_needed(domain_size: usize) -> Result<usize, SrsError> {
+    domain_size.checked_mul(3).and_then(|n| n.checked_add(1))
+        .ok_or(SrsError::NotEnoughPowers)
+}
+
+fn static_prover_key(mon_g1: Vec<G1Affine>, g2: G2Affine, tau_g2: G2Affine) -> Result<StaticProverKey, SrsError> {
+    check_powers(&mon_g1, g2, tau_g2) ?;
+    let kzg_vk = KzgVk { g1: mon_g1[0], g2, tau_in_g2: tau_g2 };
+    Ok(StaticProverKey { mon_g1, kzg_vk })
+}
+
+/// Load the `StaticProverKey` for domains of size `domain_size` from
+/// a Zcash or Filecoin powers-of-tau file holding `tau_powers_length`
+/// powers in G2, so `2*tau_powers_length-1` powers in G1.
+///
+/// Challenge files hold uncompressed points, and response files hold
+/// compressed points.
+pub fn from_powers_of_tau<R: Read>(
+    mut reader: R,
+    tau_powers_length: usize,
+    compressed: bool,
+    domain_size: usize,
+) -> Result<StaticProverKey, SrsError>
+{
+    if tau_powers_length < 2 {
+        return Err(SrsError::NotEnoughPowers);
+    }
+    let g1_powers_length = tau_powers_length.checked_mul(2).ok_or(SrsError::NotEnoughPowers) ? - 1;
+    let needed = g1_powers_needed(domain_size) ?;
+    if needed > g1_powers_length {
+        return Err(SrsError::NotEnoughPowers);
+    }
+    let g1_size = if compressed { 48 } else { 96 };
+    let read_g1 = |reader: &mut R| if compressed {
+        G1Affine::deserialize_compressed(reader)
+    } else {
+        G1Affine::deserialize_uncompressed(reader)
+    };
+    let read_g2 = |reader: &mut R| if compressed {
+        G2Affine::deserialize_compressed(reader)
+    } else {
+        G2Affine::deserialize_uncompressed(reader)
+    };
+
+    std::io::copy(&mut reader.by_ref().take(64), &mut std::io::sink()) ?;
+    let mon_g1 = (0..needed).map(|_| read_g1(&mut reader)).collect::<Result<Vec<_>,_>>() ?;
+    let skip = (g1_powers_length - needed) * g1_size;
+    let skipped = std::io::copy(&mut reader.by_ref().take(skip as u64), &mut std::io::sink()) ?;
+    if skipped != skip as u64 { return Err(SrsError::NotEnoughPowers); }
+    let g2 = read_g2(&mut reader) ?;
+    let tau_g2 = read_g2(&mut reader) ?;
+    static_prover_key(mon_g1, g2, tau_g2)
+}
+
+/// Hex strings in the JSON list `powers[key]`.
+fn json_hex_list<'a>(powers: &'a Value, key: &str) -> Result<Vec<&'a str>, SrsError> {
+    powers.get(key).and_then(Value::as_array).ok_or(SrsError::Json) ?
+        .iter()
+        .map(|s| s.as_str().ok_or(SrsError::Json))
+        .collect()
+}
+
+fn hex_point<P: CanonicalDeserialize>(s: &str) -> Result<P, SrsError> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    let bytes = hex::decode(s).map_err(|_| SrsError::Json) ?;
+    Ok(P::deserialize_compressed(bytes.as_slice()) ?)
+}
+
+/// Load the `StaticProverKey` for domains of size `domain_size` from
+/// Ethereum's KZG ceremony transcript JSON, or any one of its
+/// `transcripts`, whose `powersOfTau` hold `G1Powers` and `G2Powers`.
+///
+/// From the whole transcript, we take the first of its `transcripts`
+/// holding enough powers for `domain_size`.
+pub fn from_ethereum_ceremony(json: &str, domain_size: usize) -> Result<StaticProverKey, SrsError> {
+    let json: Value = serde_json::from_str(json).map_err(|_| SrsError::Json) ?;
+    let needed = g1_powers_needed(domain_size) ?;
+    let enough = |powers: &Value| json_hex_list(powers, "G1Powers")
+        .is_ok_and(|g1| g1.len() >= needed);
+    let powers = match json.get("transcripts") {
+        Some(transcripts) => transcripts.as_array().ok_or(SrsError::Json) ?
+            .iter()
+            .filter_map(|transcript| transcript.get("powersOfTau"))
+            .find(|powers| enough(powers))
+            .ok_or(SrsError::NotEnoughPowers) ?,
+        None => json.get("powersOfTau").ok_or(SrsError::Json) ?,
+    };
+    let g1 = json_hex_list(powers, "G1Powers") ?;
+    let g2 = json_hex_list(powers, "G2Powers") ?;
+    if g1.len() < needed || g2.len() < 2 {
+        return Err(SrsError::NotEnoughPowers);
+    }
+    let mon_g1 = g1[..needed].iter().map(|s| hex_point(s)).collect::<Result<Vec<G1Affine>,_>>() ?;
+    static_prover_key(mon_g1, hex_point(g2[0]) ?, hex_point(g2[1]) ?)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::format;
+
+    /// Powers of a known tau, like a ceremony would publish.
+    fn powers(tau: u64, n: usize) -> (Vec<G1Affine>, Vec<G2Affine>) {
+        let tau = <Bls12_381 as Pairing>::ScalarField::from(tau);
+        let mut x = <Bls12_381 as Pairing>::ScalarField::from(1u64);
+        let mut g1 = Vec::new();
+        let mut g2 = Vec::new();
+        for i in 0..n {
+            g1.push((G1Affine::generator() * x).into_affine());
+            if i < 2 { g2.push((G2Affine::generator() * x).into_affine()); }
+            x *= tau;
+        }
+        (g1, g2)
+    }
+
+    #[test]
+    fn powers_of_tau_file() {
+        let domain_size = 4;
+        let tau_powers_length = 8;
+        let (g1, g2) = powers(7, 2 * tau_powers_length - 1);
+        let mut file = vec![0u8; 64];
+        for p in g1.iter() { p.serialize_compressed(&mut file).unwrap(); }
+        for p in g2.iter() { p.serialize_compressed(&mut file).unwrap(); }
+        let srs = from_powers_of_tau(file.as_slice(), tau_powers_length, true, domain_size).unwrap();
+        assert_eq!(srs.mon_g1[..], g1[..3 * domain_size + 1]);
+        assert_eq!(srs.kzg_vk.tau_in_g2, g2[1]);
+
+        assert!(matches!(
+            from_powers_of_tau(file.as_slice(), tau_powers_length, true, 8),
+            Err(SrsError::NotEnoughPowers)
+        ));
+        assert!(matches!(
+            from_powers_of_tau(file.as_slice(), 0, true, domain_size),
+            Err(SrsError::NotEnoughPowers)
+        ));
+    }
+
+    #[test]
+    fn ethereum_ceremony_json() {
+        let domain_size = 2;
+        let (mut g1, g2) = powers(11, 3 * domain_size + 1);
+        let hex_list = |ps: Vec<Vec<u8>>| ps.iter()
+            .map(|p| format!("\"0x{}\"", hex::encode(p)))
+            .collect::<Vec<_>>().join(",");
+        let json = |g1: &[G1Affine]| format!(
+            "{{\"powersOfTau\": {{\"G1Powers\": [{}], \"G2Powers\": [{}]}}}}",
+            hex_list(g1.iter().map(|p| { let mut v = Vec::new(); p.serialize_compressed(&mut v).unwrap(); v }).collect()),
+            hex_list(g2.iter().map(|p| { let mut v = Vec::new(); p.serialize_compressed(&mut v).unwrap(); v }).collect()),
+        );
+        from_ethereum_ceremony(&json(&g1), domain_size).unwrap();
+        let whole = format!("{{\"transcripts\": [{}]}}", json(&g1));
+        from_ethereum_ceremony(&whole, domain_size).unwrap();
+        assert!(matches!(from_ethereum_ceremony("{\"powersOfTau\": [", domain_size), Err(SrsError::Json)));
+
+        g1.swap(2, 3);
+        assert!(matches!(from_ethereum_ceremony(&json(&g1), domain_size), Err(SrsError::RatioCheck)));
+    }
+}
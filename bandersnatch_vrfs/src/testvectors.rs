@@ -0,0 +1,188 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Known answer tests
+//!
+//! We derive deterministic test vectors from a seed, a message, and
+//! associated data, so other implementations could cross-validate
+//! key generation, hashing to curve, pre-outputs, and signatures.
+//!
+//! We sign using `NonceStrategy::Deterministic`, so thin and Pedersen
+//! VRF signatures depend only upon these inputs.  Ring VRF signatures
+//! consist of this same Pedersen VRF signature with `BLINDING_BASE`,
+//! plus a ring proof whose zero-knowledge blinding remains randomized,
+//! so our vectors cover only the ring VRF's Pedersen VRF portion.
+//!
+//! We emit vectors as blank line seperated blocks of `name = hex`
+//! lines, in the field order of `TestVector`.  `KNOWN_ANSWERS` pins
+//! the output of `emit_all`, so any change to our outputs shows up
+//! as a diff to `vectors/testvectors.txt`.
+
+use ark_std::{string::String, vec::Vec, format};
+
+use dleq_vrf::NonceStrategy;
+
+use crate::{
+    CanonicalSerialize, CanonicalDeserialize, AssociatedData, SecretKey, PublicKey,
+    ThinVrfSignature, Message, IntoVrfInput, thin_vrf_signature_length, pedersen_vrf,
+};
+
+
+/// One known answer test for bandersnatch VRFs.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct TestVector {
+    /// Secret key seed for `SecretKey::from_seed`.
+    pub seed: [u8; 32],
+    /// Application domain for `hash_to_bandersnatch_curve`.
+    pub domain: Vec<u8>,
+    /// VRF input message.
+    pub message: Vec<u8>,
+    /// Associated data signed via `AssociatedData`.
+    pub ad: Vec<u8>,
+    /// Compressed public key.
+    pub public: Vec<u8>,
+    /// Compressed VRF input point.
+    pub input: Vec<u8>,
+    /// Compressed VRF pre-output.
+    pub preout: Vec<u8>,
+    /// `ThinVrfSignature<1>` bytes.
+    pub thin: Vec<u8>,
+    /// Compressed Pedersen VRF signature, as in `RingVrfProof::dleq_proof`.
+    pub pedersen: Vec<u8>,
+}
+
+fn compressed<T: CanonicalSerialize>(itm: &T) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(itm.compressed_size());
+    itm.serialize_compressed(&mut bytes).expect("Vec serialization is infallible");
+    bytes
+}
+
+impl TestVector {
+    /// Generate the test vector for these inputs.
+    pub fn generate(seed: [u8; 32], domain: &[u8], message: &[u8], ad: &[u8]) -> TestVector {
+        let mut secret = SecretKey::from_seed(&seed);
+        secret.set_nonce_strategy(NonceStrategy::Deterministic);
+        let input = Message { domain, message }.into_vrf_input();
+        let io = secret.vrf_inout(input);
+
+        let thin: ThinVrfSignature<1> = secret.sign_thin_vrf(AssociatedData(ad), &[io]);
+        let (pedersen, _) = pedersen_vrf().sign_pedersen_vrf(AssociatedData(ad), &[io], None, &secret);
+
+        TestVector {
+            seed,
+            domain: domain.to_vec(),
+            message: message.to_vec(),
+            ad: ad.to_vec(),
+            public: compressed(secret.as_publickey()),
+            input: compressed(&input.0),
+            preout: compressed(&io.preoutput),
+            thin: thin.to_bytes::<{ thin_vrf_signature_length(1) }>().to_vec(),
+            pedersen: compressed(&pedersen),
+        }
+    }
+
+    /// Check this test vector against our implementation, both by
+    /// regenerating it and by verifying its signatures.
+    pub fn check(&self) -> Result<(), &'static str> {
+        let ours = TestVector::generate(self.seed, &self.domain, &self.message, &self.ad);
+        if ours.public != self.public { return Err("public key"); }
+        if ours.input != self.input { return Err("VRF input"); }
+        if ours.preout != self.preout { return Err("VRF pre-output"); }
+        if ours.thin != self.thin { return Err("thin VRF signature"); }
+        if ours.pedersen != self.pedersen { return Err("Pedersen VRF signature"); }
+
+        let public = PublicKey::deserialize_compressed(self.public.as_slice())
+            .map_err(|_| "public key encoding") ?;
+        let input = Message { domain: &self.domain, message: &self.message };
+        let thin_bytes: &[u8; thin_vrf_signature_length(1)] = self.thin.as_slice().try_into()
+            .map_err(|_| "thin VRF signature length") ?;
+        let thin = ThinVrfSignature::<1>::from_bytes(thin_bytes)
+            .map_err(|_| "thin VRF signature encoding") ?;
+        let [io] = public.verify_thin_vrf(AssociatedData(&self.ad), [input], &thin)
+            .map_err(|_| "thin VRF verification") ?;
+
+        let pedersen = CanonicalDeserialize::deserialize_compressed(self.pedersen.as_slice())
+            .map_err(|_| "Pedersen VRF signature encoding") ?;
+        pedersen_vrf().verify_pedersen_vrf(AssociatedData(&self.ad), &[io], &pedersen)
+            .map_err(|_| "Pedersen VRF verification") ?;
+        Ok(())
+    }
+
+    fn fields(&self) -> [(&'static str, &[u8]); 9] {
+        [
+            ("seed", &self.seed[..]),
+            ("domain", &self.domain),
+            ("message", &self.message),
+            ("ad", &self.ad),
+            ("public", &self.public),
+            ("input", &self.input),
+            ("preout", &self.preout),
+            ("thin", &self.thin),
+            ("pedersen", &self.pedersen),
+        ]
+    }
+
+    /// Emit this test vector as `name = hex` lines.
+    pub fn emit(&self) -> String {
+        let mut s = String::new();
+        for (name, bytes) in self.fields() {
+            s += &format!("{} = {}\n", name, hex::encode(bytes));
+        }
+        s
+    }
+
+    /// Parse one test vector block emitted by `emit`.
+    pub fn parse(block: &str) -> Option<TestVector> {
+        let mut fields: Vec<(&str, Vec<u8>)> = Vec::new();
+        for line in block.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (name, value) = line.split_once('=') ?;
+            fields.push(( name.trim(), hex::decode(value.trim()).ok() ? ));
+        }
+        let mut take = |name: &str| -> Option<Vec<u8>> {
+            let i = fields.iter().position(|(n,_)| *n == name) ?;
+            Some(fields.swap_remove(i).1)
+        };
+        let tv = TestVector {
+            seed: take("seed")?.try_into().ok()?,
+            domain: take("domain")?,
+            message: take("message")?,
+            ad: take("ad")?,
+            public: take("public")?,
+            input: take("input")?,
+            preout: take("preout")?,
+            thin: take("thin")?,
+            pedersen: take("pedersen")?,
+        };
+        if fields.is_empty() { Some(tv) } else { None }
+    }
+
+    /// Parse blank line seperated test vector blocks.
+    pub fn parse_all(text: &str) -> Option<Vec<TestVector>> {
+        text.split("\n\n")
+            .filter(|block| !block.trim().is_empty())
+            .map(TestVector::parse)
+            .collect()
+    }
+}
+
+/// Our standard test vectors, as emitted by `emit_all` when last reviewed.
+pub const KNOWN_ANSWERS: &str = include_str!("../vectors/testvectors.txt");
+
+/// Our standard test vectors' inputs, being seeds, domains, messages,
+/// and associated data.
+pub const INPUTS: [([u8; 32], &[u8], &[u8], &[u8]); 3] = [
+    ([0; 32], b"", b"", b""),
+    ([1; 32], b"domain", b"message", b""),
+    ([2; 32], b"domain", b"message", b"associated data"),
+];
+
+/// Generate our standard test vectors.
+pub fn generate_all() -> Vec<TestVector> {
+    INPUTS.iter()
+        .map(|(seed, domain, message, ad)| TestVector::generate(*seed, domain, message, ad))
+        .collect()
+}
+
+/// Emit our standard test vectors, seperated by blank lines.
+pub fn emit_all() -> String {
+    generate_all().iter().map(TestVector::emit).collect::<Vec<_>>().join("\n")
+}
@@ -0,0 +1,278 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+#![doc = include_str!("../README.md")]
+
+use core::slice;
+
+use bandersnatch_vrfs::{
+    CanonicalSerialize, CanonicalDeserialize, IntoVrfInput,
+    Message, PublicKey, SecretKey, Transcript, VrfInOut,
+    RingProver, RingVerifier, RingVrfSignature, ThinVrfSignature,
+    ring::{RingContext, RingError},
+    transcript::IsLabel, SignatureError,
+    PUBLIC_KEY_LENGTH, thin_vrf_signature_length,
+};
+
+
+pub const BANDERSNATCH_OK: i32 = 0;
+/// Some required pointer was null
+pub const BANDERSNATCH_ERR_NULL_POINTER: i32 = -1;
+/// Some input failed to deserialize
+pub const BANDERSNATCH_ERR_DESERIALIZE: i32 = -2;
+/// Signer's public key does not appear at the given ring index
+pub const BANDERSNATCH_ERR_NOT_IN_RING: i32 = -4;
+/// Output buffer too small, with the required length written anyways
+pub const BANDERSNATCH_ERR_BUFFER_TOO_SMALL: i32 = -5;
+/// Signature failed to verify, with codes `BANDERSNATCH_ERR_SIGNATURE - SignatureError::code`
+pub const BANDERSNATCH_ERR_SIGNATURE: i32 = -0x100;
+/// Ring does not fit the ring context, with codes `BANDERSNATCH_ERR_RING - RingError::code`
+pub const BANDERSNATCH_ERR_RING: i32 = -0x200;
+/// Signature failed to verify, aka `SignatureError::Invalid`
+pub const BANDERSNATCH_ERR_INVALID_SIGNATURE: i32 = signature_error(SignatureError::Invalid);
+
+pub const BANDERSNATCH_SEED_LENGTH: usize = 32;
+pub const BANDERSNATCH_PUBLIC_KEY_LENGTH: usize = PUBLIC_KEY_LENGTH;
+pub const BANDERSNATCH_THIN_SIGNATURE_LENGTH: usize = thin_vrf_signature_length(1);
+pub const BANDERSNATCH_OUTPUT_LENGTH: usize = 32;
+
+type Result<T> = core::result::Result<T, i32>;
+
+/// Borrow a caller supplied buffer, accepting null only when empty.
+unsafe fn input<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8]> {
+    if len == 0 { return Ok(&[]); }
+    if ptr.is_null() { return Err(BANDERSNATCH_ERR_NULL_POINTER); }
+    Ok(slice::from_raw_parts(ptr, len))
+}
+
+unsafe fn array<'a, const N: usize>(ptr: *const [u8; N]) -> Result<&'a [u8; N]> {
+    ptr.as_ref().ok_or(BANDERSNATCH_ERR_NULL_POINTER)
+}
+
+unsafe fn array_mut<'a, const N: usize>(ptr: *mut [u8; N]) -> Result<&'a mut [u8; N]> {
+    ptr.as_mut().ok_or(BANDERSNATCH_ERR_NULL_POINTER)
+}
+
+const fn signature_error(e: SignatureError) -> i32 {
+    BANDERSNATCH_ERR_SIGNATURE - e.code() as i32
+}
+
+const fn ring_error(e: RingError) -> i32 {
+    BANDERSNATCH_ERR_RING - e.code() as i32
+}
+
+fn code(r: Result<()>) -> i32 {
+    r.err().unwrap_or(BANDERSNATCH_OK)
+}
+
+fn deserialize<T: CanonicalDeserialize>(bytes: &[u8]) -> Result<T> {
+    T::deserialize_compressed(bytes).map_err(|_| BANDERSNATCH_ERR_DESERIALIZE)
+}
+
+fn deserialize_ring(ring: &[u8]) -> Result<Vec<PublicKey>> {
+    if ring.len() % PUBLIC_KEY_LENGTH != 0 {
+        return Err(BANDERSNATCH_ERR_DESERIALIZE);
+    }
+    ring.chunks(PUBLIC_KEY_LENGTH).map(deserialize::<PublicKey>).collect()
+}
+
+fn vrf_input(domain: &[u8], message: &[u8]) -> bandersnatch_vrfs::VrfInput {
+    Message { domain, message }.into_vrf_input()
+}
+
+fn write_output(io: &VrfInOut, output: &mut [u8; BANDERSNATCH_OUTPUT_LENGTH]) {
+    *output = io.vrf_output_bytes(Transcript::new_labeled(b"VrfOutput"));
+}
+
+/// Write the compressed public key for the secret key given by `seed`.
+///
+/// # Safety
+/// Pointers must reference buffers of the stated lengths.
+#[no_mangle]
+pub unsafe extern "C" fn bandersnatch_public_key(
+    seed: *const [u8; BANDERSNATCH_SEED_LENGTH],
+    public_out: *mut [u8; BANDERSNATCH_PUBLIC_KEY_LENGTH],
+) -> i32 {
+    code((|| -> Result<()> {
+        let secret = SecretKey::from_seed(array(seed)?);
+        let public_out = array_mut(public_out)?;
+        *public_out = secret.as_publickey().to_bytes();
+        Ok(())
+    })())
+}
+
+/// Write the VRF output for the secret key given by `seed`, without any signature.
+///
+/// # Safety
+/// Pointers must reference buffers of the stated lengths.
+#[no_mangle]
+pub unsafe extern "C" fn bandersnatch_vrf_output(
+    seed: *const [u8; BANDERSNATCH_SEED_LENGTH],
+    domain: *const u8, domain_len: usize,
+    message: *const u8, message_len: usize,
+    output_out: *mut [u8; BANDERSNATCH_OUTPUT_LENGTH],
+) -> i32 {
+    code((|| -> Result<()> {
+        let secret = SecretKey::from_seed(array(seed)?);
+        let io = secret.vrf_inout(vrf_input(input(domain, domain_len)?, input(message, message_len)?));
+        write_output(&io, array_mut(output_out)?);
+        Ok(())
+    })())
+}
+
+/// Write a thin VRF signature on one input, with associated data `ad`.
+///
+/// # Safety
+/// Pointers must reference buffers of the stated lengths.
+#[no_mangle]
+pub unsafe extern "C" fn bandersnatch_thin_sign(
+    seed: *const [u8; BANDERSNATCH_SEED_LENGTH],
+    domain: *const u8, domain_len: usize,
+    message: *const u8, message_len: usize,
+    ad: *const u8, ad_len: usize,
+    signature_out: *mut [u8; BANDERSNATCH_THIN_SIGNATURE_LENGTH],
+) -> i32 {
+    code((|| -> Result<()> {
+        let secret = SecretKey::from_seed(array(seed)?);
+        let io = secret.vrf_inout(vrf_input(input(domain, domain_len)?, input(message, message_len)?));
+        let ad = input(ad, ad_len)?;
+        let signature = secret.sign_thin_vrf(Transcript::new_labeled(IsLabel(ad)), &[io]);
+        *array_mut(signature_out)? = signature.to_bytes();
+        Ok(())
+    })())
+}
+
+/// Verify a thin VRF signature, and write its VRF output.
+///
+/// # Safety
+/// Pointers must reference buffers of the stated lengths.
+#[no_mangle]
+pub unsafe extern "C" fn bandersnatch_thin_verify(
+    public: *const [u8; BANDERSNATCH_PUBLIC_KEY_LENGTH],
+    domain: *const u8, domain_len: usize,
+    message: *const u8, message_len: usize,
+    ad: *const u8, ad_len: usize,
+    signature: *const [u8; BANDERSNATCH_THIN_SIGNATURE_LENGTH],
+    output_out: *mut [u8; BANDERSNATCH_OUTPUT_LENGTH],
+) -> i32 {
+    code((|| -> Result<()> {
+        let public = PublicKey::from_bytes(array(public)?).map_err(|_| BANDERSNATCH_ERR_DESERIALIZE)?;
+        let signature = ThinVrfSignature::<1>::from_bytes(array(signature)?)
+            .map_err(|_| BANDERSNATCH_ERR_DESERIALIZE)?;
+        let input = vrf_input(input(domain, domain_len)?, input(message, message_len)?);
+        let ad = self::input(ad, ad_len)?;
+        let [io] = public.verify_thin_vrf(Transcript::new_labeled(IsLabel(ad)), [input], &signature)
+            .map_err(signature_error)?;
+        write_output(&io, array_mut(output_out)?);
+        Ok(())
+    })())
+}
+
+/// Write a ring VRF signature on one input, with associated data `ad`,
+/// by the signer at position `index` in `ring`.
+///
+/// We always write the signature's length into `signature_len_out`,
+/// but only write the signature when it fits in `signature_capacity`.
+///
+/// # Safety
+/// Pointers must reference buffers of the stated lengths.
+#[no_mangle]
+pub unsafe extern "C" fn bandersnatch_ring_sign(
+    seed: *const [u8; BANDERSNATCH_SEED_LENGTH],
+    ring_context: *const u8, ring_context_len: usize,
+    ring: *const u8, ring_len: usize,
+    index: usize,
+    domain: *const u8, domain_len: usize,
+    message: *const u8, message_len: usize,
+    ad: *const u8, ad_len: usize,
+    signature_out: *mut u8, signature_capacity: usize,
+    signature_len_out: *mut usize,
+) -> i32 {
+    code((|| -> Result<()> {
+        let secret = SecretKey::from_seed(array(seed)?);
+        let ring_context: RingContext = deserialize(input(ring_context, ring_context_len)?)?;
+        let ring = deserialize_ring(input(ring, ring_len)?)?;
+        ring_context.check_ring_size(ring.len()).map_err(ring_error)?;
+        if ring.get(index) != Some(secret.as_publickey()) {
+            return Err(BANDERSNATCH_ERR_NOT_IN_RING);
+        }
+        let io = secret.vrf_inout(vrf_input(input(domain, domain_len)?, input(message, message_len)?));
+        let ad = input(ad, ad_len)?;
+        let ring_prover = ring_context.ring_prover(&ring, index).map_err(ring_error)?;
+        let prover = RingProver { ring_prover: &ring_prover, secret: &secret };
+        let signature = prover.sign_ring_vrf(Transcript::new_labeled(IsLabel(ad)), &[io]);
+
+        let len = signature.compressed_size();
+        *signature_len_out.as_mut().ok_or(BANDERSNATCH_ERR_NULL_POINTER)? = len;
+        if len > signature_capacity { return Err(BANDERSNATCH_ERR_BUFFER_TOO_SMALL); }
+        if signature_out.is_null() { return Err(BANDERSNATCH_ERR_NULL_POINTER); }
+        let signature_out = slice::from_raw_parts_mut(signature_out, len);
+        signature.serialize_compressed(signature_out).expect("Buffer has the compressed size");
+        Ok(())
+    })())
+}
+
+/// Verify a ring VRF signature for `ring`, and write its VRF output.
+///
+/// # Safety
+/// Pointers must reference buffers of the stated lengths.
+#[no_mangle]
+pub unsafe extern "C" fn bandersnatch_ring_verify(
+    ring_context: *const u8, ring_context_len: usize,
+    ring: *const u8, ring_len: usize,
+    domain: *const u8, domain_len: usize,
+    message: *const u8, message_len: usize,
+    ad: *const u8, ad_len: usize,
+    signature: *const u8, signature_len: usize,
+    output_out: *mut [u8; BANDERSNATCH_OUTPUT_LENGTH],
+) -> i32 {
+    code((|| -> Result<()> {
+        let ring_context: RingContext = deserialize(input(ring_context, ring_context_len)?)?;
+        let ring = deserialize_ring(input(ring, ring_len)?)?;
+        let signature: RingVrfSignature<1> = deserialize(input(signature, signature_len)?)?;
+        let input = vrf_input(input(domain, domain_len)?, input(message, message_len)?);
+        let ad = self::input(ad, ad_len)?;
+        let ring_verifier = ring_context.ring_verifier(&ring).map_err(ring_error)?;
+        let [io] = RingVerifier(&ring_verifier)
+            .verify_ring_vrf(Transcript::new_labeled(IsLabel(ad)), [input], &signature)
+            .map_err(signature_error)?;
+        write_output(&io, array_mut(output_out)?);
+        Ok(())
+    })())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thin_sign_verify() {
+        let seed = [7u8; BANDERSNATCH_SEED_LENGTH];
+        let mut public = [0u8; BANDERSNATCH_PUBLIC_KEY_LENGTH];
+        let mut signature = [0u8; BANDERSNATCH_THIN_SIGNATURE_LENGTH];
+        let mut output = [0u8; BANDERSNATCH_OUTPUT_LENGTH];
+        let mut expected = [0u8; BANDERSNATCH_OUTPUT_LENGTH];
+        let (domain, message, ad) = (b"domain", b"message", b"ad");
+        unsafe {
+            assert_eq!(bandersnatch_public_key(&seed, &mut public), BANDERSNATCH_OK);
+            assert_eq!(bandersnatch_thin_sign(
+                &seed, domain.as_ptr(), domain.len(), message.as_ptr(), message.len(),
+                ad.as_ptr(), ad.len(), &mut signature,
+            ), BANDERSNATCH_OK);
+            assert_eq!(bandersnatch_thin_verify(
+                &public, domain.as_ptr(), domain.len(), message.as_ptr(), message.len(),
+                ad.as_ptr(), ad.len(), &signature, &mut output,
+            ), BANDERSNATCH_OK);
+            assert_eq!(bandersnatch_vrf_output(
+                &seed, domain.as_ptr(), domain.len(), message.as_ptr(), message.len(), &mut expected,
+            ), BANDERSNATCH_OK);
+            assert_eq!(output, expected);
+            assert_eq!(bandersnatch_thin_verify(
+                &public, domain.as_ptr(), domain.len(), message.as_ptr(), message.len(),
+                core::ptr::null(), 0, &signature, &mut output,
+            ), BANDERSNATCH_ERR_INVALID_SIGNATURE);
+            assert_eq!(BANDERSNATCH_ERR_SIGNATURE - BANDERSNATCH_ERR_INVALID_SIGNATURE, SignatureError::Invalid.code() as i32);
+            assert_eq!(bandersnatch_public_key(core::ptr::null(), &mut public), BANDERSNATCH_ERR_NULL_POINTER);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+#![deny(unsafe_code)]
+#![doc = include_str!("../README.md")]
+
+use bandersnatch_vrfs::{
+    CanonicalSerialize, CanonicalDeserialize, IntoVrfInput,
+    Message, PublicKey, SecretKey, Transcript, VrfInOut,
+    RingProver, RingVerifier, RingVrfSignature, ThinVrfSignature,
+    ring::RingContext,
+    transcript::IsLabel,
+};
+use wasm_bindgen::prelude::*;
+
+
+/// Length of VRF outputs returned by verification.
+pub const VRF_OUTPUT_LENGTH: usize = 32;
+
+fn secret_from_seed(seed: &[u8]) -> Result<SecretKey, JsError> {
+    let seed: &[u8; 32] = seed.try_into()
+        .map_err(|_| JsError::new("Secret key seeds must be 32 bytes"))?;
+    Ok(SecretKey::from_seed(seed))
+}
+
+fn deserialize<T: CanonicalDeserialize>(bytes: &[u8], what: &str) -> Result<T, JsError> {
+    T::deserialize_compressed(bytes)
+        .map_err(|e| JsError::new(&format!("Invalid {}: {}", what, e)))
+}
+
+fn serialize<T: CanonicalSerialize>(t: &T) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(t.compressed_size());
+    t.serialize_compressed(&mut bytes).expect("Serializing into a Vec never fails");
+    bytes
+}
+
+fn deserialize_ring(ring: &[u8]) -> Result<Vec<PublicKey>, JsError> {
+    if ring.len() % bandersnatch_vrfs::PUBLIC_KEY_LENGTH != 0 {
+        return Err(JsError::new("Rings must be concatenated 33 byte public keys"));
+    }
+    ring.chunks(bandersnatch_vrfs::PUBLIC_KEY_LENGTH)
+        .map(|pk| deserialize(pk, "public key"))
+        .collect()
+}
+
+fn vrf_input(domain: &[u8], message: &[u8]) -> bandersnatch_vrfs::VrfInput {
+    Message { domain, message }.into_vrf_input()
+}
+
+fn output_bytes(io: &VrfInOut) -> Vec<u8> {
+    io.vrf_output_bytes::<VRF_OUTPUT_LENGTH>(Transcript::new_labeled(b"VrfOutput")).to_vec()
+}
+
+/// Compressed public key for the secret key given by a 32 byte seed.
+#[wasm_bindgen]
+pub fn public_key(seed: &[u8]) -> Result<Vec<u8>, JsError> {
+    Ok(serialize(secret_from_seed(seed)?.as_publickey()))
+}
+
+/// VRF output for the secret key given by a 32 byte seed,
+/// without any signature.
+#[wasm_bindgen]
+pub fn vrf_output(seed: &[u8], domain: &[u8], message: &[u8]) -> Result<Vec<u8>, JsError> {
+    let secret = secret_from_seed(seed)?;
+    Ok(output_bytes(&secret.vrf_inout(vrf_input(domain, message))))
+}
+
+/// Thin VRF signature on one input, with associated data `ad`.
+#[wasm_bindgen]
+pub fn thin_sign(seed: &[u8], domain: &[u8], message: &[u8], ad: &[u8]) -> Result<Vec<u8>, JsError> {
+    let secret = secret_from_seed(seed)?;
+    let io = secret.vrf_inout(vrf_input(domain, message));
+    let signature = secret.sign_thin_vrf(Transcript::new_labeled(IsLabel(ad)), &[io]);
+    Ok(serialize(&signature))
+}
+
+/// Verify a thin VRF signature, and return its VRF output.
+#[wasm_bindgen]
+pub fn thin_verify(public: &[u8], domain: &[u8], message: &[u8], ad: &[u8], signature: &[u8]) -> Result<Vec<u8>, JsError> {
+    let public: PublicKey = deserialize(public, "public key")?;
+    let signature: ThinVrfSignature<1> = deserialize(signature, "signature")?;
+    let [io] = public.verify_thin_vrf(Transcript::new_labeled(IsLabel(ad)), [vrf_input(domain, message)], &signature)
+        .map_err(|e| JsError::new(&format!("Invalid thin VRF signature: {}", e)))?;
+    Ok(output_bytes(&io))
+}
+
+/// Ring VRF signature on one input, with associated data `ad`,
+/// by the signer at position `index` in `ring`.
+#[wasm_bindgen]
+pub fn ring_sign(
+    seed: &[u8],
+    ring_context: &[u8],
+    ring: &[u8],
+    index: usize,
+    domain: &[u8],
+    message: &[u8],
+    ad: &[u8],
+) -> Result<Vec<u8>, JsError> {
+    let secret = secret_from_seed(seed)?;
+    let ring_context: RingContext = deserialize(ring_context, "ring context")?;
+    let ring = deserialize_ring(ring)?;
+    if ring.get(index) != Some(secret.as_publickey()) {
+        return Err(JsError::new("Signer not found at the given ring index"));
+    }
+    let ring_prover = ring_context.ring_prover(&ring, index)
+        .map_err(|e| JsError::new(&format!("Invalid ring: {}", e)))?;
+    let prover = RingProver { ring_prover: &ring_prover, secret: &secret };
+    let io = secret.vrf_inout(vrf_input(domain, message));
+    let signature = prover.sign_ring_vrf(Transcript::new_labeled(IsLabel(ad)), &[io]);
+    Ok(serialize(&signature))
+}
+
+/// Verify a ring VRF signature for `ring`, and return its VRF output.
+#[wasm_bindgen]
+pub fn ring_verify(
+    ring_context: &[u8],
+    ring: &[u8],
+    domain: &[u8],
+    message: &[u8],
+    ad: &[u8],
+    signature: &[u8],
+) -> Result<Vec<u8>, JsError> {
+    let ring_context: RingContext = deserialize(ring_context, "ring context")?;
+    let ring = deserialize_ring(ring)?;
+    let signature: RingVrfSignature<1> = deserialize(signature, "signature")?;
+    let ring_verifier = ring_context.ring_verifier(&ring)
+        .map_err(|e| JsError::new(&format!("Invalid ring: {}", e)))?;
+    let [io] = RingVerifier(&ring_verifier)
+        .verify_ring_vrf(Transcript::new_labeled(IsLabel(ad)), [vrf_input(domain, message)], &signature)
+        .map_err(|e| JsError::new(&format!("Invalid ring VRF signature: {}", e)))?;
+    Ok(output_bytes(&io))
+}
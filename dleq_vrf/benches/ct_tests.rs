@@ -0,0 +1,56 @@
+//! Dudect style constant-time tests for secret key operations.
+//!
+//! We time each operation upon two classes of secrets, and dudect's
+//! Welch t-test flags timing differences between the classes.  Run by
+//! `cargo bench --bench ct-tests`, and treat |t| above 5 as a leak.
+
+use dudect_bencher::{ctbench_main, BenchRng, Class, CtRunner};
+use dudect_bencher::rand::{Rng, RngCore};
+
+use dleq_vrf::{SecretKey, NonceStrategy, vrf::VrfInput};
+use subtle::ConstantTimeEq;
+
+type K = ark_bls12_377::G1Affine;
+
+const SAMPLES: usize = 10_000;
+
+fn seed(rng: &mut BenchRng) -> [u8; 32] {
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+    seed
+}
+
+/// Equal versus unequal secret keys.
+fn secretkey_ct_eq(runner: &mut CtRunner, rng: &mut BenchRng) {
+    let sk = SecretKey::<K>::from_seed(&seed(rng));
+    let mut others = Vec::with_capacity(SAMPLES);
+    for _ in 0..SAMPLES {
+        if rng.gen::<bool>() {
+            others.push((Class::Left, sk.clone()));
+        } else {
+            others.push((Class::Right, SecretKey::<K>::from_seed(&seed(rng))));
+        }
+    }
+    for (class, other) in others.iter() {
+        runner.run_one(*class, || bool::from(sk.ct_eq(other)));
+    }
+}
+
+/// Deterministic thin VRF signing by one fixed versus random secret keys.
+fn sign_thin_vrf(runner: &mut CtRunner, rng: &mut BenchRng) {
+    let input = VrfInput::<K>(ark_ec::AffineRepr::generator());
+    let fixed = seed(rng);
+    let mut sks = Vec::with_capacity(SAMPLES);
+    for _ in 0..SAMPLES {
+        let (class, seed) = if rng.gen::<bool>() { (Class::Left, fixed) } else { (Class::Right, seed(rng)) };
+        let mut sk = SecretKey::<K>::from_seed(&seed);
+        sk.set_nonce_strategy(NonceStrategy::Deterministic);
+        sks.push((class, sk));
+    }
+    for (class, sk) in sks.iter() {
+        let io = sk.vrf_inout(input);
+        runner.run_one(*class, || sk.sign_thin_vrf_detached(b"ct-tests", &[io]));
+    }
+}
+
+ctbench_main!(secretkey_ct_eq, sign_thin_vrf);
@@ -0,0 +1,113 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Adaptor signatures for thin VRFs
+//!
+//! An adaptor pre-signature becomes an ordinary thin VRF signature
+//! only once combined with a secret adaptor scalar, and anyone who sees
+//! both learns this scalar, so atomic swaps could condition revealing a
+//! VRF signature upon disclosing a secret.
+//!
+//! Thin VRF signatures prove `s M = R + c O` for the merged VRF input `M`
+//! and merged pre-output `O`, so adaptor points must be multiples of
+//! this same `M`, not of the keying base.  An adaptor secret holder
+//! computes the adaptor point from `ThinVrf::merged_input`, and could
+//! link it to their own public key via `dleq_prove`, like when one
+//! secret conditions signatures on several chains.
+
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::Field;
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize};
+use ark_std::{borrow::BorrowMut, vec::Vec};
+
+use crate::{
+    ThinVrf, ThinVrfProof, Transcript, IntoTranscript, PublicKey, SecretKey,
+    flavor::Batchable,
+    error::{SignatureResult, SignatureError},
+    vrf::{VrfInput, VrfInOut},
+};
+
+
+type Scalar<K> = <K as AffineRepr>::ScalarField;
+
+/// Thin VRF pre-signature, which `complete` turns into a thin VRF
+/// signature given the adaptor secret.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct AdaptorSignature<K: AffineRepr> {
+    /// Nonce commitment of the completed signature, which includes
+    /// the adaptor point.
+    pub r: K,
+    pub s: Scalar<K>,
+}
+
+impl<K: AffineRepr> ThinVrf<K> {
+    fn adaptor_transcript(&self, t: impl IntoTranscript, public: &PublicKey<K>, ios: &[VrfInOut<K>]) -> (Transcript, VrfInOut<K>) {
+        let mut t = t.into_transcript();
+        let t = t.borrow_mut();
+        crate::protocol_label(t, b"ThinVRF");
+        let io = self.thin_vrf_merge(t, public, ios);
+        (t.clone(), io)
+    }
+
+    /// Merged VRF input of the thin VRF signature by `public` upon
+    /// `t` and `ios`, of which adaptor points must be multiples.
+    pub fn merged_input(&self, t: impl IntoTranscript, public: &PublicKey<K>, ios: &[VrfInOut<K>]) -> VrfInput<K> {
+        self.adaptor_transcript(t, public, ios).1.input
+    }
+
+    /// Verify that completing `pre_signature` using the secret behind
+    /// `adaptor_point` yields a thin VRF signature by `public`.
+    pub fn verify_adaptor(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut<K>],
+        public: &PublicKey<K>,
+        adaptor_point: &K,
+        pre_signature: &AdaptorSignature<K>,
+    ) -> SignatureResult<()>
+    {
+        let (mut t, io) = self.adaptor_transcript(t, public, ios);
+        t.label(b"Thin R");
+        t.append(&pre_signature.r);
+        let c: Scalar<K> = t.challenge(b"ThinVrfChallenge").read_reduce();
+
+        // Check R - T + c O - s I = 0 by one multi-scalar multiplication
+        let z = self.msm().msm(
+            &[pre_signature.r, *adaptor_point, io.preoutput.0, io.input.0],
+            &[Scalar::<K>::ONE, -Scalar::<K>::ONE, c, -pre_signature.s],
+        );
+        if self.zero_mod_cofactor(z) { Ok(()) } else { Err(SignatureError::Invalid) }
+    }
+}
+
+impl<K: AffineRepr> SecretKey<K> {
+    /// Pre-sign a thin VRF signature upon `t` and `ios`, conditioned
+    /// upon the secret behind `adaptor_point`, which must be a multiple
+    /// of `ThinVrf::merged_input`.
+    pub fn sign_adaptor(&self, t: impl IntoTranscript, ios: &[VrfInOut<K>], adaptor_point: &K) -> AdaptorSignature<K>
+    {
+        let (mut t, io) = self.thin.adaptor_transcript(t, self.as_publickey(), ios);
+        // Bind our nonce to the adaptor point, since pre-signatures for
+        // distinct adaptor points share their signature transcript.
+        let mut tw = t.fork(b"AdaptorPoint");
+        tw.append(adaptor_point);
+        let mut w = self.new_thin_witness(&tw, &io.input);
+        w.r = (w.r.into_group() + *adaptor_point).into_affine();
+        let Batchable { r, s, .. } = w.sign_final(&mut t, self);
+        AdaptorSignature { r, s }
+    }
+}
+
+impl<K: AffineRepr> AdaptorSignature<K> {
+    /// Complete this pre-signature into a thin VRF signature, using
+    /// the adaptor secret.
+    pub fn complete(&self, adaptor_secret: &Scalar<K>) -> ThinVrfProof<K> {
+        Batchable { compk: (), r: self.r, s: self.s + adaptor_secret }
+    }
+}
+
+/// Extract the adaptor secret from a completed thin VRF signature
+/// and its pre-signature, if the signature completes the pre-signature.
+pub fn extract_secret<K: AffineRepr>(signature: &ThinVrfProof<K>, pre_signature: &AdaptorSignature<K>) -> Option<Scalar<K>> {
+    if signature.r != pre_signature.r { return None; }
+    Some(signature.s - pre_signature.s)
+}
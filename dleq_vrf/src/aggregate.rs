@@ -0,0 +1,106 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Same input VRF output aggregation
+//!
+//! Randomness beacons collect thin VRF signatures by many signers upon
+//! one shared `VrfInput`, like an epoch's seed, and then combine their
+//! pre-outputs into one output.  Any signer who contributes honestly
+//! makes the combined output unpredictable, although the last signer
+//! could still withhold its contribution.
+//!
+//! We verify the contributions together using batch verification,
+//! so the `SameInputAggregate` itself becomes the aggregate proof.
+//! We hash every pre-output into the combined output, not merely
+//! their sum, so nobody cancels others' pre-outputs.
+
+use ark_ec::AffineRepr;
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize};
+use ark_std::{borrow::BorrowMut, vec::Vec};
+
+use crate::{
+    ThinVrf, ThinVrfProof, Transcript, IntoTranscript, PublicKey,
+    error::{SignatureResult, SignatureError},
+    vrf::{VrfInput, VrfPreOut, VrfInOut},
+};
+
+
+/// One signer's contribution to a `SameInputAggregate`.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct Contribution<K: AffineRepr> {
+    pub public: PublicKey<K>,
+    pub preout: VrfPreOut<K>,
+    pub proof: ThinVrfProof<K>,
+}
+
+/// Contributions by many signers upon the same `VrfInput`, in order,
+/// which verifiers check by `ThinVrf::verify_same_input_aggregate`.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct SameInputAggregate<K: AffineRepr> {
+    pub contributions: Vec<Contribution<K>>,
+}
+
+/// Verified `SameInputAggregate`, from which we derive combined outputs.
+#[derive(Debug,Clone)]
+pub struct AggregateInOut<K: AffineRepr> {
+    /// Every signer's `VrfInOut`, in order.
+    pub ios: Vec<VrfInOut<K>>,
+}
+
+impl<K: AffineRepr> Contribution<K> {
+    /// Create a contribution by signing `input` with transcript `t`.
+    pub fn new(secret: &crate::SecretKey<K>, t: impl IntoTranscript, input: VrfInput<K>) -> Self {
+        let io = secret.vrf_inout(input);
+        let proof = secret.sign_thin_vrf_detached(t, &[io]);
+        Contribution { public: secret.to_public(), preout: io.preoutput, proof }
+    }
+}
+
+impl<K: AffineRepr> ThinVrf<K> {
+    /// Batch verify every contribution upon `input`, each signed using
+    /// a clone of transcript `t`.
+    ///
+    /// We reject aggregates without contributions, whose combined
+    /// output anyone could compute in advance.
+    pub fn verify_same_input_aggregate(
+        &self,
+        t: &Transcript,
+        input: VrfInput<K>,
+        aggregate: &SameInputAggregate<K>,
+    ) -> SignatureResult<AggregateInOut<K>>
+    {
+        if aggregate.contributions.is_empty() {
+            return Err(SignatureError::Invalid);
+        }
+        let ios: Vec<[VrfInOut<K>; 1]> = aggregate.contributions.iter()
+            .map(|c| [VrfInOut { input, preoutput: c.preout }])
+            .collect();
+        self.batch_verify_thin_vrf(
+            aggregate.contributions.iter().zip(&ios)
+                .map(|(c,io)| (t.clone(), &io[..], &c.public, &c.proof))
+        ) ?;
+        Ok(AggregateInOut { ios: ios.into_iter().map(|[io]| io).collect() })
+    }
+}
+
+impl<K: AffineRepr> AggregateInOut<K> {
+    /// Combined VRF output reader via the supplied transcript.
+    ///
+    /// Akin to `VrfInOut::vrf_output`, we label `b"AggregateVrfOutput"`,
+    /// append the number of contributions, and `vrf_output_append` each
+    /// `VrfInOut` in order.
+    pub fn vrf_output(&self, t: impl IntoTranscript) -> crate::transcript::Reader {
+        let mut t = t.into_transcript();
+        let t = t.borrow_mut();
+        t.label(b"AggregateVrfOutput");
+        t.append_u64(self.ios.len() as u64);
+        for io in self.ios.iter() {
+            io.vrf_output_append(&mut *t);
+        }
+        t.challenge(b"")
+    }
+
+    /// Combined VRF output bytes via the supplied transcript.
+    pub fn vrf_output_bytes<const N: usize>(&self, t: impl IntoTranscript) -> [u8; N] {
+        self.vrf_output(t).read_byte_array()
+    }
+}
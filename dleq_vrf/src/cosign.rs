@@ -0,0 +1,144 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Two-party thin VRF co-signing
+//!
+//! We split a thin VRF `SecretKey` between two parties, like a
+//! validator and an HSM, so that both must participate in every
+//! signature, but the result remains an ordinary `VrfSignature` for
+//! the original `PublicKey`.
+//!
+//! We run our threshold protocol with threshold two among two parties,
+//! but without any coordinator, since each party plays coordinator for
+//! its own `CoSigningSession`.  Parties exchange one message per round,
+//! all of which serialize:
+//!
+//! 1. `PreOutShares` for the VRF inputs,
+//! 2. `NonceCommitment`s upon the merged VRF input, and
+//! 3. `PartialSignature`s, which `CoSigningSession::finish` verifies
+//!    and combines into the signature.
+//!
+//! Either party could finish, so an HSM could stop after round three.
+//! Sessions consume their nonces in round three, so parties must start
+//! a fresh session for every signature.
+
+use ark_ec::AffineRepr;
+use ark_std::vec::Vec;
+
+use ark_secret_scalar::{RngCore, CryptoRng};
+
+use crate::{
+    ThinVrfProof, Transcript, PublicKey, SecretKey, VrfSignature,
+    error::{SignatureResult, SignatureError},
+    threshold::{GroupKey, SecretShare, PreOutShares, NonceCommitment, SigningNonces, PartialSignature},
+    vrf::{VrfInput, VrfInOut},
+};
+
+
+/// One party's share of a co-signing secret key.
+pub struct CoSigner<K: AffineRepr> {
+    share: SecretShare<K>,
+    group: GroupKey<K>,
+}
+
+impl<K: AffineRepr> SecretKey<K> {
+    /// Split this secret key between two co-signers, who must both
+    /// participate in every signature for our `PublicKey`.
+    ///
+    /// We act as a trusted dealer here, so callers should erase this
+    /// `SecretKey` after provisioning both co-signers.
+    pub fn split_cosigners(&self) -> [CoSigner<K>; 2] {
+        let (group, shares) = self.split_threshold(2, 2);
+        let mut shares = shares.into_iter();
+        let mut next = || CoSigner {
+            share: shares.next().expect("We split into two shares"),
+            group: group.clone(),
+        };
+        [next(), next()]
+    }
+}
+
+impl<K: AffineRepr> CoSigner<K> {
+    /// Public key for which we co-sign.
+    pub fn public(&self) -> &PublicKey<K> { &self.group.public }
+
+    /// Begin co-signing for these VRF inputs, returning our session
+    /// and our round one message.
+    pub fn start(&self, t: Transcript, inputs: Vec<VrfInput<K>>) -> (CoSigningSession<'_,K>, PreOutShares<K>) {
+        let ours = self.share.preout_shares(&inputs);
+        let session = CoSigningSession {
+            cosigner: self, t, inputs,
+            preout_shares: ark_std::vec![ours.clone()],
+            ios: Vec::new(),
+            commitments: Vec::with_capacity(2),
+            nonces: None,
+            partials: Vec::with_capacity(2),
+        };
+        (session, ours)
+    }
+}
+
+/// One party's state for co-signing one signature.
+pub struct CoSigningSession<'a, K: AffineRepr> {
+    cosigner: &'a CoSigner<K>,
+    t: Transcript,
+    inputs: Vec<VrfInput<K>>,
+    /// Both parties' messages of each round, sorted by index.
+    preout_shares: Vec<PreOutShares<K>>,
+    ios: Vec<VrfInOut<K>>,
+    commitments: Vec<NonceCommitment<K>>,
+    nonces: Option<SigningNonces<K>>,
+    partials: Vec<PartialSignature<K>>,
+}
+
+/// Insert the other party's message, keeping both sorted by index.
+fn insert_peer<M>(ours: &mut Vec<M>, peer: M, index: impl Fn(&M) -> u16) -> SignatureResult<()> {
+    if ours.len() != 1 || index(&ours[0]) == index(&peer) {
+        return Err(SignatureError::Invalid);
+    }
+    if index(&peer) < index(&ours[0]) { ours.insert(0, peer); } else { ours.push(peer); }
+    Ok(())
+}
+
+impl<'a, K: AffineRepr> CoSigningSession<'a, K> {
+    /// Round two: Combine both parties' pre-output shares into the
+    /// `VrfInOut`s, and commit to our nonces.
+    #[cfg(feature = "getrandom")]
+    pub fn commit(&mut self, peer: PreOutShares<K>) -> SignatureResult<NonceCommitment<K>> {
+        self.commit_with_rng(peer, &mut ark_secret_scalar::getrandom_or_panic())
+    }
+
+    /// Round two: Like `commit`, but hedge our nonces using a user
+    /// supplied RNG.
+    pub fn commit_with_rng<R: RngCore+CryptoRng>(&mut self, peer: PreOutShares<K>, rng: &mut R)
+     -> SignatureResult<NonceCommitment<K>>
+    {
+        insert_peer(&mut self.preout_shares, peer, |p| p.index) ?;
+        self.ios = self.cosigner.group.combine_inouts(&self.inputs, &self.preout_shares) ?;
+        let (nonces, commitment) = self.cosigner.share.commit_with_rng(self.t.clone(), &self.ios, rng);
+        self.nonces = Some(nonces);
+        self.commitments.push(commitment.clone());
+        Ok(commitment)
+    }
+
+    /// Round three: Respond given the other party's nonce commitment,
+    /// which consumes our nonces.
+    pub fn sign(&mut self, peer: NonceCommitment<K>) -> SignatureResult<PartialSignature<K>> {
+        let nonces = self.nonces.take().ok_or(SignatureError::Invalid) ?;
+        insert_peer(&mut self.commitments, peer, |c| c.index) ?;
+        let partial = self.cosigner.share.sign_partial(nonces, self.t.clone(), &self.ios, &self.commitments) ?;
+        self.partials.push(partial.clone());
+        Ok(partial)
+    }
+
+    /// `VrfInOut`s for our VRF inputs, available after round two.
+    pub fn ios(&self) -> &[VrfInOut<K>] { &self.ios }
+
+    /// Verify the other party's partial signature, and combine both
+    /// into the thin VRF signature.
+    pub fn finish<const N: usize>(mut self, peer: PartialSignature<K>) -> SignatureResult<VrfSignature<ThinVrfProof<K>,N>> {
+        insert_peer(&mut self.partials, peer, |p| p.index) ?;
+        let ios: &[VrfInOut<K>; N] = self.ios.as_slice().try_into()
+            .map_err(|_| SignatureError::WrongInputCount) ?;
+        self.cosigner.group.aggregate(self.t.clone(), ios, &self.preout_shares, &self.commitments, &self.partials)
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Hex encodings for tooling and logs
+//!
+//! We `Display` public keys and signatures as `0x` prefixed lowercase
+//! hex of their compressed encodings, and parse them by `FromStr`,
+//! with or without the `0x` prefix.  We parse only this canonical
+//! lowercase hex of exactly one encoding, so parsing round trips.
+
+use ark_ec::AffineRepr;
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize,SerializationError};
+use ark_std::{fmt, str::FromStr, vec::Vec};
+
+use crate::{PublicKey, traits::{EcVrfProof, VrfSignature}};
+
+
+fn write_hex<T: CanonicalSerialize>(itm: &T, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("0x") ?;
+    for b in crate::compressed_bytes(itm) {
+        write!(f, "{:02x}", b) ?;
+    }
+    Ok(())
+}
+
+fn read_hex<T: CanonicalSerialize+CanonicalDeserialize>(s: &str) -> Result<T, SerializationError> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    // Reject uppercase, and signs which `from_str_radix` accepts.
+    if s.len() % 2 != 0 || !s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return Err(SerializationError::InvalidData);
+    }
+    let bytes = (0..s.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i+2], 16))
+        .collect::<Result<Vec<u8>,_>>()
+        .map_err(|_| SerializationError::InvalidData) ?;
+    let mut reader = bytes.as_slice();
+    let itm = T::deserialize_compressed(&mut reader) ?;
+    // Only exactly one canonical encoding round trips.
+    if !reader.is_empty() || crate::compressed_bytes(&itm) != bytes {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(itm)
+}
+
+impl<C: AffineRepr> fmt::Display for PublicKey<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hex(self, f)
+    }
+}
+
+impl<C: AffineRepr> FromStr for PublicKey<C> {
+    type Err = SerializationError;
+    fn from_str(s: &str) -> Result<Self, SerializationError> {
+        read_hex(s)
+    }
+}
+
+impl<P: EcVrfProof, const N: usize> fmt::Display for VrfSignature<P,N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hex(self, f)
+    }
+}
+
+impl<P: EcVrfProof, const N: usize> FromStr for VrfSignature<P,N> {
+    type Err = SerializationError;
+    fn from_str(s: &str) -> Result<Self, SerializationError> {
+        read_hex(s)
+    }
+}
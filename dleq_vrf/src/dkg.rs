@@ -0,0 +1,297 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Distributed key generation
+//!
+//! We implement Pedersen's dealer-less DKG, aka joint Feldman VSS, with
+//! complaint rounds, which outputs `threshold::SecretShare`s and the
+//! `threshold::GroupKey` without any trusted dealer.
+//!
+//! 1. `Participant::new` samples a polynomial and returns its public
+//!    `DealerCommitment`, which everyone broadcasts, and the
+//!    `DealerShare`s, which go to each recipient over private channels.
+//! 2. `Participant::receive` checks everyone's commitments and our
+//!    shares, and returns `Complaint`s which we broadcast.
+//! 3. `Participant::respond` reveals the shares we sent to anyone who
+//!    complained about us, which we broadcast too.
+//! 4. `Participant::finalize` disqualifies dealers with invalid,
+//!    duplicate, or conflicting commitments, or unanswered complaints,
+//!    and sums the qualified dealers' shares.
+//!
+//! Dealers prove possession of their constant terms, like FROST,
+//! which prevents rogue key attacks.  These proofs bind a caller
+//! supplied session id, unique to each DKG run, so nobody could replay
+//! commitments from other runs.  Gennaro et al. showed adversaries
+//! could bias the joint public key slightly in this DKG, which does
+//! not harm signing security.  Broadcast and private channels remain
+//! the caller's responsibility.
+
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize};
+use ark_std::{vec::Vec, Zero};
+
+use ark_secret_scalar::{SecretScalar, RngCore, CryptoRng};
+use zeroize::Zeroize;
+
+use crate::{
+    ThinVrf, ThinVrfProof, Transcript, PublicKey,
+    error::{SignatureResult, SignatureError},
+    threshold::{GroupKey, SecretShare},
+};
+
+
+type Scalar<K> = <K as AffineRepr>::ScalarField;
+
+/// Dealer's public commitments to its polynomial's coefficients,
+/// along with a proof-of-possession of its constant term.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct DealerCommitment<K: AffineRepr> {
+    pub dealer: u16,
+    pub coefficients: Vec<K>,
+    pub pop: ThinVrfProof<K>,
+}
+
+/// Dealer's share for one recipient, which travels over a private
+/// channel, unless revealed in response to a `Complaint`.
+#[derive(Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct DealerShare<K: AffineRepr> {
+    pub dealer: u16,
+    pub recipient: u16,
+    pub share: Scalar<K>,
+}
+
+impl<K: AffineRepr> Zeroize for DealerShare<K> {
+    fn zeroize(&mut self) { self.share.zeroize(); }
+}
+impl<K: AffineRepr> Drop for DealerShare<K> {
+    fn drop(&mut self) { self.zeroize() }
+}
+
+/// Recipient's public complaint that a dealer sent it no valid share.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct Complaint {
+    pub complainer: u16,
+    pub dealer: u16,
+}
+
+/// One participant's state during distributed key generation.
+pub struct Participant<K: AffineRepr> {
+    thin: ThinVrf<K>,
+    session: Vec<u8>,
+    index: u16,
+    threshold: u16,
+    n: u16,
+    polynomial: Vec<Scalar<K>>,
+    received: Vec<Option<Scalar<K>>>,
+    /// Nonce seed for our eventual `SecretShare`.
+    nonce_seed: [u8; 32],
+}
+
+impl<K: AffineRepr> Zeroize for Participant<K> {
+    fn zeroize(&mut self) {
+        for a in self.polynomial.iter_mut() { a.zeroize(); }
+        for s in self.received.iter_mut().flatten() { s.zeroize(); }
+        self.nonce_seed.zeroize();
+    }
+}
+impl<K: AffineRepr> Drop for Participant<K> {
+    fn drop(&mut self) { self.zeroize() }
+}
+
+/// Transcript for proofs-of-possession of dealers' constant terms.
+fn pop_transcript(session: &[u8], threshold: u16, n: u16, dealer: u16) -> Transcript {
+    let mut t = Transcript::new_labeled(b"DKG");
+    t.label(b"session");
+    t.append(session);
+    t.append_u64(threshold as u64);
+    t.append_u64(n as u64);
+    t.append_u64(dealer as u64);
+    t
+}
+
+/// Evaluate polynomial at `index` by Horner's rule.
+fn evaluate<F: PrimeField>(polynomial: &[F], index: u16) -> F {
+    let x = F::from(index as u64);
+    polynomial.iter().rev().fold(F::zero(), |y, a| y * x + a)
+}
+
+/// Evaluate committed polynomial at `index` in the exponent.
+fn evaluate_commitment<K: AffineRepr>(coefficients: &[K], index: u16) -> <K as AffineRepr>::Group {
+    let x = Scalar::<K>::from(index as u64);
+    let powers: Vec<Scalar<K>> = ark_std::iter::successors(Some(Scalar::<K>::from(1u64)), |p| Some(*p * x))
+        .take(coefficients.len())
+        .collect();
+    <K as AffineRepr>::Group::msm(coefficients, &powers)
+        .expect("Equal numbers of bases and scalars")
+}
+
+impl<K: AffineRepr> Participant<K> {
+    /// Start distributed key generation as participant `index` in
+    /// `1..=n`, with any `threshold` participants able to sign.
+    ///
+    /// All participants must agree upon a `session` id, which no other
+    /// DKG run ever uses.
+    #[cfg(feature = "getrandom")]
+    pub fn new(thin: ThinVrf<K>, session: &[u8], index: u16, threshold: u16, n: u16)
+     -> (Participant<K>, DealerCommitment<K>, Vec<DealerShare<K>>)
+    {
+        Self::new_with_rng(thin, session, index, threshold, n, &mut ark_secret_scalar::getrandom_or_panic())
+    }
+
+    /// Start distributed key generation like `new`, but reproducibly
+    /// from a `testing::MockRng`, never in production.
+    #[cfg(feature = "testing")]
+    pub fn new_with_mock_rng(thin: ThinVrf<K>, session: &[u8], index: u16, threshold: u16, n: u16, rng: &crate::testing::MockRng)
+     -> (Participant<K>, DealerCommitment<K>, Vec<DealerShare<K>>)
+    {
+        Self::new_with_rng(thin, session, index, threshold, n, &mut rng.restart())
+    }
+
+    /// Start distributed key generation like `new`, but sample our
+    /// polynomial using a user supplied RNG.
+    pub fn new_with_rng<R: RngCore+CryptoRng>(thin: ThinVrf<K>, session: &[u8], index: u16, threshold: u16, n: u16, rng: &mut R)
+     -> (Participant<K>, DealerCommitment<K>, Vec<DealerShare<K>>)
+    {
+        assert!(0 < threshold && threshold <= n, "Threshold must lie between 1 and n");
+        assert!(0 < index && index <= n, "Index must lie between 1 and n");
+        let t = pop_transcript(session, threshold, n, index);
+        let mut reader = t.fork(b"witness").witness(rng);
+        let polynomial: Vec<Scalar<K>> = (0..threshold).map(|_| reader.read_reduce()).collect();
+
+        let coefficients = <K as AffineRepr>::Group::normalize_batch(
+            &polynomial.iter().map(|a| thin.keying_base * *a).collect::<Vec<_>>()
+        );
+        let mut constant = polynomial[0];
+        let constant_key = thin.clone().secretkey_from_scalar(
+            SecretScalar::from_scalar(constant), reader.read_byte_array()
+        );
+        constant.zeroize();
+        let pop = constant_key.sign_thin_vrf_detached(t, &[]);
+        let commitment = DealerCommitment { dealer: index, coefficients, pop };
+
+        let shares = (1..=n).map(|recipient| DealerShare {
+            dealer: index, recipient, share: evaluate(&polynomial, recipient),
+        }).collect();
+        let participant = Participant {
+            thin, session: session.to_vec(), index, threshold, n, polynomial,
+            received: (0..n).map(|_| None).collect(),
+            nonce_seed: reader.read_byte_array(),
+        };
+        (participant, commitment, shares)
+    }
+
+    /// Our participant index
+    pub fn index(&self) -> u16 { self.index }
+
+    /// Check one dealer's commitment is well formed.
+    fn check_commitment(&self, commitment: &DealerCommitment<K>) -> bool {
+        if commitment.coefficients.len() != self.threshold as usize { return false; }
+        if commitment.dealer == 0 || commitment.dealer > self.n { return false; }
+        let t = pop_transcript(&self.session, self.threshold, self.n, commitment.dealer);
+        let public = PublicKey(commitment.coefficients[0]);
+        self.thin.verify_thin_vrf(t, &[], &public, &commitment.pop).is_ok()
+    }
+
+    /// Whether `dealer` broadcast several commitments, whether duplicate
+    /// or conflicting, which disqualifies it.
+    fn equivocates(commitments: &[DealerCommitment<K>], dealer: u16) -> bool {
+        commitments.iter().filter(|c| c.dealer == dealer).count() > 1
+    }
+
+    /// Check a share against its dealer's commitment.
+    fn check_share(&self, commitment: &DealerCommitment<K>, share: &DealerShare<K>) -> bool {
+        commitment.dealer == share.dealer
+        && 0 < share.recipient && share.recipient <= self.n
+        && self.thin.keying_base * share.share == evaluate_commitment(&commitment.coefficients, share.recipient)
+    }
+
+    /// Record our shares from every dealer, and complain about dealers
+    /// whose share for us is missing or disagrees with their commitment.
+    pub fn receive(&mut self, commitments: &[DealerCommitment<K>], shares: &[DealerShare<K>]) -> Vec<Complaint> {
+        let mut complaints = Vec::new();
+        for commitment in commitments {
+            if ! self.check_commitment(commitment) { continue; }
+            if Self::equivocates(commitments, commitment.dealer) { continue; }
+            let share = shares.iter().find(|s| s.dealer == commitment.dealer && s.recipient == self.index);
+            match share {
+                Some(share) if self.check_share(commitment, share) =>
+                    self.received[usize::from(commitment.dealer) - 1] = Some(share.share),
+                _ => complaints.push(Complaint { complainer: self.index, dealer: commitment.dealer }),
+            }
+        }
+        complaints
+    }
+
+    /// Reveal the shares we dealt to everyone who complained about us.
+    pub fn respond(&self, complaints: &[Complaint]) -> Vec<DealerShare<K>> {
+        complaints.iter()
+            .filter(|c| c.dealer == self.index && c.complainer != 0 && c.complainer <= self.n)
+            .map(|c| DealerShare {
+                dealer: self.index, recipient: c.complainer,
+                share: evaluate(&self.polynomial, c.complainer),
+            }).collect()
+    }
+
+    /// Complete distributed key generation, given every broadcast
+    /// commitment, complaint, and response.
+    ///
+    /// We disqualify dealers with invalid commitments, who broadcast
+    /// several commitments, or who failed to answer any complaint with
+    /// a valid share, and take revealed shares in place of any we
+    /// complained about.  All honest participants
+    /// compute the same qualified set and hence the same `GroupKey`.
+    pub fn finalize(
+        mut self,
+        commitments: &[DealerCommitment<K>],
+        complaints: &[Complaint],
+        responses: &[DealerShare<K>],
+    ) -> SignatureResult<(GroupKey<K>, SecretShare<K>)>
+    {
+        let mut qualified: Vec<&DealerCommitment<K>> = Vec::new();
+        for commitment in commitments {
+            if ! self.check_commitment(commitment) { continue; }
+            if Self::equivocates(commitments, commitment.dealer) { continue; }
+            let answered = complaints.iter()
+                .filter(|c| c.dealer == commitment.dealer)
+                .all(|c| responses.iter().any(|r|
+                    r.recipient == c.complainer && self.check_share(commitment, r)
+                ));
+            if answered { qualified.push(commitment); }
+        }
+        if qualified.len() < self.threshold as usize {
+            return Err(SignatureError::Invalid);
+        }
+
+        let mut x = Scalar::<K>::zero();
+        for commitment in qualified.iter() {
+            let dealer = usize::from(commitment.dealer) - 1;
+            if self.received[dealer].is_none() {
+                self.received[dealer] = responses.iter()
+                    .find(|r| r.recipient == self.index && self.check_share(commitment, r))
+                    .map(|r| r.share);
+            }
+            x += self.received[dealer].ok_or(SignatureError::Invalid) ?;
+        }
+        let secret = self.thin.clone().secretkey_from_scalar(SecretScalar::from_scalar(x), self.nonce_seed);
+        x.zeroize();
+
+        let public = qualified.iter()
+            .fold(<K as AffineRepr>::Group::zero(), |p, c| p + c.coefficients[0]);
+        let verifying_shares = (1..=self.n).map(|m| {
+            let y = qualified.iter()
+                .fold(<K as AffineRepr>::Group::zero(), |y, c| y + evaluate_commitment(&c.coefficients, m));
+            PublicKey(y.into_affine())
+        }).collect();
+        let group = GroupKey {
+            thin: self.thin.clone(),
+            public: PublicKey(public.into_affine()),
+            threshold: self.threshold,
+            verifying_shares,
+        };
+        if secret.as_publickey() != &group.verifying_shares[usize::from(self.index) - 1] {
+            return Err(SignatureError::Invalid);
+        }
+        let share = SecretShare { index: self.index, group_public: group.public.clone(), secret };
+        Ok((group, share))
+    }
+}
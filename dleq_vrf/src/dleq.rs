@@ -0,0 +1,61 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Standalone discrete logarithm equality proofs
+//!
+//! Some protocols, like distributed randomness mixing, only need to
+//! prove that one secret scalar links several `VrfInput`s to their
+//! `VrfPreOut`s, without VRF signatures' associated data or pre-output
+//! bookkeeping.  `dleq_prove` and `dleq_verify` provide exactly this.
+//!
+//! We build these from thin VRF proofs, so the keying base and public
+//! key form one more linked pair, and verifiers must know the public
+//! key and the thin VRF flavor, aka keying base.  Callers supply their
+//! own transcript `t`, which should identify their protocol and bind
+//! its context, like its round number.  We sign under our own protocol
+//! label, so these proofs never verify as VRF signatures, nor vice versa.
+
+use ark_ec::AffineRepr;
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize};
+use ark_std::vec::Vec;
+
+use crate::{
+    ThinVrf, ThinVrfProof, IntoTranscript, PublicKey, SecretKey,
+    error::SignatureResult,
+    vrf::{IntoVrfInput, VrfInOut, VrfPreOut},
+};
+
+
+/// Protocol label for DLEQ proofs.
+const DLEQ_LABEL: &[u8] = b"DLEQ";
+
+/// Proof that one secret scalar links every `VrfInput` to its `VrfPreOut`,
+/// as well as the keying base to the public key.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct DleqProof<K: AffineRepr>(pub ThinVrfProof<K>);
+
+/// Compute pre-outputs for `inputs` and prove their discrete logarithm
+/// equality with our public key, within the context transcript `t`.
+pub fn dleq_prove<K: AffineRepr>(
+    secret: &SecretKey<K>,
+    t: impl IntoTranscript,
+    inputs: impl IntoIterator<Item = impl IntoVrfInput<K>>,
+) -> (Vec<VrfPreOut<K>>, DleqProof<K>)
+{
+    let ios: Vec<VrfInOut<K>> = inputs.into_iter().map(|input| secret.vrf_inout(input)).collect();
+    let proof = secret.sign_thin_vrf_protocol(DLEQ_LABEL, t, &ios);
+    (ios.into_iter().map(|io| io.preoutput).collect(), DleqProof(proof))
+}
+
+/// Verify that the secret key for `public` links every input to its
+/// pre-output in `ios`, within the context transcript `t`, using the
+/// keying base of `thin`.
+pub fn dleq_verify<K: AffineRepr>(
+    thin: &ThinVrf<K>,
+    t: impl IntoTranscript,
+    public: &PublicKey<K>,
+    ios: &[VrfInOut<K>],
+    proof: &DleqProof<K>,
+) -> SignatureResult<()>
+{
+    thin.verify_thin_vrf_protocol(DLEQ_LABEL, t, ios, public, &proof.0).map(|_| ())
+}
@@ -0,0 +1,146 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Elligator2 hash-to-curve for twisted Edwards curves
+//!
+//! We follow the random oracle `hash_to_curve` construction from
+//! [RFC 9380](https://www.rfc-editor.org/rfc/rfc9380.html), using
+//! `expand_message_xmd` for `hash_to_field`, and the Elligator2 map of
+//! section 6.7.1 onto the curve's Montgomery form.  Curve crates like
+//! `bandersnatch_vrfs` and `jubjub_vrfs` share this layer, and only
+//! choose their suite, their non-square `Z`, and the curve form in
+//! which they represent points.
+//!
+//! Arkworks lacks an Elligator2 map, so we provide this one until
+//! arkworks does.
+
+use ark_ec::{
+    AffineRepr, CurveGroup,
+    twisted_edwards::{Affine, TECurveConfig},
+};
+use ark_ff::{Field, PrimeField, BigInteger, Zero, field_hashers::{DefaultFieldHasher, HashToField}};
+use ark_std::vec::Vec;
+use digest::DynDigest;
+
+
+/// Sign of a field element as defined in RFC 9380 section 4.1,
+/// for prime fields.
+fn sgn0<F: PrimeField>(x: &F) -> bool {
+    x.into_bigint().is_odd()
+}
+
+/// Elligator2 map onto the Montgomery form of the twisted Edwards
+/// curve `P`, whose constants we invert only once.
+///
+/// We write the Montgomery form `K t^2 = s^3 + J s^2 + s`, with
+/// `J = 2 (a + d) / (a - d)` and `K = 4 / (a - d)`, where `a` and `d`
+/// are the twisted Edwards coefficients.
+#[derive(Debug,Clone,Copy)]
+pub struct Elligator2Map<P: TECurveConfig>
+where P::BaseField: PrimeField,
+{
+    z: P::BaseField,
+    k: P::BaseField,
+    j_over_k: P::BaseField,
+    k2_inv: P::BaseField,
+}
+
+impl<P: TECurveConfig> Elligator2Map<P>
+where P::BaseField: PrimeField,
+{
+    /// Elligator2 map with the non-square `z`, which RFC 9380 appendix
+    /// H.3's `find_z_elligator2` selects.
+    ///
+    /// Panics if `z` is a square, or if `a = d`.
+    pub fn new(z: P::BaseField) -> Self {
+        assert!(z.sqrt().is_none(), "Elligator2 needs a non-square Z");
+        let a_minus_d_inv = (P::COEFF_A - P::COEFF_D).inverse()
+            .expect("Twisted Edwards curves have a != d");
+        let j = (P::COEFF_A + P::COEFF_D).double() * a_minus_d_inv;
+        let k = P::BaseField::from(4u8) * a_minus_d_inv;
+        let k_inv = k.inverse().expect("K = 4 / (a - d) is nonzero");
+        Elligator2Map { z, k, j_over_k: j * k_inv, k2_inv: k_inv.square() }
+    }
+
+    /// Montgomery coefficient `J / K`.
+    pub fn j_over_k(&self) -> P::BaseField { self.j_over_k }
+
+    /// Elligator2 map from RFC 9380 section 6.7.1, but without cofactor
+    /// clearing, onto the curve `y^2 = x^3 + (J/K) x^2 + x/K^2`, whose
+    /// points `(x,y)` give the Montgomery points `(s,t) = (x K, y K)`.
+    pub fn map_to_scaled_montgomery(&self, u: P::BaseField) -> (P::BaseField, P::BaseField) {
+        let Elligator2Map { z, j_over_k, k2_inv, .. } = *self;
+        let one = P::BaseField::ONE;
+
+        let mut x1 = -j_over_k * (one + z * u.square()).inverse().unwrap_or(P::BaseField::ZERO);
+        if x1.is_zero() { x1 = -j_over_k; }
+        let g = |x: P::BaseField| x * (x * x + j_over_k * x + k2_inv);
+        let x2 = -x1 - j_over_k;
+        let (x, mut y, sign) = match g(x1).sqrt() {
+            Some(y) => (x1, y, true),
+            None => (x2, g(x2).sqrt().expect("Elligator2: Either g(x1) or g(x2) is square"), false),
+        };
+        if sgn0(&y) != sign { y = -y; }
+        (x, y)
+    }
+
+    /// Elligator2 map onto the twisted Edwards form, but without
+    /// cofactor clearing, using the rational map of RFC 9380 appendix
+    /// D.1, which sends its exceptional cases to the identity.
+    pub fn map_to_edwards(&self, u: P::BaseField) -> Affine<P> {
+        let (x, y) = self.map_to_scaled_montgomery(u);
+        let (s, t) = (x * self.k, y * self.k);
+        let one = P::BaseField::ONE;
+        let (Some(t_inv), Some(s1_inv)) = (t.inverse(), (s + one).inverse()) else {
+            return Affine::zero();
+        };
+        let p = Affine::new_unchecked(s * t_inv, (s - one) * s1_inv);
+        debug_assert!(p.is_on_curve());
+        p
+    }
+}
+
+/// Domain separation tag prepared for hashing many messages to field
+/// elements for `Elligator2Map`, using `expand_message_xmd` with `H`.
+///
+/// We build the DST and its `expand_message_xmd` state, and invert the
+/// Elligator2 map's constants, only once per domain.
+pub struct Elligator2Hasher<P: TECurveConfig, H: Default + DynDigest + Clone>
+where P::BaseField: PrimeField,
+{
+    hasher: DefaultFieldHasher<H>,
+    map: Elligator2Map<P>,
+}
+
+impl<P: TECurveConfig, H: Default + DynDigest + Clone> Elligator2Hasher<P,H>
+where P::BaseField: PrimeField,
+{
+    /// Prepare the DST `domain || suite_id` for the map `map`.
+    pub fn new(domain: &[u8], suite_id: &[u8], map: Elligator2Map<P>) -> Self {
+        let mut dst = Vec::with_capacity(domain.len() + suite_id.len());
+        dst.extend_from_slice(domain);
+        dst.extend_from_slice(suite_id);
+        let hasher = <DefaultFieldHasher<H> as HashToField<P::BaseField>>::new(&dst);
+        Elligator2Hasher { hasher, map }
+    }
+
+    /// Our Elligator2 map.
+    pub fn map(&self) -> &Elligator2Map<P> { &self.map }
+
+    /// RFC 9380 `hash_to_field` with count two, which random oracle
+    /// `hash_to_curve` maps and adds.
+    ///
+    /// Arkworks 0.4's `expand_message_xmd` pads by the bytes we take per
+    /// field element, like 48 for 255 bit fields, not by the hash's block
+    /// size, so our outputs differ from RFC 9380's test vectors.
+    pub fn hash_to_field(&self, message: &[u8]) -> [P::BaseField; 2] {
+        let u: Vec<P::BaseField> = self.hasher.hash_to_field(message, 2);
+        [u[0], u[1]]
+    }
+
+    /// RFC 9380 random oracle `hash_to_curve` onto the twisted Edwards form.
+    pub fn hash_to_edwards(&self, message: &[u8]) -> Affine<P> {
+        let [u0, u1] = self.hash_to_field(message);
+        let p = self.map.map_to_edwards(u0).into_group() + self.map.map_to_edwards(u1);
+        p.into_affine().clear_cofactor()
+    }
+}
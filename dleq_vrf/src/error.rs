@@ -1,7 +1,93 @@
 
 pub type SignatureResult<T> = Result<T,SignatureError>;
 
-#[derive(Debug)]
+/// Signature failures, with stable numeric codes given by `code`,
+/// which FFI and runtime callers could rely upon.
+/// 
+/// We never renumber nor reuse codes, only append new variants,
+/// so callers must handle codes they do not yet know.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+#[repr(u8)]
+#[non_exhaustive]
 pub enum SignatureError {
-    Invalid,
-}  
+    /// Signature failed to verify
+    Invalid = 1,
+    /// Number of VRF inputs differs from the number of pre-outputs
+    WrongInputCount = 2,
+    /// Public key is the identity or outside the prime order subgroup
+    InvalidPublicKey = 3,
+    /// Pre-output is the identity or outside the prime order subgroup
+    InvalidPreOut = 4,
+    /// Ring proof failed to verify, although the Pedersen VRF did
+    RingProofInvalid = 5,
+    /// Ring verifier key or SRS disagrees with the ring or domain
+    SrsMismatch = 6,
+}
+
+impl SignatureError {
+    /// Stable numeric code for this failure.
+    pub const fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Failure with the given stable numeric code, if any.
+    pub const fn from_code(code: u8) -> Option<SignatureError> {
+        use SignatureError::*;
+        Some(match code {
+            1 => Invalid,
+            2 => WrongInputCount,
+            3 => InvalidPublicKey,
+            4 => InvalidPreOut,
+            5 => RingProofInvalid,
+            6 => SrsMismatch,
+            _ => return None,
+        })
+    }
+}
+
+impl core::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use SignatureError::*;
+        f.write_str(match self {
+            Invalid => "signature failed to verify",
+            WrongInputCount => "number of VRF inputs differs from the number of pre-outputs",
+            InvalidPublicKey => "public key is the identity or outside the prime order subgroup",
+            InvalidPreOut => "pre-output is the identity or outside the prime order subgroup",
+            RingProofInvalid => "ring proof failed to verify",
+            SrsMismatch => "ring verifier key or SRS disagrees with the ring or domain",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignatureError {}
+
+/// Mnemonic phrase failures
+#[cfg(feature = "mnemonic")]
+#[derive(Debug)]
+pub enum MnemonicError {
+    /// Phrase is not a valid English BIP39 mnemonic
+    InvalidPhrase(bip39::Error),
+    /// Entropy length unsupported by Substrate's seed derivation
+    InvalidEntropy,
+}
+
+#[cfg(feature = "mnemonic")]
+impl core::fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MnemonicError::InvalidPhrase(e) => write!(f, "invalid BIP39 mnemonic: {}", e),
+            MnemonicError::InvalidEntropy => f.write_str("mnemonic entropy length unsupported"),
+        }
+    }
+}
+
+#[cfg(feature = "mnemonic")]
+impl std::error::Error for MnemonicError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MnemonicError::InvalidPhrase(e) => Some(e),
+            MnemonicError::InvalidEntropy => None,
+        }
+    }
+}
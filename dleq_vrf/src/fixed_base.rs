@@ -0,0 +1,44 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Precomputed tables for fixed base scalar multiplication
+
+use ark_ec::{AffineRepr, scalar_mul::fixed_base::FixedBase};
+use ark_ff::PrimeField;
+use ark_std::{fmt, vec::Vec};
+
+
+/// Window size in bits, so our tables are radix 16.
+const WINDOW: usize = 4;
+
+/// Precomputed multiples of a fixed base point, which speeds up
+/// scalar multiplications by that base roughly 3-4x.
+///
+/// We only employ these for bases known in advance, like the keying and
+/// blinding bases in `PedersenVrf::with_fixed_base_tables`, or the keying
+/// base shared across one `sign_thin_vrf_batch`, so never for VRF inputs.
+/// Arkworks' windowed multiplication indexes the table by scalar bits,
+/// so like arkworks' own `mul` this runs in variable time.
+#[derive(Clone)]
+pub(crate) struct FixedBaseTable<C: AffineRepr> {
+    outerc: usize,
+    table: Vec<Vec<C>>,
+}
+
+impl<C: AffineRepr> FixedBaseTable<C> {
+    pub(crate) fn new(base: &C) -> Self {
+        let scalar_size = <C::ScalarField as PrimeField>::MODULUS_BIT_SIZE as usize;
+        let outerc = scalar_size.div_ceil(WINDOW);
+        let table = FixedBase::get_window_table(scalar_size, WINDOW, base.into_group());
+        FixedBaseTable { outerc, table }
+    }
+
+    pub(crate) fn mul(&self, scalar: &C::ScalarField) -> C::Group {
+        FixedBase::windowed_mul(self.outerc, WINDOW, &self.table, scalar)
+    }
+}
+
+impl<C: AffineRepr> fmt::Debug for FixedBaseTable<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FixedBaseTable {{ windows: {} }}", self.outerc)
+    }
+}
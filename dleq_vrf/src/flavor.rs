@@ -54,6 +54,7 @@ use ark_ec::{AffineRepr};
 
 use ark_serialize::{CanonicalSerialize,CanonicalDeserialize,SerializationError};
 use ark_std::{vec::Vec, io::{Read,Write}};
+use zeroize::Zeroize;
 
 
 /// VRF flavors based upon DLEQ proofs: Thin/Schnorr vs Pedersen vs something else.
@@ -80,6 +81,13 @@ pub(crate) struct Witness<F: Flavor> {
     pub(crate) r: <F as InnerFlavor>::Affines,
 }
 
+/// We zeroize the secret nonces when dropped, including after
+/// `sign_final` consumes the witness.
+impl<F: Flavor> Drop for Witness<F> {
+    fn drop(&mut self) { self.k.zeroize() }
+}
+impl<F: Flavor> zeroize::ZeroizeOnDrop for Witness<F> {}
+
 /// Batchable VRF signature detached from VRF inputs and outpus
 #[derive(Debug,Clone,Eq,PartialEq,CanonicalSerialize,CanonicalDeserialize)]
 pub struct Batchable<F: Flavor> {
@@ -88,19 +96,11 @@ pub struct Batchable<F: Flavor> {
     pub(crate) r: <F as InnerFlavor>::Affines,
 }
 
-/*
-impl<P: Flavor> Valid for Batchable<F> {
-    fn check(&self) -> Result<(), SerializationError> {
-        if self.is_on_curve() && self.is_in_correct_subgroup_assuming_on_curve() {
-            Ok(())
-        } else {
-            Err(SerializationError::InvalidData)
-        }
-    }
-}
-*/
-
 /// Arkworks' own serialization traits should be preferred over these.
+/// 
+/// Our derived `Valid` checks every curve point in the signature for
+/// curve membership and prime order subgroup membership, so checked
+/// deserialization rejects small order components.
 impl<F: Flavor> Batchable<F> {
     pub fn as_key_commitment(&self) -> &<F as InnerFlavor>::KeyCommitment { &self.compk }
 
@@ -115,6 +115,12 @@ impl<F: Flavor> Batchable<F> {
     pub fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
         Self::deserialize_compressed(reader)
     }
+
+    /// Deserialize without curve or subgroup checks, only for
+    /// signatures from trusted storage.
+    pub fn deserialize_unchecked<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Self::deserialize_compressed_unchecked(reader)
+    }
 }
 
 /// Non-batchable VRF signature detached from VRF inputs and outpus,resembles EC VRF.
@@ -142,4 +148,10 @@ impl<F: Flavor> NonBatchable<F> {
     pub fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
         Self::deserialize_compressed(reader)
     }
+
+    /// Deserialize without curve or subgroup checks, only for
+    /// signatures from trusted storage.
+    pub fn deserialize_unchecked<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Self::deserialize_compressed_unchecked(reader)
+    }
 }
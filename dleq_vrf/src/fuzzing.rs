@@ -0,0 +1,110 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Arbitrary instances for fuzzing
+//!
+//! We map fuzzer bytes onto curve points and scalars by hashing,
+//! so points land in the prime order subgroup and fuzzers explore
+//! protocol logic, not merely our deserialization.  `MutatedEncoding`
+//! covers deserialization instead, by corrupting valid encodings.
+
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_serialize::CanonicalSerialize;
+use ark_std::{marker::PhantomData, vec::Vec};
+
+use arbitrary::{Arbitrary, Unstructured, Result};
+
+use crate::{
+    ThinVrf, Transcript, SecretKey, PublicKey, Batchable,
+    vrf::{VrfInput, VrfPreOut},
+    traits::{EcVrfProof, VrfSignature},
+};
+
+
+/// Hash fuzzer bytes onto a curve point.
+fn arbitrary_point<K: AffineRepr>(u: &mut Unstructured<'_>) -> Result<K> {
+    let bytes: [u8; 32] = u.arbitrary() ?;
+    let mut t = Transcript::new_labeled(b"Arbitrary");
+    t.append(&bytes[..]);
+    let p: <K as AffineRepr>::Group = t.challenge(b"point").read_uniform();
+    Ok(p.into_affine())
+}
+
+/// Hash fuzzer bytes onto a scalar.
+fn arbitrary_scalar<K: AffineRepr>(u: &mut Unstructured<'_>) -> Result<<K as AffineRepr>::ScalarField> {
+    let bytes: [u8; 32] = u.arbitrary() ?;
+    let mut t = Transcript::new_labeled(b"Arbitrary");
+    t.append(&bytes[..]);
+    Ok(t.challenge(b"scalar").read_reduce())
+}
+
+impl<'a, K: AffineRepr> Arbitrary<'a> for SecretKey<K> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let seed: [u8; 32] = u.arbitrary() ?;
+        Ok(SecretKey::from_seed(&seed))
+    }
+}
+
+impl<'a, K: AffineRepr> Arbitrary<'a> for PublicKey<K> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(PublicKey(arbitrary_point(u) ?))
+    }
+}
+
+impl<'a, K: AffineRepr> Arbitrary<'a> for VrfInput<K> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(VrfInput(arbitrary_point(u) ?))
+    }
+}
+
+impl<'a, K: AffineRepr> Arbitrary<'a> for VrfPreOut<K> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(VrfPreOut(arbitrary_point(u) ?))
+    }
+}
+
+impl<'a, K: AffineRepr> Arbitrary<'a> for Batchable<ThinVrf<K>> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Batchable { compk: (), r: arbitrary_point(u) ?, s: arbitrary_scalar::<K>(u) ? })
+    }
+}
+
+impl<'a, P, const N: usize> Arbitrary<'a> for VrfSignature<P,N>
+where P: EcVrfProof + Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(VrfSignature { proof: u.arbitrary() ?, preouts: u.arbitrary() ? })
+    }
+}
+
+/// Compressed encoding of an arbitrary `T`, which the fuzzer then
+/// corrupts by flipping bits and truncating or extending.
+pub struct MutatedEncoding<T> {
+    pub bytes: Vec<u8>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> ark_std::fmt::Debug for MutatedEncoding<T> {
+    fn fmt(&self, f: &mut ark_std::fmt::Formatter<'_>) -> ark_std::fmt::Result {
+        self.bytes.fmt(f)
+    }
+}
+
+impl<'a, T: Arbitrary<'a> + CanonicalSerialize> Arbitrary<'a> for MutatedEncoding<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let itm: T = u.arbitrary() ?;
+        let mut bytes = Vec::with_capacity(itm.compressed_size());
+        itm.serialize_compressed(&mut bytes).expect("Vec serialization is infallible");
+        let flips: Vec<(u16, u8)> = u.arbitrary() ?;
+        for (i, bit) in flips {
+            if bytes.is_empty() { break; }
+            let i = usize::from(i) % bytes.len();
+            bytes[i] ^= 1 << (bit % 8);
+        }
+        match u.int_in_range(0u8..=2) ? {
+            0 => {},
+            1 => { let l = u.choose_index(bytes.len() + 1) ?; bytes.truncate(l); },
+            _ => { let extra: Vec<u8> = u.arbitrary() ?; bytes.extend(extra); },
+        }
+        Ok(MutatedEncoding { bytes, _phantom: PhantomData })
+    }
+}
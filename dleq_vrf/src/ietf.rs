@@ -0,0 +1,232 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### ECVRF interface ala RFC 9381
+//!
+//! We provide the ECVRF `prove`, `verify`, and `proof_to_hash`
+//! operations from [RFC 9381](https://www.rfc-editor.org/rfc/rfc9381.html)
+//! using our thin VRF keys, so chains which standardized upon RFC 9381
+//! semantics could migrate incrementally.  Proofs consist of `Gamma`,
+//! the challenge `c` truncated to `Suite::CHALLENGE_LENGTH` bytes,
+//! and the response `s`.
+//!
+//! We follow the RFC's nonce generation from section 5.4.2.2, its
+//! challenge generation, and its `proof_to_hash`, so proofs agree
+//! byte-for-byte with other implementations of the same suite.
+//! Suites choose `point_to_string` and `string_to_point`, which
+//! default to arkworks' compressed serialization, and scalars always
+//! use arkworks' little endian serialization.  Hash to curve remains
+//! the caller's responsibility via `IntoVrfInput`, ideally using an
+//! RFC 9380 suite like our Bandersnatch Elligator2.
+//!
+//! As an extension, challenges hash additional data `ad` after the
+//! points, which matches the RFC whenever `ad` is empty.
+
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize,SerializationError};
+use ark_std::{fmt, vec::Vec};
+use zeroize::Zeroize;
+
+use crate::{
+    ThinVrf, PublicKey, SecretKey,
+    error::{SignatureResult, SignatureError},
+    transcript::digest::{Digest, Output},
+    vrf::{IntoVrfInput, VrfInput, VrfPreOut},
+};
+
+
+/// ECVRF cipher suite, ala RFC 9381 section 5.5.
+pub trait Suite {
+    /// Curve in short Weierstrass, twisted Edwards, etc. form.
+    type Affine: AffineRepr;
+
+    /// Hash function used for nonces, challenges, and outputs,
+    /// whose output must be at least 64 bytes.
+    type Hasher: Digest;
+
+    /// Cipher suite string, which domain seperates everything.
+    const SUITE_ID: &'static [u8];
+
+    /// Bytes retained from the challenge hash, aka `cLen`.
+    const CHALLENGE_LENGTH: usize;
+
+    /// Whether `proof_to_hash` multiplies `Gamma` by the cofactor,
+    /// as RFC 9381 section 5.2 does.
+    const OUTPUT_CLEARS_COFACTOR: bool = true;
+
+    /// Point encoding `point_to_string`.
+    fn point_to_string(p: &Self::Affine, buf: &mut Vec<u8>) {
+        p.serialize_compressed(buf).expect("Vec serialization is infallible");
+    }
+
+    /// Point decoding `string_to_point`, which consumes exactly
+    /// one encoded point from `bytes`.
+    fn string_to_point(bytes: &mut &[u8]) -> Result<Self::Affine, SerializationError> {
+        Self::Affine::deserialize_compressed(bytes)
+    }
+
+    /// Integer decoding `string_to_int` applied to the truncated
+    /// challenge hash.
+    fn challenge_to_scalar(c: &[u8]) -> Scalar<Self> {
+        Scalar::<Self>::from_le_bytes_mod_order(c)
+    }
+}
+
+type Scalar<S> = <<S as Suite>::Affine as AffineRepr>::ScalarField;
+
+/// ECVRF proof `pi_string`, which consists of `Gamma`, `c`, and `s`.
+pub struct Proof<S: Suite> {
+    pub gamma: VrfPreOut<S::Affine>,
+    pub c: Scalar<S>,
+    pub s: Scalar<S>,
+}
+
+impl<S: Suite> Clone for Proof<S> {
+    fn clone(&self) -> Self {
+        Proof { gamma: self.gamma, c: self.c, s: self.s }
+    }
+}
+
+impl<S: Suite> fmt::Debug for Proof<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Proof {{ gamma: {:?}, c: {:?}, s: {:?} }}", &self.gamma, &self.c, &self.s)
+    }
+}
+
+impl<S: Suite> Eq for Proof<S> {}
+
+impl<S: Suite> PartialEq for Proof<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.gamma == other.gamma && self.c == other.c && self.s == other.s
+    }
+}
+
+fn point_to_string<S: Suite>(p: &S::Affine) -> Vec<u8> {
+    let mut buf = Vec::new();
+    S::point_to_string(p, &mut buf);
+    buf
+}
+
+impl<S: Suite> Proof<S> {
+    /// Serialized length of proofs
+    pub fn serialized_length() -> usize {
+        let zero = <S::Affine as AffineRepr>::zero();
+        point_to_string::<S>(&zero).len() + S::CHALLENGE_LENGTH + Scalar::<S>::default().compressed_size()
+    }
+
+    /// Serialize as `Gamma || c || s`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::serialized_length());
+        S::point_to_string(&self.gamma.0, &mut bytes);
+        let mut c = Vec::new();
+        self.c.serialize_compressed(&mut c).unwrap();
+        bytes.extend_from_slice(&c[..S::CHALLENGE_LENGTH]);
+        self.s.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Deserialize from `Gamma || c || s`, rejecting any other length,
+    /// or non-canonical encodings.
+    pub fn from_bytes(encoded: &[u8]) -> Result<Self, SerializationError> {
+        let mut bytes = encoded;
+        if bytes.len() != Self::serialized_length() {
+            return Err(SerializationError::InvalidData);
+        }
+        let gamma = VrfPreOut(S::string_to_point(&mut bytes) ?);
+        let (c, mut bytes) = bytes.split_at(S::CHALLENGE_LENGTH);
+        let c = Scalar::<S>::from_le_bytes_mod_order(c);
+        let s = Scalar::<S>::deserialize_compressed(&mut bytes) ?;
+        let proof = Proof { gamma, c, s };
+        // Reject non-canonical encodings, so proofs have unique encodings.
+        if proof.to_bytes() != encoded { return Err(SerializationError::InvalidData); }
+        Ok(proof)
+    }
+}
+
+/// ECVRF nonce generation from RFC 9381 section 5.4.2.2, which
+/// follows RFC 8032 section 5.1.6.
+fn nonce<S: Suite>(secret: &SecretKey<S::Affine>, input: &VrfInput<S::Affine>) -> Scalar<S> {
+    let mut x = Vec::with_capacity(secret.key.secret_serialized_size());
+    secret.key.serialize_secret(&mut x).expect("Vec serialization is infallible");
+    let mut hashed_sk = S::Hasher::digest(&x);
+    x.zeroize();
+    assert!(hashed_sk.len() >= 64, "ECVRF nonces require 64 byte hashes");
+    let mut h = S::Hasher::new();
+    h.update(&hashed_sk[32..]);
+    hashed_sk.as_mut_slice().zeroize();
+    h.update(point_to_string::<S>(&input.0));
+    Scalar::<S>::from_le_bytes_mod_order(&h.finalize())
+}
+
+/// ECVRF challenge generation from RFC 9381 section 5.4.3,
+/// extended by additional data `ad`.
+fn challenge<S: Suite>(points: [&S::Affine; 5], ad: &[u8]) -> Scalar<S> {
+    let mut h = S::Hasher::new();
+    h.update(S::SUITE_ID);
+    h.update([0x02]);
+    let mut buf = Vec::new();
+    for p in points {
+        buf.clear();
+        S::point_to_string(p, &mut buf);
+        h.update(&buf);
+    }
+    h.update(ad);
+    h.update([0x00]);
+    let c = h.finalize();
+    S::challenge_to_scalar(&c[..S::CHALLENGE_LENGTH])
+}
+
+/// ECVRF proof to hash from RFC 9381 section 5.2, which yields
+/// the VRF output `beta_string`.
+pub fn proof_to_hash<S: Suite>(proof: &Proof<S>) -> Output<S::Hasher> {
+    let gamma = if S::OUTPUT_CLEARS_COFACTOR {
+        proof.gamma.0.mul_by_cofactor()
+    } else {
+        proof.gamma.0
+    };
+    let mut h = S::Hasher::new();
+    h.update(S::SUITE_ID);
+    h.update([0x03]);
+    h.update(point_to_string::<S>(&gamma));
+    h.update([0x00]);
+    h.finalize()
+}
+
+/// ECVRF proving from RFC 9381 section 5.1, with additional data `ad`,
+/// which RFC 9381 leaves empty.
+pub fn prove<S: Suite>(
+    secret: &SecretKey<S::Affine>,
+    input: impl IntoVrfInput<S::Affine>,
+    ad: &[u8],
+) -> Proof<S>
+{
+    let input: VrfInput<S::Affine> = input.into_vrf_input();
+    let gamma = secret.vrf_preout(&input);
+
+    let k = nonce::<S>(secret, &input);
+    let u = (secret.thin.keying_base * k).into_affine();
+    let v = (input.0 * k).into_affine();
+
+    let c = challenge::<S>([&secret.as_publickey().0, &input.0, &gamma.0, &u, &v], ad);
+    let s = k + secret.key.mul_by_challenge(&c);
+    Proof { gamma, c, s }
+}
+
+/// ECVRF verifying from RFC 9381 section 5.3, with additional data `ad`,
+/// which returns the VRF output `beta_string` upon success.
+pub fn verify<S: Suite>(
+    thin: &ThinVrf<S::Affine>,
+    public: &PublicKey<S::Affine>,
+    input: impl IntoVrfInput<S::Affine>,
+    ad: &[u8],
+    proof: &Proof<S>,
+) -> SignatureResult<Output<S::Hasher>>
+{
+    let input: VrfInput<S::Affine> = input.into_vrf_input();
+    let u = (thin.keying_base * proof.s - public.0 * proof.c).into_affine();
+    let v = (input.0 * proof.s - proof.gamma.0 * proof.c).into_affine();
+    if challenge::<S>([&public.0, &input.0, &proof.gamma.0, &u, &v], ad) != proof.c {
+        return Err(SignatureError::Invalid);
+    }
+    Ok(proof_to_hash(proof))
+}
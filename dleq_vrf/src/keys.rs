@@ -4,16 +4,18 @@
 
 
 use zeroize::Zeroize;
+use subtle::{Choice,ConstantTimeEq};
 
 use ark_std::{vec::Vec, io::{Read, Write}};
 // #[cfg(debug_assertions)]
 // use ark_std::{boxed::Box, sync::Mutex};
 
-use ark_ec::{AffineRepr}; // Group, CurveGroup
+use ark_ec::{AffineRepr, CurveGroup}; // Group
 use ark_serialize::{CanonicalSerialize,CanonicalDeserialize,SerializationError};
 
 #[cfg(feature = "getrandom")]
-use ark_secret_scalar::{rand_core, RngCore};
+use ark_secret_scalar::rand_core;
+use ark_secret_scalar::{RngCore, CryptoRng};
 
 use ark_secret_scalar::SecretScalar;
 
@@ -25,17 +27,52 @@ use crate::{
 
 
 /// Public key
-#[derive(Debug,Clone,Eq,Hash,CanonicalSerialize,CanonicalDeserialize)] // Copy, PartialOrd, Ord, Hash, 
+/// 
+/// We consider public keys equal modulo small cofactors, and hash and
+/// sort them by the canonical compressed encodings of their multiples
+/// by the small cofactor, so they work as map keys and in sorted
+/// validator sets.  Checked deserialization ensures this agrees with
+/// group equality.
+#[derive(Debug,Clone,CanonicalSerialize,CanonicalDeserialize)] // Copy
 #[repr(transparent)]
 pub struct PublicKey<C: AffineRepr>(pub C);
 
+impl<C: AffineRepr> PublicKey<C> {
+    /// Encoding of our key times the small cofactor, by which we
+    /// hash and sort consistently with equality modulo small cofactors.
+    fn cofactor_bytes(&self) -> [u8; crate::MAX_POINT_BYTES] {
+        crate::point_bytes(&crate::mul_by_small_cofactor(self.0.into_group()).into_affine())
+    }
+}
+
 impl<C: AffineRepr> PartialEq for PublicKey<C> {
     fn eq(&self, other: &PublicKey<C>) -> bool {
         crate::zero_mod_small_cofactor(self.0.into_group() - other.0.into_group())
     }
 }
+impl<C: AffineRepr> Eq for PublicKey<C> {}
+
+impl<C: AffineRepr> core::hash::Hash for PublicKey<C> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.cofactor_bytes().hash(state)
+    }
+}
+
+impl<C: AffineRepr> PartialOrd for PublicKey<C> {
+    fn partial_cmp(&self, other: &PublicKey<C>) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<C: AffineRepr> Ord for PublicKey<C> {
+    fn cmp(&self, other: &PublicKey<C>) -> core::cmp::Ordering {
+        self.cofactor_bytes().cmp(&other.cofactor_bytes())
+    }
+}
 
 /// Arkworks' own serialization traits should be preferred over these.
+/// 
+/// Checked deserialization rejects points not on the curve or not
+/// in the prime order subgroup.
 impl<C: AffineRepr> PublicKey<C> {
     pub fn update_digest(&self, h: &mut impl Update) {
         // This private struct works around Serialize taking the pre-existing
@@ -66,12 +103,117 @@ impl<C: AffineRepr> PublicKey<C> {
     pub fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
         Self::deserialize_compressed(reader)
     }
+
+    /// Deserialize without curve or subgroup checks, only for
+    /// public keys from trusted storage.
+    pub fn deserialize_unchecked<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Self::deserialize_compressed_unchecked(reader)
+    }
+
+    /// Serialize compressed into a fixed length byte array, avoiding allocation.
+    /// 
+    /// Panics unless `L` equals `size_of_serialized()`.
+    pub fn to_bytes<const L: usize>(&self) -> [u8; L] {
+        crate::to_bytes_array(self)
+    }
+
+    /// Deserialize compressed from a fixed length byte array,
+    /// rejecting any unused trailing bytes, or non-canonical encodings.
+    pub fn from_bytes<const L: usize>(bytes: &[u8; L]) -> Result<Self, SerializationError> {
+        crate::deserialize_canonical(bytes.as_slice())
+    }
+
+    /// Deserialize compressed from borrowed bytes without copying,
+    /// like some slice of a larger gossip message, rejecting any
+    /// unused trailing bytes, or non-canonical encodings.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, SerializationError> {
+        crate::deserialize_canonical(bytes)
+    }
+
+    /// Check our public key lies on the curve, inside the prime order
+    /// subgroup, and is not the identity, like `VrfPreOut::validate`.
+    pub fn validate(&self) -> crate::SignatureResult<()> {
+        if self.0.is_zero() || self.0.check().is_err() {
+            return Err(crate::SignatureError::InvalidPublicKey);
+        }
+        Ok(())
+    }
 }
 
 
 /// Length of the nonce seed accompanying the secret key.
 pub const NONCE_SEED_LENGTH: usize = 32;
 
+/// Nonce aka witness derivation strategy for signing.
+/// 
+/// We always hash the secret nonce seed along with the whole signing
+/// transcript, including all `VrfInOut`s, so either strategy
+/// remains secure under repeated signing.
+///
+/// Our default depends upon features: `Hedged` with `getrandom`, but
+/// `Deterministic` without, so signatures only become reproducible
+/// when no system randomness exists.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum NonceStrategy {
+    /// Hedged nonces which also hash system randomness, or which
+    /// become deterministic without the `getrandom` feature.
+    Hedged,
+    /// Fully deterministic nonces, ala RFC 6979, for embedded signers
+    /// which lack good randomness, and our default without `getrandom`.
+    Deterministic,
+}
+
+impl Default for NonceStrategy {
+    fn default() -> Self {
+        if cfg!(feature = "getrandom") {
+            NonceStrategy::Hedged
+        } else {
+            NonceStrategy::Deterministic
+        }
+    }
+}
+
+
+/// Secret key together with its public key.
+///
+/// We sign only through our `SecretKey`, whose thin VRF flavor fixes
+/// the keying base, so our public key always matches our flavor.
+/// We deref to our `SecretKey` for its signing methods.
+#[derive(Clone)]
+pub struct Keypair<K: AffineRepr> {
+    secret: SecretKey<K>,
+    public: PublicKey<K>,
+}
+
+impl<K: AffineRepr> From<SecretKey<K>> for Keypair<K> {
+    fn from(secret: SecretKey<K>) -> Keypair<K> {
+        let public = secret.to_public();
+        Keypair { secret, public }
+    }
+}
+
+impl<K: AffineRepr> Keypair<K> {
+    /// Secret key for signing.
+    pub fn secret(&self) -> &SecretKey<K> { &self.secret }
+
+    /// Public key, computed only once when creating the keypair.
+    pub fn public(&self) -> &PublicKey<K> { &self.public }
+
+    /// Thin VRF flavor whose keying base our public key uses.
+    pub fn thin_vrf(&self) -> &ThinVrf<K> { &self.secret.thin }
+
+    /// Discard the public key.
+    pub fn into_secret(self) -> SecretKey<K> { self.secret }
+}
+
+impl<K: AffineRepr> core::ops::Deref for Keypair<K> {
+    type Target = SecretKey<K>;
+    fn deref(&self) -> &SecretKey<K> { &self.secret }
+}
+
+impl<K: AffineRepr> core::borrow::Borrow<SecretKey<K>> for Keypair<K> {
+    fn borrow(&self) -> &SecretKey<K> { &self.secret }
+}
 
 /// Seceret key consisting of a scalar and a secret nonce seed.
 #[derive(Clone)]
@@ -103,9 +245,16 @@ pub struct SecretKey<K: AffineRepr> {
     /// TODO: Compjute lazilty using usafe code and std::sync::Once
     public: PublicKey<K>,
 
+    /// Nonce derivation strategy used by `witness`.
+    nonce_strategy: NonceStrategy,
+
     #[cfg(debug_assertions)]
     test_vector_fake_rng: bool,
 
+    /// Seeded RNG replacing system randomness in `witness`, only for testing.
+    #[cfg(feature = "testing")]
+    mock_rng: Option<crate::testing::MockRng>,
+
     // #[cfg(debug_assertions)]
     // rng: Option<Mutex<Box<dyn RngCore+CryptoRng+Send>>>,
 }
@@ -119,6 +268,7 @@ impl<K: AffineRepr> Zeroize for SecretKey<K> {
 impl<K: AffineRepr> Drop for SecretKey<K> {
     fn drop(&mut self) { self.zeroize() }
 }
+impl<K: AffineRepr> zeroize::ZeroizeOnDrop for SecretKey<K> {}
 
 /*
 impl<K: AffineRepr> Debug for SecretKey<K> {
@@ -127,18 +277,46 @@ impl<K: AffineRepr> Debug for SecretKey<K> {
     }
 }
 
+*/
+
 impl<K: AffineRepr> Eq for SecretKey<K> {}
 impl<K: AffineRepr> PartialEq for SecretKey<K> {
     fn eq(&self, other: &Self) -> bool {
-        self.ct_eq(other).unwrap_u8() == 1u8
+        self.ct_eq(other).into()
     }
 }
+
+/// Compare both secret scalars and nonce seeds in constant-time.
 impl<K: AffineRepr> ConstantTimeEq for SecretKey<K> {
     fn ct_eq(&self, other: &Self) -> Choice {
-        self.key.ct_eq(&other.key)
+        self.key.ct_eq(&other.key) & self.nonce_seed[..].ct_eq(&other.nonce_seed[..])
+    }
+}
+
+/// Witness reader derived from a nonce seed, exactly like
+/// `SecretKey::witness` with the same nonce seed and strategy.
+///
+/// HSM backed deployments could keep their secret scalar in hardware,
+/// but derive witnesses in software using an exported nonce seed.
+pub fn nonce_seed_witness(
+    nonce_seed: &[u8; NONCE_SEED_LENGTH],
+    t: &crate::Transcript,
+    label: impl ark_transcript::AsLabel,
+    nonce_strategy: NonceStrategy,
+) -> ark_transcript::Reader {
+    let mut t = t.fork(b"witness");
+    t.label(label);
+    t.append(&nonce_seed[..]);
+    match nonce_strategy {
+        #[cfg(feature = "getrandom")]
+        NonceStrategy::Hedged => t.witness(&mut ark_secret_scalar::getrandom_or_panic()),
+        // Without system randomness, hedged nonces fall back to
+        // deterministic ones, like `SecretScalar::resplit` does.
+        #[cfg(not(feature = "getrandom"))]
+        NonceStrategy::Hedged => t.deterministic_witness(),
+        NonceStrategy::Deterministic => t.deterministic_witness(),
     }
 }
-*/
 
 impl<K: AffineRepr> ThinVrf<K> {
     /// Generate an "unbiased" `SecretKey` from a user supplied `XofReader`.
@@ -146,23 +324,165 @@ impl<K: AffineRepr> ThinVrf<K> {
     {
         let mut nonce_seed: [u8; 32] = [0u8; 32];
         xof.read(&mut nonce_seed);
-        let mut key = SecretScalar::from_xof(&mut xof);
-        let public = self.make_public(&mut key);
-        SecretKey { thin: self, key, nonce_seed, public, 
-            #[cfg(debug_assertions)]
-            test_vector_fake_rng: false,
-        }
+        let key = SecretScalar::from_xof(&mut xof);
+        let sk = self.secretkey_from_scalar(key, nonce_seed);
+        // Arrays are Copy, so zeroize our stack copy too.
+        nonce_seed.zeroize();
+        sk
     }
 
-    /// Generate a `SecretKey` from a 32 byte seed.
-    pub fn secretkey_from_seed(self, seed: &[u8; 32]) -> SecretKey<K> {
+    /// Generate an "unbiased" `SecretKey` from a user supplied `XofReader`,
+    /// and split its secret scalar using a user supplied RNG, so this
+    /// works without `getrandom`.
+    pub fn secretkey_from_xof_with_rng<R>(self, mut xof: impl XofReader, rng: &mut R) -> SecretKey<K>
+    where R: RngCore+CryptoRng
+    {
+        let mut nonce_seed: [u8; 32] = [0u8; 32];
+        xof.read(&mut nonce_seed);
+        let key = SecretScalar::from_xof_with_rng(&mut xof, rng);
+        let sk = self.secretkey_from_scalar(key, nonce_seed);
+        nonce_seed.zeroize();
+        sk
+    }
+
+    fn seed_xof(seed: &[u8; 32]) -> impl XofReader {
         use crate::transcript::digest::{ExtendableOutput};
         let mut xof = crate::transcript::Shake128::default();
         xof.update(b"VrfSecretSeed");
         xof.update(seed.as_ref());
         xof.update(& (32u32).to_be_bytes());
         xof.update(b"VrfSecretKey");
-        self.secretkey_from_xof(xof.finalize_xof())
+        xof.finalize_xof()
+    }
+
+    /// Generate a `SecretKey` from a 32 byte seed.
+    pub fn secretkey_from_seed(self, seed: &[u8; 32]) -> SecretKey<K> {
+        self.secretkey_from_xof(Self::seed_xof(seed))
+    }
+
+    /// Generate a `SecretKey` from a 32 byte seed, like `secretkey_from_seed`,
+    /// but split its secret scalar using a user supplied RNG.
+    pub fn secretkey_from_seed_with_rng<R>(self, seed: &[u8; 32], rng: &mut R) -> SecretKey<K>
+    where R: RngCore+CryptoRng
+    {
+        self.secretkey_from_xof_with_rng(Self::seed_xof(seed), rng)
+    }
+
+    /// Generate a `SecretKey` from a user supplied RNG, which should
+    /// be system randomness or similar.
+    ///
+    /// We split the secret scalar using this RNG too, so this works in
+    /// pure `no_std` environments without `getrandom`.
+    pub fn secretkey_from_rng<R: RngCore+CryptoRng>(self, rng: &mut R) -> SecretKey<K> {
+        let mut nonce_seed = [0u8; NONCE_SEED_LENGTH];
+        rng.fill_bytes(&mut nonce_seed);
+        let key = SecretScalar::from_rng(rng);
+        let sk = self.secretkey_from_scalar(key, nonce_seed);
+        nonce_seed.zeroize();
+        sk
+    }
+
+    /// Generate a `SecretKey` from 64 uniformly random bytes.
+    /// 
+    /// We reduce all 64 bytes modulo the scalar field order, which
+    /// leaves negligible bias, and hash them into the nonce seed.
+    pub fn secretkey_from_uniform_bytes(self, bytes: &[u8; 64]) -> SecretKey<K> {
+        use ark_ff::PrimeField;
+        use crate::transcript::digest::{ExtendableOutput};
+        let mut x = <K as AffineRepr>::ScalarField::from_le_bytes_mod_order(bytes);
+        let mut xof = crate::transcript::Shake128::default();
+        xof.update(b"VrfUniformBytes");
+        xof.update(bytes.as_ref());
+        xof.update(b"VrfNonceSeed");
+        let mut nonce_seed = [0u8; NONCE_SEED_LENGTH];
+        xof.finalize_xof().read(&mut nonce_seed);
+        let sk = self.secretkey_from_scalar(SecretScalar::from_scalar(x), nonce_seed);
+        x.zeroize();
+        nonce_seed.zeroize();
+        sk
+    }
+
+    /// Generate a `SecretKey` from a BIP39 mnemonic phrase and
+    /// optional password.
+    ///
+    /// We derive the 32 byte seed exactly like Substrate's `from_phrase`,
+    /// so the same phrase yields the same seed for VRF keys as for
+    /// Substrate's other key types.
+    #[cfg(feature = "mnemonic")]
+    pub fn secretkey_from_phrase(self, phrase: &str, password: Option<&str>)
+     -> Result<SecretKey<K>, crate::error::MnemonicError>
+    {
+        use crate::error::MnemonicError;
+        let mnemonic = bip39::Mnemonic::parse_in(bip39::Language::English, phrase)
+            .map_err(MnemonicError::InvalidPhrase) ?;
+        let (mut entropy, len) = mnemonic.to_entropy_array();
+        let big_seed = substrate_bip39::seed_from_entropy(&entropy[..len], password.unwrap_or(""))
+            .map_err(|_| MnemonicError::InvalidEntropy);
+        entropy.zeroize();
+        let mut big_seed = big_seed ?;
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&big_seed[..32]);
+        big_seed.zeroize();
+        let sk = self.secretkey_from_seed(&seed);
+        seed.zeroize();
+        Ok(sk)
+    }
+
+    /// Generate a `SecretKey` from a password and salt, stretched by
+    /// Argon2id with parameters `params`.
+    ///
+    /// Brain wallets lose funds, so never use this for validator keys
+    /// or anything else of value.  We provide it only for testing and
+    /// tooling, like deriving reproducible keys for test networks.
+    #[cfg(feature = "argon2")]
+    pub fn secretkey_from_password(self, password: &[u8], salt: &[u8], params: argon2::Params)
+     -> Result<SecretKey<K>, argon2::Error>
+    {
+        let argon2 = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+        let mut seed = [0u8; 32];
+        let r = argon2.hash_password_into(password, salt, &mut seed);
+        let sk = r.map(|()| self.secretkey_from_seed(&seed));
+        seed.zeroize();
+        sk
+    }
+
+    /// Assemble a `SecretKey` from an externally supplied secret scalar
+    /// and nonce seed, like when provisioning an HSM.
+    ///
+    /// Nonce seeds must be uniformly random and secret, and never shared
+    /// between distinct secret scalars.
+    pub fn secretkey_from_parts(
+        self,
+        key: <K as AffineRepr>::ScalarField,
+        nonce_seed: &[u8; NONCE_SEED_LENGTH],
+    ) -> SecretKey<K> {
+        self.secretkey_from_scalar(SecretScalar::from_scalar(key), *nonce_seed)
+    }
+
+    /// Deserialize a `SecretKey` from `SecretKey::serialize_secret` output.
+    pub fn deserialize_secretkey<R: Read>(self, mut reader: R) -> Result<SecretKey<K>, SerializationError> {
+        let mut nonce_seed = [0u8; NONCE_SEED_LENGTH];
+        reader.read_exact(&mut nonce_seed) ?;
+        let key = SecretScalar::deserialize_secret(reader) ?;
+        let sk = self.secretkey_from_scalar(key, nonce_seed);
+        nonce_seed.zeroize();
+        Ok(sk)
+    }
+
+    /// Assemble a `SecretKey` from its secret scalar and nonce seed.
+    pub(crate) fn secretkey_from_scalar(
+        self,
+        key: SecretScalar<<K as AffineRepr>::ScalarField>,
+        nonce_seed: [u8; NONCE_SEED_LENGTH],
+    ) -> SecretKey<K> {
+        let public = self.make_public(&key);
+        SecretKey { thin: self, key, nonce_seed, public,
+            nonce_strategy: NonceStrategy::default(),
+            #[cfg(debug_assertions)]
+            test_vector_fake_rng: false,
+            #[cfg(feature = "testing")]
+            mock_rng: None,
+        }
     }
 
     /// Generate an ephemeral `SecretKey` with system randomness.
@@ -170,7 +490,9 @@ impl<K: AffineRepr> ThinVrf<K> {
     pub fn ephemeral_secretkey(self) -> SecretKey<K> {
         let mut seed: [u8; 32] = [0u8; 32];
         rand_core::OsRng.fill_bytes(&mut seed);
-        self.secretkey_from_seed(&seed)
+        let sk = self.secretkey_from_seed(&seed);
+        seed.zeroize();
+        sk
     }
 }
 // 
@@ -186,6 +508,47 @@ impl<K: AffineRepr> SecretKey<K> {
         ThinVrf::<K>::default().secretkey_from_seed(seed)
     }
 
+    /// Generate a `SecretKey` from a user supplied RNG.
+    pub fn from_rng<R: RngCore+CryptoRng>(rng: &mut R) -> Self {
+        ThinVrf::<K>::default().secretkey_from_rng(rng)
+    }
+
+    /// Generate a `SecretKey` from a 32 byte seed, but split its
+    /// secret scalar using a user supplied RNG.
+    pub fn from_seed_with_rng<R: RngCore+CryptoRng>(seed: &[u8; 32], rng: &mut R) -> Self {
+        ThinVrf::<K>::default().secretkey_from_seed_with_rng(seed, rng)
+    }
+
+    /// Generate a `SecretKey` from 64 uniformly random bytes.
+    pub fn from_uniform_bytes(bytes: &[u8; 64]) -> Self {
+        ThinVrf::<K>::default().secretkey_from_uniform_bytes(bytes)
+    }
+
+    /// Generate a `SecretKey` from a BIP39 mnemonic phrase and
+    /// optional password, like Substrate does.
+    #[cfg(feature = "mnemonic")]
+    pub fn from_phrase(phrase: &str, password: Option<&str>) -> Result<Self, crate::error::MnemonicError> {
+        ThinVrf::<K>::default().secretkey_from_phrase(phrase, password)
+    }
+
+    /// Generate a `SecretKey` from a password and salt using Argon2id,
+    /// only for testing and tooling, see `ThinVrf::secretkey_from_password`.
+    #[cfg(feature = "argon2")]
+    pub fn from_password(password: &[u8], salt: &[u8], params: argon2::Params) -> Result<Self, argon2::Error> {
+        ThinVrf::<K>::default().secretkey_from_password(password, salt, params)
+    }
+
+    /// Deserialize a `SecretKey` from `serialize_secret` output.
+    pub fn deserialize_secret<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        ThinVrf::<K>::default().deserialize_secretkey(reader)
+    }
+
+    /// Assemble a `SecretKey` from an externally supplied secret scalar
+    /// and nonce seed, see `ThinVrf::secretkey_from_parts`.
+    pub fn from_parts(key: <K as AffineRepr>::ScalarField, nonce_seed: &[u8; NONCE_SEED_LENGTH]) -> Self {
+        ThinVrf::default().secretkey_from_parts(key, nonce_seed)
+    }
+
     /// Generate an ephemeral `SecretKey` with system randomness.
     #[cfg(feature = "getrandom")]
     pub fn ephemeral() -> Self {
@@ -198,60 +561,177 @@ impl<K: AffineRepr> SecretKey<K> {
     /// Clone the `PublicKey` corresponding to this `SecretKey`.
     pub fn to_public(&self) -> PublicKey<K> { self.public.clone() }
 
+    /// Thin VRF flavor whose keying base we use, which verifiers of our
+    /// signatures must use too.
+    pub fn thin_vrf(&self) -> &ThinVrf<K> { &self.thin }
+
     // #[cfg(debug_assertions)]
     // pub fn set_rng(&mut self, rng: &Box<dyn RngCore+CryptoRng>) {
     //     self.rng = Some(Mutex::new(rng));
     // }
 
+    /// Select how we derive nonces when signing with this key.
+    pub fn set_nonce_strategy(&mut self, nonce_strategy: NonceStrategy) {
+        self.nonce_strategy = nonce_strategy;
+    }
+
     #[cfg(debug_assertions)]
     pub fn set_rng_for_test_vectors(&mut self) {
         self.test_vector_fake_rng = true;
         // transcript::tests::TestVectorFakeRng
     }
 
+    /// Replace system randomness in hedged nonces by `rng`, so signatures
+    /// become reproducible, or restore system randomness with `None`.
+    ///
+    /// Only for integration tests and differential testing.
+    #[cfg(feature = "testing")]
+    pub fn set_mock_rng(&mut self, rng: Option<crate::testing::MockRng>) {
+        self.mock_rng = rng;
+    }
+
+    /// Our `MockRng`, if any, for nonces which always hedge.
+    #[cfg(all(feature = "testing", feature = "getrandom"))]
+    pub(crate) fn mock_rng(&self) -> Option<crate::testing::MockRngReader> {
+        self.mock_rng.as_ref().map(|rng| rng.restart())
+    }
+
     pub fn witness(&self, t: &crate::Transcript, label: impl ark_transcript::AsLabel) -> ark_transcript::Reader {
-        let mut t = t.fork(b"witness");
-        t.label(label);
-        t.append(&self.nonce_seed[..]);
         #[cfg(debug_assertions)]
         if self.test_vector_fake_rng {
+            let mut t = t.fork(b"witness");
+            t.label(label);
+            t.append(&self.nonce_seed[..]);
             return t.witness(&mut ark_transcript::debug::TestVectorFakeRng);
 
         }
+        #[cfg(feature = "testing")]
+        if let Some(rng) = &self.mock_rng {
+            let mut t = t.fork(b"witness");
+            t.label(label);
+            t.append(&self.nonce_seed[..]);
+            return t.witness(&mut rng.restart());
+        }
         // #[cfg(debug_assertions)]
         // if let Some(rng) = self.rng {
         //     if let Ok(rng) = rng.lock() {
         //         return t.witness(rng.deref_mut());
         //     }
         // }
-        t.witness(&mut ark_secret_scalar::getrandom_or_panic())
+        nonce_seed_witness(&self.nonce_seed, t, label, self.nonce_strategy)
     }
 
-/*
-    #[inline]
-    pub fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+    /// Export our nonce seed, so software could derive witnesses by
+    /// `nonce_seed_witness` while an HSM holds our secret scalar.
+    ///
+    /// Under `NonceStrategy::Deterministic`, or `Hedged` without the
+    /// `getrandom` feature, the nonce seed plus any one signature
+    /// reveals our secret scalar:  Anyone recomputes the signature's
+    /// witness `k` from its transcript, and solves `s = k + c x` for
+    /// `x`.  Protect an exported nonce seed exactly like the secret
+    /// scalar itself.
+    pub fn nonce_seed(&self) -> &[u8; NONCE_SEED_LENGTH] { &self.nonce_seed }
+
+    /// Serialize the nonce seed followed by the secret scalar,
+    /// only for encrypted storage.
+    pub fn serialize_secret<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
         writer.write_all(&self.nonce_seed) ?;
-        self.key.0[0].serialize_compressed(writer)
-        self.key.0[1].serialize_compressed(writer)
+        self.key.serialize_secret(writer)
     }
 
-    #[inline]
-    pub fn serialized_size(&self) -> usize {
-        NONCE_SEED_LENGTH + 2 * self.key.compressed_size()
+    /// Serialized length of `serialize_secret`.
+    pub fn secret_serialized_size(&self) -> usize {
+        NONCE_SEED_LENGTH + self.key.secret_serialized_size()
     }
+}
 
-    #[inline]
-    pub fn deserialize<R: Read>(thin: ThinVrf<K>, mut reader: R) -> Result<Self, SerializationError> {
-        let mut nonce_seed = [0u8; 32];
-        reader.read_exact(&mut nonce_seed) ?;
-        let key = SecretScalar([
-            <K as AffineRepr>::ScalarField::deserialize_compressed(&mut reader) ?,
-            <K as AffineRepr>::ScalarField::deserialize_compressed(&mut reader) ?
-        ])
-        let public = thin.make_public(&key);
-        Ok(SecretKey { thin, key, nonce_seed, public, })
- 
-   }
-*/
+
+/// Length of chain codes used in hierarchical key derivation.
+pub const CHAIN_CODE_LENGTH: usize = 32;
+
+/// Chain code for hierarchical key derivation, ala BIP32 or schnorrkel.
+///
+/// We derive one level per chain code, so wallets represent paths like
+/// `//epoch/42` by sequences of chain codes, one per junction.
+pub type ChainCode = [u8; CHAIN_CODE_LENGTH];
+
+/// Soft derivation tweak, which anyone knowing the public key computes.
+fn soft_derivation_tweak<K: AffineRepr>(public: &PublicKey<K>, chain_code: &ChainCode)
+ -> SecretScalar<<K as AffineRepr>::ScalarField>
+{
+    let mut t = crate::Transcript::new_labeled(b"VrfSoftHDKD");
+    t.label(b"chain-code");
+    t.append(&chain_code[..]);
+    t.label(b"public");
+    t.append(public);
+    SecretScalar::from_xof(&mut t.challenge(b"tweak"))
+}
+
+impl<K: AffineRepr> ThinVrf<K> {
+    /// Soft derivation of a child `PublicKey` for this keying base,
+    /// matching `SecretKey::derive_soft`.
+    pub fn derive_soft_publickey(&self, public: &PublicKey<K>, chain_code: &ChainCode) -> PublicKey<K> {
+        let tweak = soft_derivation_tweak(public, chain_code);
+        PublicKey( (public.0 + self.make_public(&tweak).0).into() )
+    }
+}
+
+impl<K: AffineRepr> PublicKey<K> {
+    /// Soft derivation of a child `PublicKey`, using the default keying base.
+    ///
+    /// Watch-only wallets can derive the same public keys as
+    /// `SecretKey::derive_soft`, but then anyone knowing the chain code
+    /// links the parent and child keys.
+    pub fn derive_soft(&self, chain_code: &ChainCode) -> PublicKey<K> {
+        ThinVrf::<K>::default().derive_soft_publickey(self, chain_code)
+    }
+}
+
+impl<K: AffineRepr> SecretKey<K> {
+    /// Hard derivation of a child `SecretKey`, whose `PublicKey`
+    /// cannot be linked to ours, nor derived from our `PublicKey`.
+    pub fn derive_hard(&self, chain_code: &ChainCode) -> SecretKey<K> {
+        let mut t = crate::Transcript::new_labeled(b"VrfHardHDKD");
+        t.label(b"chain-code");
+        t.append(&chain_code[..]);
+        t.label(b"public");
+        t.append(&self.public);
+        // We cannot hash our secret scalar directly since `SecretScalar`
+        // never reveals its value, but its product with a challenge
+        // remains secret and determines the key.
+        let c: <K as AffineRepr>::ScalarField = t.challenge(b"key-challenge").read_reduce();
+        t.label(b"secret");
+        t.append(&self.key.mul_by_challenge(&c));
+        t.append(&self.nonce_seed[..]);
+        let mut seed: [u8; 32] = t.challenge(b"seed").read_byte_array();
+        let mut sk = self.thin.clone().secretkey_from_seed(&seed);
+        sk.nonce_strategy = self.nonce_strategy;
+        seed.zeroize();
+        sk
+    }
+
+    /// Soft derivation of a child `SecretKey`, matching
+    /// `PublicKey::derive_soft` for the default keying base, or
+    /// `ThinVrf::derive_soft_publickey` otherwise.
+    pub fn derive_soft(&self, chain_code: &ChainCode) -> SecretKey<K> {
+        let tweak = soft_derivation_tweak(&self.public, chain_code);
+        let key = &self.key + &tweak;
+        let public = self.thin.make_public(&key);
+
+        let mut t = crate::Transcript::new_labeled(b"VrfSoftHDKD");
+        t.label(b"chain-code");
+        t.append(&chain_code[..]);
+        t.label(b"nonce-seed");
+        t.append(&self.nonce_seed[..]);
+        let nonce_seed = t.challenge(b"nonce-seed").read_byte_array();
+
+        SecretKey { thin: self.thin.clone(), key, nonce_seed, public,
+            nonce_strategy: self.nonce_strategy,
+            #[cfg(debug_assertions)]
+            test_vector_fake_rng: self.test_vector_fake_rng,
+            #[cfg(feature = "testing")]
+            mock_rng: self.mock_rng.clone(),
+        }
+    }
 }
 
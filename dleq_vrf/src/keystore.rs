@@ -0,0 +1,215 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Password encrypted keystores
+//!
+//! We store secret keys as versioned JSON objects, in which scrypt
+//! derives an XChaCha20-Poly1305 key from the password.  We bind the
+//! public key as associated data, so tampering with either field fails
+//! decryption, while wallets could still identify keys without the
+//! password.
+
+use ark_ec::AffineRepr;
+use ark_serialize::{CanonicalSerialize,SerializationError};
+use ark_std::{string::String, vec::Vec};
+
+use chacha20poly1305::{XChaCha20Poly1305, XNonce, Key, aead::{Aead, KeyInit, Payload}};
+use zeroize::Zeroize;
+
+use crate::{ThinVrf, SecretKey, PublicKey};
+
+
+/// Current keystore format version.
+pub const KEYSTORE_VERSION: u32 = 1;
+
+/// Default scrypt cost parameter `log2(N)`.
+pub const SCRYPT_LOG_N: u8 = 15;
+
+/// Largest scrypt cost parameter `log2(N)` we accept, since keystores
+/// choose their own parameters and scrypt needs `128 r N` bytes.
+pub const MAX_SCRYPT_LOG_N: u8 = 20;
+
+/// Largest scrypt block size `r` we accept.
+pub const MAX_SCRYPT_R: u32 = 8;
+
+/// Largest scrypt parallelism `p` we accept.
+pub const MAX_SCRYPT_P: u32 = 4;
+
+const SALT_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+const KEY_LENGTH: usize = 32;
+
+/// Keystore failures
+#[derive(Debug)]
+pub enum KeystoreError {
+    /// Malformed JSON
+    Json(serde_json::Error),
+    /// Malformed hex field
+    Hex,
+    /// Keystore version or algorithms we do not support
+    Unsupported,
+    /// Invalid scrypt parameters
+    Kdf,
+    /// Wrong password or corrupted keystore
+    Decryption,
+    /// Secret key bytes fail to serialize
+    Serialize(SerializationError),
+    /// Secret key bytes fail to deserialize
+    Deserialize(SerializationError),
+    /// Stored public key disagrees with the secret key
+    PublicKeyMismatch,
+}
+
+impl core::fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeystoreError::Json(e) => write!(f, "malformed keystore JSON: {}", e),
+            KeystoreError::Hex => f.write_str("malformed keystore hex field"),
+            KeystoreError::Unsupported => f.write_str("unsupported keystore version or algorithm"),
+            KeystoreError::Kdf => f.write_str("invalid scrypt parameters"),
+            KeystoreError::Decryption => f.write_str("wrong password or corrupted keystore"),
+            KeystoreError::Serialize(e) => write!(f, "secret key fails to serialize: {}", e),
+            KeystoreError::Deserialize(e) => write!(f, "secret key fails to deserialize: {}", e),
+            KeystoreError::PublicKeyMismatch => f.write_str("stored public key disagrees with the secret key"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeystoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KeystoreError::Json(e) => Some(e),
+            KeystoreError::Serialize(e) => Some(e),
+            KeystoreError::Deserialize(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ScryptParams {
+    log_n: u8,
+    r: u32,
+    p: u32,
+    salt: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Keystore {
+    version: u32,
+    public: String,
+    kdf: String,
+    kdfparams: ScryptParams,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn from_hex<const L: usize>(s: &str) -> Result<[u8; L], KeystoreError> {
+    let mut bytes = [0u8; L];
+    hex::decode_to_slice(s, &mut bytes).map_err(|_| KeystoreError::Hex) ?;
+    Ok(bytes)
+}
+
+fn derive_key(password: &[u8], params: &ScryptParams, salt: &[u8]) -> Result<[u8; KEY_LENGTH], KeystoreError> {
+    if params.log_n > MAX_SCRYPT_LOG_N || params.r > MAX_SCRYPT_R || params.p > MAX_SCRYPT_P {
+        return Err(KeystoreError::Kdf);
+    }
+    let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, KEY_LENGTH)
+        .map_err(|_| KeystoreError::Kdf) ?;
+    let mut key = [0u8; KEY_LENGTH];
+    scrypt::scrypt(password, salt, &scrypt_params, &mut key)
+        .map_err(|_| KeystoreError::Kdf) ?;
+    Ok(key)
+}
+
+fn public_bytes<K: AffineRepr>(public: &PublicKey<K>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(public.compressed_size());
+    public.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+impl<K: AffineRepr> SecretKey<K> {
+    /// Encrypt this secret key under `password`, using scrypt with
+    /// cost `2^SCRYPT_LOG_N`, and return the keystore JSON.
+    pub fn to_encrypted_keystore(&self, password: &[u8]) -> Result<String, KeystoreError> {
+        self.to_encrypted_keystore_with_cost(password, SCRYPT_LOG_N)
+    }
+
+    /// Encrypt this secret key under `password`, using scrypt with
+    /// cost `2^log_n`, and return the keystore JSON.
+    ///
+    /// We return `KeystoreError::Kdf` if `log_n` exceeds `MAX_SCRYPT_LOG_N`,
+    /// since decryption would refuse such keystores.
+    pub fn to_encrypted_keystore_with_cost(&self, password: &[u8], log_n: u8) -> Result<String, KeystoreError> {
+        use ark_secret_scalar::RngCore;
+        let mut rng = ark_secret_scalar::getrandom_or_panic();
+        let mut salt = [0u8; SALT_LENGTH];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rng.fill_bytes(&mut nonce);
+
+        let kdfparams = ScryptParams { log_n, r: 8, p: 1, salt: hex::encode(salt) };
+        let mut key = derive_key(password, &kdfparams, &salt) ?;
+        let public = public_bytes(self.as_publickey());
+
+        // We size `secret` exactly, so serialization never reallocates
+        // and leaves no stray plaintext copies.
+        let mut secret = Vec::with_capacity(self.secret_serialized_size());
+        let ciphertext = self.serialize_secret(&mut secret)
+            .map_err(KeystoreError::Serialize)
+            .map(|()| XChaCha20Poly1305::new(Key::from_slice(&key))
+                .encrypt(XNonce::from_slice(&nonce), Payload { msg: &secret, aad: &public })
+                .expect("XChaCha20-Poly1305 encryption only fails beyond 256 GiB")
+            );
+        secret.zeroize();
+        key.zeroize();
+        let ciphertext = ciphertext ?;
+
+        let keystore = Keystore {
+            version: KEYSTORE_VERSION,
+            public: hex::encode(public),
+            kdf: "scrypt".into(),
+            kdfparams,
+            cipher: "xchacha20-poly1305".into(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        };
+        serde_json::to_string(&keystore).map_err(KeystoreError::Json)
+    }
+
+    /// Decrypt a keystore JSON created by `to_encrypted_keystore`,
+    /// using the default keying base.
+    pub fn from_encrypted_keystore(json: &str, password: &[u8]) -> Result<Self, KeystoreError> {
+        ThinVrf::<K>::default().secretkey_from_encrypted_keystore(json, password)
+    }
+}
+
+impl<K: AffineRepr> ThinVrf<K> {
+    /// Decrypt a keystore JSON created by `SecretKey::to_encrypted_keystore`.
+    pub fn secretkey_from_encrypted_keystore(self, json: &str, password: &[u8]) -> Result<SecretKey<K>, KeystoreError> {
+        let keystore: Keystore = serde_json::from_str(json).map_err(KeystoreError::Json) ?;
+        if keystore.version != KEYSTORE_VERSION || keystore.kdf != "scrypt" || keystore.cipher != "xchacha20-poly1305" {
+            return Err(KeystoreError::Unsupported);
+        }
+        let salt: [u8; SALT_LENGTH] = from_hex(&keystore.kdfparams.salt) ?;
+        let nonce: [u8; NONCE_LENGTH] = from_hex(&keystore.nonce) ?;
+        let public = hex::decode(&keystore.public).map_err(|_| KeystoreError::Hex) ?;
+        let ciphertext = hex::decode(&keystore.ciphertext).map_err(|_| KeystoreError::Hex) ?;
+
+        let mut key = derive_key(password, &keystore.kdfparams, &salt) ?;
+        let secret = XChaCha20Poly1305::new(Key::from_slice(&key))
+            .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &public })
+            .map_err(|_| KeystoreError::Decryption);
+        key.zeroize();
+        let mut secret = secret ?;
+
+        let sk = self.deserialize_secretkey(secret.as_slice()).map_err(KeystoreError::Deserialize);
+        secret.zeroize();
+        let sk = sk ?;
+        if public_bytes(sk.as_publickey()) != public {
+            return Err(KeystoreError::PublicKeyMismatch);
+        }
+        Ok(sk)
+    }
+}
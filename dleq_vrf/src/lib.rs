@@ -5,12 +5,15 @@
 #![doc = include_str!("../README.md")]
 
 
+extern crate alloc;
+
 // #![feature(associated_type_defaults)]
 // #![feature(array_methods)]
 
 use ark_ec::{AffineRepr, CurveGroup, models::CurveConfig};
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize,SerializationError};
 
-pub use ark_transcript::{self as transcript, Transcript, IntoTranscript};
+pub use ark_transcript::{self as transcript, Transcript, IntoTranscript, AssociatedData, SigningContext};
 
 pub mod error;
 pub use error::{SignatureResult, SignatureError};
@@ -20,29 +23,81 @@ mod flavor;
 pub use flavor::{Flavor, Batchable, NonBatchable};
 
 pub mod keys; // PublicKeyUnblinding
-pub use keys::{PublicKey, SecretKey};
+pub use keys::{PublicKey, SecretKey, Keypair, NonceStrategy, ChainCode};
 
 pub mod vrf;
-pub use vrf::{IntoVrfInput, VrfInput, VrfPreOut, VrfInOut};
+pub use vrf::{IntoVrfInput, VrfInput, VrfPreOut, VrfPreOuts, VrfInOut};
+
+/// `#[derive(VrfInput)]` hashes protocol structs into VRF inputs,
+/// see `dleq_vrf_derive`.
+#[cfg(feature = "derive")]
+pub use dleq_vrf_derive::VrfInput;
 
 mod thin;
-pub use thin::{ThinVrf,ThinVrfProof};
+pub use thin::{ThinVrf,ThinVrfProof,ProofOfPossession,SchnorrSignature};
 
 mod pedersen;
-pub use pedersen::{PedersenVrf};
+pub use pedersen::{PedersenVrf, KeyCommitment, SecretBlinding, KeyCommitmentOpening};
+
+pub mod ietf;
+
+pub mod dleq;
+pub use dleq::{DleqProof, dleq_prove, dleq_verify};
+
+pub mod threshold;
+
+pub mod cosign;
+
+pub mod musig;
+
+pub mod adaptor;
+
+pub mod dkg;
+
+pub mod aggregate;
+
+mod fixed_base;
 
-// #[cfg(feature = "getrandom")]
-// mod musig
+pub mod msm;
+pub use msm::{MsmBackend, ArkworksMsm};
+
+pub mod elligator2;
+pub use elligator2::{Elligator2Map, Elligator2Hasher};
 
 #[cfg(feature = "scale")]
 pub mod scale;
 
+#[cfg(feature = "serde")]
+mod serde;
+
+#[cfg(feature = "alloc")]
+mod display;
+
+#[cfg(feature = "keystore")]
+pub mod keystore;
+
+#[cfg(feature = "pkcs8")]
+pub mod pkcs8;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+/// Argon2 for `SecretKey::from_password` parameters.
+#[cfg(feature = "argon2")]
+pub use argon2;
+
+#[cfg(feature = "arbitrary")]
+pub mod fuzzing;
+
 pub mod traits;
 pub use traits::{
-    EcVrfSecret,EcVrfProof,EcVrfVerifier,EcVrfSigner,
-    VrfSignature,VrfSignatureVec,
+    EcVrfSecret,EcVrfProof,EcVrfVerifier,EcVrfSigner,VrfSigner,
+    VrfSignature,VrfSignatureVec,VrfSignRequest,
 };
 
+#[cfg(feature = "async")]
+pub use traits::AsyncVrfSigner;
+
 #[cfg(test)]
 mod tests;
 
@@ -85,6 +140,105 @@ pub fn zero_mod_small_cofactor<C: CurveGroup>(z: C) -> bool {
     mul_by_small_cofactor(z).is_zero()
 }
 
+/// Canonical compressed encoding, as for display.
+pub(crate) fn compressed_bytes<T: CanonicalSerialize>(itm: &T) -> ark_std::vec::Vec<u8> {
+    let mut bytes = ark_std::vec::Vec::with_capacity(itm.compressed_size());
+    itm.serialize_compressed(&mut bytes).expect("Vec serialization is infallible");
+    bytes
+}
+
+/// Longest compressed point encoding we compare in place, which
+/// suffices for BLS12-381 G2.
+pub(crate) const MAX_POINT_BYTES: usize = 128;
+
+/// Canonical compressed encoding of a curve point, zero padded into a
+/// fixed size buffer, by which we compare, hash, and sort points
+/// without allocating.
+pub(crate) fn point_bytes<C: AffineRepr>(p: &C) -> [u8; MAX_POINT_BYTES] {
+    let mut bytes = [0u8; MAX_POINT_BYTES];
+    p.serialize_compressed(bytes.as_mut_slice())
+    .expect("Compressed points fit MAX_POINT_BYTES");
+    bytes
+}
+
+/// Serialize compressed into a fixed length byte array.
+/// 
+/// Panics unless `L` equals the compressed size of `itm`.
+pub(crate) fn to_bytes_array<T: CanonicalSerialize, const L: usize>(itm: &T) -> [u8; L] {
+    assert_eq!(itm.compressed_size(), L, "Wrong serialized length");
+    let mut bytes = [0u8; L];
+    itm.serialize_compressed(bytes.as_mut_slice())
+    .expect("Serialized length already checked");
+    bytes
+}
+
+/// Writer which compares everything written against `expected`,
+/// so we check encodings are canonical without allocating.
+struct CompareWriter<'a> {
+    expected: &'a [u8],
+    equal: bool,
+}
+
+impl ark_std::io::Write for CompareWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> ark_std::io::Result<usize> {
+        match self.expected.strip_prefix(buf) {
+            Some(rest) => self.expected = rest,
+            None => { self.equal = false; self.expected = &[]; },
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> ark_std::io::Result<()> { Ok(()) }
+}
+
+/// Deserialize compressed from exactly `bytes`, rejecting any unused
+/// trailing bytes, and any encoding which differs from how we serialize
+/// the result, like scalars not reduced modulo the group order, or
+/// points with stray flag bits.
+/// 
+/// Signatures deserialized this way have unique encodings, so their
+/// bytes could serve as identifiers.  We read directly from `bytes`,
+/// and compare our re-encoding in place, so nothing gets allocated
+/// beyond `T` itself.
+pub fn deserialize_canonical<T>(bytes: &[u8]) -> Result<T, SerializationError>
+where T: CanonicalSerialize + CanonicalDeserialize,
+{
+    let mut reader = bytes;
+    let itm = T::deserialize_compressed(&mut reader) ?;
+    if !reader.is_empty() {
+        return Err(SerializationError::InvalidData);
+    }
+    let mut writer = CompareWriter { expected: bytes, equal: true };
+    itm.serialize_compressed(&mut writer) ?;
+    if !writer.equal || !writer.expected.is_empty() {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(itm)
+}
+
+/// Version of our hashing conventions, which we append after each
+/// protocol label like `b"ThinVRF"` or `b"PedersenVRF"`.
+/// 
+/// Bump this whenever signatures' hashing changes incompatibly, so
+/// old signatures fail loudly against new verifiers, instead of any
+/// incompatibility hiding in changed outputs.
+pub const PROTOCOL_VERSION: u64 = 1;
+
+/// Write a protocol label followed by our `PROTOCOL_VERSION`.
+pub(crate) fn protocol_label(t: &mut Transcript, label: &'static [u8]) {
+    t.label(label);
+    t.append_u64(PROTOCOL_VERSION);
+}
+
+/// Sample a 128 bit weight for the `i`th element of a batch, from a
+/// transcript which already hashes the entire batch.
+pub(crate) fn batch_weight<F: ark_ff::PrimeField>(t: &Transcript, i: u64) -> F {
+    let mut t0 = t.fork(b"weight");
+    t0.append_u64(i);
+    let z: [u64; 2] = t0.challenge(b"128 bits").read_uniform();
+    F::from( (z[0] as u128) | ((z[1] as u128) << 64) )
+}
+
 /*
 pub fn zero_mod_small_cofactor_affine<C: AffineRepr>(z: C) -> bool {
     use ark_ff::Zero;
@@ -0,0 +1,39 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Multi-scalar multiplication backends
+//!
+//! Verification equations reduce to multi-scalar multiplications, which
+//! integrators may offload onto GPU or FPGA accelerators, by supplying
+//! their own `MsmBackend` via `ThinVrf::with_msm`, or via
+//! `PedersenVrf::with_msm` for both the keying and pre-output curves.
+//!
+//! We use the backend for verification equations, for merging VRF
+//! inputs and pre-outputs when signing or verifying, and for multi-
+//! and threshold signature aggregation.  We hold backends in an `Arc`,
+//! so flavors and secret keys stay cheap to clone.
+//!
+//! Ring proving and verification run their multi-scalar multiplications
+//! inside ring-proof, which offers no such hook, so only their Pedersen
+//! VRF parts could use our backends.
+
+use ark_ec::{AffineRepr, VariableBaseMSM};
+use ark_std::fmt::Debug;
+
+
+/// Multi-scalar multiplication over the curve `C`.
+pub trait MsmBackend<C: AffineRepr>: Debug + Send + Sync {
+    /// Compute `sum_i scalars[i] bases[i]`, given equal numbers of
+    /// bases and scalars.
+    fn msm(&self, bases: &[C], scalars: &[C::ScalarField]) -> C::Group;
+}
+
+/// Arkworks' own multi-scalar multiplication, which our flavors use by default.
+#[derive(Debug,Clone,Copy,Default)]
+pub struct ArkworksMsm;
+
+impl<C: AffineRepr> MsmBackend<C> for ArkworksMsm {
+    fn msm(&self, bases: &[C], scalars: &[C::ScalarField]) -> C::Group {
+        <C::Group as VariableBaseMSM>::msm(bases, scalars)
+            .expect("Equal numbers of bases and scalars")
+    }
+}
@@ -0,0 +1,319 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### MuSig2 style thin VRF multi-signatures
+//!
+//! A committee aggregates its members' public keys into one joint
+//! `PublicKey`, under which all members together produce ordinary
+//! thin VRF signatures, so the committee acts as one VRF identity.
+//!
+//! We aggregate keys like MuSig, so `X = sum a_i X_i` with coefficients
+//! `a_i` hashed from all members' keys, which prevents rogue key attacks.
+//! Signing takes two rounds, with an aggregator who learns nothing secret:
+//!
+//! 1. Members send a `MuSigCommitment` holding their pre-output shares
+//!    and commitments to two nonces, ala MuSig2, upon every VRF input
+//!    and the keying base.
+//! 2. Members send `MuSigPartialSignature`s, which
+//!    `AggregateKey::aggregate_musig` verifies individually and then
+//!    sums into the signature.
+//!
+//! The thin VRF merges its VRF inputs using delinearization scalars
+//! hashed from the pre-outputs, which members only learn in round two,
+//! so we commit to nonces upon each base separately, and merge these
+//! commitments in round two, exactly like the VRF inputs.  MuSig2's
+//! binding factor then binds every nonce to the whole signing session.
+//! Members must never reuse `MuSigNonces`, so `sign_musig` consumes them.
+
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::Field;
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize};
+use ark_std::{borrow::BorrowMut, vec::Vec, Zero};
+
+use ark_secret_scalar::{RngCore, CryptoRng};
+use zeroize::Zeroize;
+
+use crate::{
+    ThinVrf, ThinVrfProof, Transcript, IntoTranscript, PublicKey, SecretKey,
+    VrfSignature,
+    flavor::Batchable,
+    error::{SignatureResult, SignatureError},
+    vrf::{self, VrfInput, VrfPreOut, VrfInOut},
+};
+
+
+type Scalar<K> = <K as AffineRepr>::ScalarField;
+
+/// Committee's aggregated public key, along with its members' keys.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct AggregateKey<K: AffineRepr> {
+    /// Thin VRF flavor of the aggregated key
+    pub thin: ThinVrf<K>,
+    /// Aggregated public key, for which we produce signatures.
+    pub public: PublicKey<K>,
+    /// Members' public keys, in order.
+    pub members: Vec<PublicKey<K>>,
+    /// Members' key aggregation coefficients, in order.
+    coefficients: Vec<Scalar<K>>,
+}
+
+/// Member's round one message.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct MuSigCommitment<K: AffineRepr> {
+    /// Member's position in `AggregateKey::members`
+    pub index: u16,
+    /// Member's own pre-outputs for each VRF input, in order.
+    pub preouts: Vec<VrfPreOut<K>>,
+    /// Hiding nonce times each VRF input, and then the keying base.
+    pub hiding: Vec<K>,
+    /// Binding nonce times each VRF input, and then the keying base.
+    pub binding: Vec<K>,
+}
+
+/// Member's secret nonces, which must be used only once.
+pub struct MuSigNonces<K: AffineRepr> {
+    hiding: Scalar<K>,
+    binding: Scalar<K>,
+}
+
+impl<K: AffineRepr> Zeroize for MuSigNonces<K> {
+    fn zeroize(&mut self) {
+        self.hiding.zeroize();
+        self.binding.zeroize();
+    }
+}
+impl<K: AffineRepr> Drop for MuSigNonces<K> {
+    fn drop(&mut self) { self.zeroize() }
+}
+
+/// Member's share of the thin VRF signature's response.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct MuSigPartialSignature<K: AffineRepr> {
+    pub index: u16,
+    pub s: Scalar<K>,
+}
+
+
+/// Merge pairs listed like the VRF inputs, followed by the pair for
+/// the keying base, using exactly the delinearization by which
+/// `thin_vrf_merge` merged the `VrfInOut`s into `t`.
+fn merge_pairs<K: AffineRepr>(thin: &ThinVrf<K>, t: &Transcript, pairs: &[VrfInOut<K>]) -> VrfInOut<K> {
+    if pairs.len() == 1 { return pairs[0]; }
+    vrf::vrfs_delinearize(t, pairs.iter(), thin.msm())
+}
+
+/// Pairs of points `a_j` and `b_j`, for merging by `merge_pairs`.
+fn pairs<K: AffineRepr>(a: impl Iterator<Item=K>, b: impl Iterator<Item=K>) -> Vec<VrfInOut<K>> {
+    a.zip(b).map(|(a,b)| VrfInOut { input: VrfInput(a), preoutput: VrfPreOut(b) }).collect()
+}
+
+/// Signing session state shared by members and the aggregator.
+struct Session<K: AffineRepr> {
+    /// Transcript after merging the `VrfInOut`s, for delinearization.
+    merged: Transcript,
+    ios: Vec<VrfInOut<K>>,
+    /// Merged VRF input
+    m: K,
+    /// MuSig2 binding factor
+    b: Scalar<K>,
+    /// Nonce commitment and thin VRF challenge
+    r: K,
+    c: Scalar<K>,
+}
+
+impl<K: AffineRepr> Session<K> {
+    /// Aggregate pre-outputs and nonce commitments, which requires one
+    /// commitment from every member, sorted by index.
+    fn new(
+        key: &AggregateKey<K>,
+        t: impl IntoTranscript,
+        inputs: &[VrfInput<K>],
+        commitments: &[MuSigCommitment<K>],
+    ) -> SignatureResult<Self> {
+        if commitments.len() != key.members.len()
+        || commitments.iter().enumerate().any(|(i,c)| usize::from(c.index) != i) {
+            return Err(SignatureError::Invalid);
+        }
+        let n = inputs.len();
+        if commitments.iter().any(|c| c.preouts.len() != n || c.hiding.len() != n+1 || c.binding.len() != n+1) {
+            return Err(SignatureError::WrongInputCount);
+        }
+
+        let ios: Vec<VrfInOut<K>> = inputs.iter().enumerate().map(|(j,input)| {
+            let bases: Vec<K> = commitments.iter().map(|c| c.preouts[j].0).collect();
+            let preout = key.thin.msm().msm(&bases, &key.coefficients);
+            VrfInOut { input: *input, preoutput: VrfPreOut(preout.into_affine()) }
+        }).collect();
+
+        let mut t = t.into_transcript();
+        let t = t.borrow_mut();
+        crate::protocol_label(t, b"ThinVRF");
+        let io = key.thin.thin_vrf_merge(t, &key.public, &ios);
+        let merged = t.clone();
+
+        let sum = |f: fn(&MuSigCommitment<K>) -> &Vec<K>, j: usize| commitments.iter()
+            .fold(<K as AffineRepr>::Group::zero(), |acc, c| acc + f(c)[j]);
+        let hiding = (0..=n).map(|j| sum(|c| &c.hiding, j));
+        let binding = (0..=n).map(|j| sum(|c| &c.binding, j));
+        let hiding = <K as AffineRepr>::Group::normalize_batch(&hiding.collect::<Vec<_>>());
+        let binding = <K as AffineRepr>::Group::normalize_batch(&binding.collect::<Vec<_>>());
+        let nonces = merge_pairs(&key.thin, &merged, &pairs(hiding.into_iter(), binding.into_iter()));
+
+        let mut tb = merged.fork(b"MuSigBinding");
+        tb.append(&nonces);
+        let b: Scalar<K> = tb.challenge(b"b").read_reduce();
+        let r = (nonces.input.0.into_group() + nonces.preoutput.0 * b).into_affine();
+        t.label(b"Thin R");
+        t.append(&r);
+        let c: Scalar<K> = t.challenge(b"ThinVrfChallenge").read_reduce();
+        Ok(Session { merged, ios, m: io.input.0, b, r, c })
+    }
+}
+
+
+impl<K: AffineRepr> ThinVrf<K> {
+    /// Aggregate committee members' public keys, in order, into one
+    /// `AggregateKey`.
+    ///
+    /// We reject duplicate members, and identity keys.
+    pub fn aggregate_keys(self, members: &[PublicKey<K>]) -> SignatureResult<AggregateKey<K>> {
+        for (i, member) in members.iter().enumerate() {
+            member.validate() ?;
+            if members[..i].contains(member) { return Err(SignatureError::InvalidPublicKey); }
+        }
+        if members.is_empty() || members.len() > usize::from(u16::MAX) {
+            return Err(SignatureError::InvalidPublicKey);
+        }
+        let mut t = Transcript::new_labeled(b"MuSigKeyAggregation");
+        t.append(&self.keying_base);
+        t.append_slice::<PublicKey<K>,_>(members);
+        let coefficients: Vec<Scalar<K>> = members.iter().map(|member| {
+            let mut t = t.fork(b"KeyCoefficient");
+            t.append(member);
+            t.challenge(b"a").read_reduce()
+        }).collect();
+        let bases: Vec<K> = members.iter().map(|member| member.0).collect();
+        let public = PublicKey(self.msm().msm(&bases, &coefficients).into_affine());
+        Ok(AggregateKey { thin: self, public, members: members.to_vec(), coefficients })
+    }
+}
+
+impl<K: AffineRepr> SecretKey<K> {
+    fn musig_index(&self, key: &AggregateKey<K>) -> SignatureResult<usize> {
+        key.members.iter().position(|member| member == self.as_publickey())
+            .ok_or(SignatureError::InvalidPublicKey)
+    }
+
+    /// Round one: Compute our pre-outputs for these VRF inputs, and
+    /// commit to fresh nonces upon every base.
+    #[cfg(feature = "getrandom")]
+    pub fn commit_musig(&self, key: &AggregateKey<K>, inputs: &[VrfInput<K>])
+     -> SignatureResult<(MuSigNonces<K>, MuSigCommitment<K>)>
+    {
+        #[cfg(feature = "testing")]
+        if let Some(mut rng) = self.mock_rng() {
+            return self.commit_musig_with_rng(key, inputs, &mut rng);
+        }
+        self.commit_musig_with_rng(key, inputs, &mut ark_secret_scalar::getrandom_or_panic())
+    }
+
+    /// Round one: Like `commit_musig`, but hedge our nonces using a
+    /// user supplied RNG.
+    pub fn commit_musig_with_rng<R: RngCore+CryptoRng>(&self, key: &AggregateKey<K>, inputs: &[VrfInput<K>], rng: &mut R)
+     -> SignatureResult<(MuSigNonces<K>, MuSigCommitment<K>)>
+    {
+        let index = self.musig_index(key) ?;
+        let mut t = Transcript::new_labeled(b"MuSigNonces");
+        t.append(&key.public);
+        t.append_u64(index as u64);
+        t.append_slice::<VrfInput<K>,_>(inputs);
+        t.append(&self.nonce_seed[..]);
+        // Always hedge here, since deterministic nonces become insecure
+        // whenever other members' commitments change.
+        let mut reader = t.fork(b"witness").witness(rng);
+        let nonces = MuSigNonces { hiding: reader.read_reduce(), binding: reader.read_reduce() };
+
+        let bases = || inputs.iter().map(|input| input.0).chain([ key.thin.keying_base ]);
+        let commit = |k: &Scalar<K>| <K as AffineRepr>::Group::normalize_batch(
+            &bases().map(|base| base * *k).collect::<Vec<_>>()
+        );
+        let commitment = MuSigCommitment {
+            index: index as u16,
+            preouts: inputs.iter().map(|input| self.vrf_preout(input)).collect(),
+            hiding: commit(&nonces.hiding),
+            binding: commit(&nonces.binding),
+        };
+        Ok((nonces, commitment))
+    }
+
+    /// Round two: Respond using our nonces from `commit_musig`, given
+    /// every member's `MuSigCommitment`, sorted by index.
+    pub fn sign_musig(
+        &self,
+        nonces: MuSigNonces<K>,
+        key: &AggregateKey<K>,
+        t: impl IntoTranscript,
+        inputs: &[VrfInput<K>],
+        commitments: &[MuSigCommitment<K>],
+    ) -> SignatureResult<MuSigPartialSignature<K>>
+    {
+        let index = self.musig_index(key) ?;
+        let ours = commitments.get(index).ok_or(SignatureError::Invalid) ?;
+        let keying_base = key.thin.keying_base;
+        if ours.hiding.last() != Some(&(keying_base * nonces.hiding).into_affine())
+        || ours.binding.last() != Some(&(keying_base * nonces.binding).into_affine()) {
+            return Err(SignatureError::Invalid);
+        }
+        let session = Session::new(key, t, inputs, commitments) ?;
+        let a = key.coefficients[index];
+        let s = nonces.hiding + nonces.binding * session.b
+            + self.key.mul_by_challenge(&(session.c * a));
+        Ok(MuSigPartialSignature { index: index as u16, s })
+    }
+}
+
+impl<K: AffineRepr> AggregateKey<K> {
+    /// Verify every `MuSigPartialSignature` and combine them into a thin
+    /// VRF signature for the aggregated public key.
+    ///
+    /// We require commitments and partial signatures from every member,
+    /// sorted by index.
+    pub fn aggregate_musig<const N: usize>(
+        &self,
+        t: impl IntoTranscript,
+        inputs: &[VrfInput<K>; N],
+        commitments: &[MuSigCommitment<K>],
+        partials: &[MuSigPartialSignature<K>],
+    ) -> SignatureResult<VrfSignature<ThinVrfProof<K>,N>>
+    {
+        let session = Session::new(self, t, inputs, commitments) ?;
+        if partials.len() != commitments.len() {
+            return Err(SignatureError::Invalid);
+        }
+
+        let mut s = Scalar::<K>::zero();
+        for (((commitment, partial), member), a) in commitments.iter().zip(partials).zip(&self.members).zip(&self.coefficients) {
+            if partial.index != commitment.index {
+                return Err(SignatureError::Invalid);
+            }
+            let nonces = merge_pairs(&self.thin, &session.merged,
+                &pairs(commitment.hiding.iter().cloned(), commitment.binding.iter().cloned()));
+            // Member's own merged pre-output, aka x_i times the merged input.
+            let preout = merge_pairs(&self.thin, &session.merged, &pairs(
+                commitment.preouts.iter().map(|p| p.0).chain([ member.0 ]),
+                commitment.preouts.iter().map(|p| p.0).chain([ member.0 ]),
+            )).input;
+            // Check s_i M = D_i + b E_i + c a_i Y_i
+            let z = self.thin.msm().msm(
+                &[nonces.input.0, nonces.preoutput.0, preout.0, session.m],
+                &[Scalar::<K>::ONE, session.b, session.c * a, -partial.s],
+            );
+            if ! self.thin.zero_mod_cofactor(z) {
+                return Err(SignatureError::Invalid);
+            }
+            s += partial.s;
+        }
+
+        let preouts = core::array::from_fn(|i| session.ios[i].preoutput);
+        Ok(VrfSignature { preouts, proof: Batchable { compk: (), r: session.r, s } })
+    }
+}
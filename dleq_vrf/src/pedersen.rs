@@ -7,10 +7,11 @@
 //! 
 //! 
 
-use ark_ff::PrimeField;
+use ark_ff::{Field, PrimeField, Zero};
 use ark_ec::{AffineRepr, CurveGroup};
 use ark_serialize::{CanonicalSerialize,CanonicalDeserialize};
-use ark_std::{borrow::BorrowMut, vec::Vec};
+use ark_std::{borrow::{Borrow,BorrowMut}, vec::Vec};
+use alloc::sync::Arc;
 
 use zeroize::Zeroize;
 
@@ -18,22 +19,41 @@ use crate::{
     Transcript, IntoTranscript, ThinVrf,
     flavor::{Flavor, InnerFlavor, Witness, Batchable, NonBatchable},
     keys::{PublicKey, SecretKey},
+    fixed_base::FixedBaseTable,
     error::{SignatureResult, SignatureError},
     vrf::{self, VrfInput, VrfInOut},
+    msm::{MsmBackend, ArkworksMsm},
 };
 
 
 /// Pedersen VRF flavor
-#[derive(Debug,Clone,Eq,PartialEq)]
+#[derive(Debug,Clone)]
 pub struct PedersenVrf<K, H=K, const B: usize=1> 
 where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
 {
     // keying_base: K,
     thin: ThinVrf<K>,
     blinding_bases: [K; B],
-    _pd: core::marker::PhantomData<H>,
+    /// Optional precomputed tables for the keying and blinding bases,
+    /// which only speed up signing.
+    tables: Option<(FixedBaseTable<K>, [FixedBaseTable<K>; B])>,
+    /// Multi-scalar multiplication backend for the pre-output curve,
+    /// see `PedersenVrf::with_msm`.
+    preout_msm: Arc<dyn MsmBackend<H>>,
 }
 
+impl<K,H,const B: usize> PartialEq for PedersenVrf<K,H,B>
+where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        // Ignore tables, which the bases determine, and backends.
+        self.thin == other.thin && self.blinding_bases == other.blinding_bases
+    }
+}
+
+impl<K,H,const B: usize> Eq for PedersenVrf<K,H,B>
+where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField> {}
+
 impl<K,H,const B: usize> core::ops::Deref for PedersenVrf<K,H,B> 
 where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
 {
@@ -74,33 +94,43 @@ impl<C: AffineRepr,const B: usize> Zeroize for SecretBlinding<C,B> {
 impl<C: AffineRepr,const B: usize> Drop for SecretBlinding<C,B> {
     fn drop(&mut self) { self.zeroize() }
 }
+impl<C: AffineRepr,const B: usize> zeroize::ZeroizeOnDrop for SecretBlinding<C,B> {}
 
-/*
-impl<C: AffineRepr,const B: usize> Drop for SecretBlinding<C,B> {
+impl<C: AffineRepr,const B: usize> SecretBlinding<C,B> {
+    /// Report if any blinding factor is nonzero.
     pub fn is_blinded(&self) -> bool {
-        use ark_ff::Zero;
-        TODO: loop
-        self.0.is_zero() // != <<C as AffineRepr>::ScalarField as Zero>::zero()
+        self.0.iter().any(|b| ! b.is_zero())
     }
-}
 
-impl<K,H,const B: usize> PedersenVrf<K,H,B>
-where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
-{
-    pub fn verify(&self, blinded: PublicKey<K>, unblinded: PublicKey<K>) -> bool {
-        TODO: loop
-        let mut b = self.blinding_base.mul(self.0);  // FIX !!
-        b.add_assign_mixed(& unblinded.0);
-        crate::zero_mod_small_cofactor(b - blinded.into_group())
+    /// Open a key commitment, like `Batchable::as_key_commitment`
+    /// from our Pedersen or ring VRF signature, into the public key
+    /// we committed to using this blinding.
+    pub fn open<H>(&self, flavor: &PedersenVrf<C,H,B>, compk: &KeyCommitment<C>) -> PublicKey<C>
+    where H: AffineRepr<ScalarField = C::ScalarField>
+    {
+        let mut p = compk.0.into_group();
+        for i in 0..B {
+            p -= flavor.blinding_bases[i] * self.0[i];
+        }
+        PublicKey(p.into_affine())
+    }
+
+    /// Verify that a key commitment opens to `public` using this blinding,
+    /// so signers could later claim their own Pedersen or ring VRF
+    /// signatures by revealing their blinding.
+    pub fn verify<H>(&self, flavor: &PedersenVrf<C,H,B>, compk: &KeyCommitment<C>, public: &PublicKey<C>) -> bool
+    where H: AffineRepr<ScalarField = C::ScalarField>
+    {
+        let b = flavor.compute_blinded_publickey(public, self);
+        flavor.zero_mod_cofactor(b.0.into_group() - compk.0.into_group())
     }
 }
-*/
 
 impl<K: AffineRepr> ThinVrf<K> {
     pub fn pedersen_vrf<H,const B: usize>(self, blinding_bases: [K; B]) -> PedersenVrf<K,H,B>
     where H: AffineRepr<ScalarField = K::ScalarField>
     {
-        PedersenVrf { thin: self, blinding_bases, _pd: core::marker::PhantomData, }
+        PedersenVrf { thin: self, blinding_bases, tables: None, preout_msm: Arc::new(ArkworksMsm), }
     }
 }
 
@@ -108,6 +138,36 @@ impl<K: AffineRepr> ThinVrf<K> {
 impl<K,H,const B: usize> PedersenVrf<K,H,B>
 where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
 {
+    /// Precompute fixed base tables for the keying and blinding bases,
+    /// which speeds up the key commitment half of Pedersen VRF witnesses.
+    /// 
+    /// Our tables never cover the pre-output half, which multiplies the
+    /// VRF input, so signing speeds up by far less than the tables' 3-4x.
+    /// Thin VRF signing ignores these tables too.
+    pub fn with_fixed_base_tables(mut self) -> Self {
+        let keying_table = FixedBaseTable::new(&self.keying_base);
+        let blinding_tables = core::array::from_fn(|i| FixedBaseTable::new(&self.blinding_bases[i]));
+        self.tables = Some((keying_table, blinding_tables));
+        self
+    }
+
+    /// Run our multi-scalar multiplications on both the keying and
+    /// pre-output curves upon `backend`, like some GPU or FPGA
+    /// accelerator, instead of arkworks.
+    pub fn with_msm<M>(mut self, backend: Arc<M>) -> Self
+    where M: MsmBackend<K> + MsmBackend<H> + 'static
+    {
+        self.thin = self.thin.with_msm(backend.clone());
+        self.preout_msm = backend;
+        self
+    }
+
+    /// Multi-scalar multiplication backend for the pre-output curve,
+    /// as set by `PedersenVrf::with_msm`.
+    pub fn preout_msm(&self) -> &dyn MsmBackend<H> {
+        &*self.preout_msm
+    }
+
     pub fn compute_blinded_publickey(
         &self,
         public: &PublicKey<K>, 
@@ -121,6 +181,8 @@ where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
     }
 }
 
+/// Pedersen commitment to a public key, which Pedersen and ring VRF
+/// signatures contain in place of the public key.
 #[derive(Debug,Clone,PartialEq,Eq,Hash,CanonicalSerialize,CanonicalDeserialize)]
 pub struct KeyCommitment<C: AffineRepr>(pub C);
 
@@ -169,9 +231,7 @@ impl<SF: PrimeField,const B: usize> Default for Scalars<SF,B> {
 impl<SF: PrimeField,const B: usize> Zeroize for Scalars<SF,B> {
     fn zeroize(&mut self) {
         self.keying.zeroize();
-        for mut b in self.blindings {
-            b.zeroize();
-        }
+        self.blindings.zeroize();
     }
 }
  
@@ -219,9 +279,17 @@ where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
          = ark_std::array::from_fn(|_| reader.read_reduce());
         let k = Scalars { keying, blindings, };
 
-        let mut keyish: <K as AffineRepr>::Group = flavor.keying_base * k.keying;
-        for i in 0..B {
-            keyish += flavor.blinding_bases[i] * k.blindings[i];
+        let mut keyish: <K as AffineRepr>::Group;
+        if let Some((keying_table,blinding_tables)) = &flavor.tables {
+            keyish = keying_table.mul(&k.keying);
+            for (table,b) in blinding_tables.iter().zip(&k.blindings) {
+                keyish += table.mul(b);
+            }
+        } else {
+            keyish = flavor.keying_base * k.keying;
+            for i in 0..B {
+                keyish += flavor.blinding_bases[i] * k.blindings[i];
+            }
         }
         let preoutish: <H as AffineRepr>::Group = input.0 * k.keying;
         let r = Affines {
@@ -245,8 +313,8 @@ where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
         let flavor = self;
         let mut t = t.into_transcript();
         let t = t.borrow_mut();
-        t.label(b"PedersenVRF");
-        let io = vrf::vrfs_merge(t, ios);
+        crate::protocol_label(t, b"PedersenVRF");
+        let io = vrf::vrfs_merge_msm(t, ios, self.preout_msm());
 
         // Allow derandomization by constructing secret_blinding and
         // witness as late as possible.
@@ -285,8 +353,8 @@ where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
         let flavor = self;
         let mut t = t.into_transcript();
         let t = t.borrow_mut();
-        t.label(b"PedersenVRF");
-        let io = vrf::vrfs_merge(t, ios);
+        crate::protocol_label(t, b"PedersenVRF");
+        let io = vrf::vrfs_merge_msm(t, ios, self.preout_msm());
 
         // Allow derandomization by constructing secret_blinding and witness as late as possible.
         let secret_blinding = secret_blinding.unwrap_or_else( || secret.new_secret_blinding(t) );
@@ -314,19 +382,16 @@ where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
         secret: &SecretKey<K>,
         compk: KeyCommitment<K>,
     ) -> (Batchable<PedersenVrf<K,H,B>>,NonBatchable<PedersenVrf<K,H,B>>) {
-        let Witness { r, mut k } = self;
+        // Dropping self zeroizes k
+        let Witness { r, k } = &self;
+        let r = r.clone();
         t.label(b"Pedersen R");
         t.append(&r);
         let c: <K as AffineRepr>::ScalarField = t.challenge(b"PedersenVrfChallenge").read_reduce();
-        let mut blindings = arrayvec::ArrayVec::<K::ScalarField,B>::new();
-        for i in 0..B {
-            blindings.push( k.blindings[i] + c * secret_blindings.0[i] );
-        }
         let s = Scalars {
             keying: k.keying + secret.key.mul_by_challenge(&c),
-            blindings: blindings.into_inner().unwrap(),
+            blindings: core::array::from_fn(|i| k.blindings[i] + c * secret_blindings.0[i]),
         };
-        k.zeroize();
         (Batchable { compk: compk.clone(), r, s: s.clone() }, NonBatchable { compk, c, s })
         // See additional rowhammer defenses thoughts in Witness<ThinVrf>::sign_final
     }
@@ -346,10 +411,25 @@ where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
         signature: &Batchable<PedersenVrf<K,H,B>>,
     ) -> SignatureResult<&'a [VrfInOut<H>]>
     {
+        self.verify_pedersen_vrf_challenge(t, ios, signature).map(|_| ios)
+    }
+
+    /// Verify Pedersen VRF signature, and return its challenge.
+    fn verify_pedersen_vrf_challenge(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut<H>],
+        signature: &Batchable<PedersenVrf<K,H,B>>,
+    ) -> SignatureResult<<K as AffineRepr>::ScalarField>
+    {
+        if ! self.in_strict_subgroup(ios.iter().map(|io| io.preoutput.0))
+        || ! self.in_strict_subgroup([signature.compk.0]) {
+            return Err(SignatureError::Invalid);
+        }
         let mut t = t.into_transcript();
         let t = t.borrow_mut();
-        t.label(b"PedersenVRF");
-        let io = vrf::vrfs_merge(t, ios);
+        crate::protocol_label(t, b"PedersenVRF");
+        let io = vrf::vrfs_merge_msm(t, ios, self.preout_msm());
         t.label(b"KeyCommitment");
         t.append(&signature.compk);
 
@@ -358,25 +438,98 @@ where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
         t.append(&signature.r);
         let c: <K as AffineRepr>::ScalarField = t.challenge(b"PedersenVrfChallenge").read_reduce();
 
-        // TODO: Benchmark
-        // let z = ark_ec::scalar_mul::variable_base::VariableBaseMSM::msm_bigint(
-        //     &[io.input, io.preoutput],
-        //     &[-signature.s, c],
-        // ) + signature.r.into_group();
-        let z1 = signature.r.preoutish.into_group() + io.preoutput.0 * c - io.input.0 * signature.s.keying;
-        if ! crate::zero_mod_small_cofactor(z1) {
+        // Check R_H + c O - s_k I = 0 by one multi-scalar multiplication on H
+        let z1 = self.preout_msm().msm(
+            &[signature.r.preoutish, io.preoutput.0, io.input.0],
+            &[K::ScalarField::ONE, c, -signature.s.keying],
+        );
+        if ! self.zero_mod_cofactor(z1) {
             return Err(SignatureError::Invalid);
         }
-        // TODO: Use an MSM here
-        let mut z2 = signature.r.keyish.into_group() + signature.compk.0 * c;
-        z2 -= self.keying_base.mul(signature.s.keying);
-        for i in 0..B {
-            z2 -= self.blinding_bases[i].mul(signature.s.blindings[i]);
-        }
-        if ! crate::zero_mod_small_cofactor(z2) {
+        // Check R_K + c C - s_k G - sum s_b B = 0 by one multi-scalar multiplication on K
+        let mut bases = Vec::with_capacity(3 + B);
+        bases.extend([ signature.r.keyish, signature.compk.0, self.keying_base ]);
+        bases.extend_from_slice(&self.blinding_bases);
+        let mut scalars = Vec::with_capacity(3 + B);
+        scalars.extend([ K::ScalarField::ONE, c, -signature.s.keying ]);
+        scalars.extend(signature.s.blindings.iter().map(|s| -*s));
+        let z2 = self.msm().msm(&bases, &scalars);
+        if ! self.zero_mod_cofactor(z2) {
             return Err(SignatureError::Invalid);
         }
-        Ok(ios)
+        Ok(c)
+    }
+
+    /// Batch verify Pedersen VRF signatures
+    /// 
+    /// We check one random linear combination of both verification
+    /// equations from every signature, using one multi-scalar
+    /// multiplication on each curve, and merging the terms for our
+    /// keying and blinding bases.  We derive the 128 bit weights from
+    /// a transcript of the entire batch, like `batch_verify_thin_vrf`.
+    pub fn batch_verify_pedersen_vrf<T,IOs,S,II>(&self, batch: II) -> SignatureResult<()>
+    where
+        T: IntoTranscript,
+        IOs: Borrow<[VrfInOut<H>]>,
+        S: Borrow<Batchable<PedersenVrf<K,H,B>>>,
+        II: IntoIterator<Item=(T,IOs,S)>,
+    {
+        let mut weights = Transcript::new_labeled(b"PedersenVrfBatch");
+        let mut h_bases = Vec::new();
+        let mut k_bases = Vec::new();
+        let mut cs = Vec::new();
+        let mut ss = Vec::new();
+        for (t,ios,signature) in batch {
+            let signature = signature.borrow();
+            if ! self.in_strict_subgroup(ios.borrow().iter().map(|io| io.preoutput.0))
+            || ! self.in_strict_subgroup([signature.compk.0]) {
+                return Err(SignatureError::Invalid);
+            }
+            let mut t = t.into_transcript();
+            let t = t.borrow_mut();
+            crate::protocol_label(t, b"PedersenVRF");
+            let io = vrf::vrfs_merge_msm(t, ios.borrow(), self.preout_msm());
+            t.label(b"KeyCommitment");
+            t.append(&signature.compk);
+            t.label(b"Pedersen R");
+            t.append(&signature.r);
+            let c: <K as AffineRepr>::ScalarField = t.challenge(b"PedersenVrfChallenge").read_reduce();
+
+            weights.append(&io);
+            weights.append(signature);
+            h_bases.extend([ signature.r.preoutish, io.preoutput.0, io.input.0 ]);
+            k_bases.extend([ signature.r.keyish, signature.compk.0 ]);
+            cs.push(c);
+            ss.push(signature.s.clone());
+        }
+        if cs.is_empty() { return Ok(()) }
+
+        let mut h_scalars = Vec::with_capacity(h_bases.len());
+        let mut k_scalars = Vec::with_capacity(k_bases.len() + 1 + B);
+        let mut keying = <K as AffineRepr>::ScalarField::zero();
+        let mut blindings = [<K as AffineRepr>::ScalarField::zero(); B];
+        for (i,(c,s)) in cs.into_iter().zip(ss).enumerate() {
+            let z1: <K as AffineRepr>::ScalarField = crate::batch_weight(&weights, 2 * i as u64);
+            let z2: <K as AffineRepr>::ScalarField = crate::batch_weight(&weights, 2 * i as u64 + 1);
+            h_scalars.extend([ z1, z1 * c, -(z1 * s.keying) ]);
+            k_scalars.extend([ z2, z2 * c ]);
+            keying -= z2 * s.keying;
+            for (b,sb) in blindings.iter_mut().zip(&s.blindings) {
+                *b -= z2 * sb;
+            }
+        }
+        k_bases.push(self.keying_base);
+        k_scalars.push(keying);
+        k_bases.extend(self.blinding_bases);
+        k_scalars.extend(blindings);
+
+        let z1 = self.preout_msm().msm(&h_bases, &h_scalars);
+        let z2 = self.msm().msm(&k_bases, &k_scalars);
+        if self.zero_mod_cofactor(z1) && self.zero_mod_cofactor(z2) {
+            Ok(())
+        } else {
+            Err(SignatureError::Invalid)
+        }
     }
 
     /// Verify Pedersen VRF signature 
@@ -387,22 +540,41 @@ where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
         signature: &NonBatchable<PedersenVrf<K,H,B>>,
     ) -> SignatureResult<&'a [VrfInOut<H>]>
     {
+        self.to_batchable(t, ios, signature).map(|_| ios)
+    }
+
+    /// Verify a non-batchable Pedersen VRF signature, and expand it
+    /// into the batchable signature, by recomputing its nonce commitments.
+    pub fn to_batchable(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut<H>],
+        signature: &NonBatchable<PedersenVrf<K,H,B>>,
+    ) -> SignatureResult<Batchable<PedersenVrf<K,H,B>>>
+    {
+        if ! self.in_strict_subgroup(ios.iter().map(|io| io.preoutput.0))
+        || ! self.in_strict_subgroup([signature.compk.0]) {
+            return Err(SignatureError::Invalid);
+        }
         let mut t = t.into_transcript();
         let t = t.borrow_mut();
-        t.label(b"PedersenVRF");
-        let io = vrf::vrfs_merge(t, ios);
+        crate::protocol_label(t, b"PedersenVRF");
+        let io = vrf::vrfs_merge_msm(t, ios, self.preout_msm());
         t.label(b"KeyCommitment");
         t.append(&signature.compk);
 
         // Recompute Witness, but cofactors not a concern this way..
-        // TODO: Use an MSM here
-        let preoutish = io.input.0 * signature.s.keying - io.preoutput.0 * signature.c;
-        // TODO: Try an MSM here
-        let mut keyish = self.keying_base.mul(signature.s.keying);
-        for i in 0..B {
-            keyish += self.blinding_bases[i].mul(signature.s.blindings[i]);
-        }
-        keyish -= signature.compk.0 * signature.c;
+        let preoutish = self.preout_msm().msm(
+            &[io.input.0, io.preoutput.0],
+            &[signature.s.keying, -signature.c],
+        );
+        let mut bases = Vec::with_capacity(2 + B);
+        bases.extend([ self.keying_base, signature.compk.0 ]);
+        bases.extend_from_slice(&self.blinding_bases);
+        let mut scalars = Vec::with_capacity(2 + B);
+        scalars.extend([ signature.s.keying, -signature.c ]);
+        scalars.extend_from_slice(&signature.s.blindings);
+        let keyish = self.msm().msm(&bases, &scalars);
         let r: Affines <K,H> = Affines {
             keyish: keyish.into_affine(),
             preoutish: preoutish.into_affine(),
@@ -412,11 +584,165 @@ where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
         t.append(&r);
         let c: <K as AffineRepr>::ScalarField = t.challenge(b"PedersenVrfChallenge").read_reduce();
         if c == signature.c {
-            Ok(ios)
+            Ok(Batchable { compk: signature.compk.clone(), r, s: signature.s.clone() })
         } else {
+            Err(SignatureError::Invalid)
+        }
+    }
+
+    /// Verify a batchable Pedersen VRF signature, and shorten it into
+    /// the non-batchable signature, which replaces both nonce commitments
+    /// by the challenge.
+    pub fn to_non_batchable(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut<H>],
+        signature: &Batchable<PedersenVrf<K,H,B>>,
+    ) -> SignatureResult<NonBatchable<PedersenVrf<K,H,B>>>
+    {
+        let c = self.verify_pedersen_vrf_challenge(t, ios, signature) ?;
+        Ok(NonBatchable { compk: signature.compk.clone(), s: signature.s.clone(), c })
+    }
+}
+
+
+
+
+// --- Key commitment openings --- //
+
+/// Zero-knowledge proof that a `KeyCommitment` opens to a specific
+/// public key, which reveals nothing about the `SecretBlinding`.
+/// 
+/// Signers could later claim their own Pedersen or ring VRF signatures,
+/// like to claim a block reward, by supplying a transcript which binds
+/// the signature being claimed.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct KeyCommitmentOpening<K: AffineRepr,const B: usize> {
+    r: K,
+    s: [<K as AffineRepr>::ScalarField; B],
+}
+
+impl<K,H,const B: usize> PedersenVrf<K,H,B>
+where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
+{
+    fn key_commitment_opening_transcript<T: IntoTranscript>(
+        &self,
+        t: T,
+        compk: &KeyCommitment<K>,
+        public: &PublicKey<K>,
+    ) -> T::Taken {
+        let mut t = t.into_transcript();
+        let tm = t.borrow_mut();
+        crate::protocol_label(tm, b"KeyCommitmentOpening");
+        tm.append(compk);
+        tm.append(public);
+        t
+    }
+
+    /// Prove that `compk` opens to `public` using `secret_blinding`.
+    pub fn prove_key_commitment_opening(
+        &self,
+        t: impl IntoTranscript,
+        compk: &KeyCommitment<K>,
+        public: &PublicKey<K>,
+        secret_blinding: &SecretBlinding<K,B>,
+    ) -> KeyCommitmentOpening<K,B>
+    {
+        let mut t = self.key_commitment_opening_transcript(t, compk, public);
+        let t = t.borrow_mut();
+        // Our witness transcript binds the whole statement and the secret
+        // blindings, so deterministic nonces suffice, and need no RNG.
+        let mut w = t.fork(b"witness");
+        w.append(secret_blinding);
+        let mut reader = w.deterministic_witness();
+        let mut k: [K::ScalarField; B] = ark_std::array::from_fn(|_| reader.read_reduce());
+        let r = self.blinding_bases.iter().zip(k.iter())
+            .fold(<K as AffineRepr>::Group::zero(), |r, (base, k)| r + *base * *k)
+            .into_affine();
+        t.label(b"Opening R");
+        t.append(&r);
+        let c: K::ScalarField = t.challenge(b"KeyCommitmentOpeningChallenge").read_reduce();
+        let s = ark_std::array::from_fn(|i| k[i] + c * secret_blinding.0[i]);
+        k.zeroize();
+        KeyCommitmentOpening { r, s }
+    }
+
+    /// Verify that `compk` opens to `public`.
+    pub fn verify_key_commitment_opening(
+        &self,
+        t: impl IntoTranscript,
+        compk: &KeyCommitment<K>,
+        public: &PublicKey<K>,
+        opening: &KeyCommitmentOpening<K,B>,
+    ) -> SignatureResult<()>
+    {
+        if ! self.in_strict_subgroup([opening.r, compk.0, public.0]) {
             return Err(SignatureError::Invalid);
         }
+        let mut t = self.key_commitment_opening_transcript(t, compk, public);
+        let t = t.borrow_mut();
+        t.label(b"Opening R");
+        t.append(&opening.r);
+        let c: K::ScalarField = t.challenge(b"KeyCommitmentOpeningChallenge").read_reduce();
+
+        // Check sum s_i B_i = R + c (compk - public) by one multi-scalar multiplication
+        let mut bases = Vec::with_capacity(B + 3);
+        let mut scalars = Vec::with_capacity(B + 3);
+        bases.extend_from_slice(&self.blinding_bases);
+        scalars.extend_from_slice(&opening.s);
+        bases.extend([opening.r, compk.0, public.0]);
+        scalars.extend([-K::ScalarField::ONE, -c, c]);
+        let z = self.msm().msm(&bases, &scalars);
+        if self.zero_mod_cofactor(z) {
+            Ok(())
+        } else {
+            Err(SignatureError::Invalid)
+        }
     }
 }
 
 
+// --- Verification against key commitments --- //
+
+impl<K,H,const B: usize> PedersenVrf<K,H,B>
+where K: AffineRepr, H: AffineRepr<ScalarField = K::ScalarField>,
+{
+    /// Prove the VRF pre-outputs in `ios` come from the public key
+    /// inside the existing key commitment `compk`, without revealing
+    /// the public key.
+    ///
+    /// Pre-outputs do not depend upon the flavor, so signers could thin
+    /// sign `ios` for verifiers who know their public key, and supply
+    /// this proof for the same pre-outputs to verifiers who hold only
+    /// `compk`, like when only key commitments appear on-chain.
+    /// We reuse the `secret_blinding` behind `compk`, which the returned
+    /// non-batchable Pedersen VRF signature then contains as its key
+    /// commitment.
+    pub fn prove_committed_vrf(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut<H>],
+        secret_blinding: &SecretBlinding<K,B>,
+        secret: &SecretKey<K>,
+    ) -> NonBatchable<PedersenVrf<K,H,B>>
+    {
+        self.sign_non_batchable_pedersen_vrf(t, ios, Some(secret_blinding.clone()), secret).0
+    }
+
+    /// Verify that the VRF pre-outputs in `ios` come from the public key
+    /// inside `compk`, given a proof from `prove_committed_vrf`.
+    pub fn verify_committed_vrf<'a>(
+        &self,
+        t: impl IntoTranscript,
+        ios: &'a [VrfInOut<H>],
+        compk: &KeyCommitment<K>,
+        proof: &NonBatchable<PedersenVrf<K,H,B>>,
+    ) -> SignatureResult<&'a [VrfInOut<H>]>
+    {
+        if proof.as_key_commitment() != compk
+        || ! self.in_strict_subgroup([compk.0]) {
+            return Err(SignatureError::Invalid);
+        }
+        self.verify_non_batchable_pedersen_vrf(t, ios, proof)
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### PKCS#8 and SPKI encodings
+//!
+//! We encode secret keys as PKCS#8 v2 aka RFC 5958 `OneAsymmetricKey`,
+//! whose private key consists of `SecretKey::serialize_secret` bytes,
+//! and which includes the compressed public key too.  We encode public
+//! keys as SPKI aka RFC 5280 `SubjectPublicKeyInfo`, whose bit string
+//! consists of the compressed public key.
+//!
+//! Neither encoding identifies the curve, so decoding checks that the
+//! secret key yields the included public key.  Decoding always employs
+//! the default keying base, so use `ThinVrf::secretkey_from_pkcs8`
+//! for other flavors.
+
+use ark_ec::AffineRepr;
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize};
+use ark_std::vec::Vec;
+
+use pkcs8::{
+    AlgorithmIdentifierRef, ObjectIdentifier, PrivateKeyInfo, SecretDocument,
+    EncodePrivateKey, EncodePublicKey, Document,
+    spki::{self, SubjectPublicKeyInfoRef},
+    der::asn1::BitStringRef,
+};
+use zeroize::Zeroize;
+
+use crate::{ThinVrf, SecretKey, PublicKey};
+
+
+/// Algorithm identifier for DLEQ VRF keys.
+///
+/// TODO: Replace this placeholder once we allocate an OID.  `const-oid`
+/// cannot encode the ITU-T X.660 example arc `2.999`, whose second arc
+/// exceeds 39, so we park this under the reserved IANA enterprise
+/// number 0, which nobody else can claim.
+pub const ALGORITHM_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.0.1.1");
+
+const ALGORITHM_ID: AlgorithmIdentifierRef<'static> = AlgorithmIdentifierRef {
+    oid: ALGORITHM_OID,
+    parameters: None,
+};
+
+fn public_bytes<K: AffineRepr>(public: &PublicKey<K>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(public.compressed_size());
+    public.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+impl<K: AffineRepr> ThinVrf<K> {
+    /// Decode a `SecretKey` from PKCS#8 `PrivateKeyInfo` for this flavor.
+    pub fn secretkey_from_pkcs8(self, info: PrivateKeyInfo<'_>) -> pkcs8::Result<SecretKey<K>> {
+        info.algorithm.assert_algorithm_oid(ALGORITHM_OID) ?;
+        let public = info.public_key.ok_or(pkcs8::Error::KeyMalformed) ?;
+        let sk = self.deserialize_secretkey(info.private_key)
+            .map_err(|_| pkcs8::Error::KeyMalformed) ?;
+        if public_bytes(sk.as_publickey()) != public {
+            return Err(pkcs8::Error::KeyMalformed);
+        }
+        Ok(sk)
+    }
+}
+
+impl<K: AffineRepr> TryFrom<PrivateKeyInfo<'_>> for SecretKey<K> {
+    type Error = pkcs8::Error;
+
+    fn try_from(info: PrivateKeyInfo<'_>) -> pkcs8::Result<Self> {
+        ThinVrf::<K>::default().secretkey_from_pkcs8(info)
+    }
+}
+
+impl<K: AffineRepr> EncodePrivateKey for SecretKey<K> {
+    fn to_pkcs8_der(&self) -> pkcs8::Result<SecretDocument> {
+        let mut secret = Vec::with_capacity(self.secret_serialized_size());
+        self.serialize_secret(&mut secret).unwrap();
+        let public = public_bytes(self.as_publickey());
+        let mut info = PrivateKeyInfo::new(ALGORITHM_ID, &secret);
+        info.public_key = Some(&public);
+        let doc = SecretDocument::try_from(info);
+        secret.zeroize();
+        doc
+    }
+}
+
+impl<K: AffineRepr> TryFrom<SubjectPublicKeyInfoRef<'_>> for PublicKey<K> {
+    type Error = spki::Error;
+
+    fn try_from(spki: SubjectPublicKeyInfoRef<'_>) -> spki::Result<Self> {
+        spki.algorithm.assert_algorithm_oid(ALGORITHM_OID) ?;
+        let bytes = spki.subject_public_key.as_bytes().ok_or(spki::Error::KeyMalformed) ?;
+        PublicKey::deserialize_compressed(bytes).map_err(|_| spki::Error::KeyMalformed)
+    }
+}
+
+impl<K: AffineRepr> EncodePublicKey for PublicKey<K> {
+    fn to_public_key_der(&self) -> spki::Result<Document> {
+        let public = public_bytes(self);
+        SubjectPublicKeyInfoRef {
+            algorithm: ALGORITHM_ID,
+            subject_public_key: BitStringRef::new(0, &public) ?,
+        }.try_into()
+    }
+}
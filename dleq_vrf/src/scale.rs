@@ -11,6 +11,8 @@ pub use ark_scale::{
     impl_encode_via_ark,
     scale::{Encode,Decode,EncodeLike}
 };
+pub use scale_info::{self,TypeInfo};
+use scale_info::{Type,TypeDefArray,MetaType};
 
 use crate::{
     VrfPreOut,PublicKey,
@@ -46,9 +48,23 @@ macro_rules! impl_point_wrapper {
                 <C as AffineRepr>::zero().serialized_size(compress)
             }
         }
+
+        /// Compressed points have fixed size encodings, so we describe
+        /// them as byte arrays.
+        impl<C: AffineRepr> TypeInfo for $t<C> {
+            type Identity = Self;
+            fn type_info() -> Type {
+                fixed_bytes_type_info(<Self as MaxEncodedLen>::max_encoded_len())
+            }
+        }
     }
 } // macro_rules! impl_point_wrapper
 
+/// Describe a fixed length encoding as a byte array in `TypeInfo`.
+fn fixed_bytes_type_info(len: usize) -> Type {
+    TypeDefArray::new(len as u32, MetaType::new::<u8>()).into()
+}
+
 impl_point_wrapper!(PublicKey);
 impl_point_wrapper!(VrfPreOut);
 impl_point_wrapper!(KeyCommitment);
@@ -82,6 +98,15 @@ impl<P: EcVrfProof+ArkScaleMaxEncodedLen, const N: usize> MaxEncodedLen for VrfS
     }
 }
 
+/// We describe signatures as byte arrays, which requires that
+/// `P`'s `max_encoded_len` be exact, as for thin and Pedersen VRFs.
+impl<P: EcVrfProof+ArkScaleMaxEncodedLen, const N: usize> TypeInfo for VrfSignature<P,N> {
+    type Identity = Self;
+    fn type_info() -> Type {
+        fixed_bytes_type_info(<Self as MaxEncodedLen>::max_encoded_len())
+    }
+}
+
 impl<SF: ark_ff::PrimeField,const B: usize> ArkScaleMaxEncodedLen for crate::pedersen::Scalars<SF,B> {
     fn max_encoded_len(compress: Compress) -> usize {
         B * <SF as ark_ff::Zero>::zero().serialized_size(compress)
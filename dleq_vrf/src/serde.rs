@@ -0,0 +1,78 @@
+
+//! ### Serde support via canonical Arkworks encodings
+//! 
+//! We serialize all types here as the bytes of their compressed
+//! Arkworks encodings, so serde formats agree with `CanonicalSerialize`.
+
+use ark_std::vec::Vec;
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize,Compress,Validate};
+use ark_ec::AffineRepr;
+
+use serde::{Serialize,Serializer,Deserialize,Deserializer,de,ser};
+
+use crate::{
+    VrfInput,VrfPreOut,PublicKey,
+    flavor::{Flavor,Batchable,NonBatchable},
+    traits::{EcVrfProof,VrfSignature,VrfSignatureVec},
+};
+
+
+fn serialize_via_ark<T,S>(t: &T, serializer: S) -> Result<S::Ok,S::Error>
+where T: CanonicalSerialize, S: Serializer,
+{
+    let mut bytes = Vec::with_capacity(t.compressed_size());
+    t.serialize_compressed(&mut bytes).map_err(ser::Error::custom) ?;
+    serializer.serialize_bytes(&bytes)
+}
+
+/// Deserialize the validated compressed Arkworks encoding, which must
+/// consume all our bytes, so each value has only one serde encoding.
+fn deserialize_via_ark<'de,T,D>(deserializer: D) -> Result<T,D::Error>
+where T: CanonicalDeserialize, D: Deserializer<'de>,
+{
+    let bytes = <Vec<u8> as Deserialize>::deserialize(deserializer) ?;
+    let mut reader = bytes.as_slice();
+    let t = T::deserialize_with_mode(&mut reader, Compress::Yes, Validate::Yes)
+        .map_err(de::Error::custom) ?;
+    if ! reader.is_empty() {
+        return Err(de::Error::invalid_length(bytes.len(), &"exactly one compressed Arkworks encoding"));
+    }
+    Ok(t)
+}
+
+macro_rules! impl_serde_via_ark {
+    ($t:ty, $($g:tt)*) => {
+        impl<$($g)*> Serialize for $t {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok,S::Error> {
+                serialize_via_ark(self, serializer)
+            }
+        }
+
+        impl<'de, $($g)*> Deserialize<'de> for $t {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+                deserialize_via_ark(deserializer)
+            }
+        }
+    }
+} // macro_rules! impl_serde_via_ark
+
+impl_serde_via_ark!(PublicKey<C>, C: AffineRepr);
+impl_serde_via_ark!(VrfPreOut<C>, C: AffineRepr);
+impl_serde_via_ark!(Batchable<F>, F: Flavor);
+impl_serde_via_ark!(NonBatchable<F>, F: Flavor);
+impl_serde_via_ark!(VrfSignatureVec<P>, P: EcVrfProof);
+impl_serde_via_ark!(VrfSignature<P,N>, P: EcVrfProof, const N: usize);
+
+/// `VrfInput` lacks `CanonicalDeserialize` because inputs should come
+/// from hash-to-curve, but applications sometimes store them anyways.
+impl<C: AffineRepr> Serialize for VrfInput<C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok,S::Error> {
+        serialize_via_ark(self, serializer)
+    }
+}
+
+impl<'de, C: AffineRepr> Deserialize<'de> for VrfInput<C> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self,D::Error> {
+        deserialize_via_ark(deserializer).map(VrfInput)
+    }
+}
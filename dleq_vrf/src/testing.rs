@@ -0,0 +1,77 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Reproducible signatures for testing
+//!
+//! Integration tests and differential testing against other
+//! implementations need reproducible signatures, but hedged nonces
+//! hash system randomness.  `SecretKey::set_mock_rng` replaces this
+//! system randomness by a seeded `MockRng`, so signatures depend only
+//! upon the seed, the secret key, and the signing transcript.
+//!
+//! Never enable the `testing` feature in production builds.
+
+use ark_secret_scalar::{rand_core, RngCore, CryptoRng};
+
+use crate::Transcript;
+
+
+/// Deterministic RNG expanded from a 32 byte seed, only for testing.
+///
+/// We restart from the seed whenever `SecretKey::witness` uses a
+/// `MockRng`, so witnesses depend upon the seed, not upon how many
+/// signatures came before.
+///
+/// We deliberately provide no public `RngCore` or `CryptoRng`, so
+/// `MockRng` only reaches signing through `SecretKey::set_mock_rng`.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct MockRng {
+    seed: [u8; 32],
+}
+
+impl MockRng {
+    /// `MockRng` expanded from `seed`.
+    pub fn new(seed: [u8; 32]) -> MockRng {
+        MockRng { seed }
+    }
+
+    /// `MockRng` expanded from a small integer seed.
+    pub fn from_u64(seed: u64) -> MockRng {
+        let mut s = [0u8; 32];
+        s[..8].copy_from_slice(&seed.to_le_bytes());
+        MockRng::new(s)
+    }
+
+    /// Restart our expansion from the seed.
+    pub(crate) fn restart(&self) -> MockRngReader {
+        MockRngReader { seed: self.seed, counter: 0 }
+    }
+}
+
+/// Expansion of one `MockRng`, which never leaves this crate.
+pub(crate) struct MockRngReader {
+    seed: [u8; 32],
+    counter: u64,
+}
+
+impl RngCore for MockRngReader {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut t = Transcript::new_labeled(b"MockRng");
+        t.append(&self.seed[..]);
+        t.append_u64(self.counter);
+        self.counter += 1;
+        t.challenge(b"bytes").read_bytes(dest);
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Not cryptographically secure, but `Transcript::witness` demands this.
+impl CryptoRng for MockRngReader {}
@@ -19,7 +19,7 @@ type PedersenVrf = crate::PedersenVrf<K>;
 pub(crate) fn pedersen_vrf_test_flavor() -> PedersenVrf {
     let mut t = Transcript::new_labeled(b"TestFlavor");
     let mut reader = t.challenge(b"Keying&Blinding");
-    crate::ThinVrf { keying_base: reader.read_uniform(), }
+    crate::ThinVrf::new(reader.read_uniform())
     .pedersen_vrf([ reader.read_uniform() ])
 }
 
@@ -75,3 +75,1191 @@ fn master() {
 
 }
 
+#[test]
+fn thin_batch_verify() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sks: Vec<_> = (0..3u8).map(|i| (*flavor).clone().secretkey_from_seed(&[i; 32])).collect();
+    let pks: Vec<_> = sks.iter().map(|sk| sk.to_public()).collect();
+
+    let mk_io = |sk: &crate::SecretKey<K>, n: u32| {
+        let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",&n.to_le_bytes()[..]).unwrap();
+        sk.vrf_inout(input)
+    };
+    let ios: Vec<[vrf::VrfInOut<K>; 2]> = sks.iter().enumerate()
+        .map(|(i,sk)| [mk_io(sk, 2*i as u32), mk_io(sk, 2*i as u32 + 1)])
+        .collect();
+    let sigs: Vec<_> = sks.iter().zip(&ios)
+        .map(|(sk,ios)| sk.sign_thin_vrf_detached(Transcript::new_labeled(b"AD"), ios))
+        .collect();
+
+    let batch = || ios.iter().zip(&pks).zip(&sigs).map(
+        |((ios,pk),sig)| (Transcript::new_labeled(b"AD"), &ios[..], pk, sig)
+    );
+    flavor.batch_verify_thin_vrf(batch()).unwrap();
+    flavor.batch_verify_thin_vrf(batch().take(0)).unwrap();
+
+    let mut bad = batch().collect::<Vec<_>>();
+    bad[1].2 = &pks[0];
+    flavor.batch_verify_thin_vrf(bad).expect_err("WTF?!?");
+}
+
+#[test]
+fn pedersen_batch_verify() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[0; 32]);
+    let mk_io = |n: u32| {
+        let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",&n.to_le_bytes()[..]).unwrap();
+        sk.vrf_inout(input)
+    };
+    let ios: [vrf::VrfInOut<K>; 3] = [mk_io(0), mk_io(1), mk_io(2)];
+    let sigs: Vec<_> = (0..3).map(|i| {
+        let t = Transcript::new_labeled(b"AD");
+        flavor.sign_pedersen_vrf(t, &ios[i..i+1], None, &sk).0
+    }).collect();
+
+    let batch = || sigs.iter().enumerate().map(
+        |(i,sig)| (Transcript::new_labeled(b"AD"), &ios[i..i+1], sig)
+    );
+    flavor.batch_verify_pedersen_vrf(batch()).unwrap();
+
+    let mut bad = batch().collect::<Vec<_>>();
+    bad[2].1 = &ios[0..1];
+    flavor.batch_verify_pedersen_vrf(bad).expect_err("WTF?!?");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_roundtrip() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[0; 32]);
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"serde").unwrap();
+    let io = sk.vrf_inout(input);
+    let sig = sk.sign_thin_vrf(Transcript::new_labeled(b"AD"), &[io]);
+
+    let pk = sk.to_public();
+    let json = serde_json::to_string(&pk).unwrap();
+    assert_eq!(pk, serde_json::from_str(&json).unwrap());
+
+    let json = serde_json::to_string(&io.preoutput).unwrap();
+    assert_eq!(io.preoutput, serde_json::from_str(&json).unwrap());
+
+    let json = serde_json::to_string(&sig).unwrap();
+    assert_eq!(sig, serde_json::from_str(&json).unwrap());
+
+    // We reject trailing bytes after the encoding.
+    let mut bytes = Vec::new();
+    pk.serialize_compressed(&mut bytes).unwrap();
+    bytes.push(0);
+    let json = serde_json::to_string(&bytes).unwrap();
+    assert!(serde_json::from_str::<crate::PublicKey<K>>(&json).is_err());
+    bytes.pop();
+    let json = serde_json::to_string(&bytes).unwrap();
+    assert_eq!(pk, serde_json::from_str(&json).unwrap());
+}
+
+#[test]
+fn deterministic_nonces() {
+    let flavor = pedersen_vrf_test_flavor();
+    let mut sk = (*flavor).clone().secretkey_from_seed(&[0; 32]);
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"nonces").unwrap();
+    let io = sk.vrf_inout(input);
+    let sign = |sk: &crate::SecretKey<K>| sk.sign_thin_vrf_detached(Transcript::new_labeled(b"AD"), &[io]);
+
+    assert_ne!(sign(&sk), sign(&sk));
+    sk.set_nonce_strategy(crate::NonceStrategy::Deterministic);
+    let sig = sign(&sk);
+    assert_eq!(sig, sign(&sk));
+    flavor.verify_thin_vrf(Transcript::new_labeled(b"AD"), &[io], sk.as_publickey(), &sig).unwrap();
+}
+
+#[test]
+fn vrf_output_spec() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[1; 32]);
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"output").unwrap();
+    let io = sk.vrf_inout(input);
+
+    let mut t = Transcript::new_labeled(b"Context");
+    t.label(b"VrfOutput");
+    t.append(&io);
+    let expected: [u8; 64] = t.challenge(b"").read_byte_array();
+
+    let bytes: [u8; 64] = io.vrf_output_bytes(Transcript::new_labeled(b"Context"));
+    assert_eq!(bytes, expected);
+    let short: [u8; 32] = io.vrf_output_bytes(Transcript::new_labeled(b"Context"));
+    assert_eq!(short[..], bytes[..32]);
+    let other: [u8; 32] = io.vrf_output_bytes(Transcript::new_labeled(b"Other"));
+    assert_ne!(short, other);
+}
+
+#[test]
+fn signature_vec_conversions() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[2; 32]);
+    let mk_io = |n: u32| {
+        let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",&n.to_le_bytes()[..]).unwrap();
+        sk.vrf_inout(input)
+    };
+    let ios = [mk_io(0), mk_io(1)];
+    let sig = sk.sign_thin_vrf(Transcript::new_labeled(b"AD"), &ios);
+
+    let sig_vec = crate::VrfSignatureVec::from(sig.clone());
+    let ios_vec = sig_vec.attach_inputs(ios.iter().map(|io| io.input)).unwrap();
+    flavor.verify_thin_vrf(Transcript::new_labeled(b"AD"), &ios_vec, sk.as_publickey(), &sig_vec.proof).unwrap();
+    let sig_back = crate::VrfSignature::<_,2>::try_from(sig_vec.clone()).unwrap();
+    assert_eq!(sig, sig_back);
+    crate::VrfSignature::<_,3>::try_from(sig_vec).expect_err("WTF?!?");
+}
+
+#[test]
+fn deserialize_rejects_non_subgroup_points() {
+    use ark_ff::One;
+
+    // Any point with x=0,1,2,.. lies outside the prime order subgroup
+    // with overwhelming probability, given the huge cofactor.
+    let mut x = curve::Fq::one();
+    let p = loop {
+        if let Some(p) = K::get_point_from_x_unchecked(x, false) {
+            break p;
+        }
+        x += curve::Fq::one();
+    };
+    assert!(p.is_on_curve() && !p.is_in_correct_subgroup_assuming_on_curve());
+
+    let mut buf = Vec::new();
+    crate::PublicKey(p).serialize_compressed(&mut buf).unwrap();
+    assert!(crate::PublicKey::<K>::deserialize(buf.as_slice()).is_err());
+    assert_eq!(crate::PublicKey::<K>::deserialize_unchecked(buf.as_slice()).unwrap().0, p);
+    assert!(vrf::VrfPreOut::<K>::deserialize_compressed(buf.as_slice()).is_err());
+}
+
+#[test]
+fn wrong_input_count() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[3; 32]);
+    let mk_input = |n: u32| vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",&n.to_le_bytes()[..]).unwrap();
+    let ios = [sk.vrf_inout(mk_input(0)), sk.vrf_inout(mk_input(1))];
+    let sig = sk.sign_thin_vrf(Transcript::new_labeled(b"AD"), &ios);
+    let sig_vec = sk.sign_thin_vrf_vec(Transcript::new_labeled(b"AD"), &ios);
+
+    for inputs in [&[mk_input(0)][..], &[mk_input(0), mk_input(1), mk_input(2)][..]] {
+        assert!(matches!(
+            sig.attach_inputs(inputs.iter().cloned()),
+            Err(crate::SignatureError::WrongInputCount)
+        ));
+        assert!(matches!(
+            sig_vec.attach_inputs(inputs.iter().cloned()),
+            Err(crate::SignatureError::WrongInputCount)
+        ));
+    }
+    assert!(vrf::collect_preoutputs_array::<3,K>(&ios).is_err());
+}
+
+struct TestSuite;
+
+impl crate::ietf::Suite for TestSuite {
+    type Affine = K;
+    type Hasher = sha2::Sha512;
+    const SUITE_ID: &'static [u8] = b"BLS12-377_SHA-512_TEST";
+    const CHALLENGE_LENGTH: usize = 16;
+}
+
+#[test]
+fn ietf_prove_verify() {
+    use crate::ietf::{self, Proof};
+
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[4; 32]);
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"ietf").unwrap();
+
+    let proof = ietf::prove::<TestSuite>(&sk, input, b"");
+    let bytes = proof.to_bytes();
+    assert_eq!(bytes.len(), Proof::<TestSuite>::serialized_length());
+    let proof = Proof::<TestSuite>::from_bytes(&bytes).unwrap();
+
+    let output = ietf::verify::<TestSuite>(&*flavor, sk.as_publickey(), input, b"", &proof).unwrap();
+    assert_eq!(output, ietf::proof_to_hash(&proof));
+    assert_eq!(proof.gamma, sk.vrf_inout(input).preoutput);
+    // RFC 9381 nonces are deterministic.
+    assert_eq!(proof, ietf::prove::<TestSuite>(&sk, input, b""));
+
+    let other = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"other").unwrap();
+    ietf::verify::<TestSuite>(&*flavor, sk.as_publickey(), other, b"", &proof).expect_err("WTF?!?");
+    ietf::verify::<TestSuite>(&*flavor, sk.as_publickey(), input, b"ad", &proof).expect_err("WTF?!?");
+    let proof = ietf::prove::<TestSuite>(&sk, input, b"ad");
+    ietf::verify::<TestSuite>(&*flavor, sk.as_publickey(), input, b"ad", &proof).unwrap();
+    assert!(Proof::<TestSuite>::from_bytes(&bytes[1..]).is_err());
+}
+
+#[test]
+fn fixed_base_tables() {
+    let flavor = pedersen_vrf_test_flavor();
+    let fast = flavor.clone().with_fixed_base_tables();
+    assert_eq!(flavor, fast);
+
+    let sk = (*flavor).clone().secretkey_from_seed(&[5; 32]);
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"tables").unwrap();
+    let io = sk.vrf_inout(input);
+    let (sig, secret_blinding) = fast.sign_pedersen_vrf(Transcript::new_labeled(b"AD"), &[io], None, &sk);
+    assert!( *sig.as_key_commitment() == flavor.compute_blinded_publickey(sk.as_publickey(),&secret_blinding) );
+    flavor.verify_pedersen_vrf(Transcript::new_labeled(b"AD"), &[io], &sig).unwrap();
+}
+
+/// Toy hardware signer, which never reveals its secret key.
+struct ToyHsm(crate::SecretKey<K>);
+
+impl crate::VrfSigner for ToyHsm {
+    type Proof = crate::ThinVrfProof<K>;
+    type Error = &'static str;
+
+    fn inout(&self, input: vrf::VrfInput<K>) -> Result<vrf::VrfInOut<K>,Self::Error> {
+        Ok(self.0.vrf_inout(input))
+    }
+
+    fn sign_detached(&self, t: Transcript, ios: &[vrf::VrfInOut<K>]) -> Result<Self::Proof,Self::Error> {
+        if ios.is_empty() { return Err("HSM refuses Schnorr signatures"); }
+        Ok(self.0.sign_thin_vrf_detached(t, ios))
+    }
+}
+
+#[test]
+fn remote_signer() {
+    use crate::VrfSigner;
+
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[6; 32]);
+    let public = sk.to_public();
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"remote").unwrap();
+
+    // Local secret keys work via the blanket impl.
+    let io = VrfSigner::inout(&sk, input).unwrap();
+    let sig = VrfSigner::sign(&sk, Transcript::new_labeled(b"AD"), &[io]).unwrap();
+    flavor.verify_thin_vrf(Transcript::new_labeled(b"AD"), &[io], &public, &sig.proof).unwrap();
+
+    let hsm = ToyHsm(sk);
+    let io = hsm.inout(input).unwrap();
+    let sig = hsm.sign(Transcript::new_labeled(b"AD"), &[io]).unwrap();
+    flavor.verify_thin_vrf(Transcript::new_labeled(b"AD"), &[io], &public, &sig.proof).unwrap();
+    assert!( hsm.sign_detached(Transcript::new_labeled(b"AD"), &[]).is_err() );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_signer_futures_are_send() {
+    use core::{future::Future, pin::pin, task::{Context, Poll, Waker}};
+    use crate::AsyncVrfSigner;
+
+    fn ready<F: Future + Send>(future: F) -> F::Output {
+        let mut cx = Context::from_waker(Waker::noop());
+        match pin!(future).poll(&mut cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("Blanket AsyncVrfSigner futures complete immediately"),
+        }
+    }
+
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[7; 32]);
+    let public = sk.to_public();
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"async").unwrap();
+
+    let hsm = ToyHsm(sk);
+    let io = ready(hsm.inout_async(input)).unwrap();
+    let proof = ready(hsm.sign_detached_async(Transcript::new_labeled(b"AD"), &[io])).unwrap();
+    flavor.verify_thin_vrf(Transcript::new_labeled(b"AD"), &[io], &public, &proof).unwrap();
+    assert!( ready(hsm.sign_detached_async(Transcript::new_labeled(b"AD"), &[])).is_err() );
+}
+
+#[test]
+fn hdkd() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[7; 32]);
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"hdkd").unwrap();
+
+    let soft = sk.derive_soft(&[1; 32]);
+    assert_eq!(soft.to_public(), flavor.derive_soft_publickey(sk.as_publickey(), &[1; 32]));
+    assert!(soft.to_public() != sk.to_public());
+    assert!(soft.to_public() != sk.derive_soft(&[2; 32]).to_public());
+    let io = soft.vrf_inout(input);
+    let sig = soft.sign_thin_vrf(Transcript::new_labeled(b"AD"), &[io]);
+    flavor.verify_thin_vrf(Transcript::new_labeled(b"AD"), &[io], soft.as_publickey(), &sig.proof).unwrap();
+
+    let hard = sk.derive_hard(&[1; 32]);
+    assert_eq!(hard.to_public(), sk.derive_hard(&[1; 32]).to_public());
+    assert!(hard.to_public() != soft.to_public());
+    assert!(hard.to_public() != sk.derive_hard(&[2; 32]).to_public());
+}
+
+#[cfg(feature = "mnemonic")]
+#[test]
+fn mnemonic_phrases() {
+    let phrase = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+    let thin = (*pedersen_vrf_test_flavor()).clone();
+    let sk = thin.clone().secretkey_from_phrase(phrase, None).unwrap();
+    assert_eq!(sk.to_public(), thin.clone().secretkey_from_phrase(phrase, Some("")).unwrap().to_public());
+    assert!(sk.to_public() != thin.clone().secretkey_from_phrase(phrase, Some("password")).unwrap().to_public());
+    assert!(thin.secretkey_from_phrase("bottom drive obey lake", None).is_err());
+}
+
+#[cfg(feature = "argon2")]
+#[test]
+fn password_stretching() {
+    let thin = (*pedersen_vrf_test_flavor()).clone();
+    let params = || argon2::Params::new(64, 1, 1, Some(32)).unwrap();
+    let sk = thin.clone().secretkey_from_password(b"password", b"saltsalt", params()).unwrap();
+    assert_eq!(sk.to_public(), thin.clone().secretkey_from_password(b"password", b"saltsalt", params()).unwrap().to_public());
+    assert!(sk.to_public() != thin.clone().secretkey_from_password(b"password", b"pepperpepper", params()).unwrap().to_public());
+    assert!(sk.to_public() != thin.clone().secretkey_from_password(b"passw0rd", b"saltsalt", params()).unwrap().to_public());
+    assert!(thin.secretkey_from_password(b"password", b"salt", params()).is_err());
+}
+
+#[test]
+fn secret_serialization() {
+    let thin = (*pedersen_vrf_test_flavor()).clone();
+    let sk = thin.clone().secretkey_from_seed(&[8; 32]);
+    let mut bytes = Vec::new();
+    sk.serialize_secret(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), sk.secret_serialized_size());
+    let sk2 = thin.deserialize_secretkey(bytes.as_slice()).unwrap();
+    assert_eq!(sk.to_public(), sk2.to_public());
+}
+
+#[cfg(feature = "keystore")]
+#[test]
+fn encrypted_keystore() {
+    let thin = (*pedersen_vrf_test_flavor()).clone();
+    let sk = thin.clone().secretkey_from_seed(&[9; 32]);
+    let json = sk.to_encrypted_keystore_with_cost(b"hunter2", 4).unwrap();
+    let sk2 = thin.clone().secretkey_from_encrypted_keystore(&json, b"hunter2").unwrap();
+    assert_eq!(sk.to_public(), sk2.to_public());
+    assert!(thin.clone().secretkey_from_encrypted_keystore(&json, b"hunter3").is_err());
+
+    // We refuse scrypt costs which could exhaust memory.
+    assert!(sk.to_encrypted_keystore_with_cost(b"hunter2", 40).is_err());
+    let costly = json.replace("\"log_n\":4", "\"log_n\":40");
+    assert!(matches!(
+        thin.secretkey_from_encrypted_keystore(&costly, b"hunter2"),
+        Err(crate::keystore::KeystoreError::Kdf)
+    ));
+}
+
+#[cfg(feature = "pkcs8")]
+#[test]
+fn pkcs8_spki() {
+    use ::pkcs8::{EncodePrivateKey, DecodePrivateKey, EncodePublicKey, DecodePublicKey};
+    type SecretKey = crate::SecretKey<K>;
+    type PublicKey = crate::PublicKey<K>;
+
+    let sk = SecretKey::from_seed(&[10; 32]);
+    let der = sk.to_pkcs8_der().unwrap();
+    let sk2 = SecretKey::from_pkcs8_der(der.as_bytes()).unwrap();
+    assert_eq!(sk.to_public(), sk2.to_public());
+
+    let der = sk.to_public().to_public_key_der().unwrap();
+    assert_eq!(sk.to_public(), PublicKey::from_public_key_der(der.as_bytes()).unwrap());
+}
+
+#[test]
+fn proof_of_possession() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[11; 32]);
+    let pop = sk.sign_pop();
+    flavor.verify_pop(sk.as_publickey(), &pop).unwrap();
+
+    let other = (*flavor).clone().secretkey_from_seed(&[12; 32]);
+    flavor.verify_pop(other.as_publickey(), &pop).expect_err("WTF?!?");
+    // Schnorr signatures on other transcripts are not proofs-of-possession.
+    let schnorr = sk.sign_thin_vrf_detached(Transcript::new_labeled(b"AD"), &[]);
+    flavor.verify_pop(sk.as_publickey(), &schnorr).expect_err("WTF?!?");
+    // Nor are thin VRF signatures upon the proof-of-possession transcript.
+    let thin = sk.sign_thin_vrf_detached(Transcript::new_labeled(b"ProofOfPossession"), &[]);
+    flavor.verify_pop(sk.as_publickey(), &thin).expect_err("WTF?!?");
+    flavor.verify_thin_vrf(Transcript::new_labeled(b"ProofOfPossession"), &[], sk.as_publickey(), &pop).expect_err("WTF?!?");
+}
+
+#[test]
+fn threshold_signing() {
+    use crate::threshold::PartialSignature;
+
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[13; 32]);
+    let (group, shares) = sk.split_threshold(2, 3);
+    assert_eq!(&group.public, sk.as_publickey());
+
+    let inputs = [ vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"threshold").unwrap() ];
+    let signers = [&shares[0], &shares[2]];
+    let t = Transcript::new_labeled(b"AD");
+
+    let preout_shares: Vec<_> = signers.iter().map(|s| s.preout_shares(&inputs)).collect();
+    let ios: [vrf::VrfInOut<K>; 1] = group.combine_inouts(&inputs, &preout_shares).unwrap().try_into().unwrap();
+    assert_eq!(ios[0].preoutput, sk.vrf_inout(inputs[0]).preoutput);
+
+    let (nonces, commitments): (Vec<_>, Vec<_>) = signers.iter().map(|s| s.commit(t.clone(), &ios)).unzip();
+    let partials: Vec<PartialSignature<K>> = signers.iter().zip(nonces)
+        .map(|(s,nonces)| s.sign_partial(nonces, t.clone(), &ios, &commitments).unwrap())
+        .collect();
+    let signature = group.aggregate(t.clone(), &ios, &preout_shares, &commitments, &partials).unwrap();
+    flavor.verify_thin_vrf(t.clone(), &ios, sk.as_publickey(), &signature.proof).unwrap();
+
+    // Corrupt partial signatures get caught.
+    let mut bad = partials.clone();
+    let s = bad[1].s;
+    bad[1].s += s;
+    group.aggregate(t.clone(), &ios, &preout_shares, &commitments, &bad).expect_err("WTF?!?");
+    // One signer falls below our threshold.
+    group.combine_inouts(&inputs, &preout_shares[..1]).expect_err("WTF?!?");
+}
+
+#[test]
+fn two_party_cosigning() {
+    use crate::threshold::{PreOutShares, NonceCommitment, PartialSignature};
+
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[14; 32]);
+    let [validator, hsm] = sk.split_cosigners();
+    assert_eq!(validator.public(), sk.as_publickey());
+
+    let inputs = ark_std::vec![ vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"cosign").unwrap() ];
+    let t = Transcript::new_labeled(b"AD");
+    // Round messages cross the wire serialized.
+    fn wire<T: CanonicalSerialize + CanonicalDeserialize>(m: T) -> T {
+        let mut bytes = Vec::new();
+        m.serialize_compressed(&mut bytes).unwrap();
+        T::deserialize_compressed(bytes.as_slice()).unwrap()
+    }
+
+    let (mut v, v1) = validator.start(t.clone(), inputs.clone());
+    let (mut h, h1) = hsm.start(t.clone(), inputs.clone());
+    let v2 = v.commit(wire::<PreOutShares<K>>(h1)).unwrap();
+    let h2 = h.commit(wire::<PreOutShares<K>>(v1)).unwrap();
+    let v3 = v.sign(wire::<NonceCommitment<K>>(h2)).unwrap();
+    let h3 = h.sign(wire::<NonceCommitment<K>>(v2.clone())).unwrap();
+    // Sessions never reuse their nonces.
+    h.sign(v2).expect_err("WTF?!?");
+
+    let ios = v.ios().to_vec();
+    assert_eq!(ios[0].preoutput, sk.vrf_inout(inputs[0]).preoutput);
+    let signature = v.finish::<1>(wire::<PartialSignature<K>>(h3.clone())).unwrap();
+    flavor.verify_thin_vrf(t.clone(), &ios, sk.as_publickey(), &signature.proof).unwrap();
+    let signature = h.finish::<1>(v3.clone()).unwrap();
+    flavor.verify_thin_vrf(t.clone(), &ios, sk.as_publickey(), &signature.proof).unwrap();
+
+    // Corrupt partial signatures get caught.
+    let (mut v, v1) = validator.start(t.clone(), inputs.clone());
+    let (mut h, h1) = hsm.start(t.clone(), inputs.clone());
+    let v2 = v.commit(h1).unwrap();
+    let h2 = h.commit(v1).unwrap();
+    v.sign(h2).unwrap();
+    let mut h3 = h.sign(v2).unwrap();
+    h3.s += v3.s;
+    v.finish::<1>(h3).expect_err("WTF?!?");
+}
+
+#[test]
+fn musig_multi_signing() {
+    let flavor = pedersen_vrf_test_flavor();
+    let thin = (*flavor).clone();
+    let sks: Vec<_> = (15..18u8).map(|i| thin.clone().secretkey_from_seed(&[i; 32])).collect();
+    let members: Vec<_> = sks.iter().map(|sk| sk.to_public()).collect();
+    let key = thin.clone().aggregate_keys(&members).unwrap();
+    assert!(thin.clone().aggregate_keys(&[members[0].clone(), members[0].clone()]).is_err());
+
+    let inputs = [
+        vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"musig 1").unwrap(),
+        vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"musig 2").unwrap(),
+    ];
+    let t = Transcript::new_labeled(b"AD");
+
+    let (nonces, commitments): (Vec<_>, Vec<_>) = sks.iter()
+        .map(|sk| sk.commit_musig(&key, &inputs).unwrap()).unzip();
+    let partials: Vec<_> = sks.iter().zip(nonces)
+        .map(|(sk,nonces)| sk.sign_musig(nonces, &key, t.clone(), &inputs, &commitments).unwrap())
+        .collect();
+    let signature = key.aggregate_musig(t.clone(), &inputs, &commitments, &partials).unwrap();
+    let ios: Vec<_> = inputs.iter().zip(&signature.preouts)
+        .map(|(input,preoutput)| vrf::VrfInOut { input: *input, preoutput: *preoutput })
+        .collect();
+    thin.verify_thin_vrf(t.clone(), &ios, &key.public, &signature.proof).unwrap();
+    thin.verify_thin_vrf(Transcript::new_labeled(b"Other"), &ios, &key.public, &signature.proof)
+        .expect_err("WTF?!?");
+
+    // Corrupt partial signatures and pre-outputs get caught.
+    let mut bad = partials.clone();
+    let s0 = bad[0].s;
+    bad[1].s += s0;
+    key.aggregate_musig(t.clone(), &inputs, &commitments, &bad).expect_err("WTF?!?");
+    let mut bad = commitments.clone();
+    bad[2].preouts[0] = bad[1].preouts[0];
+    key.aggregate_musig(t.clone(), &inputs, &bad, &partials).expect_err("WTF?!?");
+    // Every member must sign.
+    key.aggregate_musig(t.clone(), &inputs, &commitments[..2], &partials[..2]).expect_err("WTF?!?");
+}
+
+#[test]
+fn thin_adaptor_signatures() {
+    use ark_ec::{AffineRepr, CurveGroup};
+    use crate::adaptor::extract_secret;
+
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[18; 32]);
+    let ios = [ sk.vrf_inout(vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"adaptor").unwrap()) ];
+    let t = Transcript::new_labeled(b"Swap");
+
+    let secret = <K as AffineRepr>::ScalarField::from(0xdeadbeefu64);
+    let m = flavor.merged_input(t.clone(), sk.as_publickey(), &ios);
+    let adaptor_point = (m.0 * secret).into_affine();
+
+    let pre_signature = sk.sign_adaptor(t.clone(), &ios, &adaptor_point);
+    flavor.verify_adaptor(t.clone(), &ios, sk.as_publickey(), &adaptor_point, &pre_signature).unwrap();
+    let wrong_point = (m.0 * (secret + secret)).into_affine();
+    flavor.verify_adaptor(t.clone(), &ios, sk.as_publickey(), &wrong_point, &pre_signature).expect_err("WTF?!?");
+    // Pre-signatures never verify as signatures themselves.
+    let incomplete = Batchable { compk: (), r: pre_signature.r, s: pre_signature.s };
+    flavor.verify_thin_vrf(t.clone(), &ios, sk.as_publickey(), &incomplete).expect_err("WTF?!?");
+
+    let signature = pre_signature.complete(&secret);
+    flavor.verify_thin_vrf(t.clone(), &ios, sk.as_publickey(), &signature).unwrap();
+    assert_eq!(extract_secret(&signature, &pre_signature), Some(secret));
+}
+
+#[test]
+fn distributed_key_generation() {
+    use crate::dkg::{Participant, DealerShare};
+
+    let thin = (*pedersen_vrf_test_flavor()).clone();
+    let (mut participants, commitments, shares): (Vec<_>, Vec<_>, Vec<Vec<DealerShare<K>>>) = (1..=3)
+        .map(|i| Participant::new(thin.clone(), b"session 1", i, 2, 3))
+        .fold((Vec::new(), Vec::new(), Vec::new()), |(mut p, mut c, mut s), (pi, ci, si)| {
+            p.push(pi); c.push(ci); s.push(si); (p, c, s)
+        });
+    let mut shares: Vec<DealerShare<K>> = shares.into_iter().flatten().collect();
+    // Dealer 3 sends participant 1 a bad share.
+    let bad = shares.iter_mut().find(|s| s.dealer == 3 && s.recipient == 1).unwrap();
+    let s = bad.share;
+    bad.share += s;
+
+    let complaints: Vec<_> = participants.iter_mut()
+        .flat_map(|p| p.receive(&commitments, &shares))
+        .collect();
+    assert_eq!(complaints.len(), 1);
+    let responses: Vec<_> = participants.iter().flat_map(|p| p.respond(&complaints)).collect();
+
+    let results: Vec<_> = participants.into_iter()
+        .map(|p| p.finalize(&commitments, &complaints, &responses).unwrap())
+        .collect();
+    let group = results[0].0.clone();
+    assert!(results.iter().all(|(g,_)| *g == group));
+
+    // Any two participants sign for the joint public key.
+    let share = &results[1].1;
+    assert_eq!(share.verifying_share(), &group.verifying_shares[1]);
+    let t = Transcript::new_labeled(b"AD");
+    let inputs = [ vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"dkg").unwrap() ];
+    let signers = [&results[0].1, &results[2].1];
+    let preout_shares: Vec<_> = signers.iter().map(|s| s.preout_shares(&inputs)).collect();
+    let ios: [vrf::VrfInOut<K>; 1] = group.combine_inouts(&inputs, &preout_shares).unwrap().try_into().unwrap();
+    let (nonces, commitments): (Vec<_>, Vec<_>) = signers.iter().map(|s| s.commit(t.clone(), &ios)).unzip();
+    let partials: Vec<_> = signers.iter().zip(nonces)
+        .map(|(s,nonces)| s.sign_partial(nonces, t.clone(), &ios, &commitments).unwrap())
+        .collect();
+    let signature = group.aggregate(t.clone(), &ios, &preout_shares, &commitments, &partials).unwrap();
+    group.thin.verify_thin_vrf(t, &ios, &group.public, &signature.proof).unwrap();
+}
+
+#[test]
+fn dkg_disqualifies_equivocating_and_replayed_dealers() {
+    use ark_ec::CurveGroup;
+    use crate::dkg::{Participant, DealerShare};
+
+    let thin = (*pedersen_vrf_test_flavor()).clone();
+    let mut participants = Vec::new();
+    let mut commitments = Vec::new();
+    let mut shares: Vec<DealerShare<K>> = Vec::new();
+    for i in 1..=4 {
+        let (p, c, s) = Participant::new(thin.clone(), b"session 2", i, 2, 4);
+        participants.push(p);
+        commitments.push(c);
+        shares.extend(s);
+    }
+    // Dealer 3 broadcasts a second, conflicting commitment, and dealer 4
+    // replays its commitment and shares from another session.
+    let (_, conflicting, _) = Participant::new(thin.clone(), b"session 2", 3, 2, 4);
+    commitments.push(conflicting);
+    let (_, replayed, replayed_shares) = Participant::new(thin.clone(), b"session 1", 4, 2, 4);
+    commitments[3] = replayed;
+    shares.retain(|s| s.dealer != 4);
+    shares.extend(replayed_shares);
+
+    // Nobody complains about disqualified dealers.
+    let complaints: Vec<_> = participants.iter_mut()
+        .flat_map(|p| p.receive(&commitments, &shares))
+        .collect();
+    assert!(complaints.is_empty());
+
+    let results: Vec<_> = participants.into_iter()
+        .map(|p| p.finalize(&commitments, &complaints, &[]).unwrap())
+        .collect();
+    let group = results[0].0.clone();
+    assert!(results.iter().all(|(g,_)| *g == group));
+    let public = (commitments[0].coefficients[0] + commitments[1].coefficients[0]).into_affine();
+    assert!(group.public.0 == public);
+}
+
+#[test]
+fn same_input_aggregation() {
+    use crate::aggregate::{Contribution, SameInputAggregate};
+
+    let flavor = pedersen_vrf_test_flavor();
+    let t = Transcript::new_labeled(b"Beacon");
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"epoch 7").unwrap();
+    let contributions: Vec<_> = (20..23u8)
+        .map(|i| Contribution::new(&(*flavor).clone().secretkey_from_seed(&[i; 32]), t.clone(), input))
+        .collect();
+    let mut aggregate = SameInputAggregate { contributions };
+
+    let ios = flavor.verify_same_input_aggregate(&t, input, &aggregate).unwrap();
+    let output: [u8; 32] = ios.vrf_output_bytes(b"Randomness");
+    assert_eq!(output, flavor.verify_same_input_aggregate(&t, input, &aggregate).unwrap().vrf_output_bytes::<32>(b"Randomness"));
+
+    aggregate.contributions.pop();
+    let fewer = flavor.verify_same_input_aggregate(&t, input, &aggregate).unwrap();
+    assert!(output != fewer.vrf_output_bytes::<32>(b"Randomness"));
+
+    let other = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"epoch 8").unwrap();
+    flavor.verify_same_input_aggregate(&t, other, &aggregate).expect_err("WTF?!?");
+
+    aggregate.contributions.clear();
+    flavor.verify_same_input_aggregate(&t, input, &aggregate).expect_err("WTF?!?");
+}
+
+#[test]
+fn lottery_is_winner() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[14; 32]);
+    let ios: Vec<_> = (0..128u8)
+        .map(|i| sk.vrf_inout(vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",&[i]).unwrap()))
+        .collect();
+    assert!(ios.iter().all(|io| io.is_winner(1, 1, b"Lottery")));
+    assert!(ios.iter().all(|io| ! io.is_winner(0, 1, b"Lottery")));
+    let wins = ios.iter().filter(|io| io.is_winner(1, 4, b"Lottery")).count();
+    assert!(8 < wins && wins < 56);
+
+    // Only the ratio matters.
+    assert!(ios.iter().all(|io| io.is_winner(3, 7, b"Lottery") == io.is_winner(6, 14, b"Lottery")));
+}
+
+#[test]
+fn uniform_output_mappers() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[14; 32]);
+    let ios: Vec<_> = (0..128u8)
+        .map(|i| sk.vrf_inout(vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",&[i]).unwrap()))
+        .collect();
+    for io in ios.iter() {
+        assert_eq!(io.output_u128(b"Out"), u128::from_le_bytes(io.vrf_output_bytes(b"Out")));
+        let ratio = io.output_ratio(b"Out");
+        assert!((0.0..1.0).contains(&ratio));
+        assert!(io.is_winner(1, 2, b"Out") == (ratio < 0.5));
+        assert!(io.output_mod(7, b"Out") < 7);
+        assert_eq!(io.output_mod(1, b"Out"), 0);
+    }
+    let sixes = ios.iter().filter(|io| io.output_mod(6, b"Out") == 5).count();
+    assert!(4 < sixes && sixes < 48);
+    // Moduli near 2^64 reject often, but still terminate.
+    let big = u64::MAX / 2 + 2;
+    assert!(ios.iter().all(|io| io.output_mod(big, b"Out") < big));
+}
+
+#[test]
+fn keypair_caches_public_and_flavor() {
+    let flavor = pedersen_vrf_test_flavor();
+    let secret = (*flavor).clone().secretkey_from_seed(&[16; 32]);
+    let keypair = crate::Keypair::from(secret.clone());
+    assert!(keypair.thin_vrf() == &*flavor);
+    assert!(keypair.public() == &secret.to_public());
+    let io = keypair.vrf_inout(vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"keypair").unwrap());
+    let sig = keypair.sign_thin_vrf(b"keypair", &[io]);
+    keypair.thin_vrf().verify_thin_vrf(b"keypair", &[io], keypair.public(), &sig.proof).unwrap();
+
+    // Generic signers sign through our secret key too.
+    let sig = crate::EcVrfSigner::vrf_sign(&keypair, b"generic", &[io]).unwrap();
+    keypair.thin_vrf().verify_thin_vrf(b"generic", &[io], keypair.public(), &sig.proof).unwrap();
+    assert!(keypair.into_secret().to_public() == secret.to_public());
+}
+
+#[test]
+fn secretkey_from_parts_and_nonce_seed() {
+    use ark_ec::{AffineRepr, CurveGroup};
+    use crate::{Flavor, NonceStrategy, keys::nonce_seed_witness};
+
+    let flavor = pedersen_vrf_test_flavor();
+    let scalar = <K as AffineRepr>::ScalarField::from(7u64);
+    let mut sk = (*flavor).clone().secretkey_from_parts(scalar, &[3; 32]);
+    assert_eq!(sk.as_publickey().0, (*sk.thin_vrf().keying_base() * scalar).into_affine());
+    assert_eq!(sk.nonce_seed(), &[3; 32]);
+
+    sk.set_nonce_strategy(NonceStrategy::Deterministic);
+    let t = Transcript::new_labeled(b"HSM");
+    let ours: <K as AffineRepr>::ScalarField = sk.witness(&t, b"label").read_reduce();
+    let theirs: <K as AffineRepr>::ScalarField =
+        nonce_seed_witness(sk.nonce_seed(), &t, b"label", NonceStrategy::Deterministic).read_reduce();
+    assert_eq!(ours, theirs);
+}
+
+#[test]
+fn delinearization_scalars_reproduce_merge() {
+    use ark_ec::{AffineRepr, CurveGroup};
+
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[21; 32]);
+    let ios: Vec<_> = [b"one".as_slice(), b"two", b"three"].iter().map(|m| {
+        sk.vrf_inout(vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO", m).unwrap())
+    }).collect();
+    let t = Transcript::new_labeled(b"Audit");
+
+    let scalars = vrf::delinearization_scalars(&t, &ios);
+    assert_eq!(scalars.len(), ios.len());
+    let merged = vrf::vrfs_merge(&mut t.clone(), &ios);
+    let mut input = <K as AffineRepr>::Group::default();
+    let mut preoutput = <K as AffineRepr>::Group::default();
+    for (io, z) in ios.iter().zip(scalars.iter()) {
+        input += io.input.0 * z;
+        preoutput += io.preoutput.0 * z;
+    }
+    assert_eq!(merged.input.0, input.into_affine());
+    assert_eq!(merged.preoutput.0, preoutput.into_affine());
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn mock_rng_reproduces_signatures() {
+    use crate::{NonceStrategy, testing::MockRng};
+
+    let flavor = pedersen_vrf_test_flavor();
+    let mut sk = (*flavor).clone().secretkey_from_seed(&[22; 32]);
+    sk.set_nonce_strategy(NonceStrategy::Hedged);
+    let io = sk.vrf_inout(vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"mock").unwrap());
+    let sign = |sk: &crate::SecretKey<K>| {
+        let mut bytes = Vec::new();
+        sk.sign_thin_vrf_detached(b"mock", &[io]).serialize_compressed(&mut bytes).unwrap();
+        bytes
+    };
+
+    sk.set_mock_rng(Some(MockRng::from_u64(1)));
+    let sig = sign(&sk);
+    assert_eq!(sig, sign(&sk));
+    sk.set_mock_rng(Some(MockRng::from_u64(2)));
+    assert!(sig != sign(&sk));
+    sk.set_mock_rng(None);
+    assert!(sig != sign(&sk));
+
+    // Multi-party nonces always hedge, but honour our `MockRng` too.
+    let key = (*flavor).clone().aggregate_keys(&[sk.to_public()]).unwrap();
+    let inputs = [io.input];
+    let commit = |sk: &crate::SecretKey<K>| sk.commit_musig(&key, &inputs).unwrap().1;
+    sk.set_mock_rng(Some(MockRng::from_u64(1)));
+    assert_eq!(commit(&sk), commit(&sk));
+    sk.set_mock_rng(None);
+    assert!(commit(&sk) != commit(&sk));
+}
+
+#[test]
+fn associated_data() {
+    use crate::AssociatedData;
+
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[15; 32]);
+    let io = sk.vrf_inout(vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"ad").unwrap());
+    let sig = sk.sign_thin_vrf(AssociatedData(b"block 1"), &[io]);
+    flavor.verify_thin_vrf(AssociatedData(b"block 1"), &[io], sk.as_publickey(), &sig.proof).unwrap();
+    flavor.verify_thin_vrf(AssociatedData(b"block 2"), &[io], sk.as_publickey(), &sig.proof).expect_err("WTF?!?");
+    // Raw bytes give a different transcript than `AssociatedData`.
+    flavor.verify_thin_vrf(&b"block 1"[..], &[io], sk.as_publickey(), &sig.proof).expect_err("WTF?!?");
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn arbitrary_instances() {
+    use arbitrary::{Arbitrary, Unstructured};
+    use crate::{SecretKey, VrfInput, VrfSignature, ThinVrfProof, fuzzing::MutatedEncoding};
+
+    let data: Vec<u8> = (0..=255u8).cycle().take(1024).collect();
+    let mut u = Unstructured::new(&data);
+    let sk = SecretKey::<K>::arbitrary(&mut u).unwrap();
+    let input = VrfInput::<K>::arbitrary(&mut u).unwrap();
+    let io = sk.vrf_inout(input);
+    let sig = sk.sign_thin_vrf(b"Fuzz", &[io]);
+    sk.as_publickey().verify_thin_vrf(b"Fuzz", [input], &sig).unwrap();
+
+    let forged = VrfSignature::<ThinVrfProof<K>,1>::arbitrary(&mut u).unwrap();
+    sk.as_publickey().verify_thin_vrf(b"Fuzz", [input], &forged).expect_err("WTF?!?");
+
+    // Corrupted encodings must never panic during deserialization.
+    for _ in 0..16 {
+        let m = MutatedEncoding::<VrfSignature<ThinVrfProof<K>,1>>::arbitrary(&mut u).unwrap();
+        let _ = VrfSignature::<ThinVrfProof<K>,1>::deserialize_compressed(m.bytes.as_slice());
+    }
+}
+
+#[test]
+fn secretkey_constant_time_eq() {
+    use subtle::ConstantTimeEq;
+
+    let sk = crate::SecretKey::<K>::from_seed(&[20; 32]);
+    let same = crate::SecretKey::<K>::from_seed(&[20; 32]);
+    assert!(bool::from(sk.ct_eq(&same)));
+    assert!(sk == sk.clone());
+    assert!(sk != crate::SecretKey::<K>::from_seed(&[21; 32]));
+}
+
+#[test]
+fn secretkey_from_rng_and_uniform_bytes() {
+    use crate::SecretKey;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    let a = SecretKey::<K>::from_rng(&mut StdRng::seed_from_u64(0));
+    let b = SecretKey::<K>::from_rng(&mut StdRng::seed_from_u64(0));
+    assert!(a == b);
+
+    let c = SecretKey::<K>::from_uniform_bytes(&[22; 64]);
+    assert!(c == SecretKey::<K>::from_uniform_bytes(&[22; 64]));
+    assert!(c != SecretKey::<K>::from_uniform_bytes(&[23; 64]));
+    assert!(c.as_publickey() != a.as_publickey());
+}
+
+#[test]
+fn secretkey_with_external_rng() {
+    use crate::SecretKey;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    // Splitting with an external RNG leaves the key itself unchanged.
+    let mut rng = StdRng::seed_from_u64(0);
+    let a = SecretKey::<K>::from_seed_with_rng(&[24; 32], &mut rng);
+    assert!(a == SecretKey::<K>::from_seed(&[24; 32]));
+    assert!(a.as_publickey() == SecretKey::<K>::from_seed(&[24; 32]).as_publickey());
+
+    let flavor = pedersen_vrf_test_flavor();
+    let b = (*flavor).clone().secretkey_from_seed_with_rng(&[24; 32], &mut rng);
+    assert!(b == (*flavor).clone().secretkey_from_seed(&[24; 32]));
+}
+
+#[test]
+fn publickey_preout_map_keys() {
+    use ark_std::collections::BTreeSet;
+    use ark_ec::{AffineRepr, CurveGroup};
+
+    let sks: Vec<_> = (0..4u8).map(|i| crate::SecretKey::<K>::from_seed(&[i; 32])).collect();
+    let pks: Vec<_> = sks.iter().map(|sk| sk.to_public()).collect();
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"map keys").unwrap();
+    let preouts: Vec<_> = sks.iter().map(|sk| sk.vrf_preout(&input)).collect();
+
+    let sorted: BTreeSet<_> = pks.iter().chain(pks.iter()).cloned().collect();
+    assert_eq!(sorted.len(), 4);
+    let sorted: Vec<_> = sorted.into_iter().map(|pk| crate::point_bytes(&crate::mul_by_small_cofactor(pk.0.into_group()).into_affine())).collect();
+    assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+
+    let sorted: BTreeSet<_> = preouts.iter().chain(preouts.iter()).cloned().collect();
+    assert_eq!(sorted.len(), 4);
+    let sorted: Vec<_> = sorted.into_iter().map(|p| crate::point_bytes(&p.0)).collect();
+    assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(input.cmp(&input), core::cmp::Ordering::Equal);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn display_from_str() {
+    use core::str::FromStr;
+    use ark_std::string::ToString;
+    use crate::{PublicKey, VrfSignature, ThinVrfProof};
+
+    let sk = crate::SecretKey::<K>::from_seed(&[20; 32]);
+    let pk = sk.to_public();
+    let s = pk.to_string();
+    assert!(s.starts_with("0x"));
+    assert_eq!(s.len(), 2 + 2 * pk.compressed_size());
+    assert_eq!(PublicKey::<K>::from_str(&s).unwrap(), pk);
+    assert_eq!(PublicKey::<K>::from_str(&s[2..]).unwrap(), pk);
+    PublicKey::<K>::from_str(&s[..s.len()-2]).expect_err("WTF?!?");
+    PublicKey::<K>::from_str(&(s.clone() + "00")).expect_err("WTF?!?");
+    PublicKey::<K>::from_str("0xzz").expect_err("WTF?!?");
+    // We accept only canonical lowercase hex.
+    PublicKey::<K>::from_str(&s.to_uppercase()[2..]).expect_err("WTF?!?");
+    let mixed: ark_std::string::String = s.chars().enumerate()
+        .map(|(i,c)| if i % 2 == 0 { c.to_ascii_uppercase() } else { c })
+        .collect();
+    PublicKey::<K>::from_str(&mixed[2..]).expect_err("WTF?!?");
+    let signed = ark_std::format!("+{}", &s[3..]);
+    PublicKey::<K>::from_str(&signed).expect_err("WTF?!?");
+
+    let io = sk.vrf_inout(vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"hex").unwrap());
+    let sig = sk.sign_thin_vrf(b"Hex", &[io]);
+    let s = sig.to_string();
+    assert_eq!(VrfSignature::<ThinVrfProof<K>,1>::from_str(&s).unwrap(), sig);
+}
+
+#[test]
+fn key_commitment_opening() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[21; 32]);
+    let other = (*flavor).clone().secretkey_from_seed(&[22; 32]);
+    let io = sk.vrf_inout(vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"claim").unwrap());
+    let (sig, blinding) = flavor.sign_pedersen_vrf(b"Claim", &[io], None, &sk);
+    assert!(blinding.is_blinded());
+
+    let compk = sig.as_key_commitment();
+    assert!(compk.0 != sk.as_publickey().0);
+    assert_eq!(&blinding.open(&flavor, compk), sk.as_publickey());
+    assert!(blinding.verify(&flavor, compk, sk.as_publickey()));
+    assert!(! blinding.verify(&flavor, compk, other.as_publickey()));
+}
+
+#[test]
+fn committed_vrf_against_thin_signature() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[23; 32]);
+    let other = (*flavor).clone().secretkey_from_seed(&[24; 32]);
+    let blinding = sk.new_secret_blinding::<1>(&Transcript::new_labeled(b"Registration"));
+    let compk = flavor.compute_blinded_publickey(sk.as_publickey(), &blinding);
+
+    // Thin sign for verifiers who know the public key.
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"committed").unwrap();
+    let ios = [sk.vrf_inout(input)];
+    let thin = sk.sign_thin_vrf_detached(b"Committed", &ios);
+    flavor.verify_thin_vrf(b"Committed", &ios, sk.as_publickey(), &thin).unwrap();
+
+    // Prove the same pre-outputs for verifiers who hold only compk.
+    let proof = flavor.prove_committed_vrf(b"Committed", &ios, &blinding, &sk);
+    flavor.verify_committed_vrf(b"Committed", &ios, &compk, &proof).unwrap();
+    flavor.verify_committed_vrf(b"Other", &ios, &compk, &proof).expect_err("WTF?!?");
+
+    let other_compk = flavor.compute_blinded_publickey(other.as_publickey(), &blinding);
+    flavor.verify_committed_vrf(b"Committed", &ios, &other_compk, &proof).expect_err("WTF?!?");
+    let forged = [other.vrf_inout(input)];
+    flavor.verify_committed_vrf(b"Committed", &forged, &compk, &proof).expect_err("WTF?!?");
+}
+
+#[test]
+fn vrf_sign_request() {
+    let sk = crate::SecretKey::<K>::from_seed(&[7; 32]);
+    let inputs: Vec<_> = (0..3u32)
+        .map(|n| vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",&n.to_le_bytes()[..]).unwrap())
+        .collect();
+
+    let request = sk.vrf_sign().input(inputs[0]).input(inputs[1]).input(inputs[2]).ad(b"meow");
+    assert_eq!(request.ios().len(), 3);
+    let signature = request.thin();
+    assert_eq!(signature.preouts.len(), 3);
+    sk.as_publickey().verify_thin_vrf_vec(crate::AssociatedData(b"meow"), inputs.iter().cloned(), &signature).unwrap();
+    sk.as_publickey().verify_thin_vrf_vec(crate::AssociatedData(b"woof"), inputs.iter().cloned(), &signature)
+        .expect_err("WTF?!?");
+
+    let signature = sk.vrf_sign().input(inputs[0]).sign_with(&sk).unwrap();
+    sk.as_publickey().verify_thin_vrf_vec(crate::AssociatedData(b""), inputs[..1].iter().cloned(), &signature).unwrap();
+}
+
+#[test]
+fn preout_validate() {
+    use ark_ec::AffineRepr;
+    use ark_ff::One;
+
+    let sk = crate::SecretKey::<K>::from_seed(&[9; 32]);
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"validate").unwrap();
+    sk.vrf_preout(&input).validate().unwrap();
+    vrf::VrfPreOut(K::zero()).validate().expect_err("WTF?!?");
+
+    let mut x = curve::Fq::one();
+    let p = loop {
+        if let Some(p) = K::get_point_from_x_unchecked(x, false) {
+            break p;
+        }
+        x += curve::Fq::one();
+    };
+    vrf::VrfPreOut(p).validate().expect_err("WTF?!?");
+}
+
+#[test]
+fn schnorr_sign_message() {
+    let sk = crate::SecretKey::<K>::from_seed(&[11; 32]);
+    let signature = sk.sign_message(b"context", b"message");
+    sk.as_publickey().verify_message(b"context", b"message", &signature).unwrap();
+    sk.as_publickey().verify_message(b"context", b"massage", &signature).expect_err("WTF?!?");
+    sk.as_publickey().verify_message(b"other", b"message", &signature).expect_err("WTF?!?");
+
+    // Thin VRF signatures never verify as Schnorr signatures, nor vice versa.
+    let t = crate::SigningContext::new(b"context").bytes(b"message");
+    let thin = crate::SchnorrSignature(sk.sign_thin_vrf_detached(t.clone(), &[]));
+    sk.as_publickey().verify_message(b"context", b"message", &thin).expect_err("WTF?!?");
+    crate::ThinVrf::<K>::default().verify_thin_vrf(t, &[], sk.as_publickey(), &signature.0).expect_err("WTF?!?");
+
+    let mut bytes = Vec::new();
+    signature.serialize_compressed(&mut bytes).unwrap();
+    assert_eq!(signature, crate::SchnorrSignature::<K>::deserialize_compressed(bytes.as_slice()).unwrap());
+}
+
+#[test]
+fn dleq_prove_verify() {
+    let flavor = pedersen_vrf_test_flavor();
+    let thin: &crate::ThinVrf<K> = &flavor;
+    let sk = thin.clone().secretkey_from_seed(&[13; 32]);
+    let inputs: Vec<_> = (0..3u32)
+        .map(|n| vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",&n.to_le_bytes()[..]).unwrap())
+        .collect();
+    let t = Transcript::new_labeled(b"AD");
+    let (preouts, proof) = crate::dleq_prove(&sk, t.clone(), inputs.iter().cloned());
+    let mut ios: Vec<_> = inputs.iter().zip(&preouts).map(|(i,o)| o.attach_input(*i)).collect();
+    crate::dleq_verify(thin, t.clone(), sk.as_publickey(), &ios, &proof).unwrap();
+    crate::dleq_verify(thin, Transcript::new_labeled(b"other"), sk.as_publickey(), &ios, &proof).expect_err("WTF?!?");
+    crate::dleq_verify(&crate::ThinVrf::<K>::default(), t.clone(), sk.as_publickey(), &ios, &proof).expect_err("WTF?!?");
+
+    // DLEQ proofs never verify as thin VRF signatures upon the same transcript.
+    thin.verify_thin_vrf(t.clone(), &ios, sk.as_publickey(), &proof.0).expect_err("WTF?!?");
+    ios.swap(0, 1);
+    crate::dleq_verify(thin, t, sk.as_publickey(), &ios, &proof).expect_err("WTF?!?");
+}
+
+#[test]
+fn thin_vrf_batch_signing() {
+    let flavor = pedersen_vrf_test_flavor();
+    let mut sk = (*flavor).clone().secretkey_from_seed(&[14; 32]);
+    sk.set_nonce_strategy(crate::NonceStrategy::Deterministic);
+    let ios: Vec<_> = (0..3u32)
+        .map(|n| sk.vrf_inout(vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",&n.to_le_bytes()[..]).unwrap()))
+        .collect();
+    // Enough requests that we share a fixed base table too.
+    let requests: Vec<(Transcript, &[vrf::VrfInOut<K>])> = (0..10)
+        .map(|i| {
+            let mut t = Transcript::new_labeled(b"AD");
+            t.append_u64(i as u64);
+            (t, &ios[..i % (ios.len() + 1)])
+        })
+        .collect();
+
+    for requests in [&requests[..ios.len() + 1], &requests[..]] {
+        let batch = sk.sign_thin_vrf_batch(requests);
+        assert_eq!(batch.len(), requests.len());
+        for ((t, ios), sig) in requests.iter().zip(&batch) {
+            assert_eq!(*sig, sk.sign_thin_vrf_detached(t.clone(), ios));
+            flavor.verify_thin_vrf(t.clone(), ios, sk.as_publickey(), sig).unwrap();
+        }
+    }
+}
+
+#[test]
+fn custom_msm_backend() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use ark_ec::AffineRepr;
+    use crate::MsmBackend;
+
+    #[derive(Debug)]
+    struct CountingMsm(AtomicUsize);
+    impl MsmBackend<K> for CountingMsm {
+        fn msm(&self, bases: &[K], scalars: &[<K as AffineRepr>::ScalarField]) -> <K as AffineRepr>::Group {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            crate::ArkworksMsm.msm(bases, scalars)
+        }
+    }
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[15; 32]);
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"msm").unwrap();
+    let io = sk.vrf_inout(input);
+    let sig = sk.sign_thin_vrf_detached(Transcript::new_labeled(b"AD"), &[io]);
+
+    let counting = alloc::sync::Arc::new(CountingMsm(AtomicUsize::new(0)));
+    let thin = (*flavor).clone().with_msm(counting.clone());
+    assert_eq!(thin, *flavor);
+    // Merging the input with the public key, and then the verification equation.
+    thin.verify_thin_vrf(Transcript::new_labeled(b"AD"), &[io], sk.as_publickey(), &sig).unwrap();
+    assert_eq!(counting.0.swap(0, Ordering::Relaxed), 3);
+
+    // Signers use their flavor's backend too.
+    let sk = thin.clone().secretkey_from_seed(&[15; 32]);
+    sk.sign_thin_vrf_detached(Transcript::new_labeled(b"AD"), &[io]);
+    assert_eq!(counting.0.swap(0, Ordering::Relaxed), 2);
+
+    // Pedersen VRFs use the backend on both curves.
+    let pedersen = flavor.clone().with_msm(counting.clone());
+    assert_eq!(pedersen, flavor);
+    let (sig, _) = pedersen.sign_pedersen_vrf(Transcript::new_labeled(b"AD"), &[io], None, &sk);
+    assert_eq!(counting.0.swap(0, Ordering::Relaxed), 2);
+    pedersen.verify_pedersen_vrf(Transcript::new_labeled(b"AD"), &[io], &sig).unwrap();
+    assert_eq!(counting.0.swap(0, Ordering::Relaxed), 4);
+}
+
+#[test]
+fn canonical_signature_encodings() {
+    use ark_ff::{BigInteger, PrimeField};
+    type Fr = <K as ark_ec::AffineRepr>::ScalarField;
+
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[16; 32]);
+    let input = vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",b"canonical").unwrap();
+    let io = sk.vrf_inout(input);
+    let signature: crate::VrfSignature<crate::ThinVrfProof<K>,1> = sk.sign_thin_vrf(Transcript::new_labeled(b"AD"), &[io]);
+    const L: usize = 48 + 32 + 48;
+    let bytes: [u8; L] = signature.to_bytes();
+    let verify = |bytes: &[u8; L]| -> Result<(),()> {
+        let sig = crate::VrfSignature::<crate::ThinVrfProof<K>,1>::from_bytes(bytes).map_err(|_| ()) ?;
+        let ios = sig.attach_inputs([input]).map_err(|_| ()) ?;
+        flavor.verify_thin_vrf(Transcript::new_labeled(b"AD"), &ios, sk.as_publickey(), &sig.proof).map_err(|_| ()) ?;
+        Ok(())
+    };
+    verify(&bytes).unwrap();
+
+    // No single bit flip yields another valid signature.
+    for i in 0..8 * L {
+        let mut flipped = bytes;
+        flipped[i / 8] ^= 1 << (i % 8);
+        verify(&flipped).expect_err("WTF?!?");
+    }
+
+    // Scalars not reduced modulo the group order never deserialize.
+    let mut s = signature.proof.s.into_bigint();
+    s.add_with_carry(&Fr::MODULUS);
+    let mut unreduced = bytes;
+    unreduced[..32].copy_from_slice(&s.to_bytes_le());
+    crate::VrfSignature::<crate::ThinVrfProof<K>,1>::from_bytes(&unreduced).expect_err("WTF?!?");
+    crate::deserialize_canonical::<crate::ThinVrfProof<K>>(&unreduced[..80]).expect_err("WTF?!?");
+}
+
+#[test]
+fn borrowed_bytes_deserialization() {
+    let flavor = pedersen_vrf_test_flavor();
+    let sk = (*flavor).clone().secretkey_from_seed(&[17; 32]);
+    let inputs: [_; 2] = core::array::from_fn(|i|
+        vrf::ark_hash_to_curve::<K,H2C>(b"VrfIO",&[i as u8]).unwrap()
+    );
+    let ios = inputs.map(|input| sk.vrf_inout(input));
+    let signature = sk.sign_thin_vrf(Transcript::new_labeled(b"AD"), &ios);
+
+    // Gossip messages embed signatures inside larger buffers.
+    let mut message = b"header".to_vec();
+    signature.serialize_compressed(&mut message).unwrap();
+    let sig = crate::VrfSignature::<crate::ThinVrfProof<K>,2>::from_slice(&message[6..]).unwrap();
+    crate::VrfSignature::<crate::ThinVrfProof<K>,2>::from_slice(&message[5..]).expect_err("WTF?!?");
+
+    let preouts = vrf::VrfPreOuts::from(sig.preouts);
+    let ios = preouts.attach_inputs(inputs).unwrap();
+    flavor.verify_thin_vrf(Transcript::new_labeled(b"AD"), &ios, sk.as_publickey(), &sig.proof).unwrap();
+    preouts.attach_inputs(inputs[..1].iter().cloned()).expect_err("WTF?!?");
+
+    let mut bytes = Vec::new();
+    preouts.serialize_compressed(&mut bytes).unwrap();
+    assert_eq!(preouts, crate::deserialize_canonical(&bytes).unwrap());
+}
+
+#[test]
+fn signature_error_codes() {
+    use crate::SignatureError;
+    for code in 0..=u8::MAX {
+        if let Some(e) = SignatureError::from_code(code) {
+            assert_eq!(e.code(), code);
+            assert!(!ark_std::format!("{}", e).is_empty());
+        }
+    }
+    assert_eq!(SignatureError::Invalid.code(), 1);
+    assert_eq!(SignatureError::SrsMismatch.code(), 6);
+    assert_eq!(SignatureError::from_code(0), None);
+
+    let p = <K as ark_ec::AffineRepr>::zero();
+    assert_eq!(vrf::VrfPreOut(p).validate(), Err(SignatureError::InvalidPreOut));
+    assert_eq!(crate::PublicKey(p).validate(), Err(SignatureError::InvalidPublicKey));
+}
@@ -6,27 +6,104 @@
 //! ### Thin VRF routines
 
 use ark_std::{borrow::{Borrow,BorrowMut}, vec::Vec};
+use alloc::sync::Arc;
 use ark_ec::{AffineRepr, CurveGroup};
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize};
+use ark_ff::{Field, PrimeField, Zero};
 
 use crate::{
-    Transcript, IntoTranscript,
+    Transcript, IntoTranscript, SigningContext,
     flavor::{Flavor, InnerFlavor, Witness, Batchable},
     keys::{PublicKey, SecretKey},
     error::{SignatureResult, SignatureError},
     vrf::{self, IntoVrfInput, VrfInput, VrfInOut},
     EcVrfVerifier,EcVrfSigner,
+    msm::{MsmBackend, ArkworksMsm},
+    fixed_base::FixedBaseTable,
 };
 
 
 /// Thin VRF flavor
-#[derive(Debug,Clone,Eq,PartialEq)]
+#[derive(Debug,Clone)]
 pub struct ThinVrf<C: AffineRepr> {
     pub keying_base: C,
+    /// Demand exact verification equations, instead of equality modulo
+    /// small cofactors, see `ThinVrf::strict_cofactor`.
+    strict_cofactor: bool,
+    /// Multi-scalar multiplication backend, see `ThinVrf::with_msm`.
+    msm: Arc<dyn MsmBackend<C>>,
 }
 
+impl<C: AffineRepr> PartialEq for ThinVrf<C> {
+    fn eq(&self, other: &Self) -> bool {
+        // Ignore the backend, which only affects performance.
+        self.keying_base == other.keying_base && self.strict_cofactor == other.strict_cofactor
+    }
+}
+
+impl<C: AffineRepr> Eq for ThinVrf<C> {}
+
 impl<C: AffineRepr> Default for ThinVrf<C> {
     fn default() -> Self {
-        ThinVrf { keying_base: C::generator(), }
+        ThinVrf { keying_base: C::generator(), strict_cofactor: false, msm: Arc::new(ArkworksMsm), }
+    }
+}
+
+impl<C: AffineRepr> ThinVrf<C> {
+    /// Thin VRF flavor with keying base `keying_base`.
+    pub fn new(keying_base: C) -> Self {
+        ThinVrf { keying_base, strict_cofactor: false, msm: Arc::new(ArkworksMsm), }
+    }
+
+    /// Demand exact verification equations, so verifiers reject
+    /// pre-outputs, public keys, or nonces with small torsion components,
+    /// making signatures and pre-outputs non-malleable.
+    /// 
+    /// By default, we accept verification equations which hold modulo
+    /// small cofactors, so small torsion differences pass.  Checked
+    /// deserialization rejects such points anyways, but some protocols
+    /// also handle points deserialized unchecked or computed directly.
+    /// 
+    /// Every verification path, including batch verification and key
+    /// commitment openings, checks that public keys, pre-outputs, and
+    /// key commitments lie in the prime order subgroup, because random
+    /// weights and challenges alone miss torsion components whenever
+    /// they kill them.
+    pub fn strict_cofactor(mut self) -> Self {
+        self.strict_cofactor = true;
+        self
+    }
+
+    /// Whether we demand exact verification equations, as set by
+    /// `ThinVrf::strict_cofactor`.
+    pub fn is_strict_cofactor(&self) -> bool {
+        self.strict_cofactor
+    }
+
+    /// Run our multi-scalar multiplications upon `backend`, like some
+    /// GPU or FPGA accelerator, instead of arkworks.
+    pub fn with_msm(mut self, backend: Arc<dyn MsmBackend<C>>) -> Self {
+        self.msm = backend;
+        self
+    }
+
+    /// Multi-scalar multiplication backend, as set by `ThinVrf::with_msm`.
+    pub fn msm(&self) -> &dyn MsmBackend<C> {
+        &*self.msm
+    }
+
+    /// Check verification equations according to our cofactor strictness.
+    pub(crate) fn zero_mod_cofactor<G: CurveGroup>(&self, z: G) -> bool {
+        if self.strict_cofactor { z.is_zero() } else { crate::zero_mod_small_cofactor(z) }
+    }
+
+    /// Under strict cofactor mode, check `points` lie in the prime order
+    /// subgroup, because exact verification equations alone miss torsion
+    /// components whenever delinearization and challenge weights cancel
+    /// them, which happens with probability about one over the cofactor.
+    pub(crate) fn in_strict_subgroup<G: AffineRepr>(&self, points: impl IntoIterator<Item = G>) -> bool {
+        ! self.strict_cofactor
+        || points.into_iter().all(|p| p.mul_bigint(G::ScalarField::MODULUS).is_zero())
     }
 }
 
@@ -47,7 +124,7 @@ impl<C: AffineRepr> InnerFlavor for ThinVrf<C> {
 
 impl<C: AffineRepr> ThinVrf<C> {
     /// Attach a public key to its base point.
-    fn schnorr_io(&self, public: &PublicKey<C>) -> VrfInOut<C> {
+    pub(crate) fn schnorr_io(&self, public: &PublicKey<C>) -> VrfInOut<C> {
         VrfInOut {
             input: VrfInput( self.keying_base.clone() ),
             preoutput: vrf::VrfPreOut( public.0.clone() ),
@@ -55,24 +132,38 @@ impl<C: AffineRepr> ThinVrf<C> {
     }
 
     /// Merge VRF operation which incorporates the public key.
-    fn thin_vrf_merge<B>(&self, t: &mut Transcript, public: &PublicKey<C>, ios: &[B]) -> VrfInOut<C> 
+    pub(crate) fn thin_vrf_merge<B>(&self, t: &mut Transcript, public: &PublicKey<C>, ios: &[B]) -> VrfInOut<C> 
+    where B: Borrow<VrfInOut<C>>,
+    {
+        let io = self.thin_vrf_merge_append(t, public, ios);
+        if ios.len() == 0 { return io }
+        vrf::vrfs_delinearize( t, ios.iter().map(|io| io.borrow()).chain([ &io ]), self.msm() )
+    }
+
+    /// Append what `thin_vrf_merge` merges to `t`, but return only
+    /// the public key's `VrfInOut`, which `thin_vrf_merge` weights last.
+    fn thin_vrf_merge_append<B>(&self, t: &mut Transcript, public: &PublicKey<C>, ios: &[B]) -> VrfInOut<C> 
     where B: Borrow<VrfInOut<C>>,
     {
         let io = self.schnorr_io(public);
         // Append base too since we're being so polymorphic.
         t.label(b"PublicKey");
         t.append(&io);
-        if ios.len() == 0 { return io }
+        if ios.is_empty() { return io }
         t.label(b"VrfInOuts");
         t.append_u64(ios.len() as u64); 
         t.append_slice(ios);
-        vrf::vrfs_delinearize( t, ios.iter().map(|io| io.borrow()).chain([ &io ]) )
+        io
     }
 }
 
 
 // --- Sign --- //
 
+/// Smallest `sign_thin_vrf_batch` batch for which building a fixed base
+/// table, which costs a few scalar multiplications, pays off.
+const FIXED_BASE_BATCH_SIZE: usize = 8;
+
 impl<K: AffineRepr> SecretKey<K> {
     pub(crate) fn new_thin_witness(&self, t: &Transcript, input: &VrfInput<K>) -> Witness<ThinVrf<K>>
     {
@@ -86,14 +177,80 @@ impl<K: AffineRepr> SecretKey<K> {
     /// 
     /// If `ios = &[]` this reduces to a Schnorr signature.
     pub fn sign_thin_vrf_detached(&self, t: impl IntoTranscript, ios: &[VrfInOut<K>]) -> ThinVrfProof<K>
+    {
+        self.sign_thin_vrf_protocol(b"ThinVRF", t, ios)
+    }
+
+    /// Sign thin VRF signature under some `protocol` label, so Schnorr
+    /// signatures and similar never verify as thin VRF signatures.
+    pub(crate) fn sign_thin_vrf_protocol(
+        &self,
+        protocol: &'static [u8],
+        t: impl IntoTranscript,
+        ios: &[VrfInOut<K>],
+    ) -> ThinVrfProof<K>
     {
         let mut t = t.into_transcript();
         let t = t.borrow_mut();
-        t.label(b"ThinVRF");
+        crate::protocol_label(t, protocol);
         let io = self.thin.thin_vrf_merge(t, self.as_publickey(), ios);
         // Allow derandomization by constructing witness late.
         self.new_thin_witness(t,&io.input).sign_final(t,self)
     }
+
+    /// Sign many thin VRF signatures, one for each transcript and
+    /// `VrfInOut`s in `requests`.
+    ///
+    /// Identical to calling `sign_thin_vrf_detached` for each request,
+    /// but faster.  We compute each nonce commitment `k (sum_i z_i H_i + z G)`
+    /// directly as one MSM over the inputs `H_i` plus one multiplication
+    /// of the keying base `G`, which skips the merged pre-outputs signing
+    /// never uses.  Batches of `FIXED_BASE_BATCH_SIZE` or more share one
+    /// fixed base table for `G`, and we normalize all nonce commitments
+    /// together, which saves one field inversion per signature.
+    pub fn sign_thin_vrf_batch(&self, requests: &[(Transcript, &[VrfInOut<K>])]) -> Vec<ThinVrfProof<K>>
+    {
+        type F<K> = <K as AffineRepr>::ScalarField;
+        let keying_table = (requests.len() >= FIXED_BASE_BATCH_SIZE)
+            .then(|| FixedBaseTable::new(&self.thin.keying_base));
+        let mut ts = Vec::with_capacity(requests.len());
+        let mut ks = Vec::with_capacity(requests.len());
+        let mut rs = Vec::with_capacity(requests.len());
+        for (t, ios) in requests {
+            let mut t = t.clone();
+            crate::protocol_label(&mut t, b"ThinVRF");
+            self.thin.thin_vrf_merge_append(&mut t, self.as_publickey(), ios);
+            let k: F<K> = self.witness(&t,b"thin keying only").read_reduce();
+            // Without `ios`, thin_vrf_merge leaves the keying base unweighted.
+            let (mut kz, r) = if ios.is_empty() {
+                (k, <K as AffineRepr>::Group::zero())
+            } else {
+                let mut kzs: Vec<F<K>> = (0..=ios.len() as u64)
+                    .map(|i| k * vrf::delinearization_weight::<F<K>>(&t, i))
+                    .collect();
+                let kz = kzs.pop().expect("We weight the keying base last");
+                let inputs: Vec<K> = ios.iter().map(|io| io.input.0).collect();
+                let r = self.thin.msm().msm(&inputs, &kzs);
+                kzs.iter_mut().for_each(zeroize::Zeroize::zeroize);
+                (kz, r)
+            };
+            rs.push(r + match &keying_table {
+                Some(table) => table.mul(&kz),
+                None => self.thin.keying_base.mul(kz),
+            });
+            zeroize::Zeroize::zeroize(&mut kz);
+            ks.push(k);
+            ts.push(t);
+        }
+        let rs = <K as AffineRepr>::Group::normalize_batch(&rs);
+        ts.iter_mut().zip(ks.iter_mut()).zip(rs)
+        .map(|((t,k),r)| {
+            // Dropping the witness zeroizes its copy of k.
+            let witness = Witness::<ThinVrf<K>> { r, k: *k };
+            zeroize::Zeroize::zeroize(k);
+            witness.sign_final(t,self)
+        }).collect()
+    }
 }
 
 impl<K: AffineRepr> Witness<ThinVrf<K>> {
@@ -103,13 +260,12 @@ impl<K: AffineRepr> Witness<ThinVrf<K>> {
     pub(crate) fn sign_final(
         self, t: &mut Transcript, secret: &SecretKey<K>
     ) -> Batchable<ThinVrf<K>> {
-        use zeroize::Zeroize;
-        let Witness { r, mut k } = self;
+        // Dropping self zeroizes k
+        let r = self.r;
         t.label(b"Thin R");
         t.append(&r);
         let c: <K as AffineRepr>::ScalarField = t.challenge(b"ThinVrfChallenge").read_reduce();
-        let s = k + secret.key.mul_by_challenge(&c);
-        k.zeroize();  
+        let s = self.k + secret.key.mul_by_challenge(&c);
         Batchable { compk: (), r, s }
         // TODO: Add some verify_final for additional rowhammer defenses?
         // We already hash the public key though, so no issues like Ed25519.
@@ -122,18 +278,6 @@ impl<K: AffineRepr> Witness<ThinVrf<K>> {
 
 // --- Verify --- //
 
-/*
-impl<C: AffineRepr> Valid for Batchable<ThinVrf<C>> {
-    fn check(&self) -> Result<(), SerializationError> {
-        if self.is_on_curve() && self.is_in_correct_subgroup_assuming_on_curve() {
-            Ok(())
-        } else {
-            Err(SerializationError::InvalidData)
-        }
-    }
-}
-*/
-
 impl<K: AffineRepr> ThinVrf<K> {
     pub(crate) fn make_public(
         &self,
@@ -157,9 +301,27 @@ impl<K: AffineRepr> ThinVrf<K> {
         signature: &Batchable<ThinVrf<K>>,
     ) -> SignatureResult<&'a [VrfInOut<K>]>
     {
+        self.verify_thin_vrf_protocol(b"ThinVRF", t, ios, public, signature)
+    }
+
+    /// Verify thin VRF signature under some `protocol` label, like
+    /// `SecretKey::sign_thin_vrf_protocol` creates.
+    pub(crate) fn verify_thin_vrf_protocol<'a>(
+        &self,
+        protocol: &'static [u8],
+        t: impl IntoTranscript,
+        ios: &'a [VrfInOut<K>],
+        public: &PublicKey<K>,
+        signature: &Batchable<ThinVrf<K>>,
+    ) -> SignatureResult<&'a [VrfInOut<K>]>
+    {
+        let points = ios.iter().map(|io| io.preoutput.0).chain([public.0]);
+        if ! self.in_strict_subgroup(points) {
+            return Err(SignatureError::Invalid);
+        }
         let mut t = t.into_transcript();
         let t = t.borrow_mut();
-        t.label(b"ThinVRF");
+        crate::protocol_label(t, protocol);
         // A priori, one expects thin_vrf_merge's msm could be merged
         // into the multiplication by c below, except thin_vrf_merge
         // only needs 128 bit scalar multiplications, so doing this
@@ -171,18 +333,73 @@ impl<K: AffineRepr> ThinVrf<K> {
         t.append(&signature.r);
         let c: <K as AffineRepr>::ScalarField = t.challenge(b"ThinVrfChallenge").read_reduce();
 
-        // TODO: Benchmark
-        // let z = ark_ec::scalar_mul::variable_base::VariableBaseMSM::msm_bigint(
-        //     &[io.input, io.preoutput],
-        //     &[-signature.s, c],
-        // ) + signature.r.into_group();
-        let z = signature.r.into_group() + io.preoutput.0.mul(c) - io.input.0.mul(signature.s);
-        if crate::zero_mod_small_cofactor(z) {
+        // Check R + c O - s I = 0 by one multi-scalar multiplication
+        let z = self.msm().msm(
+            &[signature.r, io.preoutput.0, io.input.0],
+            &[<K as AffineRepr>::ScalarField::ONE, c, -signature.s],
+        );
+        if self.zero_mod_cofactor(z) {
             Ok(ios)
         } else {
             Err(SignatureError::Invalid)
         }
     }
+
+    /// Batch verify thin VRF signatures
+    /// 
+    /// We check one random linear combination of the verification
+    /// equations `R + c O - s I = 0` by a single multi-scalar
+    /// multiplication.  We derive the 128 bit weights from a transcript
+    /// of the entire batch, so signers cannot choose signatures whose
+    /// errors cancel.  We cannot identify the invalid signatures when
+    /// this fails, so invoke `verify_thin_vrf` individually then.
+    pub fn batch_verify_thin_vrf<T,B,P,S,II>(&self, batch: II) -> SignatureResult<()>
+    where
+        T: IntoTranscript,
+        B: Borrow<[VrfInOut<K>]>,
+        P: Borrow<PublicKey<K>>,
+        S: Borrow<Batchable<ThinVrf<K>>>,
+        II: IntoIterator<Item=(T,B,P,S)>,
+    {
+        let mut weights = Transcript::new_labeled(b"ThinVrfBatch");
+        let mut bases = Vec::new();
+        let mut cs = Vec::new();
+        let mut ss = Vec::new();
+        for (t,ios,public,signature) in batch {
+            let signature = signature.borrow();
+            let points = ios.borrow().iter().map(|io| io.preoutput.0).chain([public.borrow().0]);
+            if ! self.in_strict_subgroup(points) {
+                return Err(SignatureError::Invalid);
+            }
+            let mut t = t.into_transcript();
+            let t = t.borrow_mut();
+            crate::protocol_label(t, b"ThinVRF");
+            let io = self.thin_vrf_merge(t, public.borrow(), ios.borrow());
+            t.label(b"Thin R");
+            t.append(&signature.r);
+            let c: <K as AffineRepr>::ScalarField = t.challenge(b"ThinVrfChallenge").read_reduce();
+
+            weights.append(&io);
+            weights.append(&signature.r);
+            weights.append(&signature.s);
+            bases.extend([ signature.r, io.preoutput.0, io.input.0 ]);
+            cs.push(c);
+            ss.push(signature.s);
+        }
+
+        if bases.is_empty() { return Ok(()) }
+        let mut scalars = Vec::with_capacity(bases.len());
+        for (i,(c,s)) in cs.into_iter().zip(ss).enumerate() {
+            let z: <K as AffineRepr>::ScalarField = crate::batch_weight(&weights, i as u64);
+            scalars.extend([ z, z * c, -(z * s) ]);
+        }
+        let z = self.msm().msm(&bases, &scalars);
+        if self.zero_mod_cofactor(z) {
+            Ok(())
+        } else {
+            Err(SignatureError::Invalid)
+        }
+    }
 }
 
 
@@ -245,6 +462,83 @@ impl<K: AffineRepr> PublicKey<K> {
     }
 }
 
+
+// --- Proof-of-possession --- //
+
+/// Protocol label for proofs-of-possession, which replaces the thin
+/// VRF's protocol label, so `sign_thin_vrf_detached` never yields one.
+const POP_LABEL: &[u8] = b"ProofOfPossession";
+
+/// Proof-of-possession of a secret key, which consists of a Schnorr
+/// signature on a fixed transcript, under its own protocol label.
+/// 
+/// Rings should demand these when registering keys, so nobody registers
+/// keys derived from others' keys, ala rogue key attacks.
+pub type ProofOfPossession<K> = ThinVrfProof<K>;
+
+impl<K: AffineRepr> SecretKey<K> {
+    /// Prove we know the secret key for our `PublicKey`.
+    pub fn sign_pop(&self) -> ProofOfPossession<K> {
+        self.sign_thin_vrf_protocol(POP_LABEL, Transcript::new_labeled(POP_LABEL), &[])
+    }
+}
+
+impl<K: AffineRepr> ThinVrf<K> {
+    /// Verify a proof-of-possession for this keying base.
+    pub fn verify_pop(&self, public: &PublicKey<K>, pop: &ProofOfPossession<K>) -> SignatureResult<()> {
+        self.verify_thin_vrf_protocol(POP_LABEL, Transcript::new_labeled(POP_LABEL), &[], public, pop).map(|_| ())
+    }
+}
+
+impl<K: AffineRepr> PublicKey<K> {
+    /// Verify a proof-of-possession for the default keying base.
+    pub fn verify_pop(&self, pop: &ProofOfPossession<K>) -> SignatureResult<()> {
+        ThinVrf::<K>::default().verify_pop(self, pop)
+    }
+}
+
+
+// --- Schnorr signatures --- //
+
+/// Protocol label for plain Schnorr signatures, which replaces the
+/// thin VRF's protocol label, so no thin VRF signature ever verifies
+/// as a Schnorr signature, nor vice versa.
+const SCHNORR_LABEL: &[u8] = b"Schnorr";
+
+/// Plain Schnorr signature on a message, being a thin VRF proof with no
+/// VRF inputs, so our VRF keys double as ordinary signing keys.
+///
+/// We sign the transcript `SigningContext::new(ctx).bytes(msg)`, but
+/// under our own protocol label.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct SchnorrSignature<K: AffineRepr>(pub ThinVrfProof<K>);
+
+impl<K: AffineRepr> SecretKey<K> {
+    /// Sign message `msg` within signing context `ctx`, which should
+    /// identify the application.
+    pub fn sign_message(&self, ctx: &[u8], msg: &[u8]) -> SchnorrSignature<K> {
+        let t = SigningContext::new(ctx).bytes(msg);
+        SchnorrSignature(self.sign_thin_vrf_protocol(SCHNORR_LABEL, t, &[]))
+    }
+}
+
+impl<K: AffineRepr> ThinVrf<K> {
+    /// Verify a Schnorr signature on `msg` within signing context `ctx`
+    /// for this keying base.
+    pub fn verify_message(&self, ctx: &[u8], msg: &[u8], public: &PublicKey<K>, signature: &SchnorrSignature<K>) -> SignatureResult<()> {
+        let t = SigningContext::new(ctx).bytes(msg);
+        self.verify_thin_vrf_protocol(SCHNORR_LABEL, t, &[], public, &signature.0).map(|_| ())
+    }
+}
+
+impl<K: AffineRepr> PublicKey<K> {
+    /// Verify a Schnorr signature on `msg` within signing context `ctx`
+    /// for the default keying base.
+    pub fn verify_message(&self, ctx: &[u8], msg: &[u8], signature: &SchnorrSignature<K>) -> SignatureResult<()> {
+        ThinVrf::<K>::default().verify_message(ctx, msg, self, signature)
+    }
+}
+
 impl<K: AffineRepr> EcVrfSigner for SecretKey<K> {
     type Proof = ThinVrfProof<K>;
     type Error = ();
@@ -259,14 +553,32 @@ impl<K: AffineRepr> EcVrfSigner for SecretKey<K> {
     }
 }
 
+impl<K: AffineRepr> EcVrfSigner for crate::Keypair<K> {
+    type Proof = ThinVrfProof<K>;
+    type Error = ();
+    type Secret = SecretKey<K>;
+    fn vrf_sign_detached(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut<K>]
+    ) -> Result<Self::Proof,()>
+    {
+        Ok(self.secret().sign_thin_vrf_detached(t,ios))
+    }
+}
+
 impl<K: AffineRepr> SecretKey<K> {
+    /// Sign thin VRF signature, with associated data given by `t`,
+    /// like `AssociatedData(block_hash)`.
     pub fn sign_thin_vrf<const N: usize>(
         &self,
         t: impl IntoTranscript,
         ios: &[VrfInOut<K>; N]
     ) -> crate::VrfSignature<ThinVrfProof<K>,N>
     {
-        self.vrf_sign(t,ios).unwrap() // "Infalible"
+        let proof = self.sign_thin_vrf_detached(t,ios);
+        let preouts = core::array::from_fn(|i| ios[i].preoutput);
+        crate::VrfSignature { preouts, proof }
     }
 
     pub fn sign_thin_vrf_one<I,T,F>(&self, input: I, check: F)
@@ -285,6 +597,8 @@ impl<K: AffineRepr> SecretKey<K> {
         ios: &[VrfInOut<K>]
     ) -> crate::VrfSignatureVec<ThinVrfProof<K>>
     {
-        self.vrf_sign_vec(t,ios).unwrap() // "Infalible"
+        let proof = self.sign_thin_vrf_detached(t,ios);
+        let preouts = crate::vrf::collect_preoutputs_vec(ios);
+        crate::VrfSignatureVec { preouts, proof }
     }
 }
@@ -0,0 +1,377 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Threshold thin VRF signing
+//!
+//! We split a thin VRF `SecretKey` into Shamir shares, so any
+//! `threshold` participants create a standard `VrfSignature` for the
+//! group `PublicKey`, while fewer learn nothing about the secret key.
+//!
+//! Signing takes three rounds, with a coordinator who never learns
+//! anything secret:
+//!
+//! 1. Signers send `PreOutShares` for the VRF inputs, which
+//!    `GroupKey::combine_inouts` interpolates into `VrfInOut`s.
+//! 2. Signers send `NonceCommitment`s to two nonces, ala FROST, upon the
+//!    thin VRF's merged input, but keep their `SigningNonces` secret.
+//! 3. Signers send `PartialSignature`s, which `GroupKey::aggregate`
+//!    verifies individually and then sums into the signature.
+//!
+//! FROST style binding factors bind every nonce to the whole signing
+//! session, which prevents Drijvers-style attacks upon concurrent
+//! sessions.  Signers must never reuse `SigningNonces`, so `sign_partial`
+//! consumes them.
+
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, PrimeField};
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize};
+use ark_std::{borrow::BorrowMut, vec::Vec, Zero};
+
+use ark_secret_scalar::{SecretScalar, RngCore, CryptoRng};
+use zeroize::Zeroize;
+
+use crate::{
+    ThinVrf, ThinVrfProof, Transcript, IntoTranscript, PublicKey, SecretKey,
+    VrfSignature,
+    flavor::Batchable,
+    error::{SignatureResult, SignatureError},
+    vrf::{self, VrfInput, VrfPreOut, VrfInOut},
+};
+
+
+type Scalar<K> = <K as AffineRepr>::ScalarField;
+
+/// Participant's share of a threshold secret key.
+pub struct SecretShare<K: AffineRepr> {
+    pub(crate) index: u16,
+    pub(crate) group_public: PublicKey<K>,
+    pub(crate) secret: SecretKey<K>,
+}
+
+/// Group public key along with every participant's verifying share.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct GroupKey<K: AffineRepr> {
+    /// Thin VRF flavor of the group key
+    pub thin: ThinVrf<K>,
+    /// Group public key, for which we produce signatures.
+    pub public: PublicKey<K>,
+    /// Number of participants required for signing
+    pub threshold: u16,
+    /// Verifying shares of participants `1..=n`, in order.
+    pub verifying_shares: Vec<PublicKey<K>>,
+}
+
+/// Signer's pre-output shares for each VRF input, in order.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct PreOutShares<K: AffineRepr> {
+    pub index: u16,
+    pub preouts: Vec<VrfPreOut<K>>,
+}
+
+/// Signer's public commitments to its `SigningNonces`.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct NonceCommitment<K: AffineRepr> {
+    pub index: u16,
+    pub hiding: K,
+    pub binding: K,
+}
+
+/// Signer's secret nonces, which must be used only once.
+pub struct SigningNonces<K: AffineRepr> {
+    hiding: Scalar<K>,
+    binding: Scalar<K>,
+}
+
+impl<K: AffineRepr> Zeroize for SigningNonces<K> {
+    fn zeroize(&mut self) {
+        self.hiding.zeroize();
+        self.binding.zeroize();
+    }
+}
+impl<K: AffineRepr> Drop for SigningNonces<K> {
+    fn drop(&mut self) { self.zeroize() }
+}
+
+/// Signer's share of the thin VRF signature's response.
+#[derive(Debug,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)]
+pub struct PartialSignature<K: AffineRepr> {
+    pub index: u16,
+    pub s: Scalar<K>,
+}
+
+
+/// Lagrange coefficient at zero for `index` among `signers`.
+fn lagrange_coefficient<F: PrimeField>(index: u16, signers: impl Iterator<Item=u16>) -> F {
+    let x_i = F::from(index as u64);
+    let mut numerator = F::ONE;
+    let mut denominator = F::ONE;
+    for j in signers.filter(|j| *j != index) {
+        let x_j = F::from(j as u64);
+        numerator *= x_j;
+        denominator *= x_j - x_i;
+    }
+    numerator * denominator.inverse().expect("Signer indices are distinct")
+}
+
+/// Check signer indices are nonzero, strictly increasing, at most `n`,
+/// and at least `threshold` many.
+fn check_signers(threshold: u16, n: usize, signers: impl Iterator<Item=u16>) -> SignatureResult<()> {
+    let mut count = 0;
+    let mut last = 0;
+    for i in signers {
+        if i <= last || i as usize > n { return Err(SignatureError::Invalid); }
+        last = i;
+        count += 1;
+    }
+    if count < threshold { return Err(SignatureError::Invalid); }
+    Ok(())
+}
+
+/// Transcript state and merged `VrfInOut` shared by rounds two and three.
+struct Merged<K: AffineRepr> {
+    t: Transcript,
+    io: VrfInOut<K>,
+}
+
+impl<K: AffineRepr> Merged<K> {
+    /// Begin exactly like `SecretKey::sign_thin_vrf_detached`.
+    fn new(thin: &ThinVrf<K>, t: impl IntoTranscript, public: &PublicKey<K>, ios: &[VrfInOut<K>]) -> Self {
+        let mut t = t.into_transcript();
+        let t = t.borrow_mut();
+        crate::protocol_label(t, b"ThinVRF");
+        let io = thin.thin_vrf_merge(t, public, ios);
+        Merged { t: t.clone(), io }
+    }
+
+    /// Merged pre-output share for one signer, whose delinearization
+    /// matches the merged `VrfInOut`.
+    fn share(&self, thin: &ThinVrf<K>, verifying_share: &PublicKey<K>, inputs: &[VrfInput<K>], preouts: &[VrfPreOut<K>]) -> VrfPreOut<K> {
+        let schnorr = thin.schnorr_io(verifying_share);
+        if inputs.is_empty() { return schnorr.preoutput; }
+        let ios: Vec<VrfInOut<K>> = inputs.iter().zip(preouts)
+            .map(|(input,preoutput)| VrfInOut { input: *input, preoutput: *preoutput })
+            .collect();
+        vrf::vrfs_delinearize(&self.t, ios.iter().chain([ &schnorr ]), thin.msm()).preoutput
+    }
+
+    /// Binding factors for every `NonceCommitment`, in order.
+    fn binding_factors(&self, commitments: &[NonceCommitment<K>]) -> Vec<Scalar<K>> {
+        let mut t = self.t.fork(b"ThresholdBinding");
+        t.append_slice::<NonceCommitment<K>,_>(commitments);
+        commitments.iter().map(|c| {
+            let mut t = t.fork(b"BindingFactor");
+            t.append_u64(c.index as u64);
+            t.challenge(b"rho").read_reduce()
+        }).collect()
+    }
+
+    /// Group commitment `R` and thin VRF challenge `c`.
+    fn challenge(&self, commitments: &[NonceCommitment<K>], rhos: &[Scalar<K>]) -> (K, Scalar<K>) {
+        let r = commitments.iter().zip(rhos)
+            .fold(<K as AffineRepr>::Group::zero(), |r, (c,rho)| r + c.hiding + c.binding * *rho)
+            .into_affine();
+        let mut t = self.t.fork(b"Thin R");
+        t.append(&r);
+        (r, t.challenge(b"ThinVrfChallenge").read_reduce())
+    }
+}
+
+
+impl<K: AffineRepr> SecretKey<K> {
+    /// Split this secret key into `n` shares, any `threshold` of which
+    /// sign for our `PublicKey`.
+    ///
+    /// We act as a trusted dealer here, so callers should erase this
+    /// `SecretKey` after distributing the shares.
+    pub fn split_threshold(&self, threshold: u16, n: u16) -> (GroupKey<K>, Vec<SecretShare<K>>) {
+        assert!(0 < threshold && threshold <= n, "Threshold must lie between 1 and n");
+        let mut t = Transcript::new_labeled(b"ThresholdDealer");
+        t.append_u64(threshold as u64);
+        t.append_u64(n as u64);
+        let mut reader = self.witness(&t, b"coefficients");
+        let mut coefficients: Vec<Scalar<K>> = (1..threshold).map(|_| reader.read_reduce()).collect();
+
+        let shares: Vec<SecretShare<K>> = (1..=n).map(|index| {
+            // Evaluate our polynomial without its constant term by Horner's rule.
+            let x = Scalar::<K>::from(index as u64);
+            let mut y = coefficients.iter().rev()
+                .fold(Scalar::<K>::zero(), |y, a| (y + a) * x);
+            let key = &self.key + &SecretScalar::from_scalar(y);
+            y.zeroize();
+            let mut nonce_seed = reader.read_byte_array();
+            let secret = self.thin.clone().secretkey_from_scalar(key, nonce_seed);
+            nonce_seed.zeroize();
+            SecretShare { index, group_public: self.to_public(), secret }
+        }).collect();
+        for a in coefficients.iter_mut() { a.zeroize(); }
+
+        let group = GroupKey {
+            thin: self.thin.clone(),
+            public: self.to_public(),
+            threshold,
+            verifying_shares: shares.iter().map(|s| s.secret.to_public()).collect(),
+        };
+        (group, shares)
+    }
+}
+
+impl<K: AffineRepr> SecretShare<K> {
+    /// Participant index, starting from one.
+    pub fn index(&self) -> u16 { self.index }
+
+    /// Group public key for which we sign.
+    pub fn group_public(&self) -> &PublicKey<K> { &self.group_public }
+
+    /// Our verifying share, aka the public key of our share.
+    pub fn verifying_share(&self) -> &PublicKey<K> { self.secret.as_publickey() }
+
+    /// Round one: Compute our pre-output shares for these VRF inputs.
+    pub fn preout_shares(&self, inputs: &[VrfInput<K>]) -> PreOutShares<K> {
+        let preouts = inputs.iter().map(|input| self.secret.vrf_preout(input)).collect();
+        PreOutShares { index: self.index, preouts }
+    }
+
+    /// Round two: Commit to fresh nonces upon the merged input of
+    /// the `VrfInOut`s from `GroupKey::combine_inouts`.
+    #[cfg(feature = "getrandom")]
+    pub fn commit(&self, t: impl IntoTranscript, ios: &[VrfInOut<K>]) -> (SigningNonces<K>, NonceCommitment<K>) {
+        #[cfg(feature = "testing")]
+        if let Some(mut rng) = self.secret.mock_rng() {
+            return self.commit_with_rng(t, ios, &mut rng);
+        }
+        self.commit_with_rng(t, ios, &mut ark_secret_scalar::getrandom_or_panic())
+    }
+
+    /// Round two: Commit to fresh nonces upon the merged input of
+    /// the `VrfInOut`s, hedged using a user supplied RNG.
+    pub fn commit_with_rng<R: RngCore+CryptoRng>(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut<K>],
+        rng: &mut R,
+    ) -> (SigningNonces<K>, NonceCommitment<K>)
+    {
+        let merged = Merged::new(&self.secret.thin, t, &self.group_public, ios);
+        let mut t = merged.t.fork(b"ThresholdNonces");
+        t.append_u64(self.index as u64);
+        t.append(&self.secret.nonce_seed[..]);
+        // Always hedge here, since deterministic nonces become insecure
+        // whenever other signers' commitments change.
+        let mut reader = t.fork(b"witness").witness(rng);
+        let nonces = SigningNonces::<K> { hiding: reader.read_reduce(), binding: reader.read_reduce() };
+        let m = merged.io.input.0;
+        let commitment = NonceCommitment {
+            index: self.index,
+            hiding: (m * nonces.hiding).into_affine(),
+            binding: (m * nonces.binding).into_affine(),
+        };
+        (nonces, commitment)
+    }
+
+    /// Round three: Respond using our nonces from `commit`, given every
+    /// signer's `NonceCommitment`, sorted by index.
+    pub fn sign_partial(
+        &self,
+        nonces: SigningNonces<K>,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut<K>],
+        commitments: &[NonceCommitment<K>],
+    ) -> SignatureResult<PartialSignature<K>>
+    {
+        let signers = || commitments.iter().map(|c| c.index);
+        check_signers(1, usize::from(u16::MAX), signers()) ?;
+        let position = commitments.iter().position(|c| c.index == self.index)
+            .ok_or(SignatureError::Invalid) ?;
+        let merged = Merged::new(&self.secret.thin, t, &self.group_public, ios);
+        let m = merged.io.input.0;
+        let ours = &commitments[position];
+        if ours.hiding != (m * nonces.hiding).into_affine() || ours.binding != (m * nonces.binding).into_affine() {
+            return Err(SignatureError::Invalid);
+        }
+        let rhos = merged.binding_factors(commitments);
+        let (_r, c) = merged.challenge(commitments, &rhos);
+        let lambda: Scalar<K> = lagrange_coefficient(self.index, signers());
+        let s = nonces.hiding + nonces.binding * rhos[position]
+            + self.secret.key.mul_by_challenge(&(c * lambda));
+        Ok(PartialSignature { index: self.index, s })
+    }
+}
+
+impl<K: AffineRepr> GroupKey<K> {
+    fn verifying_share(&self, index: u16) -> SignatureResult<&PublicKey<K>> {
+        self.verifying_shares.get(usize::from(index).wrapping_sub(1))
+            .ok_or(SignatureError::Invalid)
+    }
+
+    fn find_preouts(preout_shares: &[PreOutShares<K>], index: u16, n: usize) -> SignatureResult<&[VrfPreOut<K>]> {
+        preout_shares.iter().find(|p| p.index == index)
+            .filter(|p| p.preouts.len() == n)
+            .map(|p| p.preouts.as_slice())
+            .ok_or(SignatureError::WrongInputCount)
+    }
+
+    /// Interpolate signers' `PreOutShares`, sorted by index, into
+    /// the group's `VrfInOut`s.
+    ///
+    /// We cannot check pre-output shares here, but `aggregate` rejects
+    /// sessions with bad shares.
+    pub fn combine_inouts(&self, inputs: &[VrfInput<K>], preout_shares: &[PreOutShares<K>]) -> SignatureResult<Vec<VrfInOut<K>>> {
+        let signers = || preout_shares.iter().map(|p| p.index);
+        check_signers(self.threshold, self.verifying_shares.len(), signers()) ?;
+        if preout_shares.iter().any(|p| p.preouts.len() != inputs.len()) {
+            return Err(SignatureError::WrongInputCount);
+        }
+        let lambdas: Vec<Scalar<K>> = signers().map(|i| lagrange_coefficient(i, signers())).collect();
+        Ok(inputs.iter().enumerate().map(|(j,input)| {
+            let bases: Vec<K> = preout_shares.iter().map(|p| p.preouts[j].0).collect();
+            let preout = self.thin.msm().msm(&bases, &lambdas);
+            VrfInOut { input: *input, preoutput: VrfPreOut(preout.into_affine()) }
+        }).collect())
+    }
+
+    /// Verify every `PartialSignature` and combine them into a thin
+    /// VRF signature for the group public key.
+    ///
+    /// We require `preout_shares` from every signer in `commitments`,
+    /// and the `PartialSignature`s sorted like `commitments`.
+    pub fn aggregate<const N: usize>(
+        &self,
+        t: impl IntoTranscript,
+        ios: &[VrfInOut<K>; N],
+        preout_shares: &[PreOutShares<K>],
+        commitments: &[NonceCommitment<K>],
+        partials: &[PartialSignature<K>],
+    ) -> SignatureResult<VrfSignature<ThinVrfProof<K>,N>>
+    {
+        let signers = || commitments.iter().map(|c| c.index);
+        check_signers(self.threshold, self.verifying_shares.len(), signers()) ?;
+        if partials.len() != commitments.len() {
+            return Err(SignatureError::Invalid);
+        }
+        let merged = Merged::new(&self.thin, t, &self.public, ios);
+        let rhos = merged.binding_factors(commitments);
+        let (r, c) = merged.challenge(commitments, &rhos);
+        let inputs: Vec<VrfInput<K>> = ios.iter().map(|io| io.input).collect();
+
+        let mut s = Scalar::<K>::zero();
+        for ((commitment, rho), partial) in commitments.iter().zip(&rhos).zip(partials) {
+            if partial.index != commitment.index {
+                return Err(SignatureError::Invalid);
+            }
+            let preouts = Self::find_preouts(preout_shares, partial.index, N) ?;
+            let share = merged.share(&self.thin, self.verifying_share(partial.index) ?, &inputs, preouts);
+            let lambda: Scalar<K> = lagrange_coefficient(partial.index, signers());
+            // Check s_i M = D_i + rho_i E_i + c lambda_i Y_i
+            let z = self.thin.msm().msm(
+                &[commitment.hiding, commitment.binding, share.0, merged.io.input.0],
+                &[Scalar::<K>::ONE, *rho, c * lambda, -partial.s],
+            );
+            if ! self.thin.zero_mod_cofactor(z) {
+                return Err(SignatureError::Invalid);
+            }
+            s += partial.s;
+        }
+
+        let preouts = core::array::from_fn(|i| ios[i].preoutput);
+        Ok(VrfSignature { preouts, proof: Batchable { compk: (), r, s } })
+    }
+}
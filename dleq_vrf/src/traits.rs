@@ -19,13 +19,13 @@
 //! TODO:  We have not evaluated if these traits mesh well with
 //! batch verification or threshold multi-signed VRFs.
 //! 
-//! TODO:  `IntoTranscript` might not correctly abstract signing for
-//! a remote signer.
+//! We abstract hardware or remote signers by `VrfSigner`, which takes
+//! a concrete `Transcript` instead of an `IntoTranscript`.
 
 
 use ark_std::{borrow::Borrow, fmt, vec::Vec};
 
-use ark_serialize::{CanonicalSerialize,CanonicalDeserialize}; // Valid
+use ark_serialize::{CanonicalSerialize,CanonicalDeserialize,Compress,Read,SerializationError,Valid,Validate};
 use ark_ec::{AffineRepr,CurveGroup};
 
 pub use crate::{
@@ -93,12 +93,41 @@ pub type IO<P> = VrfInOut<EC<P>>;
 
 
 /// VRF signature with variable number of input-output pairs
-#[derive(CanonicalSerialize,CanonicalDeserialize)]
+#[derive(CanonicalSerialize)]
 pub struct VrfSignature<P: EcVrfProof, const N: usize> {
     pub proof: P,
     pub preouts: [PreOut<P>; N],
 }
 
+impl<P: EcVrfProof, const N: usize> Valid for VrfSignature<P,N> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.proof.check() ?;
+        PreOut::<P>::batch_check(self.preouts.iter())
+    }
+}
+
+// We avoid arkworks' `[T; N]` deserialization because it unwraps
+// errors from its elements, so malformed pre-outputs would panic.
+impl<P: EcVrfProof, const N: usize> CanonicalDeserialize for VrfSignature<P,N> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let proof = P::deserialize_with_mode(&mut reader, compress, validate) ?;
+        let mut preouts = Vec::with_capacity(N);
+        for _ in 0..N {
+            preouts.push( PreOut::<P>::deserialize_with_mode(&mut reader, compress, Validate::No) ? );
+        }
+        let preouts: [PreOut<P>; N] = preouts.try_into()
+            .map_err(|_| SerializationError::InvalidData) ?;
+        if let Validate::Yes = validate {
+            PreOut::<P>::batch_check(preouts.iter()) ?;
+        }
+        Ok(VrfSignature { proof, preouts })
+    }
+}
+
 impl<P: EcVrfProof, const N: usize> Clone for VrfSignature<P,N> {
     fn clone(&self) -> Self {
         VrfSignature {
@@ -123,15 +152,34 @@ impl<P: EcVrfProof, const N: usize> PartialEq for VrfSignature<P,N> {
 }
 
 impl<P: EcVrfProof, const N: usize> VrfSignature<P,N> {
+    /// Serialize compressed into a fixed length byte array, avoiding allocation.
+    /// 
+    /// Panics unless `L` equals our compressed size.
+    pub fn to_bytes<const L: usize>(&self) -> [u8; L] {
+        crate::to_bytes_array(self)
+    }
+
+    /// Deserialize compressed from a fixed length byte array,
+    /// rejecting any unused trailing bytes, or non-canonical encodings.
+    pub fn from_bytes<const L: usize>(bytes: &[u8; L]) -> Result<Self, ark_serialize::SerializationError> {
+        crate::deserialize_canonical(bytes.as_slice())
+    }
+
+    /// Deserialize compressed from borrowed bytes without copying,
+    /// like some slice of a larger gossip message, rejecting any
+    /// unused trailing bytes, or non-canonical encodings.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, ark_serialize::SerializationError> {
+        crate::deserialize_canonical(bytes)
+    }
+
+    /// Attach VRF inputs to our pre-outputs, failing with
+    /// `WrongInputCount` unless we have exactly `N` inputs.
     pub fn attach_inputs(
         &self,
         inputs: impl IntoIterator<Item = impl IntoVrfInput<P::H>>
-    ) -> [IO<P>; N]
+    ) -> Result<[IO<P>; N],error::SignatureError>
     {
-        let mut inputs = inputs.into_iter();
-        let mut preouts = self.preouts.iter().cloned();
-        let cb = |_| preouts.next().unwrap().attach_input(inputs.next().unwrap());
-        core::array::from_fn(cb)
+        crate::vrf::attach_inputs_array(&self.preouts, inputs)
     }
 
     pub fn vrf_verify<V>( 
@@ -177,15 +225,32 @@ impl<P: EcVrfProof> PartialEq for VrfSignatureVec<P> {
     }
 }
 
+impl<P: EcVrfProof, const N: usize> From<VrfSignature<P,N>> for VrfSignatureVec<P> {
+    fn from(signature: VrfSignature<P,N>) -> Self {
+        let VrfSignature { proof, preouts } = signature;
+        VrfSignatureVec { proof, preouts: preouts.into() }
+    }
+}
+
+/// Fails unless the signature has exactly `N` pre-outputs.
+impl<P: EcVrfProof, const N: usize> TryFrom<VrfSignatureVec<P>> for VrfSignature<P,N> {
+    type Error = error::SignatureError;
+    fn try_from(signature: VrfSignatureVec<P>) -> Result<Self,Self::Error> {
+        let VrfSignatureVec { proof, preouts } = signature;
+        let preouts = preouts.try_into().map_err(|_| error::SignatureError::WrongInputCount) ?;
+        Ok(VrfSignature { proof, preouts })
+    }
+}
+
 impl<P: EcVrfProof> VrfSignatureVec<P> {
+    /// Attach VRF inputs to our pre-outputs, failing with
+    /// `WrongInputCount` unless we have exactly one input per pre-output.
     pub fn attach_inputs(
         &self,
         inputs: impl IntoIterator<Item = impl IntoVrfInput<P::H>>
-    ) -> Vec<IO<P>>
+    ) -> Result<Vec<IO<P>>,error::SignatureError>
     {
-        self.preouts.iter().zip(inputs)
-        .map(|(preout,input)| preout.attach_input(input))
-        .collect()
+        crate::vrf::attach_inputs_vec(&self.preouts, inputs)
     }
 
     pub fn vrf_verify<V>(
@@ -226,7 +291,7 @@ pub trait EcVrfVerifier {
         signature: &VrfSignature<Self::Proof,N>,
     ) -> Result<[IO<Self::Proof>; N],Self::Error>
     {
-        let ios: [IO<Self::Proof>; N] = signature.attach_inputs(inputs);
+        let ios: [IO<Self::Proof>; N] = signature.attach_inputs(inputs) ?;
         self.vrf_verify_detached(t,ios.as_slice(),&signature.proof) ?;
         Ok(ios)
     }
@@ -238,7 +303,7 @@ pub trait EcVrfVerifier {
         signature: &VrfSignatureVec<Self::Proof>,
     ) -> Result<Vec<IO<Self::Proof>>,Self::Error>
     {
-        let ios = signature.attach_inputs(inputs);
+        let ios = signature.attach_inputs(inputs) ?;
         self.vrf_verify_detached(t,ios.as_slice(),&signature.proof) ?;
         Ok(ios)
     }
@@ -320,3 +385,172 @@ pub trait EcVrfSigner: Borrow<Self::Secret> {
     }
 }
 
+
+
+/// Builder for VRF signatures, which maps inputs to `VrfInOut`s using
+/// our secret key and collects their pre-outputs, like
+/// `secret.vrf_sign().input(msg).ad(b"..").thin()`.
+/// 
+/// Associated data defaults to empty `AssociatedData`.
+pub struct VrfSignRequest<'a, K: AffineRepr> {
+    secret: &'a SecretKey<K>,
+    t: Option<crate::Transcript>,
+    ios: Vec<VrfInOut<K>>,
+}
+
+impl<K: AffineRepr> SecretKey<K> {
+    /// Begin building a VRF signature.
+    pub fn vrf_sign(&self) -> VrfSignRequest<'_, K> {
+        VrfSignRequest { secret: self, t: None, ios: Vec::new() }
+    }
+}
+
+impl<'a, K: AffineRepr> VrfSignRequest<'a, K> {
+    /// Add one VRF input.
+    pub fn input(mut self, input: impl IntoVrfInput<K>) -> Self {
+        self.ios.push(self.secret.vrf_inout(input));
+        self
+    }
+
+    /// Sign associated data `ad` via `AssociatedData`.
+    pub fn ad(mut self, ad: &[u8]) -> Self {
+        self.t = Some(crate::AssociatedData(ad).into_transcript());
+        self
+    }
+
+    /// Sign this transcript, replacing any associated data.
+    pub fn transcript(mut self, t: crate::Transcript) -> Self {
+        self.t = Some(t);
+        self
+    }
+
+    /// Input-output pairs so far, from which callers could compute
+    /// VRF outputs before deciding whether to sign.
+    pub fn ios(&self) -> &[VrfInOut<K>] {
+        &self.ios
+    }
+
+    fn take_transcript(&mut self) -> crate::Transcript {
+        self.t.take().unwrap_or_else(|| crate::AssociatedData(b"").into_transcript())
+    }
+
+    /// Thin VRF signature on our inputs.
+    pub fn thin(mut self) -> VrfSignatureVec<crate::ThinVrfProof<K>> {
+        let t = self.take_transcript();
+        self.secret.sign_thin_vrf_vec(t, &self.ios)
+    }
+
+    /// Sign our inputs with another signer using the same secret key,
+    /// like bandersnatch's `RingProver`.
+    pub fn sign_with<S>(mut self, signer: &S) -> Result<VrfSignatureVec<S::Proof>,S::Error>
+    where S: EcVrfSigner<Secret = SecretKey<K>>, S::Proof: EcVrfProof<H = K>,
+    {
+        debug_assert!(Borrow::<SecretKey<K>>::borrow(signer).as_publickey() == self.secret.as_publickey());
+        let t = self.take_transcript();
+        signer.vrf_sign_vec(t, &self.ios)
+    }
+}
+
+
+/// VRF signer whose secret key may live elsewhere, like inside a
+/// hardware security module or a remote signing daemon.
+/// 
+/// `EcVrfSigner` requires borrowing the secret key, which such signers
+/// cannot do, so instead they compute pre-outputs themselves.  We pass
+/// a concrete `Transcript`, not an `IntoTranscript`, so signers could
+/// ship its state elsewhere.  Any `EcVrfSigner`, like our in-memory
+/// `SecretKey` or bandersnatch's `RingProver`, implements this trait.
+pub trait VrfSigner {
+    /// Detached signature aka proof type created by the VRF
+    type Proof: EcVrfProof;
+
+    /// Signer failures, like hardware or network errors.
+    type Error;
+
+    /// Create an `InputOutput` using the signer's secret key.
+    fn inout(&self, input: VrfInput<EC<Self::Proof>>) -> Result<IO<Self::Proof>,Self::Error>;
+
+    fn sign_detached(
+        &self,
+        t: crate::Transcript,
+        ios: &[IO<Self::Proof>]
+    ) -> Result<Self::Proof,Self::Error>;
+
+    /// VRF signature for a fixed number of input-output pairs
+    fn sign<const N: usize>(
+        &self,
+        t: crate::Transcript,
+        ios: &[IO<Self::Proof>; N]
+    ) -> Result<VrfSignature<Self::Proof,N>,Self::Error>
+    {
+        let proof = self.sign_detached(t,ios) ?;
+        let preouts = core::array::from_fn(|i| ios[i].preoutput);
+        Ok(VrfSignature { preouts, proof })
+    }
+}
+
+impl<S: EcVrfSigner> VrfSigner for S {
+    type Proof = S::Proof;
+    type Error = S::Error;
+
+    fn inout(&self, input: VrfInput<EC<Self::Proof>>) -> Result<IO<Self::Proof>,Self::Error> {
+        Ok(EcVrfSecret::vrf_inout(Borrow::<S::Secret>::borrow(self),input))
+    }
+
+    fn sign_detached(
+        &self,
+        t: crate::Transcript,
+        ios: &[IO<Self::Proof>]
+    ) -> Result<Self::Proof,Self::Error>
+    {
+        EcVrfSigner::vrf_sign_detached(self,t,ios)
+    }
+}
+
+/// Asynchronous `VrfSigner`, for remote signers reached over a network.
+/// 
+/// Our futures are `Send`, so multi-threaded executors like tokio
+/// could spawn tasks awaiting them.
+/// 
+/// Any `VrfSigner` with `Send` proofs and errors implements this trait
+/// by completing immediately.
+#[cfg(feature = "async")]
+pub trait AsyncVrfSigner {
+    /// Detached signature aka proof type created by the VRF
+    type Proof: EcVrfProof;
+
+    /// Signer failures, like hardware or network errors.
+    type Error;
+
+    fn inout_async(&self, input: VrfInput<EC<Self::Proof>>)
+     -> impl core::future::Future<Output = Result<IO<Self::Proof>,Self::Error>> + Send;
+
+    fn sign_detached_async(
+        &self,
+        t: crate::Transcript,
+        ios: &[IO<Self::Proof>]
+    ) -> impl core::future::Future<Output = Result<Self::Proof,Self::Error>> + Send;
+}
+
+#[cfg(feature = "async")]
+impl<S: VrfSigner> AsyncVrfSigner for S
+where S::Proof: Send, S::Error: Send,
+{
+    type Proof = S::Proof;
+    type Error = S::Error;
+
+    fn inout_async(&self, input: VrfInput<EC<Self::Proof>>)
+     -> impl core::future::Future<Output = Result<IO<Self::Proof>,Self::Error>> + Send
+    {
+        core::future::ready(self.inout(input))
+    }
+
+    fn sign_detached_async(
+        &self,
+        t: crate::Transcript,
+        ios: &[IO<Self::Proof>]
+    ) -> impl core::future::Future<Output = Result<Self::Proof,Self::Error>> + Send
+    {
+        core::future::ready(self.sign_detached(t,ios))
+    }
+}
@@ -10,10 +10,12 @@
 //! with "soft key derivation" ala BIP32.
 
 use ark_ec::{AffineRepr, CurveGroup, hashing::{HashToCurve,HashToCurveError}};
+use ark_ff::PrimeField;
 use ark_serialize::{CanonicalSerialize,CanonicalDeserialize};
 use ark_std::{borrow::BorrowMut, iter::IntoIterator, vec::Vec};
 
-use crate::{Transcript,IntoTranscript,transcript::AsLabel,SecretKey};
+use crate::{Transcript,IntoTranscript,transcript::AsLabel,SecretKey,SignatureResult,SignatureError};
+use crate::msm::{MsmBackend, ArkworksMsm};
 
 
 use core::borrow::{Borrow}; // BorrowMut
@@ -34,6 +36,13 @@ impl<C: AffineRepr> IntoVrfInput<C> for VrfInput<C> {
     fn into_vrf_input(self) -> VrfInput<C> { self }
 }
 
+/// Already computed `VrfInput`s by reference, so verifiers could pass
+/// `inputs.iter()` without cloning.
+impl<C: AffineRepr> IntoVrfInput<C> for &VrfInput<C> {
+    #[inline(always)]
+    fn into_vrf_input(self) -> VrfInput<C> { *self }
+}
+
 /*
 impl<T: IntoTranscript,C: AffineRepr> IntoVrfInput<C> for T {
     /// Create a new VRF input from a `Transcript`.
@@ -54,6 +63,15 @@ impl<T: IntoTranscript,C: AffineRepr> IntoVrfInput<C> for T {
 }
 */
 
+/// Hash-to-curve for `#[derive(VrfInput)]`, which curve crates implement
+/// upon their own marker types, like `bandersnatch_vrfs::Elligator2`.
+pub trait HashToVrfInput {
+    type Affine: AffineRepr;
+
+    /// Hash `message` to a VRF input within application domain `domain`.
+    fn hash_to_vrf_input(domain: &[u8], message: &[u8]) -> VrfInput<Self::Affine>;
+}
+
 pub fn ark_hash_to_curve<C,H2C>(domain: impl AsLabel, message: &[u8]) -> Result<VrfInput<C>,HashToCurveError>
 where C: AffineRepr, H2C: HashToCurve<<C as AffineRepr>::Group>,
 {
@@ -78,10 +96,22 @@ where C: AffineRepr, H2C: HashToCurve<<C as AffineRepr>::Group>,
 /// As a defense in depth, we suggest thin VRF usages hash their
 /// public, given some broken applications might do soft derivations
 /// anyways.
-#[derive(Debug,Copy,Clone,CanonicalSerialize)] // CanonicalDeserialize, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Hash,CanonicalSerialize)] // CanonicalDeserialize, Default
 #[repr(transparent)]
 pub struct VrfInput<C: AffineRepr>(pub C);
 
+/// Sort by canonical compressed encoding, consistent with `Eq`.
+impl<C: AffineRepr> PartialOrd for VrfInput<C> {
+    fn partial_cmp(&self, other: &VrfInput<C>) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<C: AffineRepr> Ord for VrfInput<C> {
+    fn cmp(&self, other: &VrfInput<C>) -> core::cmp::Ordering {
+        crate::point_bytes(&self.0).cmp(&crate::point_bytes(&other.0))
+    }
+}
+
 impl<K: AffineRepr> SecretKey<K> {
     /// Compute VRF pre-output from secret key and input.
     pub fn vrf_preout<H>(&self, input: &VrfInput<H>) -> VrfPreOut<H> 
@@ -110,11 +140,42 @@ impl<K: AffineRepr> SecretKey<K> {
 
 
 /// VRF pre-output, possibly unverified.
-#[derive(Debug,Copy,Clone,PartialEq,Eq,CanonicalSerialize,CanonicalDeserialize)] // Copy, Default, PartialOrd, Ord, Hash
+/// 
+/// Checked deserialization rejects points not on the curve or not
+/// in the prime order subgroup, but `deserialize_compressed_unchecked`
+/// skips these checks for pre-outputs from trusted storage.
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Hash,CanonicalSerialize,CanonicalDeserialize)] // Default
 #[repr(transparent)]
 pub struct VrfPreOut<C: AffineRepr>(pub C);
 
+/// Sort by canonical compressed encoding, consistent with `Eq`.
+impl<C: AffineRepr> PartialOrd for VrfPreOut<C> {
+    fn partial_cmp(&self, other: &VrfPreOut<C>) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<C: AffineRepr> Ord for VrfPreOut<C> {
+    fn cmp(&self, other: &VrfPreOut<C>) -> core::cmp::Ordering {
+        crate::point_bytes(&self.0).cmp(&crate::point_bytes(&other.0))
+    }
+}
+
 impl<C: AffineRepr> VrfPreOut<C> {
+    /// Check our pre-output lies on the curve, inside the prime order
+    /// subgroup, and is not the identity.
+    /// 
+    /// Checked deserialization already does the first two checks, but
+    /// pre-outputs deserialized unchecked or built directly do not.
+    /// With the `validate-preouts` feature, we validate every pre-output
+    /// when attaching inputs during verification, so malformed
+    /// pre-outputs never reach output hashing.
+    pub fn validate(&self) -> SignatureResult<()> {
+        if self.0.is_zero() || self.0.check().is_err() {
+            return Err(SignatureError::InvalidPreOut);
+        }
+        Ok(())
+    }
+
     /// Create `VrfInOut` by attaching to our pre-output the VRF input
     /// with given malleablity from the given transcript. 
     /// 
@@ -127,22 +188,62 @@ impl<C: AffineRepr> VrfPreOut<C> {
     }
 }
 
-pub fn attach_inputs_array<const N:usize,C,I,II>(preoutputs: &[VrfPreOut<C>; N], inputs: II) -> [VrfInOut<C>; N]
+/// Attach VRF inputs to pre-outputs, failing with `WrongInputCount`
+/// unless we have exactly one input per pre-output.
+pub fn attach_inputs_array<const N:usize,C,I,II>(preoutputs: &[VrfPreOut<C>; N], inputs: II) -> SignatureResult<[VrfInOut<C>; N]>
 where C: AffineRepr, I: IntoVrfInput<C>, II: IntoIterator<Item=I>,
 {
-    preoutputs.iter().zip(inputs).map(
-        |(preoutput,input)| preoutput.attach_input(input)
-    ).collect::<arrayvec::ArrayVec<VrfInOut<C>,{N}>>().into_inner().unwrap()
+    // We avoid allocating, so gossip validation stays cheap.
+    let mut inputs = inputs.into_iter();
+    let zero = VrfInOut { input: VrfInput(C::zero()), preoutput: VrfPreOut(C::zero()) };
+    let mut ios = [zero; N];
+    for (io, preout) in ios.iter_mut().zip(preoutputs) {
+        #[cfg(feature = "validate-preouts")]
+        preout.validate() ?;
+        let input = inputs.next().ok_or(SignatureError::WrongInputCount) ?;
+        *io = preout.attach_input(input);
+    }
+    if inputs.next().is_some() { return Err(SignatureError::WrongInputCount); }
+    Ok(ios)
+}
+
+/// Fixed number of VRF pre-outputs, like those of a `VrfSignature<_,N>`.
+/// 
+/// We use `#[repr(transparent)]` here and in `VrfPreOut`, so this shares
+/// the memory layout of `[C; N]`, for callers who reinterpret buffers.
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Hash,CanonicalSerialize,CanonicalDeserialize)]
+#[repr(transparent)]
+pub struct VrfPreOuts<C: AffineRepr, const N: usize>(pub [VrfPreOut<C>; N]);
+
+impl<C: AffineRepr, const N: usize> VrfPreOuts<C,N> {
+    /// Attach VRF inputs to our pre-outputs, failing with
+    /// `WrongInputCount` unless we have exactly `N` inputs.
+    pub fn attach_inputs<I,II>(&self, inputs: II) -> SignatureResult<[VrfInOut<C>; N]>
+    where I: IntoVrfInput<C>, II: IntoIterator<Item=I>,
+    {
+        attach_inputs_array(&self.0, inputs)
+    }
+}
+
+impl<C: AffineRepr, const N: usize> From<[VrfPreOut<C>; N]> for VrfPreOuts<C,N> {
+    fn from(preouts: [VrfPreOut<C>; N]) -> Self { VrfPreOuts(preouts) }
+}
+
+impl<C: AffineRepr, const N: usize> AsRef<[VrfPreOut<C>]> for VrfPreOuts<C,N> {
+    fn as_ref(&self) -> &[VrfPreOut<C>] { &self.0 }
 }
 
-pub fn collect_preoutputs_array<const N:usize,C: AffineRepr>(ios: &[VrfInOut<C>]) -> [VrfPreOut<C>; N]
+/// Collect pre-outputs, failing with `WrongInputCount` unless
+/// `ios.len() == N`.
+pub fn collect_preoutputs_array<const N:usize,C: AffineRepr>(ios: &[VrfInOut<C>]) -> SignatureResult<[VrfPreOut<C>; N]>
 {
-    ios.iter().map(
-        |io| io.preoutput.clone()
-    ).collect::<arrayvec::ArrayVec<VrfPreOut<C>,{N}>>().into_inner().unwrap()
+    if ios.len() != N { return Err(SignatureError::WrongInputCount); }
+    Ok(core::array::from_fn(|i| ios[i].preoutput.clone()))
 }
 
-pub fn attach_inputs_vec<C,I,O,II,IO>(preoutputs: IO, inputs: II) -> Vec<VrfInOut<C>>
+/// Attach VRF inputs to pre-outputs, failing with `WrongInputCount`
+/// unless we have exactly one input per pre-output.
+pub fn attach_inputs_vec<C,I,O,II,IO>(preoutputs: IO, inputs: II) -> SignatureResult<Vec<VrfInOut<C>>>
 where
     C: AffineRepr,
     I: IntoVrfInput<C>,
@@ -150,9 +251,15 @@ where
     II: IntoIterator<Item=I>,
     IO: IntoIterator<Item=O>,
 {
-    preoutputs.into_iter().zip(inputs).map(
-        |(preout,input)| preout.borrow().attach_input(input)
-    ).collect::<Vec<VrfInOut<C>>>()
+    let mut inputs = inputs.into_iter();
+    let ios = preoutputs.into_iter().map(|preout| {
+        #[cfg(feature = "validate-preouts")]
+        preout.borrow().validate() ?;
+        let input = inputs.next().ok_or(SignatureError::WrongInputCount) ?;
+        Ok(preout.borrow().attach_input(input))
+    }).collect::<SignatureResult<Vec<VrfInOut<C>>>>() ?;
+    if inputs.next().is_some() { return Err(SignatureError::WrongInputCount); }
+    Ok(ios)
 }
 
 pub fn collect_preoutputs_vec<C: AffineRepr>(ios: &[VrfInOut<C>]) -> Vec<VrfPreOut<C>>
@@ -198,6 +305,15 @@ impl<C: AffineRepr> VrfInOut<C> {
     /// VRF output reader via the supplied transcript.
     /// 
     /// You should domain seperate outputs using the transcript.
+    /// 
+    /// We specify VRF outputs as exactly the following transcript
+    /// operations, so all users derive interoperable outputs:
+    /// 
+    /// 1. `t.label(b"VrfOutput")`,
+    /// 2. `t.append(&io)` where `io` is this `VrfInOut` except with
+    ///    its preoutput multiplied by the cofactor if the cofactor is
+    ///    small, so both points are canonical compressed serializations,
+    /// 3. `t.challenge(b"")` whose reader yields the output bytes.
     pub fn vrf_output(&self, t: impl IntoTranscript) -> crate::transcript::Reader
     {
         let mut t = t.into_transcript();
@@ -210,10 +326,76 @@ impl<C: AffineRepr> VrfInOut<C> {
     /// VRF output bytes via the supplied transcript.
     /// 
     /// You should domain seperate outputs using the transcript.
+    /// We return the first `N` bytes read from `vrf_output`, so
+    /// shorter outputs are prefixes of longer ones.
     pub fn vrf_output_bytes<const N: usize>(&self, t: impl IntoTranscript) -> [u8; N] 
     {
         self.vrf_output(t).read_byte_array()
     }
+
+    /// Lottery test whether our VRF output lies below the ratio
+    /// `numerator / denominator`, so we win with that probability.
+    /// 
+    /// We specify this test exactly as follows, so all users agree:
+    /// 
+    /// 1. Read `x` as the first 16 bytes of `vrf_output(context)`,
+    ///    interpreted as a little-endian `u128`,
+    /// 2. We win if `x * denominator < numerator * 2^128`,
+    ///    computed exactly, without rounding.
+    /// 
+    /// We never branch upon the output here.  Panics if `denominator`
+    /// equals zero.
+    pub fn is_winner(&self, numerator: u64, denominator: u64, context: impl IntoTranscript) -> bool
+    {
+        assert!(denominator != 0, "Lottery threshold denominator must be nonzero");
+        let x = self.output_u128(context);
+        let d = denominator as u128;
+        let (hi, lo) = (x >> 64, x & (u64::MAX as u128));
+        // x * d = a * 2^64 + (lo * d mod 2^64), with a < 2^128.
+        let a = hi * d + ((lo * d) >> 64);
+        a < (numerator as u128) << 64
+    }
+
+    /// Uniform `u128` VRF output, being the first 16 bytes of
+    /// `vrf_output(context)` interpreted as a little-endian `u128`,
+    /// exactly like `is_winner` reads.
+    pub fn output_u128(&self, context: impl IntoTranscript) -> u128
+    {
+        u128::from_le_bytes(self.vrf_output_bytes::<16>(context))
+    }
+
+    /// Uniform VRF output in `[0,1)`, having 53 bits of precision
+    /// taken from the top of `output_u128(context)`.
+    /// 
+    /// Consensus code should prefer `is_winner`, which avoids floating
+    /// point entirely.
+    pub fn output_ratio(&self, context: impl IntoTranscript) -> f64
+    {
+        let x = self.output_u128(context) >> (128 - f64::MANTISSA_DIGITS);
+        x as f64 / (1u64 << f64::MANTISSA_DIGITS) as f64
+    }
+
+    /// Uniform VRF output in `[0,n)`, without the bias of reducing
+    /// a fixed length output modulo `n`.
+    /// 
+    /// We read successive little-endian `u64`s from `vrf_output(context)`,
+    /// reject those at or above the largest multiple of `n` below `2^64`,
+    /// and reduce the first accepted one modulo `n`.  We reject with
+    /// probability below one half, so all users agree upon the output
+    /// after a few reads.
+    /// 
+    /// Panics if `n` equals zero.
+    pub fn output_mod(&self, n: u64, context: impl IntoTranscript) -> u64
+    {
+        assert!(n != 0, "VRF output modulus must be nonzero");
+        let n = n as u128;
+        let limit = (1u128 << 64) - (1u128 << 64) % n;
+        let mut reader = self.vrf_output(context);
+        loop {
+            let x = u64::from_le_bytes(reader.read_byte_array()) as u128;
+            if x < limit { return (x % n) as u64; }
+        }
+    }
 }
 
 
@@ -253,13 +435,49 @@ impl<C: AffineRepr> VrfInOut<C> {
 /// We also note no such requirement when the values being hashed are
 /// BLS public keys as in https://crypto.stanford.edu/~dabo/pubs/papers/BLSmultisig.html
 pub fn vrfs_merge<C,B>(t: &mut Transcript, ps: &[B]) -> VrfInOut<C>
+where
+    C: AffineRepr,
+    B: Borrow<VrfInOut<C>>,
+{
+    vrfs_merge_msm(t, ps, &ArkworksMsm)
+}
+
+/// Merge VRF input and pre-output pairs like `vrfs_merge`, but run
+/// the multi-scalar multiplications upon `msm`.
+pub(crate) fn vrfs_merge_msm<C,B>(t: &mut Transcript, ps: &[B], msm: &dyn MsmBackend<C>) -> VrfInOut<C>
 where
     C: AffineRepr,
     B: Borrow<VrfInOut<C>>,
 {
     t.label(b"VrfInOut");
     t.append_slice(ps);
-    vrfs_delinearize( t, ps.iter().map(|io| io.borrow()) )
+    vrfs_delinearize( t, ps.iter().map(|io| io.borrow()), msm )
+}
+
+/// Delinearization scalars which `vrfs_merge` applies to `ps`, given
+/// the transcript `t` as it stood before invoking `vrfs_merge`.
+///
+/// We expose these so auditors and alternative implementations could
+/// reproduce the merged `VrfInOut` as `sum_i z_i ps[i]`.  Each `z_i` is
+/// a uniform 128 bit scalar from its own transcript fork, so weights
+/// collide or vanish only with probability around `2^-128`.
+pub fn delinearization_scalars<C,B>(t: &Transcript, ps: &[B]) -> Vec<<C as AffineRepr>::ScalarField>
+where
+    C: AffineRepr,
+    B: Borrow<VrfInOut<C>>,
+{
+    let mut t = t.fork(b"VrfInOut");
+    t.append_slice(ps);
+    (0..ps.len() as u64).map(|i| delinearization_weight(&t, i)).collect()
+}
+
+/// Sample the `i`th 128 bit delinearization weight.
+pub(crate) fn delinearization_weight<F: PrimeField>(t: &Transcript, i: u64) -> F {
+    let mut t0 = t.fork(b"delinearize");   // Keep t clean, but
+    t0.append_u64(i);                        // distinguish the different outputs.
+    // Sample a 128bit scalar.  RngCore::next_u64 winds up being u64::from_le_bytes here.
+    let [lo, hi]: [u64; 2] = t0.challenge(b"128 bits").read_uniform();
+    F::from(((hi as u128) << 64) | lo as u128)
 }
 
 /// Raw delinerazation step for merger of VRF input and pre-output
@@ -267,33 +485,22 @@ where
 /// All pairs must be hashed into the transcript `t` before invoking,
 /// as otherwise malicious signers could validate invalid pairs like
 /// `[(x, (sk*a)*x, (x,(sk/a)*x)]`, breaking VRF & VUF security.
-pub(crate) fn vrfs_delinearize<'a,C,I>(t: &Transcript, ps: I) -> VrfInOut<C>
+pub(crate) fn vrfs_delinearize<'a,C,I>(t: &Transcript, ps: I, msm: &dyn MsmBackend<C>) -> VrfInOut<C>
 where
     C: AffineRepr,
     I: Iterator<Item=&'a VrfInOut<C>>
 {
-    use ark_std::Zero;
-
-    let mut i = 0;
-    let mut input = <C as AffineRepr>::Group::zero();
-    let mut preoutput = <C as AffineRepr>::Group::zero();
-    for p in ps {
-        let mut t0 = t.fork(b"delinearize");   // Keep t clean, but
-        t0.append_u64(i);                        // distinguish the different outputs.
-        // Sample a 128bit scalar.  RngCore::next_u64 winds up being u64::from_le_bytes here.
-        let z: [u64; 2] = t0.challenge(b"128 bits").read_uniform();
-
-        input += p.input.0.mul_bigint(z);
-        preoutput += p.preoutput.0.mul_bigint(z);
-        i += 1;
-    }
-    // TODO: Benchmark
-    // let v = <C as AffineRepr>::Group::normalize_batch(&[input,preouput]);
+    let (inputs, preoutputs): (Vec<C>, Vec<C>) = ps.map(|p| (p.input.0, p.preoutput.0)).unzip();
+    let zs: Vec<<C as AffineRepr>::ScalarField> = (0..inputs.len() as u64)
+        .map(|i| delinearization_weight(t, i))
+        .collect();
+    let v = <C as AffineRepr>::Group::normalize_batch(&[
+        msm.msm(&inputs, &zs),
+        msm.msm(&preoutputs, &zs),
+    ]);
     VrfInOut {
-        input: VrfInput(input.into_affine()),
-        // input: VrfInput(v[0]),
-        preoutput: VrfPreOut(preoutput.into_affine()),
-        // preoutput: VrfPreOut(v[1]),
+        input: VrfInput(v[0]),
+        preoutput: VrfPreOut(v[1]),
     }
 }
 
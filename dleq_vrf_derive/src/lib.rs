@@ -0,0 +1,110 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+#![doc = include_str!("../README.md")]
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Expr, Fields, LitStr, Path, Result};
+
+
+/// Derive `IntoVrfInput` for a struct, see the crate documentation.
+#[proc_macro_derive(VrfInput, attributes(vrf_input))]
+pub fn derive_vrf_input(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Encoding of one field
+enum Encoding {
+    Ark,
+    Scale,
+    Skip,
+}
+
+fn field_encoding(attrs: &[syn::Attribute]) -> Result<Encoding> {
+    let mut encoding = Encoding::Ark;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("vrf_input")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("scale") {
+                encoding = Encoding::Scale;
+            } else if meta.path.is_ident("skip") {
+                encoding = Encoding::Skip;
+            } else {
+                return Err(meta.error("expected `scale` or `skip`"));
+            }
+            Ok(())
+        }) ?;
+    }
+    Ok(encoding)
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let mut domain: Option<Expr> = None;
+    let mut hasher: Option<Path> = None;
+    let mut krate: Path = syn::parse_quote!(::dleq_vrf);
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("vrf_input")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("domain") {
+                domain = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("hasher") {
+                hasher = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("crate") {
+                krate = meta.value()?.parse::<LitStr>()?.parse()?;
+            } else {
+                return Err(meta.error("expected `domain`, `hasher`, or `crate`"));
+            }
+            Ok(())
+        }) ?;
+    }
+    let span = Span::call_site();
+    let domain = domain.ok_or_else(|| Error::new(span, "missing #[vrf_input(domain = ..)]")) ?;
+    let hasher = hasher.ok_or_else(|| Error::new(span, "missing #[vrf_input(hasher = ..)]")) ?;
+
+    let fields = match &input.data {
+        Data::Struct(s) => &s.fields,
+        _ => return Err(Error::new(span, "#[derive(VrfInput)] supports only structs")),
+    };
+    let mut writes = Vec::new();
+    let members: Vec<(String, TokenStream2, &syn::Field)> = match fields {
+        Fields::Named(named) => named.named.iter().map(|f| {
+            let ident = f.ident.as_ref().expect("Named fields have identifiers");
+            (ident.to_string(), quote!(#ident), f)
+        }).collect(),
+        Fields::Unnamed(unnamed) => unnamed.unnamed.iter().enumerate().map(|(i, f)| {
+            let index = syn::Index::from(i);
+            (i.to_string(), quote!(#index), f)
+        }).collect(),
+        Fields::Unit => Vec::new(),
+    };
+    for (name, member, field) in members {
+        let label = Literal::byte_string(name.as_bytes());
+        writes.push(match field_encoding(&field.attrs) ? {
+            Encoding::Ark => quote! {
+                t.label(#label);
+                t.append(&self.#member);
+            },
+            Encoding::Scale => quote! {
+                t.label(#label);
+                t.append(&#krate::scale::Encode::encode(&self.#member)[..]);
+            },
+            Encoding::Skip => quote! {},
+        });
+    }
+
+    let name = &input.ident;
+    let name_label = Literal::byte_string(name.to_string().as_bytes());
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let affine = quote!(<#hasher as #krate::vrf::HashToVrfInput>::Affine);
+    Ok(quote! {
+        impl #impl_generics #krate::IntoVrfInput<#affine> for #name #ty_generics #where_clause {
+            fn into_vrf_input(self) -> #krate::vrf::VrfInput<#affine> {
+                let mut t = #krate::Transcript::new_blank_accumulator();
+                t.label(#name_label);
+                #(#writes)*
+                let message = t.accumulator_finalize();
+                <#hasher as #krate::vrf::HashToVrfInput>::hash_to_vrf_input(#domain, &message)
+            }
+        }
+    })
+}
@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use bandersnatch_vrfs::{
+    CanonicalDeserialize, PublicKey, ThinVrfSignature, RingVrfSignature,
+    fuzzing::MutatedEncoding,
+};
+
+#[derive(Debug, arbitrary::Arbitrary)]
+enum Input {
+    Raw(Vec<u8>),
+    PublicKey(MutatedEncoding<PublicKey>),
+    ThinSignature(MutatedEncoding<ThinVrfSignature<1>>),
+}
+
+fuzz_target!(|input: Input| {
+    let bytes = match &input {
+        Input::Raw(bytes) => bytes.as_slice(),
+        Input::PublicKey(m) => m.bytes.as_slice(),
+        Input::ThinSignature(m) => m.bytes.as_slice(),
+    };
+    let _ = PublicKey::deserialize_compressed(bytes);
+    let _ = ThinVrfSignature::<1>::deserialize_compressed(bytes);
+    let _ = ThinVrfSignature::<2>::deserialize_compressed(bytes);
+    let _ = RingVrfSignature::<1>::deserialize_compressed(bytes);
+});
@@ -0,0 +1,42 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use bandersnatch_vrfs::{
+    CanonicalDeserialize, SecretKey, PublicKey, ThinVrfSignature, VrfInput, Message,
+    fuzzing::MutatedEncoding,
+};
+
+#[derive(Debug, arbitrary::Arbitrary)]
+struct Input {
+    seed: [u8; 32],
+    input: VrfInput,
+    other_input: VrfInput,
+    context: Vec<u8>,
+    message: Vec<u8>,
+    forged: ThinVrfSignature<1>,
+    mutated: MutatedEncoding<ThinVrfSignature<1>>,
+    other_public: PublicKey,
+}
+
+fuzz_target!(|input: Input| {
+    let Input { seed, input, other_input, context, message, forged, mutated, other_public } = input;
+    let secret = SecretKey::from_seed(&seed);
+    let public = secret.to_public();
+    let io = secret.vrf_inout(input);
+    let signature: ThinVrfSignature<1> = secret.sign_thin_vrf(context.as_slice(), &[io]);
+    assert!(public.verify_thin_vrf(context.as_slice(), [input], &signature).is_ok());
+
+    let hashed = Message { domain: b"fuzz", message: &message };
+    let _ = public.verify_thin_vrf(context.as_slice(), [hashed], &signature);
+    if other_input.0 != input.0 {
+        assert!(public.verify_thin_vrf(context.as_slice(), [other_input], &signature).is_err());
+    }
+    if other_public != public {
+        assert!(other_public.verify_thin_vrf(context.as_slice(), [input], &signature).is_err());
+    }
+    assert!(public.verify_thin_vrf(context.as_slice(), [input], &forged).is_err());
+    if let Ok(mutated) = ThinVrfSignature::<1>::deserialize_compressed(mutated.bytes.as_slice()) {
+        let _ = public.verify_thin_vrf(context.as_slice(), [input], &mutated);
+    }
+});
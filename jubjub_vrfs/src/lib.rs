@@ -0,0 +1,161 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(unsafe_code)]
+#![doc = include_str!("../README.md")]
+
+use ark_ff::MontFp;
+
+pub use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, SerializationError, Compress};
+
+pub use ark_ed_on_bls12_381 as jubjub;
+
+pub use dleq_vrf::{
+    Transcript, IntoTranscript, transcript,
+    error::{SignatureResult, SignatureError},
+    vrf::{self, IntoVrfInput},
+    EcVrfSecret,EcVrfSigner,EcVrfVerifier,
+    VrfSignature,VrfSignatureVec,
+};
+use dleq_vrf::{Elligator2Map, Elligator2Hasher};
+
+use jubjub::EdwardsAffine as Jubjub;
+
+pub type VrfInput = dleq_vrf::vrf::VrfInput<Jubjub>;
+pub type VrfPreOut = dleq_vrf::vrf::VrfPreOut<Jubjub>;
+pub type VrfInOut = dleq_vrf::vrf::VrfInOut<Jubjub>;
+
+/// Suite identifier appended to the application's domain in our DST.
+pub const SUITE_ID: &[u8] = b"JubJub_XMD:SHA-512_ELL2_RO_";
+
+/// Non-square selected by `find_z_elligator2` from RFC 9380 appendix H.3.
+const Z: jubjub::Fq = MontFp!("5");
+
+/// Hash to JubJub using Elligator2, aka the suite
+/// `JubJub_XMD:SHA-512_ELL2_RO_` in the terminology of RFC 9380.
+///
+/// We use `domain || SUITE_ID` as the domain seperation tag (DST).
+pub fn hash_to_jubjub_curve(domain: &[u8], message: &[u8]) -> VrfInput {
+    let hasher = Elligator2Hasher::<jubjub::JubjubConfig, sha2::Sha512>::new(domain, SUITE_ID, Elligator2Map::new(Z));
+    vrf::VrfInput( hasher.hash_to_edwards(message) )
+}
+
+pub struct Message<'a> {
+    pub domain: &'a [u8],
+    pub message: &'a [u8],
+}
+
+impl<'a> IntoVrfInput<Jubjub> for Message<'a> {
+    fn into_vrf_input(self) -> VrfInput {
+        hash_to_jubjub_curve(self.domain, self.message)
+    }
+}
+
+/// Blinding base for Pedersen VRF key commitments.
+///
+/// We derive this nothing-up-my-sleeve point by sampling uniformly
+/// from a `Transcript` labeled `b"JubJub VRF blinding base"`,
+/// so nobody knows its discrete logarithm with respect to the keying
+/// base.  Our `check_blinding_base` test reproduces this derivation.
+pub const BLINDING_BASE: Jubjub = {
+    const X: jubjub::Fq = MontFp!("14897260500683847128633117764099501034587576878748444989633345720522206104306");
+    const Y: jubjub::Fq = MontFp!("34554626635869034076002877021848349640493774388073828457315529392540689820834");
+    Jubjub::new_unchecked(X, Y)
+};
+
+
+pub type ThinVrf = dleq_vrf::ThinVrf<Jubjub>;
+
+/// Thin VRF configured by the JubJub generator for signatures.
+pub fn thin_vrf() -> ThinVrf {
+    dleq_vrf::ThinVrf::default()  //  keying_base: Jubjub::generator()
+}
+
+pub type PedersenVrf = dleq_vrf::PedersenVrf<Jubjub>;
+
+/// Pedersen VRF configured by the JubJub generator for public key
+/// certs, and blinded by `BLINDING_BASE`.
+pub fn pedersen_vrf() -> PedersenVrf {
+    thin_vrf().pedersen_vrf([ BLINDING_BASE ])
+}
+
+
+pub type SecretKey = dleq_vrf::SecretKey<Jubjub>;
+
+pub const PUBLIC_KEY_LENGTH: usize = 32;
+pub type PublicKeyBytes = [u8; PUBLIC_KEY_LENGTH];
+
+pub type PublicKey = dleq_vrf::PublicKey<Jubjub>;
+
+pub fn serialize_publickey(pk: &PublicKey) -> PublicKeyBytes {
+    pk.to_bytes()
+}
+
+pub fn deserialize_publickey(reader: &[u8]) -> Result<PublicKey, SerializationError> {
+    PublicKey::deserialize_compressed(reader)
+}
+
+
+type ThinVrfProof = dleq_vrf::Batchable<ThinVrf>;
+
+pub type ThinVrfSignature<const N: usize> = dleq_vrf::VrfSignature<ThinVrfProof,N>;
+
+pub type ThinVrfSignatureVec = dleq_vrf::VrfSignatureVec<ThinVrfProof>;
+
+pub type PedersenVrfProof = dleq_vrf::Batchable<PedersenVrf>;
+
+
+#[cfg(all(test, feature = "getrandom"))]
+mod tests {
+    use super::*;
+    use core::iter;
+    use ark_ec::{AffineRepr, CurveGroup};
+
+    #[test]
+    fn check_blinding_base() {
+        let mut t = Transcript::new_labeled(b"JubJub VRF blinding base");
+        let blinding_base: <Jubjub as AffineRepr>::Group = t.challenge(b"vrf-input").read_uniform();
+        assert_eq!(blinding_base.into_affine(), BLINDING_BASE);
+        assert!(BLINDING_BASE.is_on_curve());
+        assert!(BLINDING_BASE.is_in_correct_subgroup_assuming_on_curve());
+    }
+
+    #[test]
+    fn hash_to_curve_lands_in_subgroup() {
+        let p = hash_to_jubjub_curve(b"domain", b"message").0;
+        assert!(p.is_on_curve());
+        assert!(p.is_in_correct_subgroup_assuming_on_curve());
+        assert!(!p.is_zero());
+        assert_eq!(p, hash_to_jubjub_curve(b"domain", b"message").0);
+        assert_ne!(p, hash_to_jubjub_curve(b"other", b"message").0);
+        assert_ne!(p, hash_to_jubjub_curve(b"domain", b"other").0);
+    }
+
+    #[test]
+    fn thin_sign_verify() {
+        let secret = SecretKey::from_seed(&[0; 32]);
+        let public = secret.to_public();
+        assert_eq!(public.compressed_size(), PUBLIC_KEY_LENGTH);
+        let public = deserialize_publickey(&serialize_publickey(&public)).unwrap();
+
+        let input = Message { domain: b"domain", message: b"message" }.into_vrf_input();
+        let io = secret.vrf_inout(input.clone());
+        let transcript = Transcript::new_labeled(b"label");
+
+        let signature: ThinVrfSignature<1> = secret.sign_thin_vrf(transcript.clone(), &[io.clone()]);
+        let ios = public.verify_thin_vrf(transcript, iter::once(input), &signature).unwrap();
+        assert_eq!(ios[0].preoutput, io.preoutput);
+    }
+
+    #[test]
+    fn pedersen_sign_verify() {
+        let secret = SecretKey::from_seed(&[1; 32]);
+        let input = Message { domain: b"domain", message: b"message" }.into_vrf_input();
+        let io = secret.vrf_inout(input);
+        let flavor = pedersen_vrf();
+
+        let (signature, secret_blinding) = flavor.sign_pedersen_vrf(Transcript::new_labeled(b"label"), &[io], None, &secret);
+        assert!( *signature.as_key_commitment() == flavor.compute_blinded_publickey(secret.as_publickey(), &secret_blinding) );
+        flavor.verify_pedersen_vrf(Transcript::new_labeled(b"label"), &[io], &signature).unwrap();
+    }
+}
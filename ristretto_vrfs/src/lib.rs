@@ -0,0 +1,146 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![deny(unsafe_code)]
+#![doc = include_str!("../README.md")]
+
+use ark_ff::MontFp;
+
+pub use ark_serialize::{CanonicalSerialize, CanonicalDeserialize, SerializationError, Compress};
+
+pub use ark_curve25519 as curve25519;
+
+pub use dleq_vrf::{
+    Transcript, IntoTranscript, transcript,
+    error::{SignatureResult, SignatureError},
+    vrf::{self, IntoVrfInput},
+    EcVrfSecret,EcVrfSigner,EcVrfVerifier,
+    VrfSignature,VrfSignatureVec,
+};
+use dleq_vrf::{Elligator2Map, Elligator2Hasher};
+
+pub mod ristretto;
+
+#[cfg(feature = "schnorrkel")]
+pub mod sr25519;
+
+use curve25519::EdwardsAffine as Curve25519;
+
+pub type VrfInput = dleq_vrf::vrf::VrfInput<Curve25519>;
+pub type VrfPreOut = dleq_vrf::vrf::VrfPreOut<Curve25519>;
+pub type VrfInOut = dleq_vrf::vrf::VrfInOut<Curve25519>;
+
+/// Suite identifier appended to the application's domain in our DST.
+pub const SUITE_ID: &[u8] = b"edwards25519_XMD:SHA-512_ELL2_RO_";
+
+/// Non-square selected by `find_z_elligator2` from RFC 9380 appendix H.3.
+const Z: curve25519::Fq = MontFp!("2");
+
+/// Hash to Curve25519 using Elligator2, aka the suite
+/// `edwards25519_XMD:SHA-512_ELL2_RO_` in the terminology of RFC 9380.
+///
+/// We use `domain || SUITE_ID` as the domain seperation tag (DST).
+/// As arkworks 0.4's `expand_message_xmd` pads by 48 bytes, not by
+/// SHA-512's block size, our outputs differ from RFC 9380's vectors.
+pub fn hash_to_curve25519(domain: &[u8], message: &[u8]) -> VrfInput {
+    let hasher = Elligator2Hasher::<curve25519::Curve25519Config, sha2::Sha512>::new(domain, SUITE_ID, Elligator2Map::new(Z));
+    // RFC 9380 moves into edwards25519 by the square root of -486664
+    // opposite to the one which sends arkworks' generator to the
+    // edwards25519 base point, so we negate to agree with RFC 9380.
+    vrf::VrfInput( -hasher.hash_to_edwards(message) )
+}
+
+pub struct Message<'a> {
+    pub domain: &'a [u8],
+    pub message: &'a [u8],
+}
+
+impl<'a> IntoVrfInput<Curve25519> for Message<'a> {
+    fn into_vrf_input(self) -> VrfInput {
+        hash_to_curve25519(self.domain, self.message)
+    }
+}
+
+
+pub type ThinVrf = dleq_vrf::ThinVrf<Curve25519>;
+
+/// Thin VRF configured by the Ristretto255 base point for signatures,
+/// like sr25519.
+pub fn thin_vrf() -> ThinVrf {
+    dleq_vrf::ThinVrf::default()  //  keying_base: Curve25519::generator()
+}
+
+
+pub type SecretKey = dleq_vrf::SecretKey<Curve25519>;
+
+pub const PUBLIC_KEY_LENGTH: usize = 32;
+pub type PublicKeyBytes = [u8; PUBLIC_KEY_LENGTH];
+
+pub type PublicKey = dleq_vrf::PublicKey<Curve25519>;
+
+/// Ristretto255 encoding of a public key, identical to sr25519's.
+pub fn serialize_publickey(pk: &PublicKey) -> PublicKeyBytes {
+    ristretto::encode(&pk.0)
+}
+
+/// Decode a Ristretto255 encoded public key, like an sr25519 public key.
+pub fn deserialize_publickey(reader: &[u8]) -> Result<PublicKey, SerializationError> {
+    let bytes: &PublicKeyBytes = reader.try_into().map_err(|_| SerializationError::InvalidData) ?;
+    ristretto::decode(bytes).map(dleq_vrf::PublicKey).ok_or(SerializationError::InvalidData)
+}
+
+
+type ThinVrfProof = dleq_vrf::Batchable<ThinVrf>;
+
+pub type ThinVrfSignature<const N: usize> = dleq_vrf::VrfSignature<ThinVrfProof,N>;
+
+pub type ThinVrfSignatureVec = dleq_vrf::VrfSignatureVec<ThinVrfProof>;
+
+
+#[cfg(all(test, feature = "getrandom"))]
+mod tests {
+    use super::*;
+    use core::iter;
+    use ark_ec::{AffineRepr, CurveGroup};
+
+    #[test]
+    fn thin_sign_verify() {
+        let secret = SecretKey::from_seed(&[0; 32]);
+        let public = secret.to_public();
+        let public = deserialize_publickey(&serialize_publickey(&public)).unwrap();
+        assert_eq!(&public, secret.as_publickey());
+
+        let input = Message { domain: b"domain", message: b"message" }.into_vrf_input();
+        let io = secret.vrf_inout(input);
+        let transcript = Transcript::new_labeled(b"label");
+
+        let signature: ThinVrfSignature<1> = secret.sign_thin_vrf(transcript.clone(), &[io]);
+        let ios = public.verify_thin_vrf(transcript, iter::once(input), &signature).unwrap();
+        assert_eq!(ios[0].preoutput, io.preoutput);
+    }
+
+    #[test]
+    fn elligator2_test_vector() {
+        // RFC 9380 appendix J.5.1, with msg = "", starting from its u[0]
+        // and u[1], since arkworks 0.4's `expand_message_xmd` pads by 48
+        // bytes, not by SHA-512's 128 byte block.
+        let map = Elligator2Map::<curve25519::Curve25519Config>::new(Z);
+        let u0: curve25519::Fq = MontFp!("1807405210521868326678229579204214119143212585359944305807843646127944515386");
+        let u1: curve25519::Fq = MontFp!("54298508393843446069418281466898257185454301391090896628533180632835905948789");
+        let p = map.map_to_edwards(u0).into_group() + map.map_to_edwards(u1);
+        let p = -p.into_affine().clear_cofactor();
+        let (x, y) = ristretto::to_edwards25519(&p);
+        let x_expected: curve25519::Fq = MontFp!("27247698221496130860672250702331285492359266551758864958952564794703042719686");
+        let y_expected: curve25519::Fq = MontFp!("4365494920570081337277818293609245942863617850477917088769355520298955693089");
+        assert_eq!((x, y), (x_expected, y_expected));
+    }
+
+    #[test]
+    fn hash_to_curve_lands_in_subgroup() {
+        let p = hash_to_curve25519(b"domain", b"message").0;
+        assert!(!p.is_zero());
+        assert!(p.is_in_correct_subgroup_assuming_on_curve());
+        assert_eq!(p, hash_to_curve25519(b"domain", b"message").0);
+        assert_ne!(p, hash_to_curve25519(b"domain", b"other").0);
+    }
+}
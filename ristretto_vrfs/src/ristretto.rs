@@ -0,0 +1,187 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### Ristretto255 encoding
+//!
+//! Arkworks models Curve25519 by the twisted Edwards curve
+//! `486664 x^2 + y^2 = 1 + 486660 x^2 y^2`, not by edwards25519
+//! `-x^2 + y^2 = 1 + d x^2 y^2`, so we rescale `x` by `sqrt(-486664)`,
+//! and then encode or decode by Ristretto255 from
+//! [RFC 9496](https://www.rfc-editor.org/rfc/rfc9496.html) section 4.3.
+//!
+//! Ristretto255 identifies points which differ by 4-torsion, so we
+//! decode into the representative in the prime order subgroup.
+
+use ark_ec::{AffineRepr, CurveConfig};
+use ark_ff::{BigInteger, Field, MontFp, One, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use crate::{Curve25519, curve25519::{Curve25519Config, Fq}};
+
+
+/// `sqrt(-1)` which is nonnegative, per RFC 9496 section 4.1.
+const SQRT_M1: Fq = MontFp!("19681161376707505956807079304988542015446066515923890162744021073123829784752");
+
+/// Edwards25519 coefficient `d = -121665 / 121666`.
+const D: Fq = MontFp!("37095705934669439343138083508754565189542113879843219016388785533085940283555");
+
+/// `1 / sqrt(a - d)` which is nonnegative, per RFC 9496 section 4.1.
+const INVSQRT_A_MINUS_D: Fq = MontFp!("54469307008909316920995813868745141605393597292927456921205312896311721017578");
+
+/// `sqrt(-486664)` which sends arkworks' generator to the edwards25519
+/// base point, so `X = SQRT_M486664 x` and `x = X / SQRT_M486664`.
+const SQRT_M486664: Fq = MontFp!("51042569399160536130206135233146329284152202253034631822681833788666877215207");
+
+/// Negative field elements per RFC 9496 section 4.1, being those whose
+/// canonical encoding is odd.
+fn is_negative(x: &Fq) -> bool {
+    x.into_bigint().is_odd()
+}
+
+fn abs(x: Fq) -> Fq {
+    if is_negative(&x) { -x } else { x }
+}
+
+/// `SQRT_RATIO_M1` from RFC 9496 section 4.2, which returns whether
+/// `u/v` is square, and the nonnegative square root of either `u/v`
+/// or `SQRT_M1 u/v`.
+fn sqrt_ratio_m1(u: Fq, v: Fq) -> (bool, Fq) {
+    let v3 = v.square() * v;
+    let v7 = v3.square() * v;
+    // (p - 5) / 8, since p = 5 mod 8
+    let mut exp = Fq::MODULUS;
+    exp.divn(3);
+    let mut r = u * v3 * (u * v7).pow(exp);
+    let check = v * r.square();
+    let correct_sign_sqrt = check == u;
+    let flipped_sign_sqrt = check == -u;
+    let flipped_sign_sqrt_i = check == -u * SQRT_M1;
+    if flipped_sign_sqrt || flipped_sign_sqrt_i {
+        r *= SQRT_M1;
+    }
+    (correct_sign_sqrt || flipped_sign_sqrt, abs(r))
+}
+
+/// Edwards25519 coordinates of a point.
+pub(crate) fn to_edwards25519(p: &Curve25519) -> (Fq, Fq) {
+    (p.x * SQRT_M486664, p.y)
+}
+
+/// Point from its edwards25519 coordinates.
+fn from_edwards25519(x: Fq, y: Fq) -> Curve25519 {
+    let inv = SQRT_M486664.inverse().expect("SQRT_M486664 is nonzero");
+    Curve25519::new_unchecked(x * inv, y)
+}
+
+/// Ristretto255 encoding of `p`, from RFC 9496 section 4.3.2.
+///
+/// Only points in the prime order subgroup, or differing from it by
+/// 4-torsion, have meaningful encodings.
+pub fn encode(p: &Curve25519) -> [u8; 32] {
+    let (x0, y0) = to_edwards25519(p);
+    let (z0, t0) = (Fq::one(), x0 * y0);
+    let u1 = (z0 + y0) * (z0 - y0);
+    let u2 = x0 * y0;
+    let (_, invsqrt) = sqrt_ratio_m1(Fq::one(), u1 * u2.square());
+    let den1 = invsqrt * u1;
+    let den2 = invsqrt * u2;
+    let z_inv = den1 * den2 * t0;
+    let rotate = is_negative(&(t0 * z_inv));
+    let (x, mut y, den_inv) = if rotate {
+        (y0 * SQRT_M1, x0 * SQRT_M1, den1 * INVSQRT_A_MINUS_D)
+    } else {
+        (x0, y0, den2)
+    };
+    if is_negative(&(x * z_inv)) { y = -y; }
+    let s = abs(den_inv * (z0 - y));
+    let mut bytes = [0u8; 32];
+    s.serialize_compressed(&mut bytes[..]).expect("Field elements take 32 bytes");
+    bytes
+}
+
+/// Ristretto255 decoding from RFC 9496 section 4.3.1, into the point
+/// in the prime order subgroup.
+///
+/// We reject non-canonical and invalid encodings.
+pub fn decode(bytes: &[u8; 32]) -> Option<Curve25519> {
+    let s = Fq::deserialize_compressed(&bytes[..]).ok() ?;
+    if is_negative(&s) { return None; }
+    let ss = s.square();
+    let u1 = Fq::one() - ss;
+    let u2 = Fq::one() + ss;
+    let u2_sqr = u2.square();
+    let v = -(D * u1.square()) - u2_sqr;
+    let (was_square, invsqrt) = sqrt_ratio_m1(Fq::one(), v * u2_sqr);
+    let den_x = invsqrt * u2;
+    let den_y = invsqrt * den_x * v;
+    let x = abs(s.double() * den_x);
+    let y = u1 * den_y;
+    if !was_square || is_negative(&(x * y)) || y.is_zero() {
+        return None;
+    }
+    // Remove any 4-torsion by multiplying by 8 and then by 1/8 mod the group order.
+    let p = from_edwards25519(x, y).clear_cofactor();
+    Some(p.mul_bigint(<Curve25519Config as CurveConfig>::COFACTOR_INV.into_bigint()).into())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::CurveGroup;
+    use ark_ec::twisted_edwards::TECurveConfig;
+    use curve25519_dalek::{constants::RISTRETTO_BASEPOINT_POINT, scalar::Scalar};
+    use crate::curve25519::Fr;
+
+    #[test]
+    fn check_constants() {
+        assert_eq!(SQRT_M1.square(), -Fq::one());
+        assert!(!is_negative(&SQRT_M1));
+        assert_eq!(D, -Fq::from(121665u32) * Fq::from(121666u32).inverse().unwrap());
+        assert_eq!((INVSQRT_A_MINUS_D.square() * (-Fq::one() - D)), Fq::one());
+        assert!(!is_negative(&INVSQRT_A_MINUS_D));
+        assert_eq!(SQRT_M486664.square(), -Fq::from(486664u32));
+        let (x, y) = to_edwards25519(&<Curve25519Config as TECurveConfig>::GENERATOR);
+        let x_expected: Fq = MontFp!("15112221349535400772501151409588531511454012693041857206046113283949847762202");
+        assert_eq!((x, y), (x_expected, Fq::from(4u8) / Fq::from(5u8)));
+    }
+
+    #[test]
+    fn matches_curve25519_dalek() {
+        assert_eq!(encode(&Curve25519::zero()), [0u8; 32]);
+        assert_eq!(decode(&[0u8; 32]), Some(Curve25519::zero()));
+        for i in 1..20u64 {
+            let k = Fr::from(i) * Fr::from(0x1234_5678_9abc_def1u64).pow([i]);
+            let p = (Curve25519::generator() * k).into_affine();
+            let mut k_bytes = [0u8; 32];
+            k.serialize_compressed(&mut k_bytes[..]).unwrap();
+            let expected = (RISTRETTO_BASEPOINT_POINT * Scalar::from_bytes_mod_order(k_bytes)).compress().to_bytes();
+            assert_eq!(encode(&p), expected);
+            assert_eq!(decode(&expected), Some(p));
+        }
+    }
+
+    #[test]
+    fn decode_removes_torsion() {
+        let p = (Curve25519::generator() * Fr::from(7u8)).into_affine();
+        // The edwards25519 point (SQRT_M1, 0) has order four.
+        let t4 = from_edwards25519(SQRT_M1, Fq::zero());
+        assert!(t4.is_on_curve());
+        let q = (p.into_group() + t4).into_affine();
+        assert_ne!(p, q);
+        assert_eq!(encode(&q), encode(&p));
+        assert_eq!(decode(&encode(&q)), Some(p));
+    }
+
+    #[test]
+    fn decode_rejects_invalid() {
+        // Negative s
+        let mut bytes = [0u8; 32];
+        bytes[0] = 1;
+        assert_eq!(decode(&bytes), None);
+        // Non-canonical s = p
+        let mut bytes = [0xffu8; 32];
+        bytes[0] = 0xed;
+        bytes[31] = 0x7f;
+        assert_eq!(decode(&bytes), None);
+    }
+}
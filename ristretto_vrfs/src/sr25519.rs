@@ -0,0 +1,126 @@
+// Copyright (c) 2022-2023 Web 3 Foundation
+
+//! ### sr25519 interoperability
+//!
+//! Polkadot session keys are schnorrkel sr25519 keys, whose secret
+//! scalar and nonce seed our `SecretKey` shares, and whose public keys
+//! we encode by Ristretto255 exactly like `serialize_publickey`.  We
+//! convert keys both ways, and sign or verify schnorrkel's own VRF
+//! signatures with our keys, so validators could keep their session
+//! keys while migrating.
+//!
+//! Our `ThinVrf` signatures remain incompatible with schnorrkel's
+//! VRF signatures, because schnorrkel hashes inputs and proofs with
+//! merlin transcripts.
+
+use ark_ff::PrimeField;
+use schnorrkel::vrf::{VRFInOut, VRFPreOut, VRFProof, VRFSigningTranscript};
+use zeroize::Zeroize;
+
+use crate::{
+    PublicKey, SecretKey, SerializationError, SignatureError, SignatureResult,
+    curve25519::Fr, deserialize_publickey, serialize_publickey,
+};
+
+
+/// schnorrkel secret key with our secret scalar and nonce seed.
+pub fn secret_to_schnorrkel(secret: &SecretKey) -> schnorrkel::SecretKey {
+    // `serialize_secret` writes the nonce seed and then the scalar,
+    // while schnorrkel expects the scalar first.
+    let mut ours = [0u8; 64];
+    secret.serialize_secret(&mut ours[..]).expect("Secret keys serialize into 64 bytes");
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&ours[32..]);
+    bytes[32..].copy_from_slice(&ours[..32]);
+    let sk = schnorrkel::SecretKey::from_bytes(&bytes).expect("We serialize reduced scalars");
+    ours.zeroize();
+    bytes.zeroize();
+    sk
+}
+
+/// Our secret key with schnorrkel's secret scalar and nonce seed.
+pub fn secret_from_schnorrkel(secret: &schnorrkel::SecretKey) -> SecretKey {
+    let mut bytes = secret.to_bytes();
+    let key = Fr::from_le_bytes_mod_order(&bytes[..32]);
+    let mut nonce_seed = [0u8; 32];
+    nonce_seed.copy_from_slice(&bytes[32..]);
+    let sk = SecretKey::from_parts(key, &nonce_seed);
+    bytes.zeroize();
+    nonce_seed.zeroize();
+    sk
+}
+
+/// schnorrkel public key for our public key.
+pub fn public_to_schnorrkel(public: &PublicKey) -> Result<schnorrkel::PublicKey, SerializationError> {
+    schnorrkel::PublicKey::from_bytes(&serialize_publickey(public))
+        .map_err(|_| SerializationError::InvalidData)
+}
+
+/// Our public key for a schnorrkel public key.
+pub fn public_from_schnorrkel(public: &schnorrkel::PublicKey) -> Result<PublicKey, SerializationError> {
+    deserialize_publickey(&public.to_bytes())
+}
+
+/// schnorrkel VRF signature by `secret` upon the merlin transcript `t`,
+/// which sr25519 verifiers accept from our public key.
+pub fn sign_sr25519_vrf<T: VRFSigningTranscript>(secret: &SecretKey, t: T) -> (VRFInOut, VRFProof) {
+    let keypair = secret_to_schnorrkel(secret).to_keypair();
+    let (io, proof, _) = keypair.vrf_sign(t);
+    (io, proof)
+}
+
+/// Verify a schnorrkel VRF signature by `public` upon the merlin
+/// transcript `t`.
+pub fn verify_sr25519_vrf<T: VRFSigningTranscript>(
+    public: &PublicKey,
+    t: T,
+    preout: &VRFPreOut,
+    proof: &VRFProof,
+) -> SignatureResult<VRFInOut> {
+    let public = public_to_schnorrkel(public).map_err(|_| SignatureError::Invalid) ?;
+    let (io, _) = public.vrf_verify(t, preout, proof).map_err(|_| SignatureError::Invalid) ?;
+    Ok(io)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use merlin::Transcript;
+
+    #[test]
+    fn secret_key_roundtrip() {
+        let mini = schnorrkel::MiniSecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let theirs = mini.expand(schnorrkel::ExpansionMode::Ed25519);
+        let ours = secret_from_schnorrkel(&theirs);
+        assert_eq!(secret_to_schnorrkel(&ours).to_bytes(), theirs.to_bytes());
+        assert_eq!(serialize_publickey(ours.as_publickey()), theirs.to_public().to_bytes());
+        assert_eq!(public_from_schnorrkel(&theirs.to_public()).unwrap(), ours.to_public());
+        assert_eq!(public_to_schnorrkel(ours.as_publickey()).unwrap(), theirs.to_public());
+    }
+
+    #[test]
+    fn sr25519_vrf_interop() {
+        let ours = SecretKey::from_seed(&[3u8; 32]);
+        let t = || {
+            let mut t = Transcript::new(b"SigningContext");
+            t.append_message(b"", b"BABE");
+            t.append_message(b"sign-bytes", b"slot 42");
+            t
+        };
+
+        // schnorrkel verifies our signatures, and we verify schnorrkel's.
+        let (io, proof) = sign_sr25519_vrf(&ours, t());
+        let theirs = public_to_schnorrkel(ours.as_publickey()).unwrap();
+        assert!(theirs.vrf_verify(t(), &io.to_preout(), &proof).is_ok());
+
+        let keypair = secret_to_schnorrkel(&ours).to_keypair();
+        let (io2, proof2, _) = keypair.vrf_sign(t());
+        let io3 = verify_sr25519_vrf(ours.as_publickey(), t(), &io2.to_preout(), &proof2).unwrap();
+        assert_eq!(io3.to_preout(), io2.to_preout());
+        assert_eq!(io3.to_preout(), io.to_preout());
+
+        let other = SecretKey::from_seed(&[4u8; 32]);
+        assert!(verify_sr25519_vrf(other.as_publickey(), t(), &io.to_preout(), &proof).is_err());
+    }
+}